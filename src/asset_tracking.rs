@@ -1,8 +1,13 @@
-//! A high-level way to load collections of asset handles as resources.
+//! A high-level way to load collections of asset handles as resources, and a
+//! loader for assets written in RON.
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, marker::PhantomData};
 
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<ResourceHandles>();
@@ -36,6 +41,61 @@ impl LoadResource for App {
     }
 }
 
+pub trait LoadRonAsset {
+    /// Register `T` as an [`Asset`] loaded by a [`RonAssetLoader`] from files
+    /// ending in one of `extensions`, e.g. `["abilities.ron"]`.
+    fn init_ron_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
+}
+
+impl LoadRonAsset for App {
+    fn init_ron_asset<T: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.init_asset::<T>();
+        self.register_asset_loader(RonAssetLoader::<T>::new(extensions))
+    }
+}
+
+/// Deserializes a `T` from a RON file.
+pub struct RonAssetLoader<T> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> T>,
+}
+
+impl<T> RonAssetLoader<T> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _asset: PhantomData,
+        }
+    }
+}
+
+impl<T: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
+
 /// A function that inserts a loaded resource.
 type InsertLoadedResource = fn(&mut World, &UntypedHandle);
 
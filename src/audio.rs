@@ -1,15 +1,32 @@
-use bevy::prelude::*;
+use bevy::{
+    audio::{DefaultSpatialScale, SpatialScale, Volume},
+    prelude::*,
+};
+
+use crate::settings::Settings;
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Music>();
     app.register_type::<SoundEffect>();
+    app.insert_resource(DefaultSpatialScale(SpatialScale::new_2d(
+        1.0 / PIXELS_PER_AUDIO_UNIT,
+    )));
 
     app.add_systems(
         Update,
-        apply_global_volume.run_if(resource_changed::<GlobalVolume>),
+        (
+            apply_global_volume
+                .run_if(resource_changed::<GlobalVolume>.or(resource_changed::<Settings>)),
+            apply_category_volume_to_new_sounds,
+        ),
     );
 }
 
+/// World distance, in pixels, at which a spatial sound starts to fade with distance.
+const PIXELS_PER_AUDIO_UNIT: f32 = 100.0;
+/// Distance between the listener's ears, in pixels. Wide enough that sounds to the side pan clearly.
+pub const LISTENER_EAR_GAP: f32 = 128.0;
+
 /// An organizational marker component that should be added to a spawned [`AudioPlayer`] if it's in the
 /// general "music" category (e.g. global background music, soundtrack).
 ///
@@ -36,12 +53,73 @@ pub fn sound_effect(handle: Handle<AudioSource>) -> impl Bundle {
     (AudioPlayer(handle), PlaybackSettings::DESPAWN, SoundEffect)
 }
 
+/// A sound effect emitted from a point in the world.
+///
+/// It pans and gets quieter the further it is from the [`SpatialListener`] on the camera.
+pub fn spatial_sound_effect(handle: Handle<AudioSource>, position: Vec2) -> impl Bundle {
+    (
+        AudioPlayer(handle),
+        PlaybackSettings::DESPAWN.with_spatial(true),
+        SoundEffect,
+        Transform::from_translation(position.extend(0.0)),
+    )
+}
+
+/// The volume from [`Settings`] for the category a sound belongs to.
+fn category_volume(settings: &Settings, is_music: bool, is_sound_effect: bool) -> Volume {
+    if is_music {
+        Volume::Linear(settings.music_volume)
+    } else if is_sound_effect {
+        Volume::Linear(settings.sfx_volume)
+    } else {
+        Volume::Linear(1.0)
+    }
+}
+
 /// [`GlobalVolume`] doesn't apply to already-running audio entities, so this system will update them.
 fn apply_global_volume(
     global_volume: Res<GlobalVolume>,
-    mut audio_query: Query<(&PlaybackSettings, &mut AudioSink)>,
+    settings: Res<Settings>,
+    mut audio_query: Query<(
+        &PlaybackSettings,
+        &mut AudioSink,
+        Has<Music>,
+        Has<SoundEffect>,
+    )>,
+    mut spatial_audio_query: Query<(
+        &PlaybackSettings,
+        &mut SpatialAudioSink,
+        Has<Music>,
+        Has<SoundEffect>,
+    )>,
 ) {
-    for (playback, mut sink) in &mut audio_query {
-        sink.set_volume(global_volume.volume * playback.volume);
+    for (playback, mut sink, is_music, is_sound_effect) in &mut audio_query {
+        let category = category_volume(&settings, is_music, is_sound_effect);
+        sink.set_volume(global_volume.volume * playback.volume * category);
+    }
+    for (playback, mut sink, is_music, is_sound_effect) in &mut spatial_audio_query {
+        let category = category_volume(&settings, is_music, is_sound_effect);
+        sink.set_volume(global_volume.volume * playback.volume * category);
+    }
+}
+
+/// New sounds only pick up [`GlobalVolume`] on their own, so scale them by their category's volume too.
+fn apply_category_volume_to_new_sounds(
+    settings: Res<Settings>,
+    mut audio_query: Query<(&mut AudioSink, Has<Music>, Has<SoundEffect>), Added<AudioSink>>,
+    mut spatial_audio_query: Query<
+        (&mut SpatialAudioSink, Has<Music>, Has<SoundEffect>),
+        Added<SpatialAudioSink>,
+    >,
+) {
+    for (mut sink, is_music, is_sound_effect) in &mut audio_query {
+        let category = category_volume(&settings, is_music, is_sound_effect);
+        let volume = sink.volume() * category;
+        sink.set_volume(volume);
+    }
+    for (mut sink, is_music, is_sound_effect) in &mut spatial_audio_query {
+        let category = category_volume(&settings, is_music, is_sound_effect);
+        let volume = sink.volume() * category;
+        sink.set_volume(volume);
     }
 }
@@ -0,0 +1,140 @@
+//! Ability tuning loaded from `abilities.ron`.
+//!
+//! Each projectile that explodes has its own shockwave radius, base impulse
+//! and falloff curve, so blasts can be tuned without recompiling. Each ability
+//! also has its own [`ImpactFeel`], for how hard its hits land on the player.
+
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    asset_tracking::{LoadResource, LoadRonAsset},
+    demo::balistics::AbilityKind,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<AbilityConfig>(&["abilities.ron"]);
+    app.load_resource::<AbilityConfigAssets>();
+}
+
+/// How a shockwave's impulse drops off with distance from the explosion.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FalloffCurve {
+    Linear,
+    #[default]
+    Quadratic,
+    InverseSquare,
+}
+
+impl FalloffCurve {
+    pub const ALL: [FalloffCurve; 3] = [
+        FalloffCurve::Linear,
+        FalloffCurve::Quadratic,
+        FalloffCurve::InverseSquare,
+    ];
+
+    /// The fraction of the base impulse felt at `distance` from an explosion of `radius`.
+    pub fn factor(self, distance: f32, radius: f32) -> f32 {
+        let t = (distance / radius).clamp(0.0, 1.0);
+        match self {
+            FalloffCurve::Linear => 1.0 - t,
+            FalloffCurve::Quadratic => (1.0 - t).powi(2),
+            FalloffCurve::InverseSquare => 1.0 / (1.0 + t * t),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FalloffCurve::Linear => "Linear",
+            FalloffCurve::Quadratic => "Quadratic",
+            FalloffCurve::InverseSquare => "Inverse square",
+        }
+    }
+}
+
+/// The shockwave an exploding projectile sends out.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ExplosionConfig {
+    pub radius: f32,
+    pub base_impulse: f32,
+    #[serde(default)]
+    pub falloff: FalloffCurve,
+}
+
+impl Default for ExplosionConfig {
+    fn default() -> Self {
+        Self {
+            radius: 200.0,
+            base_impulse: 37500.0,
+            falloff: FalloffCurve::Quadratic,
+        }
+    }
+}
+
+/// The game feel of a projectile connecting, applied by [`crate::demo::impact_feel`].
+/// Anything left out of the config does nothing.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct ImpactFeel {
+    /// Frames the game freezes for, counted at 60 frames a second.
+    pub hitstop_frames: u32,
+    /// Screen shake trauma added, from 0 to 1.
+    pub shake_trauma: f32,
+    /// How strongly the screen flashes white, from 0 to 1.
+    pub flash_intensity: f32,
+    /// How hard gamepads rumble, from 0 to 1.
+    pub rumble_strength: f32,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct AbilityConfig {
+    #[serde(default)]
+    pub explosions: HashMap<AbilityKind, ExplosionConfig>,
+    #[serde(default)]
+    pub impacts: HashMap<AbilityKind, ImpactFeel>,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct AbilityConfigAssets {
+    #[dependency]
+    pub config: Handle<AbilityConfig>,
+}
+
+impl FromWorld for AbilityConfigAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            config: assets.load("abilities.ron"),
+        }
+    }
+}
+
+/// Looks up ability tuning, falling back to the defaults until the config has loaded.
+#[derive(SystemParam)]
+pub struct AbilityConfigs<'w> {
+    handles: Option<Res<'w, AbilityConfigAssets>>,
+    configs: Res<'w, Assets<AbilityConfig>>,
+}
+
+impl AbilityConfigs<'_> {
+    pub fn explosion(&self, ability: AbilityKind) -> ExplosionConfig {
+        self.config()
+            .and_then(|config| config.explosions.get(&ability).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn impact(&self, ability: AbilityKind) -> ImpactFeel {
+        self.config()
+            .and_then(|config| config.impacts.get(&ability).copied())
+            .unwrap_or_default()
+    }
+
+    fn config(&self) -> Option<&AbilityConfig> {
+        self.handles
+            .as_ref()
+            .and_then(|handles| self.configs.get(&handles.config))
+    }
+}
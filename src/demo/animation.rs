@@ -4,17 +4,22 @@
 //! - [Sprite animation](https://github.com/bevyengine/bevy/blob/latest/examples/2d/sprite_animation.rs)
 //! - [Timers](https://github.com/bevyengine/bevy/blob/latest/examples/time/timers.rs)
 
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor};
 use rand::prelude::*;
 use std::time::Duration;
 
-use crate::{AppSystems, PausableSystems, audio::sound_effect, demo::player::PlayerAssets};
+use crate::{
+    AppSystems, PausableSystems,
+    audio::sound_effect,
+    demo::{balistics::ExplosionBundle, player::PlayerAssets, pool::Pool, rng::GameRng},
+};
 
-use super::movement::MovementController;
+use super::movement::{Crouching, MovementController};
 
 pub(super) fn plugin(app: &mut App) {
     // Animate and play sound effects based on controls.
     app.register_type::<PlayerAnimation>();
+    app.add_event::<AnimationFrameEvent>();
     app.add_systems(
         Update,
         (
@@ -22,6 +27,7 @@ pub(super) fn plugin(app: &mut App) {
             (
                 update_animation_movement,
                 update_animation_atlas,
+                emit_animation_events,
                 trigger_step_sound_effect,
             )
                 .chain()
@@ -32,22 +38,51 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-/// Update the sprite direction and animation state (idling/walking).
+/// Size of each frame in the player's texture atlas.
+const PLAYER_FRAME_SIZE: f32 = 32.0;
+/// Height of the player's sprite while crouched, as a fraction of its full height.
+const CROUCH_SPRITE_SQUASH: f32 = 0.6;
+
+/// Update the sprite direction and animation state (idling/walking/crawling).
 fn update_animation_movement(
-    mut player_query: Query<(&MovementController, &mut Sprite, &mut PlayerAnimation)>,
+    mut player_query: Query<(
+        &MovementController,
+        &mut Sprite,
+        &mut PlayerAnimation,
+        Has<Crouching>,
+    )>,
 ) {
-    for (controller, mut sprite, mut animation) in &mut player_query {
+    for (controller, mut sprite, mut animation, crouching) in &mut player_query {
         let dx = controller.direction.x;
         if dx != 0.0 {
             sprite.flip_x = dx < 0.0;
         }
 
-        let animation_state = if controller.direction == Vec2::ZERO {
-            PlayerAnimationState::Idling
-        } else {
-            PlayerAnimationState::Walking
+        let moving = controller.direction != Vec2::ZERO;
+        let animation_state = match (crouching, moving) {
+            (false, false) => PlayerAnimationState::Idling,
+            (false, true) => PlayerAnimationState::Walking,
+            (true, false) => PlayerAnimationState::Crouching,
+            (true, true) => PlayerAnimationState::Crawling,
         };
         animation.update_state(animation_state);
+
+        // Squash the sprite down onto its feet while crouched.
+        let (custom_size, anchor) = if crouching {
+            (
+                Some(Vec2::new(
+                    PLAYER_FRAME_SIZE,
+                    PLAYER_FRAME_SIZE * CROUCH_SPRITE_SQUASH,
+                )),
+                Anchor::Custom(Vec2::new(0.0, 0.5 / CROUCH_SPRITE_SQUASH - 0.5)),
+            )
+        } else {
+            (None, Anchor::Center)
+        };
+        if sprite.custom_size != custom_size {
+            sprite.custom_size = custom_size;
+            sprite.anchor = anchor;
+        }
     }
 }
 
@@ -57,6 +92,7 @@ fn update_animation_timer(
     time: Res<Time>,
     mut query: Query<&mut PlayerAnimation>,
     explosion_query: Query<(Entity, &mut ExplosionAnimation)>,
+    mut explosion_pool: ResMut<Pool<ExplosionBundle>>,
 ) {
     for mut animation in &mut query {
         animation.update_timer(time.delta());
@@ -64,7 +100,7 @@ fn update_animation_timer(
     for (entity, mut explosion_animation) in explosion_query {
         explosion_animation.update_timer(time.delta());
         if explosion_animation.is_finished() {
-            commands.entity(entity).despawn();
+            explosion_pool.release(&mut commands, entity);
         }
     }
 }
@@ -94,20 +130,44 @@ fn update_animation_atlas(
     }
 }
 
-/// If the player is moving, play a step sound effect synchronized with the
-/// animation.
+/// Something that happens on a particular frame of an animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// A foot hits the ground.
+    Footstep,
+}
+
+/// Sent when a frame with an [`AnimationEvent`] starts playing.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AnimationFrameEvent {
+    pub entity: Entity,
+    pub event: AnimationEvent,
+}
+
+fn emit_animation_events(
+    player_query: Query<(Entity, &PlayerAnimation)>,
+    mut frame_events: EventWriter<AnimationFrameEvent>,
+) {
+    for (entity, animation) in &player_query {
+        if !animation.changed() {
+            continue;
+        }
+        for &event in animation.frame_events() {
+            frame_events.write(AnimationFrameEvent { entity, event });
+        }
+    }
+}
+
+/// Play a step sound effect whenever a foot lands in the walking animation.
 fn trigger_step_sound_effect(
     mut commands: Commands,
     player_assets: Res<PlayerAssets>,
-    mut step_query: Query<&PlayerAnimation>,
+    mut rng: ResMut<GameRng>,
+    mut frame_events: EventReader<AnimationFrameEvent>,
 ) {
-    for animation in &mut step_query {
-        if animation.state == PlayerAnimationState::Walking
-            && animation.changed()
-            && (animation.frame == 2 || animation.frame == 5)
-        {
-            let rng = &mut rand::thread_rng();
-            let random_step = player_assets.steps.choose(rng).unwrap().clone();
+    for frame_event in frame_events.read() {
+        if frame_event.event == AnimationEvent::Footstep {
+            let random_step = player_assets.steps.choose(&mut *rng).unwrap().clone();
             commands.spawn(sound_effect(random_step));
         }
     }
@@ -128,6 +188,10 @@ pub enum PlayerAnimationState {
     #[default]
     Idling,
     Walking,
+    /// Crouched down and staying put.
+    Crouching,
+    /// Crouched down and on the move.
+    Crawling,
 }
 
 impl PlayerAnimation {
@@ -139,6 +203,17 @@ impl PlayerAnimation {
     const WALKING_FRAMES: usize = 6;
     /// The duration of each walking frame.
     const WALKING_INTERVAL: Duration = Duration::from_millis(50);
+    /// The duration of each crawling frame, crawling reuses the walking frames.
+    const CRAWLING_INTERVAL: Duration = Duration::from_millis(120);
+    /// Events for each walking frame, the feet land on frames 2 and 5.
+    const WALKING_EVENTS: [&'static [AnimationEvent]; Self::WALKING_FRAMES] = [
+        &[],
+        &[],
+        &[AnimationEvent::Footstep],
+        &[],
+        &[],
+        &[AnimationEvent::Footstep],
+    ];
 
     fn idling() -> Self {
         Self {
@@ -156,6 +231,21 @@ impl PlayerAnimation {
         }
     }
 
+    fn crouching() -> Self {
+        Self {
+            state: PlayerAnimationState::Crouching,
+            ..Self::idling()
+        }
+    }
+
+    fn crawling() -> Self {
+        Self {
+            timer: Timer::new(Self::CRAWLING_INTERVAL, TimerMode::Repeating),
+            frame: 0,
+            state: PlayerAnimationState::Crawling,
+        }
+    }
+
     pub fn new() -> Self {
         Self::idling()
     }
@@ -169,7 +259,11 @@ impl PlayerAnimation {
         self.frame = (self.frame + 1)
             % match self.state {
                 PlayerAnimationState::Idling => Self::IDLE_FRAMES,
-                PlayerAnimationState::Walking => Self::WALKING_FRAMES,
+                // Hold the first idle frame while crouched.
+                PlayerAnimationState::Crouching => 1,
+                PlayerAnimationState::Walking | PlayerAnimationState::Crawling => {
+                    Self::WALKING_FRAMES
+                }
             };
     }
 
@@ -179,6 +273,8 @@ impl PlayerAnimation {
             match state {
                 PlayerAnimationState::Idling => *self = Self::idling(),
                 PlayerAnimationState::Walking => *self = Self::walking(),
+                PlayerAnimationState::Crouching => *self = Self::crouching(),
+                PlayerAnimationState::Crawling => *self = Self::crawling(),
             }
         }
     }
@@ -188,11 +284,22 @@ impl PlayerAnimation {
         self.timer.finished()
     }
 
+    /// The events on the current frame.
+    pub fn frame_events(&self) -> &'static [AnimationEvent] {
+        match self.state {
+            // Crawling is quiet.
+            PlayerAnimationState::Idling
+            | PlayerAnimationState::Crouching
+            | PlayerAnimationState::Crawling => &[],
+            PlayerAnimationState::Walking => Self::WALKING_EVENTS[self.frame],
+        }
+    }
+
     /// Return sprite index in the atlas.
     pub fn get_atlas_index(&self) -> usize {
         match self.state {
-            PlayerAnimationState::Idling => self.frame,
-            PlayerAnimationState::Walking => 6 + self.frame,
+            PlayerAnimationState::Idling | PlayerAnimationState::Crouching => self.frame,
+            PlayerAnimationState::Walking | PlayerAnimationState::Crawling => 6 + self.frame,
         }
     }
 }
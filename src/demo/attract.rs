@@ -0,0 +1,246 @@
+//! Attract mode, arcade style.
+//!
+//! If nobody touches anything for [`IDLE_SECS`] on the title screen, the game
+//! starts playing itself: it loads the demo run's level and feeds the first
+//! player the inputs recorded in `demo.replay.ron`, timed against the
+//! [`GameTimer`] the same way ghosts are. The inputs go through the player's
+//! usual controllers, so the castle comes down exactly as it would for a
+//! person playing. Any key, click or button press, or the end of the
+//! recording, goes back to the title screen.
+
+use bevy::{input::mouse::MouseMotion, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    asset_tracking::{LoadResource, LoadRonAsset},
+    demo::{
+        balistics::AbilityKind,
+        input::ActionType,
+        movement::{JumpController, MovementController},
+        player::{CharacterController, Player, PlayerIndex},
+        timer::GameTimer,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<DemoReplay>(&["replay.ron"]);
+    app.load_resource::<DemoReplayAssets>();
+    app.init_resource::<AttractMode>();
+    app.add_systems(OnEnter(Screen::Title), reset_idle_time);
+    app.add_systems(
+        Update,
+        start_attract_mode.run_if(in_state(Screen::Title).and(resource_exists::<DemoReplayAssets>)),
+    );
+    app.add_systems(
+        Update,
+        (
+            play_demo_replay.run_if(in_state(Screen::Gameplay)),
+            end_attract_mode,
+        )
+            .chain()
+            .run_if(attract_mode_active.and(resource_exists::<DemoReplayAssets>)),
+    );
+}
+
+/// Seconds without input on the title screen before the demo starts.
+const IDLE_SECS: f32 = 30.0;
+/// How long the demo keeps going after the last recorded input.
+const DEMO_TAIL_SECS: f32 = 3.0;
+/// Gamepad sticks moved further than this count as input.
+const STICK_DEADZONE: f32 = 0.2;
+
+/// One moment of a demo run. Anything left out stays as it was.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayInput {
+    /// Seconds since the level started.
+    pub time: f32,
+    #[serde(default)]
+    pub movement: Option<(f32, f32)>,
+    #[serde(default)]
+    pub jump: bool,
+    #[serde(default)]
+    pub cast: Option<ReplayCast>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReplayCast {
+    pub ability: AbilityKind,
+    pub direction: (f32, f32),
+}
+
+/// A run recorded as the inputs of the first player.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct DemoReplay {
+    /// Index of the level the run was played on.
+    pub level: usize,
+    /// Sorted by time.
+    pub inputs: Vec<ReplayInput>,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct DemoReplayAssets {
+    #[dependency]
+    replay: Handle<DemoReplay>,
+}
+
+impl FromWorld for DemoReplayAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            replay: assets.load("demo.replay.ron"),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct AttractMode {
+    /// Whether the demo is playing.
+    pub active: bool,
+    /// Seconds since the last input on the title screen.
+    idle_secs: f32,
+    /// Index of the next recorded input to play.
+    next_input: usize,
+}
+
+/// Whether the demo is playing. Demo runs don't count for records, ghosts,
+/// the leaderboard or progress.
+pub fn attract_mode_active(attract_mode: Res<AttractMode>) -> bool {
+    attract_mode.active
+}
+
+/// Whether anyone pressed or moved anything this frame.
+fn any_input(
+    keys: &ButtonInput<KeyCode>,
+    mouse_buttons: &ButtonInput<MouseButton>,
+    mouse_motion: &mut EventReader<MouseMotion>,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    let moved_mouse = mouse_motion.read().count() > 0;
+    keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || moved_mouse
+        || gamepads.iter().any(|gamepad| {
+            gamepad.get_just_pressed().next().is_some()
+                || gamepad.left_stick().length() > STICK_DEADZONE
+                || gamepad.right_stick().length() > STICK_DEADZONE
+        })
+}
+
+fn reset_idle_time(mut attract_mode: ResMut<AttractMode>) {
+    attract_mode.idle_secs = 0.0;
+}
+
+fn start_attract_mode(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepads: Query<&Gamepad>,
+    replay_assets: Res<DemoReplayAssets>,
+    replays: Res<Assets<DemoReplay>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if any_input(&keys, &mouse_buttons, &mut mouse_motion, &gamepads) {
+        attract_mode.idle_secs = 0.0;
+        return;
+    }
+    attract_mode.idle_secs += time.delta_secs();
+    if attract_mode.idle_secs < IDLE_SECS {
+        return;
+    }
+    let Some(replay) = replays.get(&replay_assets.replay) else {
+        return;
+    };
+    info!("Nobody's playing, starting the demo run");
+    attract_mode.active = true;
+    attract_mode.next_input = 0;
+    *level_selection = LevelSelection::index(replay.level);
+    next_screen.set(Screen::Gameplay);
+}
+
+/// Feed the first player every recorded input that's come due.
+fn play_demo_replay(
+    timer: Res<GameTimer>,
+    real_time: Res<Time<Real>>,
+    replay_assets: Res<DemoReplayAssets>,
+    replays: Res<Assets<DemoReplay>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut player_query: Query<
+        (
+            &PlayerIndex,
+            &mut MovementController,
+            &mut JumpController,
+            &mut CharacterController,
+        ),
+        With<Player>,
+    >,
+) {
+    let Some(replay) = replays.get(&replay_assets.replay) else {
+        return;
+    };
+    let Some((_, mut movement, mut jump, mut controller)) =
+        player_query.iter_mut().find(|(index, ..)| index.0 == 0)
+    else {
+        return;
+    };
+    let time = timer.timer.elapsed_secs();
+    while let Some(input) = replay.inputs.get(attract_mode.next_input) {
+        if input.time > time {
+            break;
+        }
+        attract_mode.next_input += 1;
+        if let Some((x, y)) = input.movement {
+            movement.direction = Vec2::new(x, y).normalize_or_zero();
+        }
+        if input.jump {
+            jump.request_jump();
+        } else {
+            jump.release_jump();
+        }
+        if let Some(cast) = &input.cast {
+            controller.queue_action(
+                ActionType::Cast {
+                    ability: cast.ability,
+                    direction: Vec2::new(cast.direction.0, cast.direction.1).normalize_or_zero(),
+                },
+                real_time.elapsed(),
+            );
+        }
+    }
+}
+
+/// Go back to the title screen on any input, once the recording runs out, or
+/// if the demo somehow ends the level.
+fn end_attract_mode(
+    screen: Res<State<Screen>>,
+    timer: Res<GameTimer>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepads: Query<&Gamepad>,
+    replay_assets: Res<DemoReplayAssets>,
+    replays: Res<Assets<DemoReplay>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    let finished = match screen.get() {
+        // Still on its way into the level.
+        Screen::Title => false,
+        Screen::Gameplay => replays
+            .get(&replay_assets.replay)
+            .and_then(|replay| replay.inputs.last())
+            .is_none_or(|last| timer.timer.elapsed_secs() > last.time + DEMO_TAIL_SECS),
+        _ => true,
+    };
+    if !finished && !any_input(&keys, &mouse_buttons, &mut mouse_motion, &gamepads) {
+        return;
+    }
+    info!("Demo run over, back to the title screen");
+    attract_mode.active = false;
+    next_screen.set(Screen::Title);
+}
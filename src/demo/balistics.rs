@@ -1,11 +1,22 @@
 use std::time::Duration;
 
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     asset_tracking::LoadResource,
-    demo::player::{LightningState, Player},
+    audio::sound_effect,
+    demo::{
+        charges::create_charge_bundle,
+        collision::Exploded,
+        loadout::Loadout,
+        player::LightningState,
+        pool::{Pool, Poolable},
+        progress::Progress,
+        skills::Ricochets,
+    },
+    screens::Screen,
 };
 
 use super::{
@@ -14,37 +25,197 @@ use super::{
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.init_resource::<FireballCooldown>()
+    app.add_event::<AbilityCast>()
+        .init_resource::<FireballCooldown>()
         .init_resource::<FrostCooldown>()
         .init_resource::<LightningCooldown>()
-        .insert_resource(FrostCooldown::new(1.0))
-        .insert_resource(FireballCooldown::new(0.5))
-        .insert_resource(LightningCooldown::new(5.0))
+        .init_resource::<ChargeCooldown>()
+        .init_resource::<UnlockedAbilities>()
+        .init_resource::<Pool<FireballBundle>>()
+        .init_resource::<Pool<ExplosionBundle>>()
+        .insert_resource(FrostCooldown::new(FROST_COOLDOWN_SECS))
+        .insert_resource(FireballCooldown::new(FIREBALL_COOLDOWN_SECS))
+        .insert_resource(LightningCooldown::new(LIGHTNING_COOLDOWN_SECS))
+        .insert_resource(ChargeCooldown::new(CHARGE_COOLDOWN_SECS))
         .load_resource::<ExplosionAssets>()
         .load_resource::<FrostAssets>()
+        .load_resource::<SfxAssets>()
         .add_systems(Update, (update_abilities, update_cooldowns))
-        .add_systems(Update, process_ability_actions);
+        .add_systems(
+            Update,
+            process_ability_actions.run_if(resource_exists::<SfxAssets>),
+        )
+        .add_systems(OnEnter(Screen::Gameplay), reset_unlocked_abilities);
 }
 
-fn update_cooldowns(
-    time: Res<Time>,
-    mut fire_cooldown: ResMut<FireballCooldown>,
-    mut frost_cooldown: ResMut<FrostCooldown>,
-    mut lightning_cooldown: ResMut<LightningCooldown>,
-) {
-    fire_cooldown.timer.tick(time.delta());
-    frost_cooldown.timer.tick(time.delta());
-    lightning_cooldown.timer.tick(time.delta());
+/// Cooldowns before skill upgrades shorten them, in seconds.
+pub const FIREBALL_COOLDOWN_SECS: f32 = 0.5;
+pub const FROST_COOLDOWN_SECS: f32 = 1.0;
+pub const LIGHTNING_COOLDOWN_SECS: f32 = 5.0;
+pub const CHARGE_COOLDOWN_SECS: f32 = 0.75;
+
+fn update_cooldowns(time: Res<Time>, mut caster_query: Query<&mut CasterCooldowns>) {
+    for mut cooldowns in &mut caster_query {
+        for timer in cooldowns.timers.values_mut() {
+            timer.tick(time.delta());
+        }
+    }
+}
+
+/// The cooldowns of everything one caster has cast, so co-op players don't
+/// wait on each other's abilities.
+#[derive(Component, Debug, Clone, Default)]
+pub struct CasterCooldowns {
+    timers: HashMap<AbilityKind, Timer>,
+}
+
+impl CasterCooldowns {
+    /// Whether `ability` can be cast again.
+    pub fn is_ready(&self, ability: AbilityKind) -> bool {
+        self.timers.get(&ability).is_none_or(Timer::finished)
+    }
+
+    /// Seconds before `ability` can be cast again.
+    pub fn remaining_secs(&self, ability: AbilityKind) -> f32 {
+        self.timers
+            .get(&ability)
+            .map_or(0.0, |timer| timer.remaining_secs())
+    }
+
+    /// Put `ability` on cooldown for `duration`.
+    pub fn start(&mut self, ability: AbilityKind, duration: Duration) {
+        self.timers
+            .insert(ability, Timer::new(duration, TimerMode::Once));
+    }
 }
 
 #[derive(Component)]
 pub struct Ability;
 
+/// The abilities the player can cast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect, Serialize, Deserialize)]
+pub enum AbilityKind {
+    #[default]
+    Fireball,
+    Frost,
+    Lightning,
+    /// A sticky charge, set off remotely with the detonate key.
+    Charge,
+}
+
+impl AbilityKind {
+    /// The color used to represent the ability in the world and UI.
+    pub fn color(self) -> Color {
+        match self {
+            AbilityKind::Fireball => Color::srgb(1.0, 0.45, 0.1),
+            AbilityKind::Frost => Color::srgb(0.3, 0.6, 1.0),
+            AbilityKind::Lightning => Color::srgb(1.0, 1.0, 0.3),
+            AbilityKind::Charge => Color::srgb(0.85, 0.2, 0.2),
+        }
+    }
+
+    /// Parse an ability from its name in LDtk fields, e.g. `Frost`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Fireball" => Some(AbilityKind::Fireball),
+            "Frost" => Some(AbilityKind::Frost),
+            "Lightning" => Some(AbilityKind::Lightning),
+            "Charge" => Some(AbilityKind::Charge),
+            _ => None,
+        }
+    }
+}
+
+/// Sent whenever the player casts an ability.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AbilityCast {
+    pub ability: AbilityKind,
+}
+
+/// Which abilities the player is currently allowed to cast.
+///
+/// Every run starts with just the fireball, plus whatever has been unlocked
+/// in [`Progress`] by collecting runes or completing levels.
+#[derive(Resource, Debug, Clone)]
+pub struct UnlockedAbilities {
+    pub fireball: bool,
+    pub frost: bool,
+    pub lightning: bool,
+    pub charge: bool,
+}
+
+impl Default for UnlockedAbilities {
+    fn default() -> Self {
+        Self {
+            fireball: true,
+            frost: false,
+            lightning: false,
+            charge: false,
+        }
+    }
+}
+
+impl UnlockedAbilities {
+    pub fn is_unlocked(&self, ability: AbilityKind) -> bool {
+        match ability {
+            AbilityKind::Fireball => self.fireball,
+            AbilityKind::Frost => self.frost,
+            AbilityKind::Lightning => self.lightning,
+            AbilityKind::Charge => self.charge,
+        }
+    }
+
+    pub fn set(&mut self, ability: AbilityKind, unlocked: bool) {
+        match ability {
+            AbilityKind::Fireball => self.fireball = unlocked,
+            AbilityKind::Frost => self.frost = unlocked,
+            AbilityKind::Lightning => self.lightning = unlocked,
+            AbilityKind::Charge => self.charge = unlocked,
+        }
+    }
+}
+
+fn reset_unlocked_abilities(mut unlocked: ResMut<UnlockedAbilities>, progress: Res<Progress>) {
+    *unlocked = UnlockedAbilities::default();
+    for ability in &progress.collected_abilities {
+        unlocked.set(*ability, true);
+    }
+}
+
+/// Launch speed of a fireball in world units per second.
+pub const FIREBALL_SPEED: f32 = 900.0;
+pub const FIREBALL_GRAVITY_SCALE: f32 = 1.0;
+/// How long a fireball flies before fizzling out.
+pub const FIREBALL_LIFETIME_SECS: f32 = 2.0;
+
 #[derive(Component)]
 pub struct Fireball;
 
 #[derive(Component)]
-pub struct Frostbolt;
+pub struct Frostbolt {
+    /// The direction it was cast in, used for its cone if it has stopped moving.
+    pub launch_direction: Vec2,
+    /// Velocity before the last physics step, as the impact has already
+    /// deflected or stopped it.
+    pub velocity: Vec2,
+}
+
+/// The cone of frost a projectile bursts into when it hits something.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FrostCone {
+    pub radius: f32,
+    /// Angle either side of the direction of travel that's inside the cone, in radians.
+    pub half_angle: f32,
+}
+
+impl Default for FrostCone {
+    fn default() -> Self {
+        Self {
+            radius: 128.0,
+            half_angle: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct LightningBolt;
@@ -70,6 +241,16 @@ pub struct FireballBundle {
     pub visibility: Visibility,
     pub inherited_visibility: InheritedVisibility,
     pub mass: Mass,
+    pub gravity_scale: GravityScale,
+}
+
+impl Poolable for FireballBundle {
+    fn reset(entity: &mut EntityCommands) {
+        // `Fireball` is inserted again on reuse, so upgrades see it as a new fireball.
+        entity
+            .try_remove::<(Fireball, Exploded, Ricochets)>()
+            .try_insert(LinearVelocity::ZERO);
+    }
 }
 
 fn create_fireball_bundle(
@@ -81,7 +262,7 @@ fn create_fireball_bundle(
         fireball: Fireball,
         ability: Ability,
         lifetime: Lifetime {
-            lifetime: Timer::from_seconds(2.0, TimerMode::Once),
+            lifetime: Timer::from_seconds(FIREBALL_LIFETIME_SECS, TimerMode::Once),
         },
         sprite: Sprite {
             image: asset_server.load("images/fireball.png"),
@@ -94,7 +275,7 @@ fn create_fireball_bundle(
         rigid_body: RigidBody::Dynamic,
         movement_controller: MovementController {
             direction,
-            speed: 900.0,
+            speed: FIREBALL_SPEED,
         },
         collider: Collider::circle(8.0),
         colliding_entities: CollidingEntities::default(),
@@ -102,6 +283,7 @@ fn create_fireball_bundle(
         visibility: Visibility::Visible,
         inherited_visibility: InheritedVisibility::default(),
         mass: Mass(100.),
+        gravity_scale: GravityScale(FIREBALL_GRAVITY_SCALE),
     }
 }
 
@@ -109,6 +291,7 @@ fn create_fireball_bundle(
 pub struct FrostballBundle {
     pub ability: Ability,
     pub frostbolt: Frostbolt,
+    pub cone: FrostCone,
     pub lifetime: Lifetime,
     pub sprite: Sprite,
     pub transform: Transform,
@@ -121,6 +304,7 @@ pub struct FrostballBundle {
     pub visibility: Visibility,
     pub inherited_visibility: InheritedVisibility,
     pub mass: Mass,
+    pub gravity_scale: GravityScale,
 }
 
 fn create_frostball_bundle(
@@ -130,7 +314,11 @@ fn create_frostball_bundle(
 ) -> FrostballBundle {
     FrostballBundle {
         ability: Ability,
-        frostbolt: Frostbolt,
+        frostbolt: Frostbolt {
+            launch_direction: direction,
+            velocity: Vec2::ZERO,
+        },
+        cone: FrostCone::default(),
         lifetime: Lifetime {
             lifetime: Timer::from_seconds(4.0, TimerMode::Once),
         },
@@ -154,16 +342,19 @@ fn create_frostball_bundle(
         visibility: Visibility::Visible,
         inherited_visibility: InheritedVisibility::default(),
         mass: Mass(400.0),
+        gravity_scale: GravityScale(0.5), // Slow, floaty arc
     }
 }
 
 fn spawn_ability(
-    ability_type: ActionType,
+    ability: AbilityKind,
+    caster: Entity,
     commands: &mut Commands,
     position: Vec3,
     direction: Vec2,
     asset_server: &Res<AssetServer>,
-    player_query: Query<Entity, With<Player>>,
+    sfx_assets: &SfxAssets,
+    fireball_pool: &mut Pool<FireballBundle>,
 ) {
     let offset_distance = 24.0; // Adjust based on your sprite sizes
     let spawn_position = position
@@ -172,72 +363,73 @@ fn spawn_ability(
             direction.y * offset_distance,
             1.0,
         );
-    match ability_type {
-        ActionType::FireballAttack { direction } => {
+    match ability {
+        AbilityKind::Fireball => {
             let fireball_bundle = create_fireball_bundle(spawn_position, direction, asset_server);
-            commands.spawn(fireball_bundle);
+            fireball_pool.acquire(commands, fireball_bundle);
+            commands.spawn(sound_effect(sfx_assets.fireball_launch.clone()));
         }
-        ActionType::FrostAttack { direction } => {
+        AbilityKind::Frost => {
             let frostball_bundle = create_frostball_bundle(spawn_position, direction, asset_server);
             commands.spawn(frostball_bundle);
         }
-        ActionType::LightningAttack { .. } => {
-            for entity in player_query {
-                commands.entity(entity).insert(LightningState {
-                    timer: Timer::new(Duration::from_millis(1500), TimerMode::Once),
-                });
-                return;
-            }
+        AbilityKind::Lightning => {
+            commands.entity(caster).insert(LightningState {
+                timer: Timer::new(Duration::from_millis(1500), TimerMode::Once),
+            });
+            commands.spawn(sound_effect(sfx_assets.lightning_zap.clone()));
+        }
+        AbilityKind::Charge => {
+            commands.spawn(create_charge_bundle(
+                spawn_position,
+                direction,
+                asset_server,
+            ));
+            commands.spawn(sound_effect(sfx_assets.fireball_launch.clone()));
         }
     }
 }
 
 fn process_ability_actions(
     mut commands: Commands,
-    mut fireball_cooldown: ResMut<FireballCooldown>,
-    mut frost_cooldown: ResMut<FrostCooldown>,
-    mut lightning_cooldown: ResMut<LightningCooldown>,
-    mut controllers: Query<(&Transform, &mut CharacterController)>,
+    // Actions are timestamped in real time so slow motion doesn't stretch the buffering window.
+    time: Res<Time<Real>>,
+    cooldowns: AbilityCooldowns,
+    loadout: Res<Loadout>,
+    mut controllers: Query<(
+        Entity,
+        &Transform,
+        &mut CharacterController,
+        &mut CasterCooldowns,
+    )>,
     asset_server: Res<AssetServer>,
-    player_query: Query<Entity, With<Player>>,
+    sfx_assets: Res<SfxAssets>,
+    mut fireball_pool: ResMut<Pool<FireballBundle>>,
+    mut cast_events: EventWriter<AbilityCast>,
 ) {
-    for (transform, mut controller) in &mut controllers {
-        if let Some(action) = controller.pop_action() {
-            match action {
-                ActionType::FireballAttack { direction } => {
-                    spawn_ability(
-                        action,
-                        &mut commands,
-                        transform.translation,
-                        direction,
-                        &asset_server,
-                        player_query,
-                    );
-                    fireball_cooldown.timer.reset();
-                }
-                ActionType::FrostAttack { direction } => {
-                    spawn_ability(
-                        action,
-                        &mut commands,
-                        transform.translation,
-                        direction,
-                        &asset_server,
-                        player_query,
-                    );
-                    frost_cooldown.timer.reset();
-                }
-                ActionType::LightningAttack { direction } => {
-                    spawn_ability(
-                        action,
-                        &mut commands,
-                        transform.translation,
-                        direction,
-                        &asset_server,
-                        player_query,
-                    );
-                    lightning_cooldown.timer.reset();
+    for (caster, transform, mut controller, mut caster_cooldowns) in &mut controllers {
+        for action in controller.take_actions(time.elapsed()) {
+            let (ability, direction) = match action {
+                ActionType::UseSlot { slot, direction } => {
+                    let Some(ability) = loadout.slots.get(slot).copied().flatten() else {
+                        continue;
+                    };
+                    (ability, direction)
                 }
-            }
+                ActionType::Cast { ability, direction } => (ability, direction),
+            };
+            spawn_ability(
+                ability,
+                caster,
+                &mut commands,
+                transform.translation,
+                direction,
+                &asset_server,
+                &sfx_assets,
+                &mut fireball_pool,
+            );
+            caster_cooldowns.start(ability, cooldowns.duration(ability));
+            cast_events.write(AbilityCast { ability });
         }
     }
 }
@@ -245,11 +437,17 @@ fn process_ability_actions(
 fn update_abilities(
     mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(Entity, &mut Lifetime), With<Ability>>,
+    mut query: Query<(Entity, &mut Lifetime, Has<Fireball>), With<Ability>>,
+    mut fireball_pool: ResMut<Pool<FireballBundle>>,
 ) {
-    for (entity, mut lifetime) in &mut query {
+    for (entity, mut lifetime, is_fireball) in &mut query {
         lifetime.lifetime.tick(time.delta());
-        if lifetime.lifetime.finished() {
+        if !lifetime.lifetime.finished() {
+            continue;
+        }
+        if is_fireball {
+            fireball_pool.release(&mut commands, entity);
+        } else {
             commands.entity(entity).despawn();
         }
     }
@@ -294,6 +492,43 @@ impl LightningCooldown {
     }
 }
 
+#[derive(Resource, Default)]
+pub struct ChargeCooldown {
+    pub timer: Timer,
+}
+
+impl ChargeCooldown {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Every ability's cooldown length, looked up by [`AbilityKind`].
+///
+/// The cooldown resources only hold how long each cooldown lasts, with
+/// upgrades and difficulty applied. The cooldowns themselves run per caster
+/// in [`CasterCooldowns`].
+#[derive(SystemParam)]
+pub struct AbilityCooldowns<'w> {
+    fireball: Res<'w, FireballCooldown>,
+    frost: Res<'w, FrostCooldown>,
+    lightning: Res<'w, LightningCooldown>,
+    charge: Res<'w, ChargeCooldown>,
+}
+
+impl AbilityCooldowns<'_> {
+    pub fn duration(&self, ability: AbilityKind) -> Duration {
+        match ability {
+            AbilityKind::Fireball => self.fireball.timer.duration(),
+            AbilityKind::Frost => self.frost.timer.duration(),
+            AbilityKind::Lightning => self.lightning.timer.duration(),
+            AbilityKind::Charge => self.charge.timer.duration(),
+        }
+    }
+}
+
 #[derive(Bundle, Default)]
 pub struct ExplosionBundle {
     pub animation: ExplosionAnimation,
@@ -328,6 +563,13 @@ impl ExplosionBundle {
     }
 }
 
+impl Poolable for ExplosionBundle {
+    fn reset(entity: &mut EntityCommands) {
+        // Bloom, spectator framing and the music all watch for `Added<ExplosionAnimation>`.
+        entity.try_remove::<ExplosionAnimation>();
+    }
+}
+
 #[derive(Resource, Asset, Clone, Reflect)]
 pub struct ExplosionAssets {
     pub image_handle: Handle<Image>,
@@ -355,6 +597,38 @@ impl FromWorld for ExplosionAssets {
     }
 }
 
+/// Sound effects for abilities and the castle coming apart.
+#[derive(Resource, Asset, Clone, Reflect)]
+pub struct SfxAssets {
+    #[dependency]
+    pub fireball_launch: Handle<AudioSource>,
+    #[dependency]
+    pub explosion: Handle<AudioSource>,
+    #[dependency]
+    pub frost_shatter: Handle<AudioSource>,
+    #[dependency]
+    pub lightning_zap: Handle<AudioSource>,
+    #[dependency]
+    pub block_collapse: Handle<AudioSource>,
+    #[dependency]
+    pub section_destroyed: Handle<AudioSource>,
+}
+
+impl FromWorld for SfxAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        // Placeholders until we have dedicated sounds for each of these.
+        Self {
+            fireball_launch: assets.load("audio/sound_effects/button_click.ogg"),
+            explosion: assets.load("audio/sound_effects/step4.ogg"),
+            frost_shatter: assets.load("audio/sound_effects/button_hover.ogg"),
+            lightning_zap: assets.load("audio/sound_effects/button_click.ogg"),
+            block_collapse: assets.load("audio/sound_effects/step2.ogg"),
+            section_destroyed: assets.load("audio/sound_effects/step1.ogg"),
+        }
+    }
+}
+
 #[derive(Resource, Asset, Clone, Reflect)]
 pub struct FrostAssets {
     pub image_handle: Handle<Image>,
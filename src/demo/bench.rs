@@ -0,0 +1,172 @@
+//! `--bench-mode`: times the shockwave and frostbite systems on a big castle.
+//!
+//! Spawns a castle of 1000 blocks in the headless app, sets off fireballs and
+//! freezes blocks all over it for a while, then prints how long
+//! `apply_explosion_shockwave` and `apply_frostbite` took in the frames they
+//! ran. Run it before and after changing how those systems find blocks:
+//!
+//! ```sh
+//! cargo run --features gameplay_spans -- --bench-mode
+//! ```
+//!
+//! The times come from the spans those systems open, which are compiled out of
+//! release builds, so compare dev builds with each other.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    log::tracing_subscriber::{Layer, Registry, layer::Context, prelude::*, registry::LookupSpan},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use rand::Rng;
+use tracing::{Subscriber, span};
+
+use crate::demo::{
+    blueprint::CastleBlueprint,
+    castle::CastleBlock,
+    collision::FrostEffect,
+    headless::{
+        CELL, FRAMES_PER_SEC, SimulationLog, block, detonate_fireball, headless_app, spawn_castle,
+        step_secs,
+    },
+    rng::GameRng,
+};
+
+/// The castle is this many blocks wide and tall, 1000 in all.
+const CASTLE_WIDTH: i32 = 40;
+const CASTLE_HEIGHT: i32 = 25;
+const BENCH_SECS: u32 = 20;
+/// Frames between each fireball going off somewhere on the castle.
+const FIREBALL_INTERVAL_FRAMES: u32 = 16;
+/// Frames between each block that's frozen.
+const FROST_INTERVAL_FRAMES: u32 = 32;
+/// So every run sets off the same fireballs in the same places.
+const BENCH_SEED: u64 = 0;
+/// The spans timed, opened by `apply_explosion_shockwave` and `apply_frostbite`.
+const TIMED_SPANS: [&str; 2] = ["shockwave", "frostbite"];
+
+/// Run the benchmark and print the results, instead of starting the game.
+pub fn run() -> AppExit {
+    let span_times = SpanTimes::default();
+    // Systems run on other threads, so this has to be the global subscriber.
+    let subscriber = Registry::default().with(SpanTimer(span_times.clone()));
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("nothing else sets a subscriber in bench mode");
+
+    let mut app = headless_app();
+    spawn_castle(&mut app, grid_castle());
+    // Let the castle settle before anything hits it.
+    step_secs(&mut app, 1);
+    span_times.take();
+
+    let mut rng = GameRng::from_seed(BENCH_SEED);
+    let mut samples = HashMap::<&str, Vec<Duration>>::default();
+    for frame in 0..BENCH_SECS * FRAMES_PER_SEC {
+        if frame % FIREBALL_INTERVAL_FRAMES == 0 {
+            let position = Vec2::new(
+                rng.gen_range(0.0..CASTLE_WIDTH as f32 * CELL),
+                rng.gen_range(0.0..CASTLE_HEIGHT as f32 * CELL),
+            );
+            detonate_fireball(&mut app, position);
+        }
+        if frame % FROST_INTERVAL_FRAMES == 0 {
+            freeze_random_block(&mut app, &mut rng);
+        }
+        app.update();
+        for (name, time) in span_times.take() {
+            samples.entry(name).or_default().push(time);
+        }
+    }
+
+    let knocked_loose = app.world().resource::<SimulationLog>().destroyed.len();
+    report(&samples, knocked_loose);
+    AppExit::Success
+}
+
+/// A solid wall of 1x1 blocks.
+fn grid_castle() -> CastleBlueprint {
+    let mut blocks = Vec::new();
+    for column in 0..CASTLE_WIDTH {
+        for row in 0..CASTLE_HEIGHT {
+            blocks.push(block(column, row, "wall"));
+        }
+    }
+    CastleBlueprint { blocks }
+}
+
+fn freeze_random_block(app: &mut App, rng: &mut GameRng) {
+    let blocks: Vec<Entity> = app
+        .world_mut()
+        .query_filtered::<Entity, (With<CastleBlock>, Without<FrostEffect>)>()
+        .iter(app.world())
+        .collect();
+    if blocks.is_empty() {
+        return;
+    }
+    let frozen = blocks[rng.gen_range(0..blocks.len())];
+    app.world_mut()
+        .entity_mut(frozen)
+        .insert(FrostEffect { magnitude: 1.0 });
+}
+
+fn report(samples: &HashMap<&str, Vec<Duration>>, knocked_loose: usize) {
+    println!(
+        "{} castle blocks, {} frames, {knocked_loose} blocks knocked loose",
+        CASTLE_WIDTH * CASTLE_HEIGHT,
+        BENCH_SECS * FRAMES_PER_SEC
+    );
+    for name in TIMED_SPANS {
+        let mut times = samples.get(name).cloned().unwrap_or_default();
+        if times.is_empty() {
+            println!("{name}: never ran, is this a dev build with `gameplay_spans`?");
+            continue;
+        }
+        times.sort();
+        let total: Duration = times.iter().sum();
+        println!(
+            "{name}: {} frames, mean {:.3} ms, median {:.3} ms, max {:.3} ms",
+            times.len(),
+            total.as_secs_f64() * 1000.0 / times.len() as f64,
+            times[times.len() / 2].as_secs_f64() * 1000.0,
+            times[times.len() - 1].as_secs_f64() * 1000.0,
+        );
+    }
+}
+
+/// Time spent in each of the [`TIMED_SPANS`] since they were last taken.
+#[derive(Clone, Default)]
+struct SpanTimes(Arc<Mutex<HashMap<&'static str, Duration>>>);
+
+impl SpanTimes {
+    fn take(&self) -> HashMap<&'static str, Duration> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// A tracing layer that adds up how long each of the [`TIMED_SPANS`] is entered for.
+struct SpanTimer(SpanTimes);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanTimer {
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if TIMED_SPANS.contains(&span.name()) {
+            span.extensions_mut().insert(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let Some(entered) = span.extensions_mut().remove::<Instant>() else {
+            return;
+        };
+        *self.0.0.lock().unwrap().entry(span.name()).or_default() += entered.elapsed();
+    }
+}
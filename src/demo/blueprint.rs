@@ -0,0 +1,216 @@
+//! Castle blueprints: a castle's block layout saved as RON.
+//!
+//! A blueprint is captured from the castle blocks in the world and can be
+//! spawned again anywhere with a [`SpawnBlueprint`] event, so castles can be
+//! generated at runtime or built by players instead of coming from LDtk.
+//! Spawned blocks get their mass and mortar joints just like the ones in the level.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    demo::{
+        castle::{BlockSize, CastleBlock, CastleSection},
+        collision::CollisionBundle,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<BlockMaterial>();
+    app.add_event::<SpawnBlueprint>();
+    app.add_systems(Update, spawn_blueprints.run_if(in_state(Screen::Gameplay)));
+}
+
+/// Size of a castle grid cell in pixels.
+const CELL_SIZE: f32 = 16.0;
+/// Where blueprints are saved to and loaded from in native builds.
+#[cfg(not(target_family = "wasm"))]
+const BLUEPRINT_DIR: &str = "assets/blueprints";
+
+/// What a block is made of, which decides how it looks and whether it burns.
+#[derive(
+    Component, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[reflect(Component)]
+pub enum BlockMaterial {
+    #[default]
+    Stone,
+    /// Catches fire and burns away, see [`crate::demo::elements`].
+    Wood,
+}
+
+impl BlockMaterial {
+    pub fn image_path(self) -> &'static str {
+        match self {
+            BlockMaterial::Stone => "images/stone.png",
+            // Until there's a proper wood texture.
+            BlockMaterial::Wood => "images/stone3.png",
+        }
+    }
+}
+
+impl From<&EntityInstance> for BlockMaterial {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let material = entity_instance
+            .field_instances
+            .iter()
+            .find(|f| f.identifier == "Material")
+            .and_then(|f| match &f.value {
+                FieldValue::Enum(Some(name)) | FieldValue::String(Some(name)) => Some(name),
+                _ => None,
+            });
+        match material.map(String::as_str) {
+            Some("Wood") => BlockMaterial::Wood,
+            _ => BlockMaterial::Stone,
+        }
+    }
+}
+
+/// One block in a blueprint, positioned relative to the castle's bottom left corner.
+///
+/// Vectors are stored as plain arrays to keep the RON readable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlueprintBlock {
+    /// Offset from the castle's bottom left corner to the block's center.
+    pub offset: [f32; 2],
+    /// The block's top left grid cell, relative to the lowest cell used by any block.
+    pub cell: [i32; 2],
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub material: BlockMaterial,
+    #[serde(default)]
+    pub section: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CastleBlueprint {
+    pub blocks: Vec<BlueprintBlock>,
+}
+
+impl CastleBlueprint {
+    /// Capture a blueprint from blocks given as
+    /// `(position, size, top left cell, section, material)`.
+    ///
+    /// Rotation is ignored, so capture castles while they're still standing.
+    pub fn capture<'a>(
+        blocks: impl IntoIterator<
+            Item = (
+                Vec2,
+                &'a BlockSize,
+                &'a GridCoords,
+                &'a CastleSection,
+                &'a BlockMaterial,
+            ),
+        >,
+    ) -> Self {
+        let blocks: Vec<_> = blocks.into_iter().collect();
+        let min_corner = blocks
+            .iter()
+            .map(|(position, size, ..)| position - size.0 / 2.0)
+            .reduce(Vec2::min)
+            .unwrap_or_default();
+        let min_cell = blocks
+            .iter()
+            .map(|(_, _, cell, ..)| IVec2::new(cell.x, cell.y))
+            .reduce(IVec2::min)
+            .unwrap_or_default();
+        let blocks = blocks
+            .into_iter()
+            .map(|(position, size, cell, section, material)| BlueprintBlock {
+                offset: (position - min_corner).into(),
+                cell: (IVec2::new(cell.x, cell.y) - min_cell).into(),
+                size: size.0.into(),
+                material: *material,
+                section: section.0.clone(),
+            })
+            .collect();
+        Self { blocks }
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, String> {
+        ron::from_str(contents).map_err(|error| error.to_string())
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save(&self, name: &str) -> Result<(), String> {
+        let contents = self.to_ron()?;
+        std::fs::create_dir_all(BLUEPRINT_DIR)
+            .and_then(|()| std::fs::write(blueprint_path(name), contents))
+            .map_err(|error| error.to_string())
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load(name: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(blueprint_path(name)).map_err(|error| error.to_string())?;
+        Self::from_ron(&contents)
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub fn save(&self, _name: &str) -> Result<(), String> {
+        Err("Blueprints can't be saved on the web".into())
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub fn load(_name: &str) -> Result<Self, String> {
+        Err("Blueprints can't be loaded on the web".into())
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn blueprint_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(BLUEPRINT_DIR).join(format!("{name}.ron"))
+}
+
+/// Spawn a castle from `blueprint` with its bottom left corner at `position`.
+#[derive(Event, Debug, Clone)]
+pub struct SpawnBlueprint {
+    pub blueprint: CastleBlueprint,
+    pub position: Vec2,
+}
+
+fn spawn_blueprints(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut spawn_events: EventReader<SpawnBlueprint>,
+) {
+    for SpawnBlueprint {
+        blueprint,
+        position,
+    } in spawn_events.read()
+    {
+        // Keep the blueprint's cells lined up with the grid cell it's spawned in.
+        let origin_cell = (*position / CELL_SIZE).floor().as_ivec2();
+        for block in &blueprint.blocks {
+            let cell = origin_cell + IVec2::from(block.cell);
+            let size = Vec2::from(block.size);
+            commands.spawn((
+                Name::new("Blueprint Castle Block"),
+                CastleBlock::default(),
+                Sprite::from_image(asset_server.load(block.material.image_path())),
+                CollisionBundle {
+                    collider: Collider::rectangle(size.x, size.y),
+                    ..default()
+                },
+                GridCoords::new(cell.x, cell.y),
+                CastleSection(block.section.clone()),
+                block.material,
+                BlockSize(size),
+                Transform::from_translation((*position + Vec2::from(block.offset)).extend(0.0)),
+                StateScoped(Screen::Gameplay),
+            ));
+        }
+        info!(
+            "Spawned a {} block castle blueprint at {position}",
+            blueprint.blocks.len()
+        );
+    }
+}
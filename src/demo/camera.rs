@@ -1,84 +1,434 @@
-use super::player::Player;
+use super::{
+    castle::CastleBlock,
+    input::PlatformerContext,
+    level::{EnteredNeighborLevel, LevelState},
+    player::{Player, PlayerIndex},
+};
+use crate::{screens::Screen, settings::Settings};
+use avian2d::prelude::*;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_ecs_ldtk::{LdtkProjectHandle, prelude::*};
+use bevy_enhanced_input::prelude::*;
 
-const ZOOM_FACTOR: f32 = 1.0;
+/// Limits for the zoom the player picks. Bigger values show more of the level.
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 1.5;
+/// How much one notch of the mouse wheel zooms.
+const WHEEL_ZOOM_STEP: f32 = 0.1;
+/// How fast fully pressed gamepad triggers zoom, per second.
+const TRIGGER_ZOOM_SPEED: f32 = 1.0;
+/// Castle blocks moving faster than this count as collapsing.
+const COLLAPSE_SPEED: f32 = 80.0;
+/// Extra zoom-out per collapsing block, up to `MAX_COLLAPSE_ZOOM`.
+const COLLAPSE_ZOOM_PER_BLOCK: f32 = 0.01;
+const MAX_COLLAPSE_ZOOM: f32 = 0.5;
+/// How quickly the actual zoom catches up with the target, per second.
+const ZOOM_SMOOTHING: f32 = 4.0;
+/// How long the camera glides for after walking into a neighbouring level.
+const LEVEL_TRANSITION_SECS: f32 = 0.8;
+/// How quickly the camera catches up with the new level while gliding, per second.
+const LEVEL_TRANSITION_SMOOTHING: f32 = 6.0;
+/// Trauma lost per second.
+const TRAUMA_DECAY_PER_SEC: f32 = 1.5;
+/// How far the camera moves at full trauma, in world units.
+const MAX_SHAKE_OFFSET: f32 = 8.0;
+/// How fast the shake wobbles, in radians per second.
+const SHAKE_FREQUENCY: f32 = 40.0;
+/// How long the intro lingers on the castle before panning over to the player.
+const INTRO_HOLD_SECS: f32 = 0.75;
+const INTRO_PAN_SECS: f32 = 2.0;
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Update, snap_camera_to_current_level);
+    app.register_type::<CameraZoom>();
+    app.init_resource::<CameraZoom>();
+    app.init_resource::<ScreenShake>();
+    app.init_resource::<CameraMode>();
+    app.add_systems(OnEnter(Screen::Gameplay), queue_intro_pan);
+    app.add_systems(
+        Update,
+        (
+            zoom_with_input,
+            zoom_out_on_collapse,
+            smooth_zoom,
+            (start_intro_pan, advance_intro_pan).run_if(in_state(LevelState::Ready)),
+            snap_camera_to_current_level.run_if(in_state(LevelState::Ready)),
+            shake_cameras.run_if(in_state(LevelState::Ready)),
+        )
+            .chain(),
+    );
 }
 
+/// How far the camera is zoomed out, as a multiple of the level-fitting view.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct CameraZoom {
+    /// The zoom the player chose.
+    pub base: f32,
+    /// Temporary extra zoom-out, e.g. while the castle is collapsing.
+    pub dynamic: f32,
+    /// The zoom actually in use, easing towards `base + dynamic`.
+    current: f32,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            base: 1.0,
+            dynamic: 0.0,
+            current: 1.0,
+        }
+    }
+}
+
+impl CameraZoom {
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+}
+
+fn zoom_with_input(
+    time: Res<Time>,
+    mut zoom: ResMut<CameraZoom>,
+    mut wheel_events: EventReader<MouseWheel>,
+    gamepads: Query<&Gamepad>,
+) {
+    let mut delta = 0.0;
+    for event in wheel_events.read() {
+        let notches = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        };
+        // Scrolling up zooms in.
+        delta -= notches * WHEEL_ZOOM_STEP;
+    }
+    for gamepad in &gamepads {
+        let zoom_in = gamepad.get(GamepadButton::LeftTrigger2).unwrap_or(0.0);
+        let zoom_out = gamepad.get(GamepadButton::RightTrigger2).unwrap_or(0.0);
+        delta += (zoom_out - zoom_in) * TRIGGER_ZOOM_SPEED * time.delta_secs();
+    }
+
+    if delta != 0.0 {
+        zoom.base = (zoom.base + delta).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+/// Pull the camera back while lots of castle blocks are falling so the destruction stays in view.
+fn zoom_out_on_collapse(
+    mut zoom: ResMut<CameraZoom>,
+    blocks: Query<&LinearVelocity, With<CastleBlock>>,
+) {
+    let collapsing = blocks
+        .iter()
+        .filter(|velocity| velocity.length() > COLLAPSE_SPEED)
+        .count();
+    let dynamic = (collapsing as f32 * COLLAPSE_ZOOM_PER_BLOCK).min(MAX_COLLAPSE_ZOOM);
+    if zoom.dynamic != dynamic {
+        zoom.dynamic = dynamic;
+    }
+}
+
+fn smooth_zoom(time: Res<Time>, mut zoom: ResMut<CameraZoom>) {
+    let target = zoom.base + zoom.dynamic;
+    if zoom.current == target {
+        return;
+    }
+    let t = (ZOOM_SMOOTHING * time.delta_secs()).min(1.0);
+    zoom.current = zoom.current.lerp(target, t);
+    if (zoom.current - target).abs() < 0.001 {
+        zoom.current = target;
+    }
+}
+
+/// What the gameplay cameras are looking at. Whatever it is, the view is
+/// still kept inside the level by [`snap_camera_to_current_level`].
+#[derive(Resource, Debug, Default)]
+pub enum CameraMode {
+    /// Following the player around.
+    #[default]
+    Following,
+    /// About to show off the castle, once the level is ready.
+    IntroQueued,
+    /// Panning from the castle over to where the player starts, with the
+    /// player's input locked until it's done or skipped.
+    IntroPan { castle: Vec2, timer: Timer },
+}
+
+impl CameraMode {
+    /// Where the camera should center on, given where the player it follows is.
+    fn focus(&self, player: Vec2) -> Vec2 {
+        match self {
+            CameraMode::IntroPan { castle, timer } => {
+                let pan_secs = (timer.elapsed_secs() - INTRO_HOLD_SECS).max(0.0);
+                let t = (pan_secs / INTRO_PAN_SECS).min(1.0);
+                // Ease in and out, so the pan doesn't start or stop with a jolt.
+                castle.lerp(player, t * t * (3.0 - 2.0 * t))
+            }
+            CameraMode::Following | CameraMode::IntroQueued => player,
+        }
+    }
+}
+
+/// Whether the intro pan is over and the players have control.
+pub fn intro_finished(mode: Res<CameraMode>) -> bool {
+    matches!(*mode, CameraMode::Following)
+}
+
+fn queue_intro_pan(mut mode: ResMut<CameraMode>) {
+    *mode = CameraMode::IntroQueued;
+}
+
+/// Start the intro pan from the middle of the castle, taking the players' input away.
+fn start_intro_pan(
+    mut commands: Commands,
+    mut mode: ResMut<CameraMode>,
+    block_query: Query<&GlobalTransform, With<CastleBlock>>,
+    player_query: Query<Entity, (With<Player>, With<Actions<PlatformerContext>>)>,
+) {
+    if !matches!(*mode, CameraMode::IntroQueued) || player_query.is_empty() {
+        return;
+    }
+    let (sum, count) = block_query
+        .iter()
+        .fold((Vec2::ZERO, 0), |(sum, count), transform| {
+            (sum + transform.translation().truncate(), count + 1)
+        });
+    if count == 0 {
+        // Nothing to show off.
+        *mode = CameraMode::Following;
+        return;
+    }
+    *mode = CameraMode::IntroPan {
+        castle: sum / count as f32,
+        timer: Timer::from_seconds(INTRO_HOLD_SECS + INTRO_PAN_SECS, TimerMode::Once),
+    };
+    for player in &player_query {
+        commands
+            .entity(player)
+            .remove::<Actions<PlatformerContext>>();
+    }
+}
+
+/// Finish the intro pan when it reaches the player or any button is pressed,
+/// and hand the players their input back.
+fn advance_intro_pan(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut mode: ResMut<CameraMode>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    player_query: Query<Entity, (With<Player>, Without<Actions<PlatformerContext>>)>,
+) {
+    let CameraMode::IntroPan { timer, .. } = &mut *mode else {
+        return;
+    };
+    timer.tick(time.delta());
+    let skipped = keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.get_just_pressed().next().is_some());
+    if !timer.finished() && !skipped {
+        return;
+    }
+    *mode = CameraMode::Following;
+    // Putting the actions back binds them again.
+    for player in &player_query {
+        commands
+            .entity(player)
+            .insert(Actions::<PlatformerContext>::default());
+    }
+}
+
+/// How shaken up the camera is, from 0 to 1. The shake grows with the square
+/// of the trauma, so small knocks barely register and big ones stack up.
+///
+/// Trauma is added by hits, see [`crate::demo::impact_feel`].
+#[derive(Resource, Debug, Default)]
+pub struct ScreenShake {
+    pub trauma: f32,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+/// Offset the following cameras by the shake. They're snapped back into place
+/// every frame, so the offset doesn't build up.
+#[allow(clippy::type_complexity)]
+fn shake_cameras(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+    mut camera_query: Query<
+        &mut Transform,
+        (
+            With<Camera2d>,
+            Without<UiOnlyCamera>,
+            Without<FreeCamera>,
+            Without<SpectatorCamera>,
+        ),
+    >,
+) {
+    if shake.trauma <= 0.0 {
+        return;
+    }
+    shake.trauma = (shake.trauma - TRAUMA_DECAY_PER_SEC * time.delta_secs()).max(0.0);
+    if !settings.screen_shake {
+        shake.trauma = 0.0;
+        return;
+    }
+    // Two sines at unrelated frequencies wobble without an obvious pattern.
+    let t = time.elapsed_secs() * SHAKE_FREQUENCY;
+    let direction = Vec2::new((t * 1.1).sin(), (t * 0.7 + 1.3).sin());
+    let offset = direction * MAX_SHAKE_OFFSET * shake.trauma * shake.trauma;
+    for mut transform in &mut camera_query {
+        transform.translation += offset.extend(0.0);
+    }
+}
+
+/// Clamp `value` to `min..=max`, or center it if the range is empty because the view is bigger than the level.
+fn clamp_or_center(value: f32, min: f32, max: f32) -> f32 {
+    if min <= max {
+        value.clamp(min, max)
+    } else {
+        (min + max) / 2.0
+    }
+}
+
+/// Makes a camera follow the player with this [`PlayerIndex`] instead of the first player.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerCamera(pub usize);
+
+/// Marks cameras that only draw UI and shouldn't follow anyone.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct UiOnlyCamera;
+
+/// Detaches a camera from the player and level so it can be flown around freely.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct FreeCamera;
+
+/// Hands a camera over to the spectator director, which frames the most destructive action.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct SpectatorCamera;
+
 #[allow(clippy::type_complexity)]
 pub fn snap_camera_to_current_level(
     mut camera_query: Query<
-        (&mut bevy::render::camera::Projection, &mut Transform),
-        Without<Player>,
+        (
+            &Camera,
+            &mut bevy::render::camera::Projection,
+            &mut Transform,
+            Option<&PlayerCamera>,
+        ),
+        (
+            Without<Player>,
+            Without<UiOnlyCamera>,
+            Without<FreeCamera>,
+            Without<SpectatorCamera>,
+        ),
     >,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(&Transform, &PlayerIndex), With<Player>>,
     level_query: Query<(&Transform, &LevelIid), (Without<Projection>, Without<Player>)>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     level_selection: Res<LevelSelection>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
     primary_window_query: Query<&Window, With<PrimaryWindow>>,
+    zoom: Res<CameraZoom>,
+    mode: Res<CameraMode>,
+    time: Res<Time>,
+    mut transition: Local<Timer>,
+    mut entered_events: EventReader<EnteredNeighborLevel>,
 ) -> Result {
-    // Bail early if the player isn't spawned.
-    let Ok(Transform {
-        translation: player_translation,
-        ..
-    }) = player_query.single()
-    else {
-        return Ok(());
-    };
-
     let primary_window = primary_window_query.single()?;
-    let aspect_ratio = primary_window.resolution.width() / primary_window.resolution.height();
-    let player_translation = *player_translation;
+    transition.tick(time.delta());
+    if entered_events.read().count() > 0 {
+        *transition = Timer::from_seconds(LEVEL_TRANSITION_SECS, TimerMode::Once);
+    }
 
-    let (mut projection, mut camera_transform) = camera_query.single_mut()?;
-    let Projection::Orthographic(ref mut orthographic_projection) = *projection else {
-        return Err(BevyError::from("non-orthographic projection found"));
-    };
+    for (camera, mut projection, mut camera_transform, player_camera) in &mut camera_query {
+        let followed = player_camera.map_or(0, |player_camera| player_camera.0);
+        // Skip cameras whose player isn't spawned.
+        let Some((
+            Transform {
+                translation: player_translation,
+                ..
+            },
+            _,
+        )) = player_query.iter().find(|(_, index)| index.0 == followed)
+        else {
+            continue;
+        };
+        // Usually the player, unless the intro is showing off the castle first.
+        let focus = mode.focus(player_translation.truncate());
 
-    for (level_transform, level_iid) in &level_query {
-        let ldtk_project = ldtk_project_assets
-            .get(ldtk_projects.single()?)
-            .expect("Project should be loaded if level has spawned");
+        // Split-screen cameras only cover part of the window.
+        let viewport_size = camera
+            .logical_viewport_size()
+            .unwrap_or(primary_window.resolution.size());
+        let aspect_ratio = viewport_size.x / viewport_size.y;
 
-        let level = ldtk_project
-            .get_raw_level_by_iid(&level_iid.to_string())
-            .expect("Spawned level should exist in LDtk project");
+        let Projection::Orthographic(ref mut orthographic_projection) = *projection else {
+            return Err(BevyError::from("non-orthographic projection found"));
+        };
 
-        if level_selection.is_match(&LevelIndices::default(), level) {
+        for (level_transform, level_iid) in &level_query {
+            let ldtk_project = ldtk_project_assets
+                .get(ldtk_projects.single()?)
+                .expect("Project should be loaded if level has spawned");
+
+            let level = ldtk_project
+                .get_raw_level_by_iid(&level_iid.to_string())
+                .expect("Spawned level should exist in LDtk project");
+
+            if !level_selection.is_match(&LevelIndices::default(), level) {
+                continue;
+            }
+            // Levels are placed at their LDtk world position, so everything here is in world space.
             let level_ratio = level.px_wid as f32 / level.px_hei as f32;
             orthographic_projection.viewport_origin = Vec2::ZERO;
-            if level_ratio > aspect_ratio {
+            let target = if level_ratio > aspect_ratio {
                 // level is wider than the screen
-                let height = (level.px_hei as f32 / 9.).round() * 9. * ZOOM_FACTOR;
+                let height = (level.px_hei as f32 / 9.).round() * 9. * zoom.current();
                 let width = height * aspect_ratio;
                 orthographic_projection.scaling_mode =
                     bevy::render::camera::ScalingMode::Fixed { width, height };
-                camera_transform.translation.x = (player_translation.x - width / 2.).clamp(
-                    level_transform.translation.x,
-                    level_transform.translation.x + level.px_wid as f32 - width,
-                );
-                camera_transform.translation.y = level_transform.translation.y;
+                Vec2::new(
+                    clamp_or_center(
+                        focus.x - width / 2.,
+                        level_transform.translation.x,
+                        level_transform.translation.x + level.px_wid as f32 - width,
+                    ),
+                    level_transform.translation.y,
+                )
             } else {
                 // level is taller than the screen
-                let width = (level.px_wid as f32 / 16.).round() * 16. * ZOOM_FACTOR;
+                let width = (level.px_wid as f32 / 16.).round() * 16. * zoom.current();
                 let height = width / aspect_ratio;
                 orthographic_projection.scaling_mode =
                     bevy::render::camera::ScalingMode::Fixed { width, height };
-                camera_transform.translation.y = (player_translation.y - height / 2.).clamp(
-                    level_transform.translation.y,
-                    level_transform.translation.y + level.px_hei as f32 - height,
-                );
-                camera_transform.translation.x = level_transform.translation.x;
-            }
+                Vec2::new(
+                    level_transform.translation.x,
+                    clamp_or_center(
+                        focus.y - height / 2.,
+                        level_transform.translation.y,
+                        level_transform.translation.y + level.px_hei as f32 - height,
+                    ),
+                )
+            };
 
-            // Adjust camera translation to follow the player
-            camera_transform.translation.x += level_transform.translation.x;
-            camera_transform.translation.y += level_transform.translation.y;
+            // Glide over to a neighbouring level rather than cutting to it
+            let translation = if transition.finished() {
+                target
+            } else {
+                let t = (LEVEL_TRANSITION_SMOOTHING * time.delta_secs()).min(1.0);
+                camera_transform.translation.truncate().lerp(target, t)
+            };
+            camera_transform.translation = translation.extend(camera_transform.translation.z);
         }
     }
     Ok(())
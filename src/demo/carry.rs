@@ -0,0 +1,266 @@
+//! Picking up small loose objects, carrying them overhead and throwing them.
+//!
+//! Anything [`Throwable`] within reach can be grabbed, which for now is any
+//! single-cell castle block once it's been knocked loose. While carried it
+//! stops colliding and floats above the carrier's head. Throwing it sends it
+//! flying the way the player is aiming, and whatever it hits first gets a
+//! [`ShockwaveHit`], so a thrown block wears down mortar like a blast does.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::{BlockDestroyed, BlockSize, CastleBlock},
+        collision::{CombatHit, ShockwaveHit},
+        despawn_queue::DespawnQueue,
+        player::Ragdoll,
+        trajectory::Aiming,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Throwable>();
+    app.register_type::<Carrier>();
+    app.add_systems(
+        Update,
+        (
+            make_loose_blocks_throwable,
+            grab_or_throw,
+            carry_held_objects,
+            hit_with_thrown_objects,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// How close something has to be to the carrier's center to be grabbed.
+const GRAB_REACH: f32 = 24.0;
+/// Loose castle blocks no bigger than this on either side can be picked up.
+const MAX_THROWABLE_BLOCK_SIZE: f32 = 16.0;
+/// Where a carried object is held, relative to the carrier.
+const CARRY_OFFSET: Vec2 = Vec2::new(0.0, 18.0);
+/// Speed a thrown object leaves the carrier's hands at, on top of their own velocity.
+const THROW_SPEED: f32 = 160.0;
+/// Which way objects are thrown when the player isn't aiming, before facing is applied.
+const DEFAULT_THROW_DIRECTION: Vec2 = Vec2::new(1.0, 0.5);
+/// Fraction of a thrown object's momentum that goes into the mortar of what it hits.
+const THROWN_IMPULSE_FACTOR: f32 = 0.5;
+
+/// Something small enough to be picked up and thrown.
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component)]
+pub struct Throwable;
+
+/// Lets a character pick up a [`Throwable`] and throw it.
+#[derive(Component, Reflect, Clone, Default, Debug)]
+#[reflect(Component)]
+pub struct Carrier {
+    requested: bool,
+    carrying: Option<Entity>,
+}
+
+impl Carrier {
+    /// Grab the nearest object in reach, or throw the one being carried.
+    pub fn request_grab_or_throw(&mut self) {
+        self.requested = true;
+    }
+}
+
+/// An object being carried, and who by.
+#[derive(Component, Debug, Clone, Copy)]
+struct Carried {
+    by: Entity,
+}
+
+/// An object in flight after being thrown, until it hits something.
+#[derive(Component, Debug, Clone, Copy)]
+struct Thrown {
+    by: Entity,
+    /// Velocity before the last physics step, as the impact has already slowed it down.
+    velocity: Vec2,
+}
+
+fn make_loose_blocks_throwable(
+    mut commands: Commands,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    despawn_queue: Res<DespawnQueue>,
+    block_query: Query<&BlockSize, (With<CastleBlock>, Without<RigidBodyDisabled>)>,
+) {
+    for event in destroyed_events.read() {
+        // Shattered and burned blocks are destroyed on their way out.
+        if despawn_queue.contains(event.block) {
+            continue;
+        }
+        let Ok(block_size) = block_query.get(event.block) else {
+            continue;
+        };
+        if block_size.0.max_element() <= MAX_THROWABLE_BLOCK_SIZE {
+            commands.entity(event.block).try_insert(Throwable);
+        }
+    }
+}
+
+fn grab_or_throw(
+    mut commands: Commands,
+    mut carrier_query: Query<(
+        Entity,
+        &mut Carrier,
+        &GlobalTransform,
+        &LinearVelocity,
+        &Sprite,
+        Option<&Aiming>,
+        Has<Ragdoll>,
+    )>,
+    throwable_query: Query<
+        (Entity, &GlobalTransform, &RigidBody, &Visibility),
+        (
+            With<Throwable>,
+            Without<Carried>,
+            Without<RigidBodyDisabled>,
+        ),
+    >,
+    despawn_queue: Res<DespawnQueue>,
+) {
+    for (entity, mut carrier, transform, velocity, sprite, aiming, ragdoll) in &mut carrier_query {
+        let requested = std::mem::take(&mut carrier.requested);
+        if let Some(carried) = carrier.carrying {
+            // Objects are dropped by players knocked senseless.
+            if !requested && !ragdoll {
+                continue;
+            }
+            let throw_velocity = if ragdoll {
+                Vec2::ZERO
+            } else {
+                let facing = if sprite.flip_x { -1.0 } else { 1.0 };
+                let direction = aiming
+                    .map(|aiming| aiming.direction)
+                    .filter(|direction| *direction != Vec2::ZERO)
+                    .unwrap_or(DEFAULT_THROW_DIRECTION * Vec2::new(facing, 1.0))
+                    .normalize_or_zero();
+                direction * THROW_SPEED
+            };
+            carrier.carrying = None;
+            let Ok(mut carried) = commands.get_entity(carried) else {
+                continue;
+            };
+            carried.remove::<(Carried, Sensor)>().insert((
+                RigidBody::Dynamic,
+                LinearVelocity(velocity.0 + throw_velocity),
+                Thrown {
+                    by: entity,
+                    velocity: velocity.0 + throw_velocity,
+                },
+            ));
+            continue;
+        }
+        if !requested || ragdoll {
+            continue;
+        }
+
+        let position = transform.translation().truncate();
+        let nearest = throwable_query
+            .iter()
+            .filter(|(throwable, _, rigid_body, visibility)| {
+                matches!(rigid_body, RigidBody::Dynamic)
+                    && **visibility != Visibility::Hidden
+                    && !despawn_queue.contains(*throwable)
+            })
+            .map(|(throwable, throwable_transform, ..)| {
+                let distance = throwable_transform
+                    .translation()
+                    .truncate()
+                    .distance(position);
+                (throwable, distance)
+            })
+            .filter(|(_, distance)| *distance <= GRAB_REACH)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((grabbed, _)) = nearest else {
+            continue;
+        };
+        carrier.carrying = Some(grabbed);
+        // Carried objects stop colliding so they don't shove their carrier around.
+        commands.entity(grabbed).remove::<Thrown>().insert((
+            Carried { by: entity },
+            RigidBody::Kinematic,
+            Sensor,
+        ));
+    }
+}
+
+/// Hold carried objects over their carrier's head, dropping any whose carrier is gone.
+fn carry_held_objects(
+    mut commands: Commands,
+    mut carried_query: Query<(
+        Entity,
+        &Carried,
+        &mut Position,
+        &mut Rotation,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+    carrier_query: Query<(&Carrier, &Position), Without<Carried>>,
+) {
+    for (entity, carried, mut position, mut rotation, mut velocity, mut angular_velocity) in
+        &mut carried_query
+    {
+        let Ok((carrier, carrier_position)) = carrier_query.get(carried.by) else {
+            commands
+                .entity(entity)
+                .remove::<(Carried, Sensor)>()
+                .insert(RigidBody::Dynamic);
+            continue;
+        };
+        if carrier.carrying != Some(entity) {
+            continue;
+        }
+        position.0 = carrier_position.0 + CARRY_OFFSET;
+        *rotation = Rotation::IDENTITY;
+        velocity.0 = Vec2::ZERO;
+        angular_velocity.0 = 0.0;
+    }
+}
+
+/// Hit the first thing a thrown object lands on the way a shockwave would.
+fn hit_with_thrown_objects(
+    mut commands: Commands,
+    mut thrown_query: Query<(
+        Entity,
+        &mut Thrown,
+        &CollidingEntities,
+        &LinearVelocity,
+        &Mass,
+    )>,
+    block_query: Query<(), With<CastleBlock>>,
+    mut hit_events: EventWriter<CombatHit>,
+) {
+    for (entity, mut thrown, colliding_entities, velocity, mass) in &mut thrown_query {
+        let targets: Vec<Entity> = colliding_entities
+            .iter()
+            .copied()
+            .filter(|target| *target != thrown.by)
+            .collect();
+        if targets.is_empty() {
+            thrown.velocity = velocity.0;
+            continue;
+        }
+        let impulse = thrown.velocity * mass.0 * THROWN_IMPULSE_FACTOR;
+        for target in targets {
+            if block_query.contains(target) {
+                commands.entity(target).try_insert(ShockwaveHit { impulse });
+            }
+            hit_events.write(CombatHit {
+                attacker: Some(entity),
+                target,
+                ability: None,
+                damage: 0.0,
+                impulse,
+                frost: 0.0,
+            });
+        }
+        commands.entity(entity).remove::<Thrown>();
+    }
+}
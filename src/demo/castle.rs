@@ -1,31 +1,79 @@
 use avian2d::prelude::*;
-use bevy::{platform::collections::HashMap, prelude::*};
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
 use bevy_ecs_ldtk::prelude::*;
 
 use avian2d::math::Vector2 as Vec2;
 
-use crate::demo::{balistics::ExplosionAssets, collision::ShockwaveHit, level::LdtkReady};
+use crate::{
+    audio::spatial_sound_effect,
+    demo::{
+        balistics::{ExplosionAssets, SfxAssets},
+        blueprint::BlockMaterial,
+        collision::ShockwaveHit,
+        difficulty::DifficultyTuning,
+        level::LevelState,
+        level_physics::LevelPhysics,
+        tint::Tints,
+    },
+    screens::Screen,
+};
 
 use super::collision::CollisionBundle;
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_event::<BlockDestroyed>();
+    app.add_event::<BlockCleared>();
+    app.add_event::<CastleReady>();
+    app.add_event::<JointBroken>();
+    app.register_type::<MortarHealth>();
+    app.init_resource::<CastleGrid>();
     app.register_ldtk_entity::<CastleBundle>("Castle")
-        .add_systems(Update, create_mortar_joints)
+        .add_systems(Update, (index_castle_blocks, unindex_castle_blocks))
         .add_systems(
             Update,
-            update_castle_mass.run_if(resource_exists::<LdtkReady>),
+            (
+                start_mortar_joints.run_if(in_state(LevelState::Ready)),
+                build_mortar_joints.run_if(resource_exists::<MortarJointBuilder>),
+            )
+                .chain(),
         )
+        .add_systems(OnExit(Screen::Gameplay), abandon_mortar_joints)
         .add_systems(
             Update,
-            (handle_castle_impulses).run_if(resource_exists::<ExplosionAssets>),
+            update_castle_mass.run_if(in_state(LevelState::Ready)),
+        )
+        .add_systems(
+            Update,
+            (handle_castle_impulses)
+                .run_if(resource_exists::<ExplosionAssets>.and(resource_exists::<SfxAssets>)),
         );
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
+#[require(Tints)]
 pub struct CastleBlock {
     joints: Vec<Entity>,
 }
 
+/// Sent when a castle block is knocked loose from its mortar or shattered.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BlockDestroyed {
+    pub block: Entity,
+    /// Heavier blocks are bigger, so they're worth more points.
+    pub mass: f32,
+}
+
+/// Sent when a castle block is taken away without being destroyed, like the
+/// rubble cleared between endless waves. It's worth no points and isn't
+/// counted as destroyed.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BlockCleared {
+    pub block: Entity,
+}
+
 #[derive(Component, Debug, Clone, Copy)]
 pub struct BlockSize(pub Vec2);
 
@@ -45,20 +93,13 @@ impl From<&EntityInstance> for BlockSize {
     }
 }
 
-#[derive(Component, Default, Clone, Debug)]
-pub struct CastleSection();
+/// The named part of the castle a block belongs to, from the `SectionName` LDtk field.
+#[derive(Component, Default, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CastleSection(pub String);
 
-// Add this implementation
 impl From<&EntityInstance> for CastleSection {
     fn from(entity_instance: &EntityInstance) -> Self {
-        // Extract the section name from LDTK entity fields
-        for field in &entity_instance.field_instances {
-            info!(
-                "Found field: {} of type: {:?}",
-                field.identifier, field.value
-            );
-        }
-        let _section_name = entity_instance
+        let section_name = entity_instance
             .field_instances
             .iter()
             .find(|f| f.identifier == "SectionName")
@@ -67,8 +108,7 @@ impl From<&EntityInstance> for CastleSection {
                 _ => None,
             });
 
-        // CastleSection(section_name.unwrap_or_else(|| "default".to_string()))
-        CastleSection()
+        CastleSection(section_name.unwrap_or_else(|| "default".to_string()))
     }
 }
 
@@ -86,32 +126,37 @@ pub struct CastleBundle {
     pub section: CastleSection,
     #[from_entity_instance]
     pub block_size: BlockSize,
+    #[from_entity_instance]
+    pub material: BlockMaterial,
 }
 
+/// Give newly spawned blocks their mass and tiling, whether they came from
+/// LDtk, a level reload or a blueprint.
 fn update_castle_mass(
-    mut ran_update_mass: Local<bool>,
     mut commands: Commands,
-    query: Query<(Entity, &BlockSize, &Sprite), Added<CastleBlock>>,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &BlockSize, &Sprite, Option<&BlockMaterial>), Added<CastleBlock>>,
 ) {
-    if *ran_update_mass {
-        return; // Prevent running this system multiple times
-    }
-    if query.is_empty() {
-        info!("No castle blocks found to update mass.");
-        return;
-    }
-    for (entity, block_size, sprite) in query {
+    for (entity, block_size, sprite, material) in query {
         let base_mass = 100.0; // Base mass for a 16x16 block
         let area = block_size.0.x * block_size.0.y;
         let mass = base_mass * (area / (16.0 * 16.0)).sqrt();
 
         // let mass = 100.;
-        info!("Setting mass for castle entity: {:?}", entity);
+        debug!("Setting mass for castle entity: {:?}", entity);
         commands.entity(entity).insert(Mass(mass)); // Set a default mass for the castle
         let desired_tile_size = 16.; // Tile size in pixels
         let stretch_value_x = desired_tile_size / block_size.0.x;
         let stretch_value_y = desired_tile_size / block_size.0.y;
+        // LDtk gives every block the stone sprite, whatever it's made of.
+        let image = match material {
+            Some(material) if *material != BlockMaterial::Stone => {
+                asset_server.load(material.image_path())
+            }
+            _ => sprite.image.clone(),
+        };
         let updated_sprite = Sprite {
+            image,
             image_mode: SpriteImageMode::Tiled {
                 tile_x: true,
                 tile_y: true,
@@ -121,8 +166,6 @@ fn update_castle_mass(
         };
         commands.entity(entity).insert(updated_sprite);
     }
-
-    *ran_update_mass = true; // Mark that we've run this system
 }
 
 // fn visualize_castle_sections(mut query: Query<(&CastleSection, &mut Sprite), With<CastleBlock>>) {
@@ -149,6 +192,99 @@ fn update_castle_mass(
 //     }
 // }
 
+/// Which castle block covers each grid cell, as laid out in LDtk.
+///
+/// Lets systems find a block's neighbours directly instead of measuring the
+/// distance to every other block.
+#[derive(Resource, Default, Debug)]
+pub struct CastleGrid {
+    cells: HashMap<GridCoords, Entity>,
+    blocks: HashMap<Entity, Vec<GridCoords>>,
+}
+
+impl CastleGrid {
+    fn insert(&mut self, entity: Entity, top_left: &GridCoords, block_size: &BlockSize) {
+        let cells = covered_cells(top_left, block_size);
+        for cell in &cells {
+            self.cells.insert(*cell, entity);
+        }
+        self.blocks.insert(entity, cells);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        for cell in self.blocks.remove(&entity).unwrap_or_default() {
+            if self.cells.get(&cell) == Some(&entity) {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Every block in the grid.
+    pub fn blocks(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.blocks.keys().copied()
+    }
+
+    /// Whether `entity` has no block resting directly on top of any part of it.
+    pub fn is_exposed(&self, entity: Entity) -> bool {
+        self.blocks.get(&entity).into_iter().flatten().any(|cell| {
+            match self.cells.get(&GridCoords::new(cell.x, cell.y + 1)) {
+                Some(&above) => above == entity,
+                None => true,
+            }
+        })
+    }
+
+    /// Blocks touching `entity`, including diagonally.
+    pub fn neighbors(&self, entity: Entity) -> Vec<Entity> {
+        let mut neighbors = Vec::new();
+        for cell in self.blocks.get(&entity).into_iter().flatten() {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let neighbor_cell = GridCoords::new(cell.x + dx, cell.y + dy);
+                    let Some(&neighbor) = self.cells.get(&neighbor_cell) else {
+                        continue;
+                    };
+                    if neighbor != entity && !neighbors.contains(&neighbor) {
+                        neighbors.push(neighbor);
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+/// The grid cells covered by a block whose top left cell is `top_left`.
+fn covered_cells(top_left: &GridCoords, block_size: &BlockSize) -> Vec<GridCoords> {
+    let width_normalised = block_size.0.x as i32 / GRID_SIZE;
+    let depth_normalised = block_size.0.y as i32 / GRID_SIZE;
+    let mut cells = Vec::new();
+    for x in top_left.x..top_left.x + width_normalised {
+        for y in top_left.y - depth_normalised + 1..=top_left.y {
+            cells.push(GridCoords { x, y });
+        }
+    }
+    cells
+}
+
+fn index_castle_blocks(
+    mut castle_grid: ResMut<CastleGrid>,
+    query: Query<(Entity, &GridCoords, &BlockSize), Added<CastleBlock>>,
+) {
+    for (entity, coords, block_size) in &query {
+        castle_grid.insert(entity, coords, block_size);
+    }
+}
+
+fn unindex_castle_blocks(
+    mut castle_grid: ResMut<CastleGrid>,
+    mut removed: RemovedComponents<CastleBlock>,
+) {
+    for entity in removed.read() {
+        castle_grid.remove(entity);
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 struct BlockComposite {
     entity: Entity,
@@ -181,7 +317,7 @@ fn register_all_blocks_for_castle_section(
                 block_size: block_size.clone(),
                 center_point: center_point,
             };
-            info!("{:?} inserted at {:?}", bk, (x, y));
+            debug!("{:?} inserted at {:?}", bk, (x, y));
             global_grid.insert(GridCoords { x, y }, bk);
         }
     }
@@ -219,31 +355,84 @@ fn calculate_anchor(bk1: BlockComposite, bk2: BlockComposite) -> Vec2 {
     }
 }
 
-fn create_mortar_joints(
-    mut ran_mortar_joints: Local<bool>,
+/// Grid cells whose joints are created per frame, so large castles don't stall a single frame.
+const MORTAR_CELLS_PER_FRAME: usize = 128;
+
+/// Sent once every mortar joint in the castle has been created.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CastleReady;
+
+/// Mortar joints still waiting to be created, worked through a batch at a time.
+#[derive(Resource)]
+struct MortarJointBuilder {
+    global_grid: HashMap<GridCoords, BlockComposite>,
+    pending: Vec<GridCoords>,
+}
+
+/// Start mortaring each batch of newly spawned blocks together.
+///
+/// Blocks from a level (re)load or a blueprint all arrive in the same frame.
+/// A batch that arrives while another is still being built joins it, so
+/// blocks from both batches are mortared to each other too.
+fn start_mortar_joints(
     mut commands: Commands,
-    mut castle_query: Query<(Entity, &GridCoords, &CastleSection, &BlockSize), Added<CastleBlock>>,
+    mut physics_time: ResMut<Time<Physics>>,
+    builder: Option<ResMut<MortarJointBuilder>>,
+    castle_query: Query<(Entity, &GridCoords, &BlockSize), Added<CastleBlock>>,
 ) {
-    if *ran_mortar_joints {
-        return; // Prevent running this system multiple times
-    }
     if castle_query.is_empty() {
-        info!("No castle blocks found to create mortar joints.");
         return;
     }
-    info!("Creating mortar joints for castle blocks...");
+    info!(
+        "Creating mortar joints for {} castle blocks...",
+        castle_query.iter().count()
+    );
     let mut global_grid = HashMap::<GridCoords, BlockComposite>::new();
-    // First pass: collect all blocks by section
-    for (castle_entity, coords, _section, block_size) in &mut castle_query {
+    // First pass: collect every cell the new blocks cover
+    for (castle_entity, coords, block_size) in &castle_query {
         register_all_blocks_for_castle_section(&mut global_grid, coords, castle_entity, block_size);
     }
+    let pending: Vec<GridCoords> = global_grid.keys().copied().collect();
+    match builder {
+        Some(mut builder) => {
+            builder.global_grid.extend(global_grid);
+            builder.pending.extend(pending);
+        }
+        None => commands.insert_resource(MortarJointBuilder {
+            global_grid,
+            pending,
+        }),
+    }
+    // Hold the castle still until it's mortared together, otherwise it slumps while we build.
+    physics_time.pause();
+}
 
+fn build_mortar_joints(
+    mut commands: Commands,
+    mut builder: ResMut<MortarJointBuilder>,
+    mut physics_time: ResMut<Time<Physics>>,
+    mut castle_ready: EventWriter<CastleReady>,
+    block_query: Query<(), With<CastleBlock>>,
+    level_physics: Res<LevelPhysics>,
+    difficulty: DifficultyTuning,
+) {
+    let mortar_strength =
+        MORTAR_STRENGTH * level_physics.mortar_strength * difficulty.multipliers().mortar_strength;
     let directions = [
         GridCoords::new(1, 0),  // Right
         GridCoords::new(0, -1), // Down
     ];
-    // Second pass: for each section, build grid map and create joints
-    for (coordinate, block_composite) in &global_grid {
+    // Second pass: join each cell in this frame's batch to its neighbours
+    let batch_start = builder.pending.len().saturating_sub(MORTAR_CELLS_PER_FRAME);
+    let batch: Vec<GridCoords> = builder.pending.drain(batch_start..).collect();
+    #[cfg(feature = "gameplay_spans")]
+    let _span = info_span!("mortar_joints", cells = batch.len()).entered();
+    for coordinate in batch {
+        let block_composite = builder.global_grid[&coordinate];
+        // The level may have been reloaded or switched since the batch started.
+        if !block_query.contains(block_composite.entity) {
+            continue;
+        }
         // Detect neighbours
         // If neighbours, detect if same entity, if so pass
 
@@ -253,31 +442,47 @@ fn create_mortar_joints(
                 y: coordinate.y + dir.y,
             };
 
-            let candidate = global_grid.get(&potential_neighbor_coords);
+            let candidate = builder.global_grid.get(&potential_neighbor_coords);
 
             if candidate.is_none() {
                 continue;
             }
             let candidate = candidate.unwrap();
-            if candidate.entity == block_composite.entity {
+            if candidate.entity == block_composite.entity || !block_query.contains(candidate.entity)
+            {
                 continue;
             }
 
             let joint_id = commands
-                .spawn(create_joint(*block_composite, *candidate))
+                .spawn((
+                    create_joint(block_composite, *candidate),
+                    MortarHealth::new(mortar_strength),
+                ))
                 .id();
             commands.entity(block_composite.entity).add_child(joint_id);
             commands.entity(candidate.entity).add_child(joint_id);
         }
     }
-    *ran_mortar_joints = true; // Mark that we've run this system
+
+    if builder.pending.is_empty() {
+        info!("Finished creating mortar joints.");
+        commands.remove_resource::<MortarJointBuilder>();
+        physics_time.unpause();
+        castle_ready.write(CastleReady);
+    }
+}
+
+/// Stop building if the level is left before the castle is finished.
+fn abandon_mortar_joints(mut commands: Commands, mut physics_time: ResMut<Time<Physics>>) {
+    commands.remove_resource::<MortarJointBuilder>();
+    physics_time.unpause();
 }
 
 fn create_joint(bk1: BlockComposite, bk2: BlockComposite) -> FixedJoint {
     let anchor1 = calculate_anchor(bk1, bk2);
     let anchor2 = calculate_anchor(bk2, bk1);
-    info!("Anchor point 1 {:?}", anchor1);
-    info!("Anchor point 2 {:?}", anchor2);
+    debug!("Anchor point 1 {:?}", anchor1);
+    debug!("Anchor point 2 {:?}", anchor2);
     let mut joint = FixedJoint::new(bk1.entity, bk2.entity)
         .with_compliance(0.00001)
         .with_linear_velocity_damping(0.1) // Some vibration damping
@@ -290,39 +495,139 @@ fn create_joint(bk1: BlockComposite, bk2: BlockComposite) -> FixedJoint {
     joint
 }
 
+/// Total impulse a mortar joint can absorb before it breaks.
+const MORTAR_STRENGTH: f32 = 5000.0;
+
+/// How much more impulse a mortar joint can take, accumulated over every hit.
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct MortarHealth {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl MortarHealth {
+    pub fn new(strength: f32) -> Self {
+        Self {
+            current: strength,
+            max: strength,
+        }
+    }
+}
+
+impl Default for MortarHealth {
+    fn default() -> Self {
+        Self::new(MORTAR_STRENGTH)
+    }
+}
+
+/// Sent when a mortar joint between two castle blocks breaks.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct JointBroken {
+    pub joint: Entity,
+    pub block1: Entity,
+    pub block2: Entity,
+    /// World position of the mortar that broke.
+    pub position: Vec2,
+}
+
 fn handle_castle_impulses(
     mut commands: Commands,
     mut castle_query: Query<
-        (Entity, &ShockwaveHit, &Children),
+        (Entity, &ShockwaveHit, &Mass),
         (With<CastleBlock>, Added<ShockwaveHit>),
     >,
+    mut joint_query: Query<(Entity, &FixedJoint, &mut MortarHealth)>,
+    block_transforms: Query<&GlobalTransform, With<CastleBlock>>,
+    sfx_assets: Res<SfxAssets>,
+    mut broken_events: EventWriter<JointBroken>,
+    mut destroyed_events: EventWriter<BlockDestroyed>,
 ) {
-    const BREAKING_IMPULSE_THRESHOLD: f32 = 5000.0; // Adjust this value
+    if castle_query.is_empty() {
+        return;
+    }
+    // A joint is parented to only one of the blocks it holds, so go by its
+    // endpoints rather than the hierarchy to find every joint holding a block.
+    let mut block_joints: HashMap<Entity, Vec<Entity>> = HashMap::default();
+    for (joint_entity, joint, _) in &joint_query {
+        block_joints
+            .entry(joint.entity1)
+            .or_default()
+            .push(joint_entity);
+        block_joints
+            .entry(joint.entity2)
+            .or_default()
+            .push(joint_entity);
+    }
+    // Joints broken this frame, which the block on their other end may still list.
+    let mut broken: HashSet<Entity> = HashSet::default();
 
-    for (castle_entity, shockwave_hit, child_joints) in &mut castle_query {
+    let mut collapsed_at = None;
+    for (castle_entity, shockwave_hit, mass) in &mut castle_query {
+        #[cfg(feature = "gameplay_spans")]
+        let _span = info_span!("castle_impulse", block = ?castle_entity).entered();
         let impulse_magnitude = shockwave_hit.impulse.length();
 
-        info!(
+        debug!(
             "Castle {:?} received impulse, magnitude: {}",
             castle_entity, impulse_magnitude
         );
 
-        // Check if the impulse exceeds the breaking threshold
-        if impulse_magnitude > BREAKING_IMPULSE_THRESHOLD {
-            info!(
-                "Castle {:?} received a breaking impulse of {}",
-                castle_entity, impulse_magnitude
-            );
-            // Clone the joints to avoid borrowing issues
-
-            // Find all joints connected to this castle entity
-            info!("length of child joints {:?}", child_joints.len());
-            for joint_entity in child_joints {
-                commands.entity(*joint_entity).despawn();
+        // Wear down every joint holding this block, breaking the ones that give out
+        let mut intact_joints = 0;
+        let mut broken_joints = 0;
+        for joint_entity in block_joints.get(&castle_entity).into_iter().flatten() {
+            if broken.contains(joint_entity) {
+                broken_joints += 1;
+                continue;
             }
+            let Ok((_, joint, mut mortar)) = joint_query.get_mut(*joint_entity) else {
+                continue;
+            };
+            mortar.current -= impulse_magnitude;
+            if mortar.current > 0.0 {
+                intact_joints += 1;
+                continue;
+            }
+
+            let position = block_transforms
+                .get(joint.entity1)
+                .map(|transform| transform.transform_point(joint.local_anchor1.extend(0.0)))
+                .unwrap_or_default()
+                .truncate();
+            debug!("Mortar joint {:?} broke at {:?}", joint_entity, position);
+            broken_events.write(JointBroken {
+                joint: *joint_entity,
+                block1: joint.entity1,
+                block2: joint.entity2,
+                position,
+            });
+            commands.entity(*joint_entity).despawn();
+            broken.insert(*joint_entity);
+            broken_joints += 1;
+        }
+
+        // The block is knocked loose once the last joint holding it breaks
+        if broken_joints > 0 && intact_joints == 0 {
+            destroyed_events.write(BlockDestroyed {
+                block: castle_entity,
+                mass: mass.0,
+            });
+            collapsed_at = block_transforms
+                .get(castle_entity)
+                .ok()
+                .map(|transform| transform.translation().truncate());
         }
 
         // Remove the ShockwaveHit component after processing
         commands.entity(castle_entity).remove::<ShockwaveHit>();
     }
+
+    // One rumble per frame, however many blocks came loose
+    if let Some(position) = collapsed_at {
+        commands.spawn(spatial_sound_effect(
+            sfx_assets.block_collapse.clone(),
+            position,
+        ));
+    }
 }
@@ -0,0 +1,117 @@
+//! Headless simulations of a whole castle, to catch physics regressions.
+//!
+//! Each test spawns a known castle from a blueprint, sets off a fireball,
+//! steps the physics for a while and then checks which parts of the castle
+//! came down.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::demo::{
+    blueprint::CastleBlueprint,
+    castle::{CastleBlock, CastleSection},
+    headless::{
+        CELL, SimulationLog, block, detonate_fireball, headless_app, spawn_castle, step_secs,
+    },
+};
+
+const FOUNDATION_WIDTH: i32 = 12;
+const FOUNDATION_HEIGHT: i32 = 2;
+const TOWER_COLUMNS: [i32; 2] = [5, 6];
+const TOWER_HEIGHT: i32 = 12;
+/// Rows at the top of the tower that a blast beside them should bring down.
+const TOWER_TOP_ROWS: i32 = 4;
+
+/// A wide, squat foundation with a tall, narrow tower in the middle of it.
+fn tower_castle() -> CastleBlueprint {
+    let mut blocks = Vec::new();
+    for column in 0..FOUNDATION_WIDTH {
+        for row in 0..FOUNDATION_HEIGHT {
+            blocks.push(block(column, row, "foundation"));
+        }
+    }
+    for column in TOWER_COLUMNS {
+        for row in FOUNDATION_HEIGHT..FOUNDATION_HEIGHT + TOWER_HEIGHT {
+            blocks.push(block(column, row, "tower"));
+        }
+    }
+    CastleBlueprint { blocks }
+}
+
+/// Where every castle block is, with the section it belongs to.
+fn block_positions(app: &mut App) -> HashMap<Entity, (String, Vec2)> {
+    app.world_mut()
+        .query_filtered::<(Entity, &CastleSection, &GlobalTransform), With<CastleBlock>>()
+        .iter(app.world())
+        .map(|(entity, section, transform)| {
+            (
+                entity,
+                (section.0.clone(), transform.translation().truncate()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn castle_stands_on_its_own() {
+    let mut app = headless_app();
+    spawn_castle(&mut app, tower_castle());
+    let before = block_positions(&mut app);
+
+    step_secs(&mut app, 10);
+
+    let after = block_positions(&mut app);
+    assert!(app.world().resource::<SimulationLog>().destroyed.is_empty());
+    for (entity, (section, position)) in &before {
+        let moved = after[entity].1.distance(*position);
+        assert!(
+            moved < CELL / 4.0,
+            "{section} block at {position} slumped {moved} units"
+        );
+    }
+}
+
+#[test]
+fn blast_beside_the_tower_top_leaves_the_foundation_standing() {
+    let mut app = headless_app();
+    spawn_castle(&mut app, tower_castle());
+    step_secs(&mut app, 1);
+    let before = block_positions(&mut app);
+
+    // Just left of the tower, level with its top, and out of reach of the foundation.
+    let tower_top = (FOUNDATION_HEIGHT + TOWER_HEIGHT) as f32 * CELL;
+    detonate_fireball(
+        &mut app,
+        Vec2::new((TOWER_COLUMNS[0] - 1) as f32 * CELL, tower_top - CELL),
+    );
+    step_secs(&mut app, 10);
+
+    let after = block_positions(&mut app);
+    let destroyed = &app.world().resource::<SimulationLog>().destroyed;
+    let top_rows_start = tower_top - TOWER_TOP_ROWS as f32 * CELL;
+    for (entity, (section, position)) in &before {
+        let moved = after[entity].1.distance(*position);
+        match section.as_str() {
+            "foundation" => {
+                assert!(
+                    !destroyed.contains(entity),
+                    "foundation block at {position} was knocked loose"
+                );
+                assert!(
+                    moved < CELL / 2.0,
+                    "foundation block at {position} moved {moved} units"
+                );
+            }
+            _ if position.y > top_rows_start => {
+                assert!(
+                    destroyed.contains(entity),
+                    "tower block at {position} is still mortared in"
+                );
+                assert!(
+                    moved > CELL,
+                    "tower block at {position} only moved {moved} units"
+                );
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Sticky charges, set off remotely.
+//!
+//! A charge is lobbed like a fireball but doesn't go off on contact. It sticks
+//! to the first surface it hits with a fixed joint, riding along if that block
+//! later falls, and waits. Pressing the detonate key sets off every placed
+//! charge in the same frame, each sending a [`Detonation`] through the usual
+//! explosion and shockwave code with the `Charge` tuning from `abilities.ron`.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        balistics::{Ability, AbilityKind, Lifetime},
+        collision::{Detonation, Exploded},
+        player::Player,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<DetonateCharges>();
+    app.add_systems(
+        Update,
+        (stick_charges, detonate_charges)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Launch speed of a charge in world units per second.
+const CHARGE_SPEED: f32 = 300.0;
+/// Charges nobody sets off are cleared away after this long.
+const CHARGE_LIFETIME_SECS: f32 = 60.0;
+const CHARGE_RADIUS: f32 = 4.0;
+const CHARGE_COLOR: Color = Color::srgb(0.85, 0.2, 0.2);
+
+/// Sent to set off every placed charge at once.
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct DetonateCharges;
+
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct StickyCharge {
+    /// Whether it has stuck to something yet.
+    stuck: bool,
+}
+
+#[derive(Bundle)]
+pub struct ChargeBundle {
+    pub ability: Ability,
+    pub charge: StickyCharge,
+    pub lifetime: Lifetime,
+    pub sprite: Sprite,
+    pub transform: Transform,
+    pub rigid_body: RigidBody,
+    pub linear_velocity: LinearVelocity,
+    pub collider: Collider,
+    pub colliding_entities: CollidingEntities,
+    pub name: Name,
+    pub mass: Mass,
+}
+
+pub fn create_charge_bundle(
+    spawn_position: Vec3,
+    direction: Vec2,
+    asset_server: &Res<AssetServer>,
+) -> ChargeBundle {
+    ChargeBundle {
+        ability: Ability,
+        charge: StickyCharge::default(),
+        lifetime: Lifetime {
+            lifetime: Timer::from_seconds(CHARGE_LIFETIME_SECS, TimerMode::Once),
+        },
+        sprite: Sprite {
+            image: asset_server.load("images/fireball.png"),
+            color: CHARGE_COLOR,
+            custom_size: Some(Vec2::splat(CHARGE_RADIUS * 2.0)),
+            ..default()
+        },
+        transform: Transform::from_translation(spawn_position),
+        rigid_body: RigidBody::Dynamic,
+        linear_velocity: LinearVelocity(direction * CHARGE_SPEED),
+        collider: Collider::circle(CHARGE_RADIUS),
+        colliding_entities: CollidingEntities::default(),
+        name: Name::new("Charge"),
+        mass: Mass(10.0),
+    }
+}
+
+/// Glue each charge to the first surface it touches.
+fn stick_charges(
+    mut commands: Commands,
+    mut charge_query: Query<(
+        Entity,
+        &mut StickyCharge,
+        &CollidingEntities,
+        &GlobalTransform,
+        &mut LinearVelocity,
+    )>,
+    // Charges don't stick to players, other projectiles or triggers.
+    surface_query: Query<
+        &GlobalTransform,
+        (
+            With<RigidBody>,
+            Without<Player>,
+            Without<Ability>,
+            Without<Sensor>,
+        ),
+    >,
+) {
+    for (entity, mut charge, colliding_entities, transform, mut velocity) in &mut charge_query {
+        if charge.stuck {
+            continue;
+        }
+        let Some((surface, surface_transform)) = colliding_entities
+            .iter()
+            .find_map(|&surface| Some((surface, surface_query.get(surface).ok()?)))
+        else {
+            continue;
+        };
+        charge.stuck = true;
+        velocity.0 = Vec2::ZERO;
+        let anchor = surface_transform
+            .affine()
+            .inverse()
+            .transform_point3(transform.translation())
+            .truncate();
+        // The joint is a child of the charge so it goes when the charge does.
+        commands.spawn((
+            FixedJoint::new(surface, entity).with_local_anchor_1(anchor),
+            ChildOf(entity),
+        ));
+    }
+}
+
+fn detonate_charges(
+    mut commands: Commands,
+    mut detonate_events: EventReader<DetonateCharges>,
+    charge_query: Query<(Entity, &GlobalTransform), (With<StickyCharge>, Without<Exploded>)>,
+    mut detonation_events: EventWriter<Detonation>,
+) {
+    if detonate_events.read().count() == 0 {
+        return;
+    }
+    for (entity, transform) in &charge_query {
+        commands.entity(entity).insert(Exploded);
+        detonation_events.write(Detonation {
+            projectile: entity,
+            transform: transform.compute_transform(),
+            ability: AbilityKind::Charge,
+        });
+    }
+}
@@ -3,86 +3,247 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use rand::Rng;
 
-use crate::demo::{
-    balistics::{Ability, ExplosionBundle, FrostAssets, FrostBundle, Frostbolt},
-    castle::CastleBlock,
-    player::{LightningState, Player},
+use crate::{
+    AppSystems, PausableSystems,
+    audio::spatial_sound_effect,
+    demo::{
+        ability_config::{AbilityConfigs, ExplosionConfig},
+        balistics::{
+            Ability, AbilityKind, ExplosionBundle, FireballBundle, FrostAssets, FrostBundle,
+            FrostCone, Frostbolt, SfxAssets,
+        },
+        castle::{BlockDestroyed, CastleBlock, CastleGrid},
+        charges::StickyCharge,
+        despawn_queue::DespawnQueue,
+        fluids::Water,
+        health::DamageEvent,
+        merging::{MergedRegion, wake_region},
+        player::{LightningState, Player},
+        pool::Pool,
+        progress::Progress,
+        rng::GameRng,
+        skills::Ricochets,
+        tint::{TintLayer, Tints},
+        weather::{SNOW_FROST_STACKS, Snowed, Weather},
+    },
+    settings::Settings,
 };
 
 use super::balistics::{ExplosionAssets, Fireball};
 
 pub fn plugin(app: &mut App) {
+    app.add_event::<Shockwave>();
+    app.add_event::<Detonation>();
+    app.add_event::<FrostImpact>();
+    app.add_event::<CombatHit>();
     app.add_systems(Update, spawn_ground_sensor)
         .add_systems(Update, update_on_ground)
+        .add_systems(Update, damage_player_on_impact)
         .add_systems(
             Update,
-            (fireball_collisions, frostbolt_collisions, apply_frostbite)
-                .run_if(resource_exists::<ExplosionAssets>),
+            (apply_frost_slow, thaw_frost, tint_frozen_blocks)
+                .chain()
+                .in_set(AppSystems::Update)
+                .in_set(PausableSystems),
+        )
+        .add_systems(
+            Update,
+            (
+                (detonate_fireballs, explode_detonations).chain(),
+                intercept_hostile_projectiles,
+                frostbolt_collisions,
+                apply_frostbite,
+            )
+                .run_if(resource_exists::<ExplosionAssets>.and(resource_exists::<SfxAssets>)),
         );
 }
 
-fn fireball_collisions(
+/// Marks a projectile that has already gone off, so the explosion, shockwave
+/// and despawn only happen once however many contacts it has.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Exploded;
+
+/// Sent once when a projectile goes off.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Detonation {
+    pub projectile: Entity,
+    pub transform: Transform,
+    /// The ability that fired the projectile, which picks the explosion's tuning.
+    pub ability: AbilityKind,
+}
+
+/// Sent once when a frostbolt bursts, with everything it was touching.
+#[derive(Event, Debug, Clone)]
+pub struct FrostImpact {
+    pub position: Vec2,
+    pub contacts: Vec<Entity>,
+}
+
+/// Decide which fireballs go off this frame, marking each one as [`Exploded`].
+fn detonate_fireballs(
     mut commands: Commands,
-    explosion_assets: Res<ExplosionAssets>, // Changed from ResMut if not mutated by this system directly
-    fireball_query: Query<(Entity, &CollidingEntities, &GlobalTransform), With<Fireball>>,
-    // Query for all dynamic rigid bodies that could be affected by the shockwave
-    mut dynamic_bodies_query: Query<
-        (Entity, &GlobalTransform, &RigidBody),
-        (Without<Fireball>, Without<LightningState>),
+    mut fireball_query: Query<
+        (
+            Entity,
+            &CollidingEntities,
+            &GlobalTransform,
+            Option<&mut Ricochets>,
+        ),
+        (With<Fireball>, Without<Exploded>),
     >,
+    castle_query: Query<(), Or<(With<CastleBlock>, With<MergedRegion>)>>,
+    water_query: Query<(), With<Water>>,
+    mut detonation_events: EventWriter<Detonation>,
 ) {
-    for (fireball_entity, colliding_entities, fireball_gt) in &fireball_query {
+    for (fireball_entity, colliding_entities, fireball_gt, ricochets) in &mut fireball_query {
         if colliding_entities.is_empty() {
+            if let Some(mut ricochets) = ricochets {
+                ricochets.touching = false;
+            }
+            continue;
+        }
+        // Upgraded fireballs bounce off walls and floors, but never off the castle.
+        if let Some(mut ricochets) = ricochets {
+            let hit_castle = colliding_entities
+                .iter()
+                .any(|entity| castle_query.contains(*entity));
+            if ricochets.touching && !hit_castle {
+                continue;
+            }
+            if ricochets.remaining > 0 && !hit_castle {
+                ricochets.remaining -= 1;
+                ricochets.touching = true;
+                continue;
+            }
+        }
+        // Fireballs that hit water fizzle out instead of exploding, see `fluids.rs`.
+        if colliding_entities
+            .iter()
+            .any(|entity| water_query.contains(*entity))
+        {
             continue;
         }
 
-        info!(
+        debug!(
             "Fireball entity: {:?} has {} colliding entities. Creating explosion.",
             fireball_entity,
             colliding_entities.len()
         );
+        commands.entity(fireball_entity).insert(Exploded);
+        detonation_events.write(Detonation {
+            projectile: fireball_entity,
+            transform: fireball_gt.compute_transform(),
+            ability: AbilityKind::Fireball,
+        });
+    }
+}
 
-        // Spawn the explosion visual
-        // Ensure ExplosionBundle::new takes Vec3 and &ExplosionAssets
-        commands.spawn(ExplosionBundle::new(
-            &fireball_gt.compute_transform(),
-            &explosion_assets,
+fn explode_detonations(
+    mut commands: Commands,
+    explosion_assets: Res<ExplosionAssets>,
+    sfx_assets: Res<SfxAssets>,
+    progress: Res<Progress>,
+    weather: Res<Weather>,
+    ability_configs: AbilityConfigs,
+    // Query for all dynamic rigid bodies that could be affected by the shockwave
+    dynamic_bodies_query: Query<
+        (Entity, &GlobalTransform, &RigidBody, Has<Player>),
+        (
+            Without<Fireball>,
+            Without<StickyCharge>,
+            Without<LightningState>,
+        ),
+    >,
+    spatial_query: SpatialQuery,
+    region_query: Query<&MergedRegion>,
+    mut detonation_events: EventReader<Detonation>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut shockwave_events: EventWriter<Shockwave>,
+    mut hit_events: EventWriter<CombatHit>,
+    mut explosion_pool: ResMut<Pool<ExplosionBundle>>,
+    mut fireball_pool: ResMut<Pool<FireballBundle>>,
+) {
+    for detonation in detonation_events.read() {
+        #[cfg(feature = "gameplay_spans")]
+        let _span = info_span!("explosion", projectile = ?detonation.projectile).entered();
+        explosion_pool.acquire(
+            &mut commands,
+            ExplosionBundle::new(&detonation.transform, &explosion_assets),
+        );
+        commands.spawn(spatial_sound_effect(
+            sfx_assets.explosion.clone(),
+            detonation.transform.translation.truncate(),
         ));
 
-        // Apply shockwave by calling the new function
         apply_explosion_shockwave(
-            &mut commands,             // Pass commands
-            fireball_gt.translation(), // Use the fireball's position as the explosion origin
-            &mut dynamic_bodies_query, // Pass the query for dynamic bodies
+            &mut commands,
+            detonation.projectile,
+            detonation.transform.translation,
+            &dynamic_bodies_query,
+            &spatial_query,
+            &region_query,
+            &mut damage_events,
+            &mut shockwave_events,
+            &mut hit_events,
+            detonation.ability,
+            ability_configs.explosion(detonation.ability),
+            progress.upgrades.blast_radius_multiplier() * weather.fire_radius_multiplier(),
         );
 
-        // Despawn the fireball
-        commands.entity(fireball_entity).despawn();
+        if detonation.ability == AbilityKind::Fireball {
+            fireball_pool.release(&mut commands, detonation.projectile);
+        } else {
+            commands.entity(detonation.projectile).try_despawn();
+        }
     }
 }
 
 fn frostbolt_collisions(
     mut commands: Commands,
     frost_assets: Res<FrostAssets>,
-    frostbolt_query: Query<(Entity, &CollidingEntities, &GlobalTransform), With<Frostbolt>>,
-    mut dynamic_bodies_query: Query<(Entity, &GlobalTransform, &RigidBody), Without<Ability>>,
+    mut frostbolt_query: Query<(
+        Entity,
+        &mut Frostbolt,
+        &FrostCone,
+        &CollidingEntities,
+        &GlobalTransform,
+        &LinearVelocity,
+    )>,
+    mut dynamic_bodies_query: Query<
+        (Entity, &GlobalTransform, &RigidBody, Has<Snowed>),
+        Without<Ability>,
+    >,
+    mut hit_events: EventWriter<CombatHit>,
+    mut impact_events: EventWriter<FrostImpact>,
 ) {
-    for (frostbolt_entity, colliding_entities, frostbolt_gt) in &frostbolt_query {
+    for (frostbolt_entity, mut frostbolt, cone, colliding_entities, frostbolt_gt, velocity) in
+        &mut frostbolt_query
+    {
         if colliding_entities.is_empty() {
+            frostbolt.velocity = velocity.0;
             continue;
         }
+        #[cfg(feature = "gameplay_spans")]
+        let _span = info_span!("frost_cone", frostbolt = ?frostbolt_entity).entered();
         commands.spawn(FrostBundle::new(
             &frostbolt_gt.compute_transform(),
             &frost_assets,
         ));
-        const CONE_RADIUS: f32 = 128.0;
-        const CONE_RADIUS_SQUARED: f32 = CONE_RADIUS * CONE_RADIUS;
+        impact_events.write(FrostImpact {
+            position: frostbolt_gt.translation().truncate(),
+            contacts: colliding_entities.iter().copied().collect(),
+        });
         const FROST_EFFECT_BASE: f32 = 200.0;
 
         let frostbolt_position = frostbolt_gt.translation();
-        let frostbolt_direction = Vec2::new(1.0, 0.0); // Fixed direction (rightward)
-
-        for (target_entity, target_transform, target_rb) in &mut dynamic_bodies_query {
+        // The cone opens the way the frostbolt was travelling before it hit.
+        let frostbolt_direction = frostbolt
+            .velocity
+            .try_normalize()
+            .or_else(|| frostbolt.launch_direction.try_normalize())
+            .unwrap_or(Vec2::X);
+
+        for (target_entity, target_transform, target_rb, snowed) in &mut dynamic_bodies_query {
             if !matches!(target_rb, RigidBody::Dynamic) {
                 continue;
             }
@@ -91,10 +252,10 @@ fn frostbolt_collisions(
             let vector_to_target = target_position - frostbolt_position;
             let distance_squared = vector_to_target.length_squared();
 
-            if distance_squared > CONE_RADIUS_SQUARED {
+            if distance_squared > cone.radius * cone.radius {
                 continue;
             }
-            info!("Inserting frost effect");
+            debug!("Inserting frost effect");
             commands
                 .entity(target_entity)
                 .insert(FrostEffect { magnitude: 1.0 });
@@ -113,12 +274,15 @@ fn frostbolt_collisions(
                 direction_to_target
             );
 
-            if angle_to_target.abs() <= std::f32::consts::PI / 4.0 {
+            if angle_to_target.abs() <= cone.half_angle {
                 let distance = distance_squared.sqrt();
-                let falloff_factor = (1.0 - (distance / CONE_RADIUS)).powi(2);
-                let frost_effect_magnitude = FROST_EFFECT_BASE * falloff_factor;
+                let falloff_factor = (1.0 - (distance / cone.radius)).powi(2);
+                let mut frost_effect_magnitude = FROST_EFFECT_BASE * falloff_factor;
+                if snowed {
+                    frost_effect_magnitude += SNOW_FROST_STACKS;
+                }
 
-                info!(
+                debug!(
                     "Applying frost effect to entity {:?}. Distance: {}, Falloff factor: {}, Magnitude: {}",
                     target_entity, distance, falloff_factor, frost_effect_magnitude
                 );
@@ -126,8 +290,16 @@ fn frostbolt_collisions(
                 commands.entity(target_entity).insert(FrostEffect {
                     magnitude: frost_effect_magnitude,
                 });
+                hit_events.write(CombatHit {
+                    attacker: Some(frostbolt_entity),
+                    target: target_entity,
+                    ability: Some(AbilityKind::Frost),
+                    damage: 0.0,
+                    impulse: Vec2::ZERO,
+                    frost: frost_effect_magnitude,
+                });
             } else {
-                info!(
+                debug!(
                     "Entity {:?} is outside the cone angle. Angle to target: {}",
                     target_entity, angle_to_target
                 );
@@ -175,9 +347,20 @@ pub struct HeroCollisionBundle {
     pub ground_detection: GroundDetection,
 }
 
-#[derive(Clone, Default, Component)]
+#[derive(Clone, Component)]
 pub struct GroundDetection {
     pub on_ground: bool,
+    /// Surface normal of the ground underfoot, straight up when airborne.
+    pub ground_normal: Vec2,
+}
+
+impl Default for GroundDetection {
+    fn default() -> Self {
+        Self {
+            on_ground: false,
+            ground_normal: Vec2::Y,
+        }
+    }
 }
 
 pub fn spawn_ground_sensor(
@@ -205,9 +388,119 @@ pub fn spawn_ground_sensor(
 pub fn update_on_ground(mut ground_detectors: Query<(&mut GroundDetection, &ShapeHits)>) {
     for (mut ground_detection, hits) in &mut ground_detectors {
         ground_detection.on_ground = !hits.is_empty();
+        ground_detection.ground_normal = hits.iter().next().map_or(Vec2::Y, |hit| hit.normal1);
     }
 }
 
+/// Deals damage to the player on contact, e.g. for enemy projectiles.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ContactDamage {
+    pub amount: f32,
+}
+
+/// A projectile fired at the player, e.g. a boulder. Fireballs that meet one
+/// mid-air detonate it, destroying both in the explosion.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct HostileProjectile;
+
+/// Fireballs already explode on any contact in `detonate_fireballs`, this
+/// makes sure the projectile they hit goes down with them.
+fn intercept_hostile_projectiles(
+    mut commands: Commands,
+    fireball_query: Query<&CollidingEntities, With<Fireball>>,
+    hostile_query: Query<(), With<HostileProjectile>>,
+) {
+    for colliding_entities in &fireball_query {
+        for &entity in colliding_entities.iter() {
+            if hostile_query.contains(entity) {
+                info!("Fireball intercepted hostile projectile {:?}", entity);
+                commands.entity(entity).try_despawn();
+            }
+        }
+    }
+}
+
+/// Hurt the player when something harmful touches them or a castle block lands on them.
+fn damage_player_on_impact(
+    mut collision_events: EventReader<CollisionStarted>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    falling_block_query: Query<(&GlobalTransform, &LinearVelocity), With<CastleBlock>>,
+    contact_damage_query: Query<(&ContactDamage, &GlobalTransform)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut hit_events: EventWriter<CombatHit>,
+) {
+    const FALLING_BLOCK_MIN_SPEED: f32 = 60.0;
+    const FALLING_BLOCK_DAMAGE_PER_SPEED: f32 = 0.25;
+
+    for CollisionStarted(entity1, entity2) in collision_events.read() {
+        for (player, other) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let Ok(player_gt) = player_query.get(player) else {
+                continue;
+            };
+
+            if let Ok((contact_damage, source_gt)) = contact_damage_query.get(other) {
+                damage_events.write(DamageEvent::hit(
+                    player,
+                    contact_damage.amount,
+                    (player_gt.translation() - source_gt.translation()).truncate(),
+                ));
+                hit_events.write(CombatHit::damage(other, player, contact_damage.amount));
+            }
+
+            if let Ok((block_gt, block_velocity)) = falling_block_query.get(other) {
+                let falling_speed = -block_velocity.y;
+                let above_player = block_gt.translation().y > player_gt.translation().y;
+                if above_player && falling_speed > FALLING_BLOCK_MIN_SPEED {
+                    info!("Player hit by a falling block at speed {}", falling_speed);
+                    let damage =
+                        (falling_speed - FALLING_BLOCK_MIN_SPEED) * FALLING_BLOCK_DAMAGE_PER_SPEED;
+                    damage_events.write(DamageEvent::hit(
+                        player,
+                        damage,
+                        (player_gt.translation() - block_gt.translation()).truncate(),
+                    ));
+                    hit_events.write(CombatHit::damage(other, player, damage));
+                }
+            }
+        }
+    }
+}
+
+/// Sent for every body something hits, so the numbers can be inspected when
+/// balancing and tallied into the run's stats.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CombatHit {
+    /// The projectile or body that did the hitting, if there was one.
+    pub attacker: Option<Entity>,
+    pub target: Entity,
+    pub ability: Option<AbilityKind>,
+    pub damage: f32,
+    pub impulse: Vec2,
+    /// Frost magnitude applied to the target.
+    pub frost: f32,
+}
+
+impl CombatHit {
+    /// Plain damage from something that isn't an ability, like a boulder.
+    fn damage(attacker: Entity, target: Entity, damage: f32) -> Self {
+        Self {
+            attacker: Some(attacker),
+            target,
+            ability: None,
+            damage,
+            impulse: Vec2::ZERO,
+            frost: 0.0,
+        }
+    }
+}
+
+/// Sent when an explosion sends out a shockwave.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Shockwave {
+    pub origin: Vec2,
+    pub radius: f32,
+}
+
 #[derive(Component, Debug, Clone, Copy)]
 pub struct ShockwaveHit {
     pub impulse: Vec2,
@@ -217,18 +510,86 @@ pub struct FrostEffect {
     pub magnitude: f32,
 }
 
+/// Frost magnitude at which a body is slowed as much as it can be.
+const FROST_FULL_SLOW_MAGNITUDE: f32 = 100.0;
+/// Fraction of speed lost at full frost.
+const MAX_FROST_SLOW: f32 = 0.7;
+/// Fraction of velocity lost per second at full frost.
+const FROST_DRAG: f32 = 3.0;
+/// Frost magnitude lost per second once a body is out of the cold.
+const FROST_THAW_PER_SEC: f32 = 40.0;
+/// Frost stacks at which a castle block freezes solid and shatters.
+const MAX_FROST_STACKS: u32 = 4;
+
+impl FrostEffect {
+    /// How much of a body's speed is taken away, from 0 to [`MAX_FROST_SLOW`].
+    pub fn slow(&self) -> f32 {
+        (self.magnitude / FROST_FULL_SLOW_MAGNITUDE).clamp(0.0, 1.0) * MAX_FROST_SLOW
+    }
+
+    /// The multiplier for a frozen character's movement speed.
+    pub fn speed_factor(&self) -> f32 {
+        1.0 - self.slow()
+    }
+}
+
+fn apply_frost_slow(time: Res<Time>, mut query: Query<(&FrostEffect, &mut LinearVelocity)>) {
+    let delta_time = time.delta_secs();
+    for (frost_effect, mut linear_velocity) in &mut query {
+        let drag = frost_effect.slow() / MAX_FROST_SLOW * FROST_DRAG;
+        linear_velocity.0 *= (1.0 - drag * delta_time).max(0.0);
+    }
+}
+
+/// Frost wears off anything that isn't part of the castle. Frozen castle
+/// blocks keep building up frostbite until they shatter, see `apply_frostbite`.
+fn thaw_frost(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut FrostEffect), Without<CastleBlock>>,
+) {
+    for (entity, mut frost_effect) in &mut query {
+        frost_effect.magnitude -= FROST_THAW_PER_SEC * time.delta_secs();
+        if frost_effect.magnitude <= 0.0 {
+            commands.entity(entity).try_remove::<FrostEffect>();
+        }
+    }
+}
+
+/// Frozen castle blocks turn progressively bluer as frost stacks up on them.
+fn tint_frozen_blocks(
+    frozen_query: Query<(Entity, &FrostEffect), (With<CastleBlock>, Changed<FrostEffect>)>,
+    mut thawed: RemovedComponents<FrostEffect>,
+    mut tints_query: Query<&mut Tints>,
+) {
+    for entity in thawed.read() {
+        if let Ok(mut tints) = tints_query.get_mut(entity) {
+            tints.clear(TintLayer::Frost);
+        }
+    }
+    for (entity, frost_effect) in &frozen_query {
+        if let Ok(mut tints) = tints_query.get_mut(entity) {
+            let strength = frost_effect.magnitude / MAX_FROST_STACKS as f32;
+            tints.set(TintLayer::Frost, strength);
+        }
+    }
+}
+
 fn apply_frostbite(
     mut commands: Commands,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+    castle_grid: Res<CastleGrid>,
+    sfx_assets: Res<SfxAssets>,
+    settings: Res<Settings>,
     mut frostbite_timer: Local<Timer>,
     mut frost_query: Query<
-        (Entity, &Transform, &mut FrostEffect, &mut Sprite),
+        (Entity, &GlobalTransform, &mut FrostEffect, &Mass),
         (With<CastleBlock>, Without<Player>),
     >,
-    mut adjacent_query: Query<
-        (Entity, &Transform, &mut Sprite),
-        (Without<FrostEffect>, With<CastleBlock>),
-    >,
+    adjacent_query: Query<(), (Without<FrostEffect>, With<CastleBlock>)>,
+    mut destroyed_events: EventWriter<BlockDestroyed>,
+    mut despawn_queue: ResMut<DespawnQueue>,
 ) {
     // Initialize the timer if it hasn't been set yet
     if frostbite_timer.elapsed_secs() == 0.0 {
@@ -242,62 +603,45 @@ fn apply_frostbite(
         return;
     }
 
-    const MAX_FROST_STACKS: u32 = 4;
-    const SPREAD_RADIUS: f32 = 32.0; // Distance to check for adjacent entities
     const PROPAGATION_CHANCE: f32 = 0.1;
+    #[cfg(feature = "gameplay_spans")]
+    let _span = info_span!("frostbite", blocks = frost_query.iter().count()).entered();
 
     // Collect entities to despawn after processing
     let mut entities_to_despawn = Vec::new();
 
     // Iterate over all frostbitten entities
-    for (frostbitten_entity, frostbitten_transform, mut frost_effect, mut sprite) in
+    for (frostbitten_entity, frostbitten_transform, mut frost_effect, mass) in
         frost_query.iter_mut()
     {
-        info!(
+        debug!(
             "Spreading frostbite from entity {:?} with magnitude: {}",
             frostbitten_entity, frost_effect.magnitude
         );
 
-        // Adjust the sprite color progressively more blue based on frost magnitude
-        let blue_intensity = (frost_effect.magnitude / MAX_FROST_STACKS as f32).clamp(0.0, 1.0);
-        sprite.color = Color::srgb(1.0 - blue_intensity, 1.0 - blue_intensity, 1.0);
+        // Spread frost to neighbouring blocks without FrostEffect
+        for adjacent_entity in castle_grid.neighbors(frostbitten_entity) {
+            if !adjacent_query.contains(adjacent_entity) {
+                continue;
+            }
 
-        // Spread frost to adjacent entities without FrostEffect
-        for (adjacent_entity, adjacent_transform, mut adjacent_sprite) in adjacent_query.iter_mut()
-        {
-            let distance = frostbitten_transform
-                .translation
-                .distance(adjacent_transform.translation);
-
-            if distance <= SPREAD_RADIUS {
-                // Generate a random number and check against the propagation chance
-                let mut rng = rand::thread_rng();
-                let random_value: f32 = rng.r#gen();
-
-                if random_value <= PROPAGATION_CHANCE {
-                    info!(
-                        "Applying frostbite to adjacent entity {:?} at distance: {}",
-                        adjacent_entity, distance
-                    );
-
-                    commands.entity(adjacent_entity).insert(FrostEffect {
-                        magnitude: frost_effect.magnitude + 1.0, // Increment magnitude
-                    });
-
-                    // Adjust the adjacent sprite color progressively more blue
-                    let adjacent_blue_intensity =
-                        ((frost_effect.magnitude + 1.0) / MAX_FROST_STACKS as f32).clamp(0.0, 1.0);
-                    adjacent_sprite.color = Color::srgb(
-                        1.0 - adjacent_blue_intensity,
-                        1.0 - adjacent_blue_intensity,
-                        1.0,
-                    );
-                } else {
-                    info!(
-                        "Frostbite propagation to entity {:?} failed (random value: {}).",
-                        adjacent_entity, random_value
-                    );
-                }
+            // Generate a random number and check against the propagation chance
+            let random_value: f32 = rng.r#gen();
+
+            if random_value <= PROPAGATION_CHANCE {
+                debug!(
+                    "Applying frostbite to adjacent entity {:?}",
+                    adjacent_entity
+                );
+
+                commands.entity(adjacent_entity).insert(FrostEffect {
+                    magnitude: frost_effect.magnitude + 1.0, // Increment magnitude
+                });
+            } else {
+                debug!(
+                    "Frostbite propagation to entity {:?} failed (random value: {}).",
+                    adjacent_entity, random_value
+                );
             }
         }
 
@@ -306,40 +650,98 @@ fn apply_frostbite(
 
         // Add the entity to the despawn list if the magnitude reaches the maximum
         if frost_effect.magnitude >= MAX_FROST_STACKS as f32 {
-            info!(
+            debug!(
                 "Marking frostbitten entity {:?} for despawn as it reached max frost stacks.",
                 frostbitten_entity
             );
-            entities_to_despawn.push(frostbitten_entity);
+            entities_to_despawn.push((frostbitten_entity, frostbitten_transform.translation()));
+            destroyed_events.write(BlockDestroyed {
+                block: frostbitten_entity,
+                mass: mass.0,
+            });
         }
     }
 
-    // Despawn entities after processing
-    for entity in entities_to_despawn {
-        commands.entity(entity).despawn();
+    // One shatter covers every block that froze solid this tick
+    if let Some((_, position)) = entities_to_despawn.first() {
+        commands.spawn(spatial_sound_effect(
+            sfx_assets.frost_shatter.clone(),
+            position.truncate(),
+        ));
+    }
+
+    // A whole section can freeze solid at once, so the blocks go a few at a time
+    let frost_color = TintLayer::Frost.color(settings.color_palette);
+    for (entity, position) in entities_to_despawn {
+        despawn_queue.despawn_with_debris(&mut commands, entity, position.truncate(), frost_color);
     }
 }
 
 fn apply_explosion_shockwave(
     commands: &mut Commands,
+    projectile: Entity,
     explosion_origin_pos: Vec3,
-    dynamic_bodies_query: &mut Query<
-        (Entity, &GlobalTransform, &RigidBody),
-        (Without<Fireball>, Without<LightningState>),
+    dynamic_bodies_query: &Query<
+        (Entity, &GlobalTransform, &RigidBody, Has<Player>),
+        (
+            Without<Fireball>,
+            Without<StickyCharge>,
+            Without<LightningState>,
+        ),
     >,
+    spatial_query: &SpatialQuery,
+    region_query: &Query<&MergedRegion>,
+    damage_events: &mut EventWriter<DamageEvent>,
+    shockwave_events: &mut EventWriter<Shockwave>,
+    hit_events: &mut EventWriter<CombatHit>,
+    ability: AbilityKind,
+    explosion: ExplosionConfig,
+    radius_multiplier: f32,
 ) {
-    info!(
+    debug!(
         "Starting shockwave application at position: {:?}",
         explosion_origin_pos
     );
-    const SHOCKWAVE_RADIUS: f32 = 200.0;
-    const SHOCKWAVE_RADIUS_SQUARED: f32 = SHOCKWAVE_RADIUS * SHOCKWAVE_RADIUS;
-    const SHOCKWAVE_BASE_IMPULSE: f32 = 7500.0 * 5.;
+    #[cfg(feature = "gameplay_spans")]
+    let _span = info_span!("shockwave", origin = ?explosion_origin_pos).entered();
     const MIN_DISTANCE_SQUARED: f32 = 0.01;
+    // Damage dealt to the player per unit of shockwave impulse.
+    const SHOCKWAVE_DAMAGE_PER_IMPULSE: f32 = 0.001;
+    let shockwave_radius = explosion.radius * radius_multiplier;
+    let shockwave_radius_squared = shockwave_radius * shockwave_radius;
+
+    shockwave_events.write(Shockwave {
+        origin: explosion_origin_pos.truncate(),
+        radius: shockwave_radius,
+    });
+
+    // Only look at bodies near the explosion instead of every body in the world.
+    let nearby_entities = spatial_query.shape_intersections(
+        &Collider::circle(shockwave_radius),
+        explosion_origin_pos.truncate(),
+        0.0,
+        &SpatialQueryFilter::default(),
+    );
 
-    for (target_entity, target_gt, target_rb) in dynamic_bodies_query.iter_mut() {
-        // iter_mut if you might modify components, else iter
-        if !matches!(target_rb, RigidBody::Dynamic) {
+    // Merged castle regions come apart into their blocks, which all feel the shockwave.
+    let mut targets = Vec::new();
+    for nearby_entity in nearby_entities {
+        let Ok(region) = region_query.get(nearby_entity) else {
+            targets.push(nearby_entity);
+            continue;
+        };
+        wake_region(commands, nearby_entity, region);
+        targets.extend_from_slice(region.blocks());
+    }
+
+    for nearby_entity in targets {
+        let Ok((target_entity, target_gt, target_rb, is_player)) =
+            dynamic_bodies_query.get(nearby_entity)
+        else {
+            continue;
+        };
+        // Kinematic players still get blown back, see `kinematic`.
+        if !matches!(target_rb, RigidBody::Dynamic) && !is_player {
             continue;
         }
 
@@ -347,7 +749,7 @@ fn apply_explosion_shockwave(
         let vector_to_target = target_world_pos - explosion_origin_pos;
         let distance_squared = vector_to_target.length_squared();
 
-        if distance_squared < SHOCKWAVE_RADIUS_SQUARED && distance_squared > MIN_DISTANCE_SQUARED {
+        if distance_squared < shockwave_radius_squared && distance_squared > MIN_DISTANCE_SQUARED {
             let distance = distance_squared.sqrt();
             let direction_2d = (vector_to_target.truncate() / distance).normalize_or_zero();
 
@@ -355,10 +757,8 @@ fn apply_explosion_shockwave(
                 continue;
             }
 
-            // let falloff_factor = 1.0 - (distance / SHOCKWAVE_RADIUS); // Linear falloff
-            let falloff_factor = (1.0 - (distance / SHOCKWAVE_RADIUS)).powi(2); // Quadratic falloff
-            // let falloff_factor = 1.0 / (1.0 + distance_squared / (SHOCKWAVE_RADIUS * SHOCKWAVE_RADIUS)).max(0.0); // Inverse square falloff
-            let impulse_magnitude = SHOCKWAVE_BASE_IMPULSE * falloff_factor;
+            let falloff_factor = explosion.falloff.factor(distance, shockwave_radius);
+            let impulse_magnitude = explosion.base_impulse * falloff_factor;
             if impulse_magnitude <= 0.0 {
                 continue;
             }
@@ -369,6 +769,159 @@ fn apply_explosion_shockwave(
                     impulse: direction_2d * impulse_magnitude,
                 }, // Add the tag component with the impulse
             ));
+
+            let damage = if is_player {
+                impulse_magnitude * SHOCKWAVE_DAMAGE_PER_IMPULSE
+            } else {
+                0.0
+            };
+            if is_player {
+                // The impulse above already knocks the player back, so the hit carries no extra push.
+                damage_events.write(DamageEvent {
+                    target: target_entity,
+                    amount: damage,
+                    knockback: Some(Vec2::ZERO),
+                });
+            }
+            hit_events.write(CombatHit {
+                attacker: Some(projectile),
+                target: target_entity,
+                ability: Some(ability),
+                damage,
+                impulse: direction_2d * impulse_magnitude,
+                frost: 0.0,
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::player::PlayerBundle;
+
+    #[derive(Resource, Default)]
+    struct DetonationCount(usize);
+
+    fn count_detonations(
+        mut detonation_events: EventReader<Detonation>,
+        mut count: ResMut<DetonationCount>,
+    ) {
+        count.0 += detonation_events.read().count();
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<Detonation>();
+        app.init_resource::<DetonationCount>();
+        app.add_systems(Update, (detonate_fireballs, count_detonations).chain());
+        app
+    }
+
+    fn spawn_fireball(app: &mut App, contacts: &[Entity]) -> Entity {
+        app.world_mut()
+            .spawn((
+                Fireball,
+                CollidingEntities(contacts.iter().copied().collect()),
+                GlobalTransform::default(),
+            ))
+            .id()
+    }
+
+    fn detonations(app: &App) -> usize {
+        app.world().resource::<DetonationCount>().0
+    }
+
+    #[test]
+    fn multiple_contacts_detonate_once() {
+        let mut app = app();
+        let contacts: Vec<Entity> = (0..4).map(|_| app.world_mut().spawn_empty().id()).collect();
+        let fireball = spawn_fireball(&mut app, &contacts);
+
+        app.update();
+        app.update();
+        app.update();
+
+        assert_eq!(detonations(&app), 1);
+        assert!(app.world().entity(fireball).contains::<Exploded>());
+    }
+
+    #[test]
+    fn fireballs_sharing_a_contact_each_detonate_once() {
+        let mut app = app();
+        let block = app.world_mut().spawn(CastleBlock::default()).id();
+        spawn_fireball(&mut app, &[block]);
+        spawn_fireball(&mut app, &[block]);
+
+        app.update();
+        app.update();
+
+        assert_eq!(detonations(&app), 2);
+    }
+
+    #[test]
+    fn exploded_fireballs_never_detonate_again() {
+        let mut app = app();
+        let wall = app.world_mut().spawn_empty().id();
+        let fireball = spawn_fireball(&mut app, &[wall]);
+        app.world_mut().entity_mut(fireball).insert(Exploded);
+
+        app.update();
+
+        assert_eq!(detonations(&app), 0);
+    }
+
+    #[derive(Resource, Default)]
+    struct PlayerDamage(Vec<DamageEvent>);
+
+    fn record_player_damage(
+        mut damage_events: EventReader<DamageEvent>,
+        mut damage: ResMut<PlayerDamage>,
+    ) {
+        damage.0.extend(damage_events.read().copied());
+    }
+
+    #[test]
+    fn players_report_their_collisions() {
+        let mut world = World::new();
+        let events_enabled = world.register_component::<CollisionEventsEnabled>();
+        let bundle = world.register_bundle::<PlayerBundle>();
+
+        // `damage_player_on_impact` never hears about hits otherwise.
+        assert!(bundle.contributed_components().contains(&events_enabled));
+    }
+
+    #[test]
+    fn contact_damage_hurts_the_player() {
+        let mut app = App::new();
+        app.add_event::<CollisionStarted>();
+        app.add_event::<DamageEvent>();
+        app.add_event::<CombatHit>();
+        app.init_resource::<PlayerDamage>();
+        app.add_systems(
+            Update,
+            (damage_player_on_impact, record_player_damage).chain(),
+        );
+        let player = app
+            .world_mut()
+            .spawn((Player, GlobalTransform::default()))
+            .id();
+        let boulder = app
+            .world_mut()
+            .spawn((
+                ContactDamage { amount: 10.0 },
+                GlobalTransform::from_xyz(0.0, 16.0, 0.0),
+            ))
+            .id();
+        app.world_mut()
+            .send_event(CollisionStarted(boulder, player));
+
+        app.update();
+
+        let damage = &app.world().resource::<PlayerDamage>().0;
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].target, player);
+        assert_eq!(damage[0].amount, 10.0);
+        assert!(damage[0].knockback.is_some());
+    }
+}
@@ -0,0 +1,134 @@
+//! Local co-op. A second player joins by pressing Start on a gamepad, after
+//! which the screen is split between the two players.
+
+use bevy::{
+    prelude::*,
+    render::{camera::Viewport, view::RenderLayers},
+    window::PrimaryWindow,
+};
+
+use crate::{
+    demo::{
+        camera::{PlayerCamera, UiOnlyCamera},
+        input::InputDevice,
+        player::{Player, PlayerBundle, PlayerIndex},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (join_second_player, update_split_screen)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), reset_split_screen);
+}
+
+/// How far from the first player the second one appears.
+const JOIN_OFFSET: Vec3 = Vec3::new(16.0, 0.0, 0.0);
+/// A render layer nothing in the world is on, so the UI camera only draws UI.
+const UI_CAMERA_LAYER: usize = 31;
+
+fn join_second_player(
+    mut commands: Commands,
+    gamepads: Query<(Entity, &Gamepad)>,
+    player_query: Query<(&Transform, &PlayerIndex, Option<&ChildOf>), With<Player>>,
+) {
+    if player_query.iter().any(|(_, index, _)| index.0 == 1) {
+        return;
+    }
+    let Some((gamepad, _)) = gamepads
+        .iter()
+        .find(|(_, gamepad)| gamepad.just_pressed(GamepadButton::Start))
+    else {
+        return;
+    };
+    let Some((first_transform, _, parent)) = player_query.iter().find(|(_, index, _)| index.0 == 0)
+    else {
+        return;
+    };
+
+    info!("Player 2 joined with gamepad {:?}", gamepad);
+    let mut second_player = commands.spawn((
+        Name::new("Player 2"),
+        PlayerBundle::joining(1),
+        InputDevice::Gamepad(gamepad),
+        Transform::from_translation(first_transform.translation + JOIN_OFFSET),
+    ));
+    // Live in the same layer as the first player so both share a coordinate space.
+    if let Some(parent) = parent {
+        second_player.insert(ChildOf(parent.parent()));
+    }
+}
+
+fn update_split_screen(
+    mut commands: Commands,
+    player_query: Query<(&PlayerIndex, Option<&InputDevice>), With<Player>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Camera, Option<&PlayerCamera>), Without<UiOnlyCamera>>,
+    ui_camera_query: Query<(), With<UiOnlyCamera>>,
+) {
+    // An online player has their own screen.
+    if !player_query
+        .iter()
+        .any(|(index, device)| index.0 == 1 && device != Some(&InputDevice::Remote))
+    {
+        return;
+    }
+
+    let window_size = window.physical_size();
+    let half_size = UVec2::new(window_size.x / 2, window_size.y);
+    let mut has_second_camera = false;
+    for (mut camera, player_camera) in &mut camera_query {
+        let index = player_camera.map_or(0, |player_camera| player_camera.0);
+        has_second_camera |= index == 1;
+        let position = UVec2::new(half_size.x * index as u32, 0);
+        let up_to_date = camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == half_size
+        });
+        if !up_to_date {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: half_size,
+                ..default()
+            });
+        }
+    }
+
+    if !has_second_camera {
+        commands.spawn((
+            Name::new("Player 2 Camera"),
+            Camera2d,
+            Camera {
+                order: 1,
+                ..default()
+            },
+            PlayerCamera(1),
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+    // The HUD would otherwise be squeezed into one player's half of the screen.
+    if ui_camera_query.is_empty() {
+        commands.spawn((
+            Name::new("UI Camera"),
+            Camera2d,
+            Camera {
+                order: 2,
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            RenderLayers::layer(UI_CAMERA_LAYER),
+            IsDefaultUiCamera,
+            UiOnlyCamera,
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+}
+
+fn reset_split_screen(mut camera_query: Query<&mut Camera, Without<UiOnlyCamera>>) {
+    for mut camera in &mut camera_query {
+        camera.viewport = None;
+    }
+}
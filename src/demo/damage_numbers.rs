@@ -0,0 +1,170 @@
+//! Floating numbers showing the damage dealt to players, enemies and castle blocks.
+//!
+//! Numbers rise and fade out over a moment. Big shockwave hits on the castle
+//! get a larger, brighter number. Like dust puffs, only so many are shown at
+//! once and they're recycled through a [`Pool`], so mass destruction doesn't
+//! spawn hundreds of them.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::CastleBlock,
+        collision::ShockwaveHit,
+        health::DamageEvent,
+        pool::{Pool, Poolable},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DamageNumber>();
+    app.init_resource::<Pool<DamageNumberBundle>>();
+    app.add_systems(
+        Update,
+        (
+            show_damage_event_numbers,
+            show_block_hit_numbers,
+            animate_damage_numbers,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Numbers that can be on screen at once. Extra hits while they're all in use go without.
+const MAX_DAMAGE_NUMBERS: usize = 48;
+const DAMAGE_NUMBER_SECS: f32 = 0.7;
+/// How far a number floats up over its lifetime.
+const DAMAGE_NUMBER_RISE: f32 = 24.0;
+const DAMAGE_NUMBER_FONT_SIZE: f32 = 10.0;
+const DAMAGE_NUMBER_COLOR: Color = Color::srgb(1.0, 0.95, 0.85);
+const CRIT_COLOR: Color = Color::srgb(1.0, 0.6, 0.1);
+/// Shockwave impulse on a block that counts as a big hit.
+const CRIT_IMPULSE: f32 = 20000.0;
+const CRIT_SCALE: f32 = 1.8;
+/// Block hits are measured in impulse, which is shown scaled down to read like damage.
+const DAMAGE_PER_IMPULSE: f32 = 0.01;
+/// Numbers are drawn in front of everything else in the level.
+const DAMAGE_NUMBER_Z: f32 = 20.0;
+
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+struct DamageNumber {
+    timer: Timer,
+    origin: Vec2,
+    color: Color,
+}
+
+#[derive(Bundle)]
+struct DamageNumberBundle {
+    name: Name,
+    number: DamageNumber,
+    text: Text2d,
+    font: TextFont,
+    text_color: TextColor,
+    transform: Transform,
+    state_scoped: StateScoped<Screen>,
+}
+
+impl DamageNumberBundle {
+    /// A number that floats up from `position`.
+    fn new(position: Vec2, amount: f32, crit: bool) -> Self {
+        let color = if crit {
+            CRIT_COLOR
+        } else {
+            DAMAGE_NUMBER_COLOR
+        };
+        Self {
+            name: Name::new("Damage Number"),
+            number: DamageNumber {
+                timer: Timer::from_seconds(DAMAGE_NUMBER_SECS, TimerMode::Once),
+                origin: position,
+                color,
+            },
+            text: Text2d(format!("{:.0}", amount.max(1.0))),
+            font: TextFont::from_font_size(DAMAGE_NUMBER_FONT_SIZE),
+            text_color: TextColor(color),
+            transform: Transform::from_translation(position.extend(DAMAGE_NUMBER_Z))
+                .with_scale(Vec3::splat(if crit { CRIT_SCALE } else { 1.0 })),
+            state_scoped: StateScoped(Screen::Gameplay),
+        }
+    }
+}
+
+impl Poolable for DamageNumberBundle {
+    fn reset(_entity: &mut EntityCommands) {}
+}
+
+fn show_damage_event_numbers(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    target_query: Query<&GlobalTransform>,
+    mut pool: ResMut<Pool<DamageNumberBundle>>,
+    number_query: Query<(), With<DamageNumber>>,
+) {
+    let mut numbers = number_query.iter().count();
+    for event in damage_events.read() {
+        // Damage over time lands a sliver every frame, which would be a blur of numbers.
+        if event.knockback.is_none() || event.amount <= 0.0 {
+            continue;
+        }
+        let Ok(target_transform) = target_query.get(event.target) else {
+            continue;
+        };
+        if numbers >= MAX_DAMAGE_NUMBERS {
+            break;
+        }
+        let position = target_transform.translation().truncate();
+        pool.acquire(
+            &mut commands,
+            DamageNumberBundle::new(position, event.amount, false),
+        );
+        numbers += 1;
+    }
+}
+
+fn show_block_hit_numbers(
+    mut commands: Commands,
+    block_query: Query<(&ShockwaveHit, &GlobalTransform), (With<CastleBlock>, Added<ShockwaveHit>)>,
+    mut pool: ResMut<Pool<DamageNumberBundle>>,
+    number_query: Query<(), With<DamageNumber>>,
+) {
+    let mut numbers = number_query.iter().count();
+    for (hit, transform) in &block_query {
+        if numbers >= MAX_DAMAGE_NUMBERS {
+            break;
+        }
+        let impulse = hit.impulse.length();
+        pool.acquire(
+            &mut commands,
+            DamageNumberBundle::new(
+                transform.translation().truncate(),
+                impulse * DAMAGE_PER_IMPULSE,
+                impulse >= CRIT_IMPULSE,
+            ),
+        );
+        numbers += 1;
+    }
+}
+
+fn animate_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<DamageNumberBundle>>,
+    mut number_query: Query<(Entity, &mut DamageNumber, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut number, mut transform, mut color) in &mut number_query {
+        number.timer.tick(time.delta());
+        let progress = number.timer.fraction();
+        let position = number.origin + Vec2::Y * DAMAGE_NUMBER_RISE * progress;
+        transform.translation = position.extend(DAMAGE_NUMBER_Z);
+        color.0 = number.color.with_alpha(1.0 - progress);
+
+        if number.timer.finished() {
+            pool.release(&mut commands, entity);
+        }
+    }
+}
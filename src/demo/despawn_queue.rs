@@ -0,0 +1,164 @@
+//! Spread large batches of despawns over several frames.
+//!
+//! When a whole section freezes solid or a wave's rubble is cleared, dozens of
+//! blocks go at once and despawning them all in one frame hitches. Blocks are
+//! instead hidden and taken out of the physics right away, then despawned a
+//! few per frame, each leaving a little debris behind as it goes. The debris
+//! is recycled through a [`Pool`].
+
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::CastleBlock,
+        pool::{Pool, Poolable},
+        rng::GameRng,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DespawnQueue>();
+    app.init_resource::<Pool<DebrisBundle>>();
+    app.add_systems(OnEnter(Screen::Gameplay), clear_despawn_queue);
+    app.add_systems(
+        Update,
+        (process_despawn_queue, animate_debris)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Entities despawned per frame.
+const DESPAWNS_PER_FRAME: usize = 8;
+const DEBRIS_PER_BLOCK: usize = 3;
+const DEBRIS_SIZE: f32 = 4.0;
+const DEBRIS_SPEED: f32 = 80.0;
+const DEBRIS_GRAVITY: f32 = 240.0;
+const DEBRIS_SECS: f32 = 0.6;
+
+/// An entity waiting to be despawned, and the debris it leaves behind.
+#[derive(Debug, Clone, Copy)]
+struct QueuedDespawn {
+    entity: Entity,
+    debris: Option<(Vec2, Color)>,
+}
+
+/// Entities waiting their turn to be despawned.
+#[derive(Resource, Default, Debug)]
+pub struct DespawnQueue {
+    pending: VecDeque<QueuedDespawn>,
+}
+
+impl DespawnQueue {
+    /// Take `entity` out of the game now and despawn it when its turn comes.
+    pub fn despawn(&mut self, commands: &mut Commands, entity: Entity) {
+        self.push(commands, entity, None);
+    }
+
+    /// Like [`DespawnQueue::despawn`], scattering debris of `color` at `position` when it goes.
+    pub fn despawn_with_debris(
+        &mut self,
+        commands: &mut Commands,
+        entity: Entity,
+        position: Vec2,
+        color: Color,
+    ) {
+        self.push(commands, entity, Some((position, color)));
+    }
+
+    /// Whether `entity` is waiting to be despawned.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.pending.iter().any(|queued| queued.entity == entity)
+    }
+
+    fn push(&mut self, commands: &mut Commands, entity: Entity, debris: Option<(Vec2, Color)>) {
+        if self.contains(entity) {
+            return;
+        }
+        // Removing `CastleBlock` takes it out of the castle grid and every castle system.
+        commands
+            .entity(entity)
+            .try_remove::<CastleBlock>()
+            .try_insert((Visibility::Hidden, RigidBodyDisabled, ColliderDisabled));
+        self.pending.push_back(QueuedDespawn { entity, debris });
+    }
+}
+
+/// A bit of rubble left by a despawned block, light enough to be blown about.
+#[derive(Component, Debug)]
+pub struct Debris {
+    pub velocity: Vec2,
+    timer: Timer,
+}
+
+#[derive(Bundle)]
+struct DebrisBundle {
+    name: Name,
+    debris: Debris,
+    sprite: Sprite,
+    transform: Transform,
+    state_scoped: StateScoped<Screen>,
+}
+
+impl Poolable for DebrisBundle {
+    fn reset(_entity: &mut EntityCommands) {}
+}
+
+fn clear_despawn_queue(mut queue: ResMut<DespawnQueue>) {
+    queue.pending.clear();
+}
+
+fn process_despawn_queue(
+    mut commands: Commands,
+    mut queue: ResMut<DespawnQueue>,
+    mut rng: ResMut<GameRng>,
+    mut pool: ResMut<Pool<DebrisBundle>>,
+) {
+    let count = queue.pending.len().min(DESPAWNS_PER_FRAME);
+    for QueuedDespawn { entity, debris } in queue.pending.drain(..count) {
+        commands.entity(entity).try_despawn();
+        let Some((position, color)) = debris else {
+            continue;
+        };
+        for _ in 0..DEBRIS_PER_BLOCK {
+            let angle = rng.gen_range(0.0..std::f32::consts::PI);
+            pool.acquire(
+                &mut commands,
+                DebrisBundle {
+                    name: Name::new("Debris"),
+                    debris: Debris {
+                        velocity: Vec2::from_angle(angle) * DEBRIS_SPEED,
+                        timer: Timer::from_seconds(DEBRIS_SECS, TimerMode::Once),
+                    },
+                    sprite: Sprite::from_color(color, Vec2::splat(DEBRIS_SIZE)),
+                    transform: Transform::from_translation(position.extend(1.0)),
+                    state_scoped: StateScoped(Screen::Gameplay),
+                },
+            );
+        }
+    }
+}
+
+fn animate_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<DebrisBundle>>,
+    mut debris_query: Query<(Entity, &mut Debris, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut debris, mut transform, mut sprite) in &mut debris_query {
+        debris.velocity.y -= DEBRIS_GRAVITY * time.delta_secs();
+        transform.translation += (debris.velocity * time.delta_secs()).extend(0.0);
+        if debris.timer.tick(time.delta()).finished() {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+        sprite.color.set_alpha(1.0 - debris.timer.fraction());
+    }
+}
@@ -0,0 +1,63 @@
+//! Deterministic mode, so two runs with the same inputs collapse castles the
+//! same way. Needed for replays and headless regression tests.
+//!
+//! Turn it on by setting the `GAME_DETERMINISTIC` environment variable, or by
+//! building with the `deterministic` feature, which also switches avian to its
+//! cross-platform math so runs match across machines too. In this mode:
+//! - Every frame advances time by exactly one physics step, however long it
+//!   really took, so frame-rate dependent systems see the same deltas.
+//! - Systems run one at a time, in the order they were added when nothing
+//!   else orders them, instead of whichever order the threads finish in.
+//! - The RNG is seeded with [`DETERMINISTIC_SEED`] every run, unless
+//!   `GAME_SEED` picks another seed.
+
+use std::time::Duration;
+
+use avian2d::{prelude::*, schedule::PhysicsSchedule};
+use bevy::{
+    ecs::schedule::{ExecutorKind, ScheduleLabel},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::demo::rng::{GameRng, RngSeed};
+
+pub(super) fn plugin(app: &mut App) {
+    if cfg!(feature = "deterministic") || std::env::var_os("GAME_DETERMINISTIC").is_some() {
+        info!("Running in deterministic mode");
+        make_deterministic(app);
+    }
+}
+
+/// The seed every deterministic run starts from.
+const DETERMINISTIC_SEED: u64 = 0;
+/// One physics step at avian's default fixed timestep of 64 Hz.
+const DETERMINISTIC_FRAME: Duration = Duration::from_micros(15_625);
+
+/// Put `app` in deterministic mode. Call it after adding the physics plugins,
+/// so it doesn't replace their schedule.
+pub fn make_deterministic(app: &mut App) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(DETERMINISTIC_FRAME));
+
+    for schedule in [
+        PreUpdate.intern(),
+        Update.intern(),
+        PostUpdate.intern(),
+        FixedPreUpdate.intern(),
+        FixedUpdate.intern(),
+        FixedPostUpdate.intern(),
+        PhysicsSchedule.intern(),
+    ] {
+        app.edit_schedule(schedule, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+    }
+
+    let seed = app
+        .world()
+        .get_resource::<RngSeed>()
+        .and_then(|seed| seed.fixed)
+        .unwrap_or(DETERMINISTIC_SEED);
+    app.insert_resource(RngSeed { fixed: Some(seed) });
+    app.insert_resource(GameRng::from_seed(seed));
+}
@@ -0,0 +1,283 @@
+//! Text boxes for dialogue and tutorial hints.
+//!
+//! Conversations live in `levels/level.dialogue.ron`, keyed by id. One is
+//! queued when the player walks into a trigger zone with the same id, or when
+//! any system sends [`ShowDialogue`]. Lines are typed out a letter at a time,
+//! and [`AdvanceDialogueAction`](crate::demo::input::AdvanceDialogueAction),
+//! Enter or Start on a gamepad, skips to the end of the line or moves on to
+//! the next one.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{prelude::*, ui::Val::*};
+use serde::Deserialize;
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::{LoadResource, LoadRonAsset},
+    demo::triggers::TriggerActivated,
+    screens::Screen,
+    theme::{palette::LABEL_TEXT, widget},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<DialogueScript>(&["dialogue.ron"]);
+    app.load_resource::<DialogueAssets>();
+    app.add_event::<ShowDialogue>();
+    app.init_resource::<DialogueQueue>();
+    app.add_systems(OnExit(Screen::Gameplay), clear_dialogue);
+    app.add_systems(
+        Update,
+        advance_dialogue
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        (
+            queue_dialogue.run_if(resource_exists::<DialogueAssets>),
+            show_next_line,
+            type_out_line,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const LETTERS_PER_SEC: f32 = 40.0;
+const PORTRAIT_SIZE: f32 = 96.0;
+const DIALOGUE_BOX_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.75);
+
+/// One text box of a conversation.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueLine {
+    pub speaker: String,
+    pub text: String,
+    #[serde(default)]
+    pub portrait: Option<Portrait>,
+}
+
+/// An image shown next to a line, optionally cropped to `rect` for sprite sheets.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Portrait {
+    pub image: String,
+    /// `(min_x, min_y, max_x, max_y)` in pixels.
+    #[serde(default)]
+    pub rect: Option<(f32, f32, f32, f32)>,
+}
+
+/// Every conversation in the game, keyed by id.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct DialogueScript {
+    pub conversations: HashMap<String, Vec<DialogueLine>>,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct DialogueAssets {
+    #[dependency]
+    script: Handle<DialogueScript>,
+}
+
+impl FromWorld for DialogueAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            script: assets.load("levels/level.dialogue.ron"),
+        }
+    }
+}
+
+/// Queue the conversation with this id, e.g. from a scripted sequence.
+#[derive(Event, Debug, Clone)]
+pub struct ShowDialogue {
+    pub id: String,
+}
+
+/// Lines waiting to be shown, and the one on screen.
+#[derive(Resource, Default, Debug)]
+pub struct DialogueQueue {
+    pending: VecDeque<DialogueLine>,
+    current: Option<Entity>,
+    advance_requested: bool,
+}
+
+impl DialogueQueue {
+    /// Whether a text box is up or waiting to be shown.
+    pub fn is_active(&self) -> bool {
+        self.current.is_some() || !self.pending.is_empty()
+    }
+
+    /// Finish typing the current line, or close it if it's already finished.
+    pub fn request_advance(&mut self) {
+        self.advance_requested = true;
+    }
+}
+
+/// The text box on screen, typing out its line.
+#[derive(Component, Debug)]
+struct DialogueBox {
+    text: String,
+    letters_shown: f32,
+}
+
+impl DialogueBox {
+    fn is_finished(&self) -> bool {
+        self.letters_shown as usize >= self.text.chars().count()
+    }
+}
+
+/// The part of the box the line is typed into.
+#[derive(Component, Debug)]
+struct DialogueText;
+
+fn clear_dialogue(mut queue: ResMut<DialogueQueue>) {
+    *queue = DialogueQueue::default();
+}
+
+fn queue_dialogue(
+    mut show_events: EventReader<ShowDialogue>,
+    mut trigger_events: EventReader<TriggerActivated>,
+    dialogue_assets: Res<DialogueAssets>,
+    scripts: Res<Assets<DialogueScript>>,
+    mut queue: ResMut<DialogueQueue>,
+) {
+    let Some(script) = scripts.get(&dialogue_assets.script) else {
+        return;
+    };
+    let requested = show_events.read().map(|event| (&event.id, true));
+    // Trigger zones don't all have something to say.
+    let triggered = trigger_events.read().map(|event| (&event.id, false));
+    for (id, required) in requested.chain(triggered) {
+        match script.conversations.get(id) {
+            Some(lines) => queue.pending.extend(lines.iter().cloned()),
+            None if required => warn!("No dialogue with id {id}"),
+            None => {}
+        }
+    }
+}
+
+fn show_next_line(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut queue: ResMut<DialogueQueue>,
+) {
+    if queue.current.is_some() {
+        return;
+    }
+    let Some(line) = queue.pending.pop_front() else {
+        return;
+    };
+    let portrait = line.portrait.map(|portrait| ImageNode {
+        image: asset_server.load(portrait.image),
+        rect: portrait
+            .rect
+            .map(|(min_x, min_y, max_x, max_y)| Rect::new(min_x, min_y, max_x, max_y)),
+        ..default()
+    });
+    let dialogue_box = commands
+        .spawn((
+            Name::new("Dialogue Box"),
+            DialogueBox {
+                text: line.text,
+                letters_shown: 0.0,
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Px(24.0),
+                left: Percent(15.0),
+                width: Percent(70.0),
+                padding: UiRect::all(Px(16.0)),
+                column_gap: Px(16.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(DIALOGUE_BOX_COLOR),
+            BorderRadius::all(Px(8.0)),
+            GlobalZIndex(3),
+            StateScoped(Screen::Gameplay),
+        ))
+        .with_children(|parent| {
+            if let Some(portrait) = portrait {
+                parent.spawn((
+                    Name::new("Portrait"),
+                    portrait,
+                    Node {
+                        width: Px(PORTRAIT_SIZE),
+                        height: Px(PORTRAIT_SIZE),
+                        flex_shrink: 0.0,
+                        ..default()
+                    },
+                ));
+            }
+            parent
+                .spawn((
+                    Name::new("Dialogue Lines"),
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Px(8.0),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn(widget::label(line.speaker));
+                    parent.spawn((
+                        Name::new("Dialogue Text"),
+                        DialogueText,
+                        Text::default(),
+                        TextFont::from_font_size(20.0),
+                        TextColor(LABEL_TEXT),
+                    ));
+                });
+        })
+        .id();
+    queue.current = Some(dialogue_box);
+}
+
+fn type_out_line(
+    time: Res<Time>,
+    mut box_query: Query<&mut DialogueBox>,
+    mut text_query: Query<&mut Text, With<DialogueText>>,
+) {
+    let Ok(mut dialogue_box) = box_query.single_mut() else {
+        return;
+    };
+    let letter_count = dialogue_box.text.chars().count() as f32;
+    dialogue_box.letters_shown =
+        (dialogue_box.letters_shown + LETTERS_PER_SEC * time.delta_secs()).min(letter_count);
+    let shown: String = dialogue_box
+        .text
+        .chars()
+        .take(dialogue_box.letters_shown as usize)
+        .collect();
+    for mut text in &mut text_query {
+        if text.0 != shown {
+            text.0 = shown.clone();
+        }
+    }
+}
+
+/// Finish typing the current line, or close it if it's already finished.
+fn advance_dialogue(
+    mut commands: Commands,
+    mut queue: ResMut<DialogueQueue>,
+    mut box_query: Query<&mut DialogueBox>,
+) {
+    if !std::mem::take(&mut queue.advance_requested) {
+        return;
+    }
+    let Some(current) = queue.current else {
+        return;
+    };
+    let Ok(mut dialogue_box) = box_query.get_mut(current) else {
+        queue.current = None;
+        return;
+    };
+    if dialogue_box.is_finished() {
+        commands.entity(current).despawn();
+        queue.current = None;
+    } else {
+        dialogue_box.letters_shown = dialogue_box.text.chars().count() as f32;
+    }
+}
@@ -0,0 +1,140 @@
+//! How hard the current run is.
+//!
+//! The difficulty is picked on the level select screen. Each one scales mortar
+//! strength, the damage the player takes, ability cooldowns and par times by
+//! multipliers loaded from `difficulty.ron`, so presets can be tuned without
+//! recompiling.
+
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::asset_tracking::{LoadResource, LoadRonAsset};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Difficulty>();
+    app.init_resource::<Difficulty>();
+    app.init_ron_asset::<DifficultyConfig>(&["difficulty.ron"]);
+    app.load_resource::<DifficultyConfigAssets>();
+}
+
+#[derive(
+    Resource, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+#[reflect(Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Parse a difficulty from its name, as written in LDtk fields.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Easy" => Some(Self::Easy),
+            "Normal" => Some(Self::Normal),
+            "Hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// The key a level's records, ghost and leaderboard are kept under on this
+    /// difficulty, so runs are only ever compared with runs on the same one.
+    ///
+    /// Normal keeps the bare level identifier, where runs saved before there
+    /// were difficulties already are.
+    pub fn record_key(self, level: &str) -> String {
+        match self {
+            Difficulty::Normal => level.to_string(),
+            _ => format!("{level}_{}", self.name()),
+        }
+    }
+
+    /// The difficulty after this one, for cycling through them in menus.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|difficulty| *difficulty == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// How much one difficulty scales the game by, 1 being normal.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct DifficultyMultipliers {
+    /// How much force it takes to break the castle's mortar.
+    pub mortar_strength: f32,
+    /// Damage the player takes.
+    pub damage_taken: f32,
+    /// How long ability cooldowns last.
+    pub cooldown: f32,
+    /// How long the par times are.
+    pub par_time: f32,
+}
+
+impl Default for DifficultyMultipliers {
+    fn default() -> Self {
+        Self {
+            mortar_strength: 1.0,
+            damage_taken: 1.0,
+            cooldown: 1.0,
+            par_time: 1.0,
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct DifficultyConfig {
+    #[serde(default)]
+    pub presets: HashMap<Difficulty, DifficultyMultipliers>,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct DifficultyConfigAssets {
+    #[dependency]
+    pub config: Handle<DifficultyConfig>,
+}
+
+impl FromWorld for DifficultyConfigAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            config: assets.load("difficulty.ron"),
+        }
+    }
+}
+
+/// Looks up the multipliers for the current difficulty, falling back to
+/// normal until the config has loaded.
+#[derive(SystemParam)]
+pub struct DifficultyTuning<'w> {
+    difficulty: Res<'w, Difficulty>,
+    handles: Option<Res<'w, DifficultyConfigAssets>>,
+    configs: Res<'w, Assets<DifficultyConfig>>,
+}
+
+impl DifficultyTuning<'_> {
+    pub fn multipliers(&self) -> DifficultyMultipliers {
+        self.handles
+            .as_ref()
+            .and_then(|handles| self.configs.get(&handles.config))
+            .and_then(|config| config.presets.get(&*self.difficulty).copied())
+            .unwrap_or_default()
+    }
+}
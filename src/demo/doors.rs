@@ -0,0 +1,216 @@
+//! Locked doors and the keys that open them, placed in LDtk.
+//!
+//! Both entities have a `KeyId` string field. Keys go into the player's
+//! [`Inventory`] when touched, and a door opens once the player bumps into it
+//! while holding a key with the same id. Doors also open when a trigger zone
+//! with a matching id is activated, which makes for simple pressure plates.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::sound_effect,
+    demo::{
+        pickups::{PickupAssets, PickupSensor},
+        player::{Inventory, Player},
+        triggers::TriggerActivated,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<KeyBundle>("Key");
+    app.register_ldtk_entity::<DoorBundle>("Door");
+    app.add_systems(
+        Update,
+        (
+            collect_keys.run_if(resource_exists::<PickupAssets>),
+            unlock_doors,
+            open_doors_on_trigger,
+            animate_opening_doors,
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const KEY_COLOR: Color = Color::srgb(0.9, 0.75, 0.2);
+const DOOR_COLOR: Color = Color::srgb(0.55, 0.35, 0.2);
+const DOOR_OPEN_DURATION: Duration = Duration::from_millis(400);
+
+fn key_id(entity_instance: &EntityInstance) -> String {
+    entity_instance
+        .get_string_field("KeyId")
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct Key {
+    pub id: String,
+}
+
+impl From<&EntityInstance> for Key {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            id: key_id(entity_instance),
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct KeyBundle {
+    #[from_entity_instance]
+    pub key: Key,
+    pub sprite: Sprite,
+    pub pickup_sensor: PickupSensor,
+}
+
+impl Default for KeyBundle {
+    fn default() -> Self {
+        Self {
+            key: Key::default(),
+            sprite: Sprite::from_color(KEY_COLOR, Vec2::new(6.0, 12.0)),
+            pickup_sensor: PickupSensor::default(),
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct Door {
+    /// The id of the key that opens this door.
+    pub key_id: String,
+}
+
+impl From<&EntityInstance> for Door {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            key_id: key_id(entity_instance),
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct DoorBundle {
+    #[from_entity_instance]
+    pub door: Door,
+    #[with(door_sprite)]
+    pub sprite: Sprite,
+    #[with(door_collider)]
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+    pub colliding_entities: CollidingEntities,
+}
+
+impl Default for DoorBundle {
+    fn default() -> Self {
+        Self {
+            door: Door::default(),
+            sprite: Sprite::default(),
+            collider: Collider::rectangle(16.0, 16.0),
+            rigid_body: RigidBody::Static,
+            colliding_entities: CollidingEntities::default(),
+        }
+    }
+}
+
+fn door_sprite(entity_instance: &EntityInstance) -> Sprite {
+    Sprite::from_color(
+        DOOR_COLOR,
+        Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+    )
+}
+
+fn door_collider(entity_instance: &EntityInstance) -> Collider {
+    Collider::rectangle(entity_instance.width as f32, entity_instance.height as f32)
+}
+
+/// A door that has been unlocked and is playing its opening animation.
+#[derive(Component, Debug)]
+struct DoorOpening {
+    timer: Timer,
+}
+
+fn collect_keys(
+    mut commands: Commands,
+    pickup_assets: Res<PickupAssets>,
+    key_query: Query<(Entity, &Key, &CollidingEntities)>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+) {
+    for (key_entity, key, colliding_entities) in &key_query {
+        for entity in colliding_entities.iter() {
+            let Ok(mut inventory) = player_query.get_mut(*entity) else {
+                continue;
+            };
+            info!("Picked up key {:?}", key.id);
+            inventory.keys.insert(key.id.clone());
+            commands.spawn(sound_effect(pickup_assets.pickup.clone()));
+            commands.entity(key_entity).despawn();
+            break;
+        }
+    }
+}
+
+fn unlock_doors(
+    mut commands: Commands,
+    door_query: Query<(Entity, &Door, &CollidingEntities), Without<DoorOpening>>,
+    player_query: Query<&Inventory, With<Player>>,
+) {
+    for (door_entity, door, colliding_entities) in &door_query {
+        let has_key = colliding_entities.iter().any(|entity| {
+            player_query
+                .get(*entity)
+                .is_ok_and(|inventory| inventory.keys.contains(&door.key_id))
+        });
+        if !has_key {
+            continue;
+        }
+        info!("Door {:?} unlocked with key {:?}", door_entity, door.key_id);
+        open_door(&mut commands, door_entity);
+    }
+}
+
+fn open_doors_on_trigger(
+    mut commands: Commands,
+    mut trigger_events: EventReader<TriggerActivated>,
+    door_query: Query<(Entity, &Door), Without<DoorOpening>>,
+) {
+    for event in trigger_events.read() {
+        for (door_entity, door) in &door_query {
+            if door.key_id == event.id {
+                info!("Door {:?} opened by trigger {:?}", door_entity, event.id);
+                open_door(&mut commands, door_entity);
+            }
+        }
+    }
+}
+
+fn open_door(commands: &mut Commands, door_entity: Entity) {
+    // Let the player through straight away, the animation is just for show.
+    commands
+        .entity(door_entity)
+        .remove::<(Collider, RigidBody)>()
+        .insert(DoorOpening {
+            timer: Timer::new(DOOR_OPEN_DURATION, TimerMode::Once),
+        });
+}
+
+fn animate_opening_doors(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut door_query: Query<(Entity, &mut DoorOpening, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut opening, mut transform, mut sprite) in &mut door_query {
+        opening.timer.tick(time.delta());
+        // Slide the door up into the ceiling while fading it out.
+        let remaining = 1.0 - opening.timer.fraction();
+        transform.scale.y = remaining.max(0.01);
+        sprite.color.set_alpha(remaining);
+        if opening.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
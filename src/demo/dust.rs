@@ -0,0 +1,144 @@
+//! Dust puffs and crumbling sounds where castle mortar breaks.
+//!
+//! A whole wall coming down can break hundreds of joints in one frame, so only
+//! so many puffs are shown at once, recycled through a [`Pool`], and only a few
+//! crumble sounds are started per frame.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    audio::spatial_sound_effect,
+    demo::{
+        castle::JointBroken,
+        pool::{Pool, Poolable},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DustAssets>();
+    app.load_resource::<DustAssets>();
+    app.register_type::<DustPuff>();
+    app.init_resource::<Pool<DustPuffBundle>>();
+    app.add_systems(
+        Update,
+        (
+            spawn_dust_on_broken_joints,
+            play_crumble_sounds.run_if(resource_exists::<DustAssets>),
+            animate_dust_puffs,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Puffs that can be on screen at once. Extra breaks while they're all in use go without.
+const MAX_DUST_PUFFS: usize = 64;
+const MAX_CRUMBLE_SOUNDS_PER_FRAME: usize = 2;
+const DUST_PUFF_SECS: f32 = 0.5;
+const DUST_PUFF_SIZE: f32 = 6.0;
+/// How much bigger a puff is at the end of its animation than at the start.
+const DUST_PUFF_GROWTH: f32 = 2.5;
+const DUST_COLOR: Color = Color::srgba(0.75, 0.7, 0.6, 0.8);
+/// Puffs are drawn in front of the castle blocks.
+const DUST_Z: f32 = 10.0;
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct DustAssets {
+    #[dependency]
+    crumble: Handle<AudioSource>,
+}
+
+impl FromWorld for DustAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            // Placeholder until we have a dedicated crumble sound.
+            crumble: assets.load("audio/sound_effects/step3.ogg"),
+        }
+    }
+}
+
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+struct DustPuff {
+    timer: Timer,
+}
+
+#[derive(Bundle)]
+struct DustPuffBundle {
+    name: Name,
+    puff: DustPuff,
+    sprite: Sprite,
+    transform: Transform,
+    state_scoped: StateScoped<Screen>,
+}
+
+impl DustPuffBundle {
+    fn new(position: Vec2) -> Self {
+        Self {
+            name: Name::new("Dust Puff"),
+            puff: DustPuff {
+                timer: Timer::from_seconds(DUST_PUFF_SECS, TimerMode::Once),
+            },
+            sprite: Sprite::from_color(DUST_COLOR, Vec2::splat(DUST_PUFF_SIZE)),
+            transform: Transform::from_translation(position.extend(DUST_Z)),
+            state_scoped: StateScoped(Screen::Gameplay),
+        }
+    }
+}
+
+impl Poolable for DustPuffBundle {
+    fn reset(_entity: &mut EntityCommands) {}
+}
+
+fn spawn_dust_on_broken_joints(
+    mut commands: Commands,
+    mut broken_events: EventReader<JointBroken>,
+    mut pool: ResMut<Pool<DustPuffBundle>>,
+    puff_query: Query<(), With<DustPuff>>,
+) {
+    let mut puffs = puff_query.iter().count();
+    for event in broken_events.read() {
+        if puffs >= MAX_DUST_PUFFS {
+            break;
+        }
+        pool.acquire(&mut commands, DustPuffBundle::new(event.position));
+        puffs += 1;
+    }
+}
+
+fn play_crumble_sounds(
+    mut commands: Commands,
+    mut broken_events: EventReader<JointBroken>,
+    dust_assets: Res<DustAssets>,
+) {
+    for event in broken_events.read().take(MAX_CRUMBLE_SOUNDS_PER_FRAME) {
+        commands.spawn(spatial_sound_effect(
+            dust_assets.crumble.clone(),
+            event.position,
+        ));
+    }
+}
+
+fn animate_dust_puffs(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<DustPuffBundle>>,
+    mut puff_query: Query<(Entity, &mut DustPuff, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut puff, mut transform, mut sprite) in &mut puff_query {
+        puff.timer.tick(time.delta());
+        let progress = puff.timer.fraction();
+        transform.scale = Vec3::splat(1.0 + progress * (DUST_PUFF_GROWTH - 1.0));
+        sprite.color = DUST_COLOR.with_alpha(DUST_COLOR.alpha() * (1.0 - progress));
+
+        if puff.timer.finished() {
+            pool.release(&mut commands, entity);
+        }
+    }
+}
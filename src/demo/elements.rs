@@ -0,0 +1,351 @@
+//! How the elements interact when they meet on the same block.
+//!
+//! Each rule is a system that looks for a combination of status effects:
+//! - A fireball hitting a frozen block shatters it outright.
+//! - A fireball sets the blocks it touches [`Burning`], and frost landing on a
+//!   burning block puts the fire out in a cloud of steam that hides the view.
+//! - Wooden blocks burn for real: the fire eats through their mortar, spreads
+//!   to the wooden blocks next to them, and leaves only charred debris.
+//! - Lightning arcs from the block it touches to its neighbours, and much
+//!   further through blocks that are wet.
+
+use std::collections::VecDeque;
+
+use avian2d::prelude::*;
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::spatial_sound_effect,
+    demo::{
+        balistics::{Fireball, SfxAssets},
+        blueprint::BlockMaterial,
+        castle::{BlockDestroyed, CastleBlock, CastleGrid},
+        collision::{FrostEffect, ShockwaveHit},
+        despawn_queue::DespawnQueue,
+        fluids::Submerged,
+        player::LightningState,
+        tint::{TintLayer, Tints},
+    },
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Burning>();
+    app.register_type::<Shocked>();
+    app.register_type::<SteamCloud>();
+    app.add_systems(
+        Update,
+        (
+            shatter_frozen_blocks.run_if(resource_exists::<SfxAssets>),
+            ignite_blocks,
+            quench_burning_blocks,
+            spread_fire,
+            arc_lightning,
+            tick_burning,
+            tick_shocked,
+            fade_steam_clouds,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const BURN_SECS: f32 = 4.0;
+/// Wooden blocks burn for longer, until there's nothing left of them.
+const WOOD_BURN_SECS: f32 = 6.0;
+/// How often a burning wooden block takes fire damage.
+const WOOD_BURN_DAMAGE_SECS: f32 = 0.5;
+/// Impulse worn off a burning wooden block's mortar each time it takes fire damage.
+const WOOD_BURN_DAMAGE: f32 = 1000.0;
+/// How long a wooden block burns before the fire spreads to the wooden blocks around it.
+const FIRE_SPREAD_SECS: f32 = 1.5;
+const CHARRED_COLOR: Color = Color::srgb(0.15, 0.12, 0.1);
+const STEAM_SECS: f32 = 3.0;
+const STEAM_SIZE: f32 = 96.0;
+const STEAM_COLOR: Color = Color::srgba(0.9, 0.9, 0.95, 0.85);
+/// Steam is drawn in front of everything else in the level so it blocks the view.
+const STEAM_Z: f32 = 50.0;
+/// Impulse each block an arc passes through takes, worn off its mortar like a shockwave.
+const LIGHTNING_ARC_IMPULSE: f32 = 2500.0;
+/// How many blocks away from the one it touched lightning can arc through dry blocks.
+const DRY_ARC_STEPS: usize = 1;
+/// How many blocks away lightning can arc while it stays within wet blocks.
+const WET_ARC_STEPS: usize = 6;
+/// A shocked block can't be arced through again for this long.
+const SHOCK_COOLDOWN_SECS: f32 = 1.0;
+
+/// A block set alight by a fireball, or by the wooden block next to it.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Burning {
+    timer: Timer,
+    /// Wooden blocks take fire damage each time this goes off.
+    damage: Timer,
+    /// Wooden blocks set their wooden neighbours alight when this goes off.
+    spread: Timer,
+}
+
+impl Burning {
+    fn new(material: BlockMaterial) -> Self {
+        let burn_secs = match material {
+            BlockMaterial::Stone => BURN_SECS,
+            BlockMaterial::Wood => WOOD_BURN_SECS,
+        };
+        Self {
+            timer: Timer::from_seconds(burn_secs, TimerMode::Once),
+            damage: Timer::from_seconds(WOOD_BURN_DAMAGE_SECS, TimerMode::Repeating),
+            spread: Timer::from_seconds(FIRE_SPREAD_SECS, TimerMode::Once),
+        }
+    }
+}
+
+/// A block lightning has recently arced through.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct Shocked {
+    timer: Timer,
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct SteamCloud {
+    timer: Timer,
+}
+
+fn shatter_frozen_blocks(
+    mut commands: Commands,
+    sfx_assets: Res<SfxAssets>,
+    settings: Res<Settings>,
+    fireball_query: Query<&CollidingEntities, With<Fireball>>,
+    frozen_blocks: Query<(&GlobalTransform, &Mass), (With<CastleBlock>, With<FrostEffect>)>,
+    mut destroyed_events: EventWriter<BlockDestroyed>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    let mut shattered = HashSet::new();
+    for colliding_entities in &fireball_query {
+        for &block in colliding_entities.iter() {
+            let Ok((transform, mass)) = frozen_blocks.get(block) else {
+                continue;
+            };
+            if !shattered.insert(block) {
+                continue;
+            }
+            info!("Fireball shattered frozen block {:?}", block);
+            commands.spawn(spatial_sound_effect(
+                sfx_assets.frost_shatter.clone(),
+                transform.translation().truncate(),
+            ));
+            destroyed_events.write(BlockDestroyed {
+                block,
+                mass: mass.0,
+            });
+            despawn_queue.despawn_with_debris(
+                &mut commands,
+                block,
+                transform.translation().truncate(),
+                TintLayer::Frost.color(settings.color_palette),
+            );
+        }
+    }
+}
+
+fn ignite_blocks(
+    mut commands: Commands,
+    fireball_query: Query<&CollidingEntities, With<Fireball>>,
+    mut block_query: Query<
+        (&mut Tints, Option<&BlockMaterial>),
+        (With<CastleBlock>, Without<FrostEffect>, Without<Burning>),
+    >,
+) {
+    for colliding_entities in &fireball_query {
+        for &block in colliding_entities.iter() {
+            let Ok((mut tints, material)) = block_query.get_mut(block) else {
+                continue;
+            };
+            commands
+                .entity(block)
+                .try_insert(Burning::new(material.copied().unwrap_or_default()));
+            tints.set(TintLayer::Burning, 1.0);
+        }
+    }
+}
+
+fn quench_burning_blocks(
+    mut commands: Commands,
+    mut query: Query<(Entity, &GlobalTransform, &mut Tints), (With<Burning>, Added<FrostEffect>)>,
+) {
+    for (entity, transform, mut tints) in &mut query {
+        info!("Frost quenched burning block {:?}", entity);
+        tints.clear(TintLayer::Burning);
+        commands
+            .entity(entity)
+            .try_remove::<(Burning, FrostEffect)>();
+        commands.spawn((
+            Name::new("Steam Cloud"),
+            SteamCloud {
+                timer: Timer::from_seconds(STEAM_SECS, TimerMode::Once),
+            },
+            Sprite::from_color(STEAM_COLOR, Vec2::splat(STEAM_SIZE)),
+            Transform::from_translation(transform.translation().truncate().extend(STEAM_Z)),
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+}
+
+/// Burn through the mortar of wooden blocks on fire, and pass the fire on to
+/// the wooden blocks around them.
+fn spread_fire(
+    mut commands: Commands,
+    time: Res<Time>,
+    castle_grid: Res<CastleGrid>,
+    mut burning_query: Query<(Entity, &mut Burning, &BlockMaterial), With<CastleBlock>>,
+    mut unlit_query: Query<
+        (&BlockMaterial, &mut Tints),
+        (With<CastleBlock>, Without<Burning>, Without<FrostEffect>),
+    >,
+) {
+    let mut ignited = HashSet::new();
+    for (block, mut burning, material) in &mut burning_query {
+        if *material != BlockMaterial::Wood {
+            continue;
+        }
+        burning.damage.tick(time.delta());
+        if burning.damage.just_finished() {
+            // Fire wears the mortar down from the inside, so it has no direction.
+            commands.entity(block).try_insert(ShockwaveHit {
+                impulse: Vec2::Y * WOOD_BURN_DAMAGE,
+            });
+        }
+
+        burning.spread.tick(time.delta());
+        if !burning.spread.just_finished() {
+            continue;
+        }
+        for neighbor in castle_grid.neighbors(block) {
+            let Ok((neighbor_material, mut tints)) = unlit_query.get_mut(neighbor) else {
+                continue;
+            };
+            if *neighbor_material != BlockMaterial::Wood || !ignited.insert(neighbor) {
+                continue;
+            }
+            commands
+                .entity(neighbor)
+                .try_insert(Burning::new(BlockMaterial::Wood));
+            tints.set(TintLayer::Burning, 1.0);
+        }
+    }
+}
+
+fn arc_lightning(
+    mut commands: Commands,
+    castle_grid: Res<CastleGrid>,
+    lightning_query: Query<&GlobalTransform, With<LightningState>>,
+    block_query: Query<
+        (Entity, &GlobalTransform, &CollidingEntities, Has<Submerged>),
+        (With<CastleBlock>, Without<Shocked>),
+    >,
+) {
+    // Start from every block a lightning bolt is touching, arcing away from that bolt
+    let mut frontier: VecDeque<(Entity, Vec2, usize)> = block_query
+        .iter()
+        .filter_map(|(block, _, colliding_entities, _)| {
+            let lightning_transform = lightning_query
+                .iter_many(colliding_entities.iter())
+                .next()?;
+            Some((block, lightning_transform.translation().truncate(), 0))
+        })
+        .collect();
+    let mut shocked = HashSet::new();
+
+    // Walk outwards from the touched blocks, dry blocks stop the arc sooner than wet ones
+    while let Some((block, origin, steps)) = frontier.pop_front() {
+        let Ok((_, block_transform, _, wet)) = block_query.get(block) else {
+            continue;
+        };
+        if !shocked.insert(block) {
+            continue;
+        }
+        let direction = (block_transform.translation().truncate() - origin).normalize_or_zero();
+        commands.entity(block).try_insert((
+            ShockwaveHit {
+                impulse: direction * LIGHTNING_ARC_IMPULSE,
+            },
+            Shocked {
+                timer: Timer::from_seconds(SHOCK_COOLDOWN_SECS, TimerMode::Once),
+            },
+        ));
+
+        let max_steps = if wet { WET_ARC_STEPS } else { DRY_ARC_STEPS };
+        if steps >= max_steps {
+            continue;
+        }
+        for neighbor in castle_grid.neighbors(block) {
+            frontier.push_back((neighbor, origin, steps + 1));
+        }
+    }
+}
+
+/// Put out fires that have burned out. Wooden blocks are consumed by then.
+fn tick_burning(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut Burning,
+        &mut Tints,
+        &GlobalTransform,
+        Option<&BlockMaterial>,
+        Option<&Mass>,
+    )>,
+    mut destroyed_events: EventWriter<BlockDestroyed>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+) {
+    for (entity, mut burning, mut tints, transform, material, mass) in &mut query {
+        burning.timer.tick(time.delta());
+        if !burning.timer.finished() {
+            continue;
+        }
+        if material == Some(&BlockMaterial::Wood) {
+            destroyed_events.write(BlockDestroyed {
+                block: entity,
+                mass: mass.map_or(0.0, |mass| mass.0),
+            });
+            despawn_queue.despawn_with_debris(
+                &mut commands,
+                entity,
+                transform.translation().truncate(),
+                CHARRED_COLOR,
+            );
+            continue;
+        }
+        tints.clear(TintLayer::Burning);
+        commands.entity(entity).try_remove::<Burning>();
+    }
+}
+
+fn tick_shocked(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mut Shocked)>) {
+    for (entity, mut shocked) in &mut query {
+        shocked.timer.tick(time.delta());
+        if shocked.timer.finished() {
+            commands.entity(entity).try_remove::<Shocked>();
+        }
+    }
+}
+
+fn fade_steam_clouds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut SteamCloud, &mut Sprite)>,
+) {
+    for (entity, mut steam, mut sprite) in &mut query {
+        steam.timer.tick(time.delta());
+        let remaining = 1.0 - steam.timer.fraction();
+        sprite.color = STEAM_COLOR.with_alpha(STEAM_COLOR.alpha() * remaining);
+        if steam.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Endless mode: survive wave after wave of castles.
+//!
+//! Once the level's castle has been brought down, a new one is generated in
+//! its place from a procedural [`CastleBlueprint`], taller with every wave.
+//! Boulders are lobbed at the player faster and harder as the waves go on,
+//! and the run ends the first time the player dies. The throwers can't see
+//! through [`Smoke`], so while the player hides in it they aim at wherever
+//! they last saw them.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        blueprint::{BlockMaterial, BlueprintBlock, CastleBlueprint, SpawnBlueprint},
+        castle::{BlockCleared, BlockSize, CastleBlock, CastleReady},
+        collision::{ContactDamage, HostileProjectile},
+        despawn_queue::DespawnQueue,
+        game_mode::{GameMode, game_mode_is},
+        hud::{HudAnchor, HudRegion},
+        player::{Player, PlayerIndex},
+        rng::GameRng,
+        score::Score,
+        sections::CastleSections,
+        smoke::Smoke,
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<EndlessRun>();
+    app.init_resource::<EndlessRun>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (reset_endless_run, spawn_wave_ui).run_if(game_mode_is(GameMode::Endless)),
+    );
+    app.add_systems(
+        Update,
+        (advance_waves, throw_boulders, expire_boulders)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(game_mode_is(GameMode::Endless)),
+    );
+    app.add_systems(
+        Update,
+        update_wave_ui.run_if(in_state(Screen::Gameplay).and(resource_changed::<EndlessRun>)),
+    );
+}
+
+/// Seconds between clearing a wave and the next castle going up.
+const WAVE_BREAK_SECS: f32 = 3.0;
+/// Bonus for clearing a wave, multiplied by the wave number.
+const POINTS_PER_WAVE: u32 = 500;
+const CELL_SIZE: f32 = 16.0;
+/// Castle width used if the level's castle couldn't be measured.
+const DEFAULT_CASTLE_COLUMNS: i32 = 8;
+/// Tallest column in the first generated castle, growing by one each wave.
+const BASE_CASTLE_HEIGHT: i32 = 3;
+const MAX_CASTLE_HEIGHT: i32 = 16;
+const BOULDER_BASE_INTERVAL_SECS: f32 = 4.0;
+/// How much faster boulders come with each wave.
+const BOULDER_RATE_PER_WAVE: f32 = 0.3;
+const BOULDER_BASE_DAMAGE: f32 = 10.0;
+const BOULDER_DAMAGE_PER_WAVE: f32 = 5.0;
+const BOULDER_RADIUS: f32 = 6.0;
+const BOULDER_COLOR: Color = Color::srgb(0.45, 0.4, 0.35);
+/// Where boulders are thrown from, relative to the player.
+const BOULDER_LAUNCH_OFFSET: Vec2 = Vec2::new(240.0, 160.0);
+const BOULDER_SPEED: f32 = 260.0;
+const BOULDER_LIFETIME_SECS: f32 = 6.0;
+
+#[derive(Reflect, Debug, Clone, Default)]
+enum WavePhase {
+    /// Waiting for the wave's castle to be mortared together.
+    #[default]
+    Building,
+    Fighting,
+    /// The castle is down, the next one goes up when the timer finishes.
+    Break(Timer),
+}
+
+/// The state of the current endless run.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct EndlessRun {
+    /// The wave being fought, starting from 1 with the level's own castle.
+    pub wave: u32,
+    phase: WavePhase,
+    /// Bottom left corner of the level's castle, where new castles are built.
+    castle_origin: Option<Vec2>,
+    castle_columns: i32,
+    boulder_timer: Timer,
+    /// Where the boulder throwers last saw the player, for aiming through smoke.
+    last_seen_player: Option<Vec2>,
+}
+
+impl Default for EndlessRun {
+    fn default() -> Self {
+        Self {
+            wave: 1,
+            phase: WavePhase::default(),
+            castle_origin: None,
+            castle_columns: DEFAULT_CASTLE_COLUMNS,
+            boulder_timer: Timer::from_seconds(BOULDER_BASE_INTERVAL_SECS, TimerMode::Once),
+            last_seen_player: None,
+        }
+    }
+}
+
+/// A boulder thrown at the player, despawned after a while so they don't pile up.
+#[derive(Component, Debug)]
+struct Boulder {
+    timer: Timer,
+}
+
+fn reset_endless_run(mut run: ResMut<EndlessRun>) {
+    *run = EndlessRun::default();
+}
+
+fn advance_waves(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut run: ResMut<EndlessRun>,
+    mut rng: ResMut<GameRng>,
+    mut score: ResMut<Score>,
+    sections: Res<CastleSections>,
+    mut castle_ready: EventReader<CastleReady>,
+    block_query: Query<(Entity, &GlobalTransform, &BlockSize), With<CastleBlock>>,
+    mut spawn_events: EventWriter<SpawnBlueprint>,
+    mut despawn_queue: ResMut<DespawnQueue>,
+    mut cleared_events: EventWriter<BlockCleared>,
+) {
+    let run = &mut *run;
+    match &mut run.phase {
+        WavePhase::Building => {
+            if castle_ready.read().count() == 0 {
+                return;
+            }
+            if run.castle_origin.is_none() {
+                measure_castle_site(run, &block_query);
+            }
+            run.phase = WavePhase::Fighting;
+        }
+        WavePhase::Fighting => {
+            if sections.all_destroyed() {
+                info!("Cleared endless wave {}", run.wave);
+                score.add(POINTS_PER_WAVE * run.wave);
+                run.phase = WavePhase::Break(Timer::from_seconds(WAVE_BREAK_SECS, TimerMode::Once));
+            }
+        }
+        WavePhase::Break(timer) => {
+            if !timer.tick(time.delta()).finished() {
+                return;
+            }
+            // Clear away the rubble before the next castle goes up.
+            for (block, ..) in &block_query {
+                cleared_events.write(BlockCleared { block });
+                despawn_queue.despawn(&mut commands, block);
+            }
+            run.wave += 1;
+            spawn_events.write(SpawnBlueprint {
+                blueprint: generate_castle(&mut rng, run.wave, run.castle_columns),
+                position: run.castle_origin.unwrap_or_default(),
+            });
+            run.phase = WavePhase::Building;
+        }
+    }
+}
+
+/// Remember where the level's castle stands so later waves are built in the same place.
+fn measure_castle_site(
+    run: &mut EndlessRun,
+    block_query: &Query<(Entity, &GlobalTransform, &BlockSize), With<CastleBlock>>,
+) {
+    let bounds = block_query
+        .iter()
+        .map(|(_, transform, size)| {
+            let center = transform.translation().truncate();
+            Rect::from_center_size(center, size.0)
+        })
+        .reduce(|a, b| a.union(b));
+    let Some(bounds) = bounds else {
+        return;
+    };
+    run.castle_origin = Some(bounds.min);
+    run.castle_columns = ((bounds.width() / CELL_SIZE).round() as i32).max(1);
+}
+
+/// A castle of random height columns, taller on later waves.
+fn generate_castle(rng: &mut GameRng, wave: u32, columns: i32) -> CastleBlueprint {
+    let max_height = (BASE_CASTLE_HEIGHT + wave as i32 - 2).clamp(2, MAX_CASTLE_HEIGHT);
+    let section = format!("Wave {wave}");
+    let mut blocks = Vec::new();
+    for x in 0..columns {
+        let height = rng.gen_range(1..=max_height);
+        for y in 0..height {
+            blocks.push(BlueprintBlock {
+                offset: [(x as f32 + 0.5) * CELL_SIZE, (y as f32 + 0.5) * CELL_SIZE],
+                cell: [x, y],
+                size: [CELL_SIZE, CELL_SIZE],
+                material: BlockMaterial::Stone,
+                section: section.clone(),
+            });
+        }
+    }
+    CastleBlueprint { blocks }
+}
+
+fn throw_boulders(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut run: ResMut<EndlessRun>,
+    mut rng: ResMut<GameRng>,
+    smoke: Smoke,
+    player_query: Query<(&PlayerIndex, &GlobalTransform), With<Player>>,
+) {
+    if !run.boulder_timer.tick(time.delta()).finished() {
+        return;
+    }
+    let extra_waves = run.wave.saturating_sub(1) as f32;
+    let interval = BOULDER_BASE_INTERVAL_SECS / (1.0 + extra_waves * BOULDER_RATE_PER_WAVE);
+    run.boulder_timer = Timer::from_seconds(interval, TimerMode::Once);
+
+    let Some((_, player_transform)) = player_query.iter().find(|(index, _)| index.0 == 0) else {
+        return;
+    };
+    let player_position = player_transform.translation().truncate();
+    let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let origin =
+        player_position + Vec2::new(BOULDER_LAUNCH_OFFSET.x * side, BOULDER_LAUNCH_OFFSET.y);
+    if !smoke.blocks_line_of_sight(origin, player_position) {
+        run.last_seen_player = Some(player_position);
+    }
+    // Hidden in smoke before they ever saw the player, there's nothing to aim at.
+    let Some(target) = run.last_seen_player else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Boulder"),
+        Boulder {
+            timer: Timer::from_seconds(BOULDER_LIFETIME_SECS, TimerMode::Once),
+        },
+        HostileProjectile,
+        ContactDamage {
+            amount: BOULDER_BASE_DAMAGE + extra_waves * BOULDER_DAMAGE_PER_WAVE,
+        },
+        Sprite::from_color(BOULDER_COLOR, Vec2::splat(BOULDER_RADIUS * 2.0)),
+        Transform::from_translation(origin.extend(1.0)),
+        RigidBody::Dynamic,
+        Collider::circle(BOULDER_RADIUS),
+        CollidingEntities::default(),
+        LinearVelocity((target - origin).normalize_or_zero() * BOULDER_SPEED),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+fn expire_boulders(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut boulder_query: Query<(Entity, &mut Boulder)>,
+) {
+    for (entity, mut boulder) in &mut boulder_query {
+        if boulder.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[derive(Component)]
+struct WaveLabel;
+
+fn spawn_wave_ui(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Wave Display"),
+        Node::default(),
+        HudAnchor::new(HudRegion::TopCenter, 0),
+        children![(widget::label("Wave 1"), WaveLabel)],
+    ));
+}
+
+fn update_wave_ui(run: Res<EndlessRun>, mut label_query: Query<&mut Text, With<WaveLabel>>) {
+    for mut text in &mut label_query {
+        text.0 = format!("Wave {}", run.wave);
+    }
+}
@@ -0,0 +1,139 @@
+//! Water volumes painted into the LDtk int-grid.
+//!
+//! Dynamic bodies inside water are pushed upwards and slowed down, the player
+//! swims at reduced speed, fireballs fizzle out and submerged sprites get a
+//! blue tint.
+
+use avian2d::prelude::*;
+use bevy::{platform::collections::HashSet, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        balistics::{Fireball, FireballBundle},
+        pool::Pool,
+        tint::{TintLayer, Tints},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_int_cell::<WaterBundle>(WATER_INT_CELL);
+    app.register_type::<Submerged>();
+    app.add_systems(
+        Update,
+        (
+            update_submerged,
+            apply_buoyancy,
+            extinguish_fireballs,
+            tint_submerged_sprites,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// The int-grid value used for water cells in LDtk.
+const WATER_INT_CELL: i32 = 2;
+/// Upwards acceleration for submerged bodies. Slightly stronger than gravity so things float.
+const BUOYANCY: f32 = 120.0;
+/// Fraction of velocity lost per second while submerged.
+const WATER_DRAG: f32 = 2.5;
+/// How much slower characters move while swimming.
+pub const SUBMERGED_SPEED_FACTOR: f32 = 0.5;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Water;
+
+#[derive(Clone, Debug, Bundle, LdtkIntCell)]
+pub struct WaterBundle {
+    pub water: Water,
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+    pub sensor: Sensor,
+    pub colliding_entities: CollidingEntities,
+}
+
+impl Default for WaterBundle {
+    fn default() -> Self {
+        Self {
+            water: Water,
+            collider: Collider::rectangle(16., 16.),
+            rigid_body: RigidBody::Static,
+            sensor: Sensor,
+            colliding_entities: CollidingEntities::default(),
+        }
+    }
+}
+
+/// Marks a dynamic body that is currently overlapping a water cell.
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component)]
+pub struct Submerged;
+
+fn update_submerged(
+    mut commands: Commands,
+    water_query: Query<&CollidingEntities, With<Water>>,
+    submerged_query: Query<Entity, With<Submerged>>,
+    bodies: Query<&RigidBody, Without<Water>>,
+) {
+    let in_water: HashSet<Entity> = water_query
+        .iter()
+        .flat_map(|colliding_entities| colliding_entities.iter().copied())
+        .filter(|entity| matches!(bodies.get(*entity), Ok(RigidBody::Dynamic)))
+        .collect();
+
+    for entity in &submerged_query {
+        if !in_water.contains(&entity) {
+            commands.entity(entity).try_remove::<Submerged>();
+        }
+    }
+    for entity in in_water {
+        if !submerged_query.contains(entity) {
+            commands.entity(entity).try_insert(Submerged);
+        }
+    }
+}
+
+fn apply_buoyancy(time: Res<Time>, mut query: Query<&mut LinearVelocity, With<Submerged>>) {
+    let delta_time = time.delta_secs();
+    for mut linear_velocity in &mut query {
+        linear_velocity.y += BUOYANCY * delta_time;
+        linear_velocity.0 *= (1.0 - WATER_DRAG * delta_time).max(0.0);
+    }
+}
+
+fn extinguish_fireballs(
+    mut commands: Commands,
+    fireball_query: Query<(Entity, &CollidingEntities), With<Fireball>>,
+    water_query: Query<(), With<Water>>,
+    mut fireball_pool: ResMut<Pool<FireballBundle>>,
+) {
+    for (fireball_entity, colliding_entities) in &fireball_query {
+        if colliding_entities
+            .iter()
+            .any(|entity| water_query.contains(*entity))
+        {
+            info!("Fireball {:?} fizzled out in water", fireball_entity);
+            fireball_pool.release(&mut commands, fireball_entity);
+        }
+    }
+}
+
+fn tint_submerged_sprites(
+    entering: Query<Entity, Added<Submerged>>,
+    mut leaving: RemovedComponents<Submerged>,
+    mut tints_query: Query<&mut Tints>,
+) {
+    for entity in leaving.read() {
+        if let Ok(mut tints) = tints_query.get_mut(entity) {
+            tints.clear(TintLayer::Water);
+        }
+    }
+    for entity in &entering {
+        if let Ok(mut tints) = tints_query.get_mut(entity) {
+            tints.set(TintLayer::Water, 0.5);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Which way the levels are being played.
+
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<GameMode>();
+    app.init_resource::<GameMode>();
+}
+
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Resource)]
+pub enum GameMode {
+    /// Reach the level's goals, or bring down its castle.
+    #[default]
+    Campaign,
+    /// Survive waves of regenerated castles until the player dies.
+    Endless,
+    /// Finish the level as fast as possible for a medal.
+    TimeAttack,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Campaign, GameMode::Endless, GameMode::TimeAttack];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Campaign => "Campaign",
+            GameMode::Endless => "Endless",
+            GameMode::TimeAttack => "Time attack",
+        }
+    }
+
+    /// The mode after this one, for cycling through them in menus.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// A run condition that checks the current [`GameMode`].
+pub fn game_mode_is(mode: GameMode) -> impl Fn(Res<GameMode>) -> bool + Clone {
+    move |current: Res<GameMode>| *current == mode
+}
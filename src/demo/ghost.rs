@@ -0,0 +1,233 @@
+//! A translucent ghost duck that re-enacts the best run of the current level
+//! on the current difficulty.
+//!
+//! Every run is recorded as a list of timestamped samples of the first
+//! player. When a run beats the saved ghost it replaces it, and on the next
+//! attempt the ghost plays back in step with the [`GameTimer`].
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        attract::attract_mode_active,
+        difficulty::Difficulty,
+        level::{LevelAssets, selected_level_identifier},
+        player::{Player, PlayerIndex},
+        timer::GameTimer,
+    },
+    persistence,
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GhostRecording>();
+    app.init_resource::<BestGhost>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (start_recording, load_best_ghost),
+    );
+    app.add_systems(
+        OnEnter(Screen::LevelComplete),
+        save_ghost_if_faster.run_if(not(attract_mode_active)),
+    );
+    app.add_systems(
+        Update,
+        (record_player, spawn_ghost, play_back_ghost)
+            .chain()
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Seconds between recorded samples.
+const SAMPLE_INTERVAL: f32 = 1.0 / 20.0;
+const GHOST_ALPHA: f32 = 0.4;
+/// Draw the ghost just behind the player.
+const GHOST_Z_OFFSET: f32 = -0.1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct GhostSample {
+    /// Seconds since the run started.
+    time: f32,
+    /// World position, stored as an array so it doesn't depend on glam's serde support.
+    position: [f32; 3],
+    flip_x: bool,
+    atlas_index: usize,
+}
+
+/// A recorded run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+struct GhostReplay {
+    /// Time the run took to complete the level.
+    duration: f32,
+    samples: Vec<GhostSample>,
+}
+
+impl GhostReplay {
+    /// The interpolated sample at `time`, holding the last pose once the replay has ended.
+    fn sample_at(&self, time: f32) -> Option<GhostSample> {
+        let next = self.samples.partition_point(|sample| sample.time <= time);
+        let after = self.samples.get(next);
+        let before = next
+            .checked_sub(1)
+            .and_then(|index| self.samples.get(index));
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                let t = (time - before.time) / (after.time - before.time).max(f32::EPSILON);
+                let position = Vec3::from(before.position).lerp(Vec3::from(after.position), t);
+                Some(GhostSample {
+                    time,
+                    position: position.to_array(),
+                    ..*before
+                })
+            }
+            (Some(sample), None) | (None, Some(sample)) => Some(*sample),
+            (None, None) => None,
+        }
+    }
+}
+
+/// The run currently being recorded.
+#[derive(Resource, Debug, Default)]
+struct GhostRecording(GhostReplay);
+
+/// The fastest recorded run of the level being played, if there is one.
+#[derive(Resource, Debug, Default)]
+struct BestGhost(Option<GhostReplay>);
+
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct Ghost;
+
+fn ghost_key(level: &str, difficulty: Difficulty) -> String {
+    format!("ghost_{}", difficulty.record_key(level))
+}
+
+fn current_level(
+    level_selection: &LevelSelection,
+    level_assets: &LevelAssets,
+    projects: &Assets<LdtkProject>,
+) -> Option<String> {
+    let project = projects.get(&level_assets.ldtk_level.handle)?;
+    selected_level_identifier(level_selection, project)
+}
+
+fn start_recording(mut recording: ResMut<GhostRecording>) {
+    *recording = GhostRecording::default();
+}
+
+fn load_best_ghost(
+    mut best_ghost: ResMut<BestGhost>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    difficulty: Res<Difficulty>,
+) {
+    best_ghost.0 = current_level(&level_selection, &level_assets, &projects)
+        .and_then(|level| persistence::load(&ghost_key(&level, *difficulty)));
+}
+
+fn save_ghost_if_faster(
+    mut recording: ResMut<GhostRecording>,
+    timer: Res<GameTimer>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    difficulty: Res<Difficulty>,
+) {
+    let Some(level) = current_level(&level_selection, &level_assets, &projects) else {
+        return;
+    };
+    let key = ghost_key(&level, *difficulty);
+    let duration = timer.elapsed_secs();
+    let previous = persistence::load::<GhostReplay>(&key);
+    if previous.is_some_and(|previous| previous.duration <= duration) {
+        return;
+    }
+
+    info!("New fastest run on {level}, saving its ghost");
+    let mut replay = std::mem::take(&mut recording.0);
+    replay.duration = duration;
+    persistence::save(&key, &replay);
+}
+
+fn record_player(
+    timer: Res<GameTimer>,
+    mut recording: ResMut<GhostRecording>,
+    player_query: Query<(&GlobalTransform, &Sprite, &PlayerIndex), With<Player>>,
+) {
+    let time = timer.timer.elapsed_secs();
+    if recording
+        .0
+        .samples
+        .last()
+        .is_some_and(|last| time - last.time < SAMPLE_INTERVAL)
+    {
+        return;
+    }
+    let Some((transform, sprite, _)) = player_query.iter().find(|(_, _, index)| index.0 == 0)
+    else {
+        return;
+    };
+    recording.0.samples.push(GhostSample {
+        time,
+        position: transform.translation().to_array(),
+        flip_x: sprite.flip_x,
+        atlas_index: sprite.texture_atlas.as_ref().map_or(0, |atlas| atlas.index),
+    });
+}
+
+/// Spawn the ghost once the player exists, borrowing its sprite sheet.
+fn spawn_ghost(
+    mut commands: Commands,
+    best_ghost: Res<BestGhost>,
+    ghost_query: Query<(), With<Ghost>>,
+    player_query: Query<(&Sprite, &PlayerIndex), With<Player>>,
+) {
+    if best_ghost.0.is_none() || !ghost_query.is_empty() {
+        return;
+    }
+    let Some((sprite, _)) = player_query.iter().find(|(_, index)| index.0 == 0) else {
+        return;
+    };
+    if sprite.texture_atlas.is_none() {
+        // The player's sprite sheet hasn't been set up yet.
+        return;
+    }
+
+    let mut ghost_sprite = sprite.clone();
+    ghost_sprite.color = Color::WHITE.with_alpha(GHOST_ALPHA);
+    commands.spawn((
+        Name::new("Ghost"),
+        Ghost,
+        ghost_sprite,
+        Transform::default(),
+        Visibility::Hidden,
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+fn play_back_ghost(
+    timer: Res<GameTimer>,
+    best_ghost: Res<BestGhost>,
+    mut ghost_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<Ghost>>,
+) {
+    let Some(replay) = &best_ghost.0 else {
+        return;
+    };
+    let Some(sample) = replay.sample_at(timer.timer.elapsed_secs()) else {
+        return;
+    };
+    for (mut transform, mut sprite, mut visibility) in &mut ghost_query {
+        transform.translation = Vec3::from(sample.position) + Vec3::Z * GHOST_Z_OFFSET;
+        sprite.flip_x = sample.flip_x;
+        if let Some(atlas) = sprite.texture_atlas.as_mut() {
+            atlas.index = sample.atlas_index;
+        }
+        *visibility = Visibility::Inherited;
+    }
+}
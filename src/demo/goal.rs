@@ -0,0 +1,91 @@
+//! Goals placed in LDtk. Reaching every goal in the level completes it.
+//! Levels without goals are completed by destroying every castle section instead.
+//! Neither applies in endless mode, where the run only ends when the player dies.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        game_mode::{GameMode, game_mode_is},
+        pickups::PickupSensor,
+        player::Player,
+        sections::CastleSections,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<GoalBundle>("Goal");
+    app.add_systems(
+        Update,
+        (
+            reach_goals,
+            complete_when_castle_destroyed.run_if(resource_changed::<CastleSections>),
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(not(game_mode_is(GameMode::Endless))),
+    );
+}
+
+const GOAL_COLOR: Color = Color::srgb(0.3, 1.0, 0.45);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Goal;
+
+#[derive(Bundle, LdtkEntity)]
+pub struct GoalBundle {
+    pub goal: Goal,
+    pub sprite: Sprite,
+    pub pickup_sensor: PickupSensor,
+}
+
+impl Default for GoalBundle {
+    fn default() -> Self {
+        Self {
+            goal: Goal,
+            sprite: Sprite::from_color(GOAL_COLOR, Vec2::splat(12.0)),
+            pickup_sensor: PickupSensor::default(),
+        }
+    }
+}
+
+fn reach_goals(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    goal_query: Query<(Entity, &CollidingEntities), With<Goal>>,
+    player_query: Query<(), With<Player>>,
+) {
+    let mut remaining = 0;
+    let mut reached_any = false;
+    for (goal_entity, colliding_entities) in &goal_query {
+        if colliding_entities
+            .iter()
+            .any(|entity| player_query.contains(*entity))
+        {
+            info!("Reached goal {:?}", goal_entity);
+            commands.entity(goal_entity).despawn();
+            reached_any = true;
+        } else {
+            remaining += 1;
+        }
+    }
+
+    if reached_any && remaining == 0 {
+        next_screen.set(Screen::LevelComplete);
+    }
+}
+
+fn complete_when_castle_destroyed(
+    mut next_screen: ResMut<NextState<Screen>>,
+    sections: Res<CastleSections>,
+    goal_query: Query<(), With<Goal>>,
+) {
+    if goal_query.is_empty() && sections.all_destroyed() {
+        info!("Every castle section destroyed");
+        next_screen.set(Screen::LevelComplete);
+    }
+}
@@ -0,0 +1,99 @@
+//! Hazard tiles such as lava, painted into the LDtk int-grid.
+//!
+//! Anything with [`Health`] standing on a hazard takes damage every frame,
+//! and frozen castle blocks touching one thaw out immediately.
+
+use avian2d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::CastleBlock,
+        collision::FrostEffect,
+        health::{DamageEvent, Health},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_int_cell::<HazardBundle>(LAVA_INT_CELL);
+    app.add_systems(
+        Update,
+        (damage_from_hazards, melt_frost_on_hazards)
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// The int-grid value used for lava cells in LDtk.
+const LAVA_INT_CELL: i32 = 3;
+const LAVA_DAMAGE_PER_SECOND: f32 = 40.0;
+
+#[derive(Copy, Clone, PartialEq, Debug, Component)]
+pub struct Hazard {
+    pub damage_per_second: f32,
+}
+
+#[derive(Clone, Debug, Bundle, LdtkIntCell)]
+pub struct HazardBundle {
+    pub hazard: Hazard,
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+    pub colliding_entities: CollidingEntities,
+}
+
+impl Default for HazardBundle {
+    fn default() -> Self {
+        Self {
+            hazard: Hazard {
+                damage_per_second: LAVA_DAMAGE_PER_SECOND,
+            },
+            collider: Collider::rectangle(16., 16.),
+            rigid_body: RigidBody::Static,
+            colliding_entities: CollidingEntities::default(),
+        }
+    }
+}
+
+fn damage_from_hazards(
+    time: Res<Time>,
+    hazard_query: Query<(&Hazard, &CollidingEntities)>,
+    health_query: Query<(), With<Health>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    // Standing across several tiles shouldn't multiply the damage, so only the worst one counts.
+    let mut damage_per_target = HashMap::<Entity, f32>::new();
+    for (hazard, colliding_entities) in &hazard_query {
+        for entity in colliding_entities.iter() {
+            if !health_query.contains(*entity) {
+                continue;
+            }
+            let damage = damage_per_target.entry(*entity).or_default();
+            *damage = damage.max(hazard.damage_per_second);
+        }
+    }
+
+    for (target, damage_per_second) in damage_per_target {
+        damage_events.write(DamageEvent::over_time(
+            target,
+            damage_per_second * time.delta_secs(),
+        ));
+    }
+}
+
+fn melt_frost_on_hazards(
+    mut commands: Commands,
+    hazard_query: Query<&CollidingEntities, With<Hazard>>,
+    frozen_query: Query<(), (With<CastleBlock>, With<FrostEffect>)>,
+) {
+    for colliding_entities in &hazard_query {
+        for entity in colliding_entities.iter() {
+            if !frozen_query.contains(*entity) {
+                continue;
+            }
+            info!("Frost on {:?} melted by a hazard", entity);
+            commands.entity(*entity).remove::<FrostEffect>();
+        }
+    }
+}
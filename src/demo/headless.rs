@@ -0,0 +1,180 @@
+//! A bare app that simulates castles without a window or renderer.
+//!
+//! It runs the real castle, blueprint and explosion plugins in deterministic
+//! mode, with stand-ins for the resources the rest of the game would provide.
+//! Assets the game would load are left as empty handles. Used by the castle
+//! stability tests and `--bench-mode`.
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, state::app::StatesPlugin};
+
+use crate::{
+    demo::{
+        ability_config::AbilityConfig,
+        balistics::{
+            ExplosionAssets, ExplosionBundle, Fireball, FireballBundle, FrostAssets, SfxAssets,
+        },
+        blueprint::{self, BlockMaterial, BlueprintBlock, CastleBlueprint, SpawnBlueprint},
+        castle::{self, BlockDestroyed, CastleBlock, CastleReady},
+        collision,
+        despawn_queue::DespawnQueue,
+        determinism::make_deterministic,
+        difficulty::{Difficulty, DifficultyConfig},
+        health::DamageEvent,
+        level::LevelState,
+        level_physics::LevelPhysics,
+        movement::GRAVITY,
+        pool::Pool,
+        progress::Progress,
+        weather::Weather,
+    },
+    screens::Screen,
+    settings::Settings,
+};
+
+/// Deterministic mode steps the physics once per frame, at 64 Hz.
+pub const FRAMES_PER_SEC: u32 = 64;
+/// Frames the castle gets to mortar itself together before giving up.
+const MAX_SETUP_FRAMES: u32 = 30;
+/// Size of a castle grid cell.
+pub const CELL: f32 = 16.0;
+
+/// What happened to the castle during a simulation.
+#[derive(Resource, Default)]
+pub struct SimulationLog {
+    pub castle_ready: bool,
+    pub destroyed: Vec<Entity>,
+}
+
+fn record_simulation_events(
+    mut log: ResMut<SimulationLog>,
+    mut ready_events: EventReader<CastleReady>,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+) {
+    if ready_events.read().count() > 0 {
+        log.castle_ready = true;
+    }
+    log.destroyed
+        .extend(destroyed_events.read().map(|event| event.block));
+}
+
+/// The castle and explosion plugins on top of a bare app, with stand-ins for
+/// the resources the rest of the game would provide.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        StatesPlugin,
+        AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        PhysicsPlugins::default(),
+    ));
+    app.insert_resource(Gravity(Vec2::NEG_Y * GRAVITY));
+    app.init_resource::<Assets<Mesh>>();
+    app.init_asset::<Image>();
+    app.init_asset::<AbilityConfig>();
+    app.init_asset::<DifficultyConfig>();
+    app.init_state::<Screen>();
+    app.init_state::<LevelState>();
+
+    app.init_resource::<Difficulty>();
+    app.init_resource::<LevelPhysics>();
+    app.init_resource::<Progress>();
+    app.init_resource::<Weather>();
+    app.init_resource::<Settings>();
+    app.init_resource::<DespawnQueue>();
+    app.init_resource::<Pool<ExplosionBundle>>();
+    app.init_resource::<Pool<FireballBundle>>();
+    app.insert_resource(ExplosionAssets {
+        image_handle: default(),
+        layout_handle: default(),
+    });
+    app.insert_resource(FrostAssets {
+        image_handle: default(),
+        layout_handle: default(),
+    });
+    app.insert_resource(SfxAssets {
+        fireball_launch: default(),
+        explosion: default(),
+        frost_shatter: default(),
+        lightning_zap: default(),
+        block_collapse: default(),
+        section_destroyed: default(),
+    });
+    app.add_event::<DamageEvent>();
+
+    app.add_plugins((castle::plugin, collision::plugin, blueprint::plugin));
+    app.init_resource::<SimulationLog>();
+    app.add_systems(Update, record_simulation_events);
+    make_deterministic(&mut app);
+
+    app.world_mut()
+        .resource_mut::<NextState<Screen>>()
+        .set(Screen::Gameplay);
+    app.world_mut()
+        .resource_mut::<NextState<LevelState>>()
+        .set(LevelState::Ready);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// A 1x1 block whose bottom left corner is at `column`, `row`.
+pub fn block(column: i32, row: i32, section: &str) -> BlueprintBlock {
+    BlueprintBlock {
+        offset: [(column as f32 + 0.5) * CELL, (row as f32 + 0.5) * CELL],
+        cell: [column, row],
+        size: [CELL, CELL],
+        material: BlockMaterial::Stone,
+        section: section.to_string(),
+    }
+}
+
+/// Spawn `blueprint` on flat ground and step until it's mortared together.
+pub fn spawn_castle(app: &mut App, blueprint: CastleBlueprint) {
+    app.world_mut().spawn((
+        Name::new("Ground"),
+        RigidBody::Static,
+        Collider::rectangle(100.0 * CELL, CELL),
+        Transform::from_xyz(0.0, -CELL / 2.0, 0.0),
+    ));
+    app.world_mut().send_event(SpawnBlueprint {
+        blueprint,
+        position: Vec2::ZERO,
+    });
+    for _ in 0..MAX_SETUP_FRAMES {
+        app.update();
+        if app.world().resource::<SimulationLog>().castle_ready {
+            return;
+        }
+    }
+    panic!("the castle wasn't mortared together within {MAX_SETUP_FRAMES} frames");
+}
+
+/// Set off a fireball at `position`, as if it had just hit the nearest castle block.
+pub fn detonate_fireball(app: &mut App, position: Vec2) {
+    let nearest_block = app
+        .world_mut()
+        .query_filtered::<(Entity, &GlobalTransform), With<CastleBlock>>()
+        .iter(app.world())
+        .min_by(|(_, a), (_, b)| {
+            let a = a.translation().truncate().distance(position);
+            let b = b.translation().truncate().distance(position);
+            a.total_cmp(&b)
+        })
+        .map(|(entity, _)| entity)
+        .expect("there's a castle to hit");
+    app.world_mut().spawn((
+        Fireball,
+        CollidingEntities([nearest_block].into_iter().collect()),
+        Transform::from_translation(position.extend(0.0)),
+        GlobalTransform::from_translation(position.extend(0.0)),
+    ));
+}
+
+pub fn step_secs(app: &mut App, secs: u32) {
+    for _ in 0..secs * FRAMES_PER_SEC {
+        app.update();
+    }
+}
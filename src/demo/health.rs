@@ -0,0 +1,100 @@
+//! Health and the damage pipeline.
+//!
+//! Anything that wants to hurt an entity sends a [`DamageEvent`] instead of
+//! touching [`Health`] directly, so damage sources don't need to know about
+//! each other.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        difficulty::DifficultyTuning,
+        player::{Invulnerable, Player},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Health>();
+    app.add_event::<DamageEvent>();
+    app.add_systems(
+        Update,
+        apply_damage
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Remaining health between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).clamp(0.0, 1.0)
+    }
+}
+
+/// Request to deal `amount` damage to `target`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    /// Direction to knock the target back in, for discrete hits.
+    ///
+    /// `None` means damage over time (e.g. standing in lava), which neither
+    /// knocks back nor grants i-frames. A zero vector is a hit whose source
+    /// already pushed the target, like an explosion shockwave.
+    pub knockback: Option<Vec2>,
+}
+
+impl DamageEvent {
+    /// A single hit that knocks the target away along `direction`.
+    pub fn hit(target: Entity, amount: f32, direction: Vec2) -> Self {
+        Self {
+            target,
+            amount,
+            knockback: Some(direction.normalize_or_zero()),
+        }
+    }
+
+    /// Damage that is applied continuously, a little every frame.
+    pub fn over_time(target: Entity, amount: f32) -> Self {
+        Self {
+            target,
+            amount,
+            knockback: None,
+        }
+    }
+}
+
+fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut health_query: Query<(&mut Health, Has<Player>), Without<Invulnerable>>,
+    difficulty: DifficultyTuning,
+) {
+    let damage_taken = difficulty.multipliers().damage_taken;
+    for event in damage_events.read() {
+        let Ok((mut health, is_player)) = health_query.get_mut(event.target) else {
+            continue;
+        };
+        let amount = if is_player {
+            event.amount * damage_taken
+        } else {
+            event.amount
+        };
+        health.current = (health.current - amount).max(0.0);
+    }
+}
@@ -0,0 +1,325 @@
+//! Health bars floating over anything with [`Health`], and over castle blocks.
+//!
+//! A bar is spawned once its owner is hurt, its mortar wears or it's frosted,
+//! and fades away and is despawned after a few seconds without any of those
+//! changing. A block's bar shows how much is left of its most worn mortar
+//! joint, since that's what gives out first. Bars aren't children of their
+//! owner, they follow it around so they stay level however it spins. Bars over
+//! something that's off screen aren't updated or drawn. They can be turned off
+//! in the settings.
+
+use avian2d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*, sprite::Anchor};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::{CastleBlock, MortarHealth},
+        collision::FrostEffect,
+        health::Health,
+    },
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (spawn_health_bars, update_health_bars)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const HEALTH_BAR_SIZE: Vec2 = Vec2::new(24.0, 3.0);
+/// How far above its owner's middle a bar floats.
+const HEALTH_BAR_OFFSET: f32 = 18.0;
+/// Seconds a bar stays up after its owner's health or frost last changed.
+/// Staying frosted doesn't keep it up.
+const HEALTH_BAR_SECS: f32 = 3.0;
+const HEALTH_BAR_FADE_SECS: f32 = 0.5;
+const HEALTH_BAR_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const HEALTH_BAR_FILL: Color = Color::srgb(0.85, 0.1, 0.15);
+const HEALTH_BAR_FROST_FILL: Color = Color::srgb(0.6, 0.85, 1.0);
+/// Drawn just behind damage numbers.
+const HEALTH_BAR_Z: f32 = 19.0;
+
+/// A bar showing how much [`Health`] or mortar `owner` has left.
+#[derive(Component, Debug)]
+struct HealthBar {
+    owner: Entity,
+    fill: Entity,
+    /// Seconds before the bar is gone.
+    secs_left: f32,
+    /// The owner's remaining health last frame, between 0 and 1, to tell
+    /// when it changes.
+    fraction: f32,
+    /// Whether the owner was frosted last frame.
+    was_frosted: bool,
+}
+
+/// The part of a health bar that shrinks as health runs out.
+#[derive(Component, Debug)]
+struct HealthBarFill;
+
+/// Marks an owner that has a [`HealthBar`] up, so it doesn't get another.
+#[derive(Component, Debug)]
+struct HasHealthBar;
+
+/// Spawn a bar for anything that was just hurt, worn or frosted and doesn't
+/// have one yet.
+fn spawn_health_bars(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    hurt_query: Query<(Entity, &Health), (Changed<Health>, Without<HasHealthBar>)>,
+    frosted_query: Query<
+        Entity,
+        (
+            Added<FrostEffect>,
+            Or<(With<Health>, With<CastleBlock>)>,
+            Without<HasHealthBar>,
+        ),
+    >,
+    worn_joint_query: Query<(&FixedJoint, &MortarHealth), Changed<MortarHealth>>,
+    block_query: Query<(), (With<CastleBlock>, Without<HasHealthBar>)>,
+    health_query: Query<&Health>,
+    joint_query: Query<(&FixedJoint, &MortarHealth)>,
+) {
+    if !settings.health_bars {
+        return;
+    }
+    let mut owners: Vec<Entity> = hurt_query
+        .iter()
+        .filter(|(_, health)| health.fraction() < 1.0)
+        .map(|(owner, _)| owner)
+        .chain(&frosted_query)
+        .collect();
+    for (joint, mortar) in &worn_joint_query {
+        if mortar.current < mortar.max {
+            owners.extend(
+                [joint.entity1, joint.entity2]
+                    .into_iter()
+                    .filter(|&block| block_query.contains(block)),
+            );
+        }
+    }
+    owners.sort_unstable();
+    owners.dedup();
+    if owners.is_empty() {
+        return;
+    }
+
+    // A block's bar starts at its weakest joint, worn before or just now.
+    let mut weakest_mortar: HashMap<Entity, f32> = HashMap::default();
+    if owners.iter().any(|&owner| !health_query.contains(owner)) {
+        for (joint, mortar) in &joint_query {
+            let fraction = (mortar.current / mortar.max).clamp(0.0, 1.0);
+            for block in [joint.entity1, joint.entity2] {
+                let weakest = weakest_mortar.entry(block).or_insert(fraction);
+                *weakest = weakest.min(fraction);
+            }
+        }
+    }
+
+    for owner in owners {
+        let fraction = health_query.get(owner).map_or_else(
+            |_| weakest_mortar.get(&owner).copied().unwrap_or(1.0),
+            Health::fraction,
+        );
+        let fill = commands
+            .spawn((
+                Name::new("Health Bar Fill"),
+                HealthBarFill,
+                Sprite {
+                    color: HEALTH_BAR_FILL,
+                    custom_size: Some(HEALTH_BAR_SIZE),
+                    anchor: Anchor::CenterLeft,
+                    ..default()
+                },
+                Transform::from_xyz(-HEALTH_BAR_SIZE.x / 2.0, 0.0, 0.1),
+            ))
+            .id();
+        commands
+            .spawn((
+                Name::new("Health Bar"),
+                HealthBar {
+                    owner,
+                    fill,
+                    secs_left: HEALTH_BAR_SECS,
+                    fraction,
+                    was_frosted: false,
+                },
+                Sprite::from_color(HEALTH_BAR_BACKGROUND, HEALTH_BAR_SIZE),
+                Transform::default(),
+                Visibility::Hidden,
+                StateScoped(Screen::Gameplay),
+            ))
+            .add_child(fill);
+        commands.entity(owner).insert(HasHealthBar);
+    }
+}
+
+fn update_health_bars(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    owner_query: Query<
+        (
+            Option<&Health>,
+            &GlobalTransform,
+            Option<&ViewVisibility>,
+            Has<FrostEffect>,
+        ),
+        Or<(With<Health>, With<CastleBlock>)>,
+    >,
+    joint_query: Query<(&FixedJoint, &MortarHealth), Changed<MortarHealth>>,
+    mut bar_query: Query<(
+        Entity,
+        &mut HealthBar,
+        &mut Transform,
+        &mut Visibility,
+        &mut Sprite,
+    )>,
+    mut fill_query: Query<&mut Sprite, (With<HealthBarFill>, Without<HealthBar>)>,
+) {
+    // Mortar only wears down, so the joints that changed are all it takes to
+    // keep each block's weakest joint up to date.
+    let mut worn_blocks: HashMap<Entity, f32> = HashMap::default();
+    for (joint, mortar) in &joint_query {
+        let fraction = (mortar.current / mortar.max).clamp(0.0, 1.0);
+        for block in [joint.entity1, joint.entity2] {
+            let weakest = worn_blocks.entry(block).or_insert(fraction);
+            *weakest = weakest.min(fraction);
+        }
+    }
+
+    for (entity, mut bar, mut transform, mut visibility, mut sprite) in &mut bar_query {
+        let Ok((health, owner_transform, owner_visibility, frosted)) = owner_query.get(bar.owner)
+        else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let fraction = match health {
+            Some(health) => health.fraction(),
+            None => worn_blocks
+                .get(&bar.owner)
+                .map_or(bar.fraction, |worn| worn.min(bar.fraction)),
+        };
+        let changed = fraction != bar.fraction || frosted != bar.was_frosted;
+        bar.fraction = fraction;
+        bar.was_frosted = frosted;
+        if changed && (frosted || fraction < 1.0) {
+            bar.secs_left = HEALTH_BAR_SECS;
+        } else {
+            bar.secs_left -= time.delta_secs();
+        }
+        if !settings.health_bars || bar.secs_left <= 0.0 {
+            commands.entity(entity).despawn();
+            commands.entity(bar.owner).try_remove::<HasHealthBar>();
+            continue;
+        }
+
+        // Owners without a sprite of their own are never culled.
+        let on_screen = owner_visibility.is_none_or(|visibility| visibility.get());
+        if !on_screen {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Visible;
+        let position = owner_transform.translation().truncate() + Vec2::Y * HEALTH_BAR_OFFSET;
+        transform.translation = position.extend(HEALTH_BAR_Z);
+
+        let alpha = (bar.secs_left / HEALTH_BAR_FADE_SECS).min(1.0);
+        sprite
+            .color
+            .set_alpha(HEALTH_BAR_BACKGROUND.alpha() * alpha);
+        if let Ok(mut fill) = fill_query.get_mut(bar.fill) {
+            let color = if frosted {
+                HEALTH_BAR_FROST_FILL
+            } else {
+                HEALTH_BAR_FILL
+            };
+            fill.color = color.with_alpha(alpha);
+            fill.custom_size = Some(Vec2::new(HEALTH_BAR_SIZE.x * fraction, HEALTH_BAR_SIZE.y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<Settings>();
+        app.add_systems(Update, (spawn_health_bars, update_health_bars).chain());
+        app
+    }
+
+    fn bar_of(app: &mut App, owner: Entity) -> Option<(Visibility, f32)> {
+        let world = app.world_mut();
+        let (bar, visibility) = world
+            .query::<(&HealthBar, &Visibility)>()
+            .iter(world)
+            .find(|(bar, _)| bar.owner == owner)?;
+        let fill = world.entity(bar.fill).get::<Sprite>().unwrap();
+        Some((*visibility, fill.custom_size.unwrap().x / HEALTH_BAR_SIZE.x))
+    }
+
+    #[test]
+    fn damaged_block_shows_its_weakest_mortar() {
+        let mut app = app();
+        let block = app
+            .world_mut()
+            .spawn((CastleBlock::default(), GlobalTransform::default()))
+            .id();
+        let neighbour = app
+            .world_mut()
+            .spawn((CastleBlock::default(), GlobalTransform::default()))
+            .id();
+        let joint = app
+            .world_mut()
+            .spawn((FixedJoint::new(block, neighbour), MortarHealth::new(100.0)))
+            .id();
+        app.update();
+        assert_eq!(bar_of(&mut app, block), None);
+
+        app.world_mut()
+            .get_mut::<MortarHealth>(joint)
+            .unwrap()
+            .current = 25.0;
+        app.update();
+
+        let (visibility, fraction) = bar_of(&mut app, block).expect("block should have a bar");
+        assert_eq!(visibility, Visibility::Visible);
+        assert_eq!(fraction, 0.25);
+    }
+
+    #[test]
+    fn staying_frosted_does_not_keep_a_bar_up() {
+        let mut app = app();
+        let block = app
+            .world_mut()
+            .spawn((CastleBlock::default(), GlobalTransform::default()))
+            .id();
+        app.update();
+        app.world_mut()
+            .entity_mut(block)
+            .insert(FrostEffect { magnitude: 50.0 });
+        app.update();
+        assert!(bar_of(&mut app, block).is_some());
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(HEALTH_BAR_SECS + 0.1));
+        app.update();
+
+        assert_eq!(bar_of(&mut app, block), None);
+        assert!(!app.world().entity(block).contains::<HasHealthBar>());
+    }
+}
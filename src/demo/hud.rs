@@ -0,0 +1,134 @@
+//! The in-game HUD.
+//!
+//! The HUD is one UI root covering the window, split into anchored regions:
+//! the top left holds the timer and health, the top right the score, the top
+//! middle whatever the game mode wants to show, and the bottom the ability
+//! hotbar. The regions are flex containers pinned to the window's edges, so
+//! they stay in their corners and stack their contents however the window is
+//! resized.
+//!
+//! Widgets don't position themselves. They're spawned with a [`HudAnchor`]
+//! naming their region, and get moved into it when they're added, in order of
+//! [`HudAnchor::order`].
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::screens::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_hud);
+    app.add_systems(
+        Update,
+        attach_hud_widgets.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Space between the HUD and the edges of the window.
+const HUD_MARGIN: f32 = 10.0;
+/// Space between widgets in the same region.
+const HUD_GAP: f32 = 6.0;
+/// The corner regions never grow past this share of the window's width, so
+/// they can't run into each other in a narrow window.
+const CORNER_MAX_WIDTH: f32 = 40.0;
+
+/// A part of the screen HUD widgets are anchored to.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudRegion {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    Bottom,
+}
+
+/// Puts a UI node in one of the HUD's regions.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HudAnchor {
+    pub region: HudRegion,
+    /// Widgets in the same region are laid out from the lowest order to the highest.
+    pub order: i32,
+}
+
+impl HudAnchor {
+    pub fn new(region: HudRegion, order: i32) -> Self {
+        Self { region, order }
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        Name::new("HUD"),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Percent(100.0),
+            height: Percent(100.0),
+            padding: UiRect::all(Px(HUD_MARGIN)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceBetween,
+            ..default()
+        },
+        GlobalZIndex(2),
+        Pickable::IGNORE,
+        StateScoped(Screen::Gameplay),
+        children![
+            (
+                Name::new("HUD Top"),
+                Node {
+                    width: Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                Pickable::IGNORE,
+                children![
+                    region(HudRegion::TopLeft, AlignItems::FlexStart),
+                    region(HudRegion::TopCenter, AlignItems::Center),
+                    region(HudRegion::TopRight, AlignItems::FlexEnd),
+                ],
+            ),
+            region(HudRegion::Bottom, AlignItems::Center),
+        ],
+    ));
+}
+
+/// A column of widgets, lined up against one side of it.
+fn region(region: HudRegion, align_items: AlignItems) -> impl Bundle {
+    let max_width = match region {
+        HudRegion::TopLeft | HudRegion::TopRight => Percent(CORNER_MAX_WIDTH),
+        HudRegion::TopCenter | HudRegion::Bottom => Auto,
+    };
+    (
+        Name::new(format!("HUD {region:?}")),
+        region,
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items,
+            row_gap: Px(HUD_GAP),
+            max_width,
+            ..default()
+        },
+        Pickable::IGNORE,
+    )
+}
+
+/// Move newly spawned widgets into their region.
+fn attach_hud_widgets(
+    mut commands: Commands,
+    widget_query: Query<(Entity, &HudAnchor), Added<HudAnchor>>,
+    region_query: Query<(Entity, &HudRegion)>,
+) {
+    let mut widgets: Vec<_> = widget_query.iter().collect();
+    if widgets.is_empty() {
+        return;
+    }
+    widgets.sort_by_key(|(_, anchor)| anchor.order);
+    for (widget, anchor) in widgets {
+        let Some((region, _)) = region_query
+            .iter()
+            .find(|(_, region)| **region == anchor.region)
+        else {
+            warn!("No HUD region {:?} to put a widget in", anchor.region);
+            continue;
+        };
+        commands.entity(widget).insert(ChildOf(region));
+    }
+}
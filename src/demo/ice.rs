@@ -0,0 +1,102 @@
+//! Ice platforms left behind by frostbolts.
+//!
+//! A frostbolt that bursts on water freezes a raft on it, and one that bursts
+//! against a wall or the floor leaves a ledge of ice sticking out of it. Either
+//! way it's a solid platform the player can stand on until it melts a few
+//! seconds later, which opens up frost-based routes through a level.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{castle::CastleBlock, collision::FrostImpact, fluids::Water},
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<IcePlatform>();
+    app.add_systems(
+        Update,
+        (spawn_ice_platforms, melt_ice_platforms)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const ICE_PLATFORM_SIZE: Vec2 = Vec2::new(48.0, 8.0);
+const ICE_PLATFORM_MELT_SECS: f32 = 8.0;
+const ICE_COLOR: Color = Color::srgba(0.75, 0.9, 1.0, 0.9);
+/// A new platform isn't frozen this close to an existing one, so spamming
+/// frost in one spot doesn't pile them up.
+const MIN_PLATFORM_SPACING: f32 = 16.0;
+
+/// A temporary platform of ice that melts away.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct IcePlatform {
+    pub melt: Timer,
+}
+
+fn spawn_ice_platforms(
+    mut commands: Commands,
+    mut impact_events: EventReader<FrostImpact>,
+    water_query: Query<(), With<Water>>,
+    // Walls and floors, but not the castle, which the frost freezes instead.
+    ground_query: Query<&RigidBody, (Without<CastleBlock>, Without<Sensor>)>,
+    platform_query: Query<&GlobalTransform, With<IcePlatform>>,
+) {
+    for impact in impact_events.read() {
+        let on_water = impact
+            .contacts
+            .iter()
+            .any(|entity| water_query.contains(*entity));
+        let on_ground = impact
+            .contacts
+            .iter()
+            .any(|entity| matches!(ground_query.get(*entity), Ok(RigidBody::Static)));
+        if !on_water && !on_ground {
+            continue;
+        }
+        let too_close = platform_query.iter().any(|transform| {
+            transform.translation().truncate().distance(impact.position) < MIN_PLATFORM_SPACING
+        });
+        if too_close {
+            continue;
+        }
+        commands.spawn((
+            Name::new("Ice Platform"),
+            IcePlatform {
+                melt: Timer::from_seconds(ICE_PLATFORM_MELT_SECS, TimerMode::Once),
+            },
+            Sprite {
+                color: ICE_COLOR,
+                custom_size: Some(ICE_PLATFORM_SIZE),
+                ..default()
+            },
+            Transform::from_translation(impact.position.extend(1.0)),
+            RigidBody::Static,
+            Collider::rectangle(ICE_PLATFORM_SIZE.x, ICE_PLATFORM_SIZE.y),
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+}
+
+/// Fade platforms out as they melt, and remove them once they're gone.
+fn melt_ice_platforms(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut platform_query: Query<(Entity, &mut IcePlatform, &mut Sprite)>,
+) {
+    for (entity, mut platform, mut sprite) in &mut platform_query {
+        platform.melt.tick(time.delta());
+        if platform.melt.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite
+            .color
+            .set_alpha(ICE_COLOR.alpha() * platform.melt.fraction_remaining());
+    }
+}
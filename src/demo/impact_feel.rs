@@ -0,0 +1,147 @@
+//! Hitstop, shake, flash and rumble when projectiles connect.
+//!
+//! How each ability's hits feel comes from its [`ImpactFeel`] in
+//! `abilities.ron`, so a fireball can thump while frost barely nudges the
+//! camera without touching the code. A projectile connects when it explodes or
+//! when it hits something, and counts once per frame however many bodies it
+//! hit. When several connect in the same frame their shake adds up, and the
+//! rest go with the strongest.
+
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        ability_config::{AbilityConfigs, ImpactFeel},
+        balistics::AbilityKind,
+        camera::ScreenShake,
+        collision::{CombatHit, Detonation},
+        time_scale::TimeScale,
+    },
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (apply_impact_feel, fade_screen_flashes)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Hitstop is set in frames, counted at this many a second.
+const HITSTOP_FRAMES_PER_SEC: f32 = 60.0;
+const FLASH_SECS: f32 = 0.15;
+/// Screen alpha of a flash at full intensity.
+const MAX_FLASH_ALPHA: f32 = 0.6;
+const RUMBLE_SECS: f32 = 0.2;
+
+/// A full screen overlay that fades out after a hit.
+#[derive(Component, Debug)]
+struct ScreenFlash {
+    timer: Timer,
+    alpha: f32,
+}
+
+fn apply_impact_feel(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    ability_configs: AbilityConfigs,
+    mut detonation_events: EventReader<Detonation>,
+    mut hit_events: EventReader<CombatHit>,
+    mut shake: ResMut<ScreenShake>,
+    mut time_scale: ResMut<TimeScale>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+) {
+    // Each projectile that connected this frame, with the ability it came from.
+    let mut impacts: HashMap<Entity, AbilityKind> = HashMap::default();
+    for detonation in detonation_events.read() {
+        impacts.insert(detonation.projectile, detonation.ability);
+    }
+    for hit in hit_events.read() {
+        if let (Some(attacker), Some(ability)) = (hit.attacker, hit.ability) {
+            impacts.entry(attacker).or_insert(ability);
+        }
+    }
+    if impacts.is_empty() {
+        return;
+    }
+
+    let feel = impacts
+        .values()
+        .map(|ability| ability_configs.impact(*ability))
+        .fold(ImpactFeel::default(), |total, feel| ImpactFeel {
+            hitstop_frames: total.hitstop_frames.max(feel.hitstop_frames),
+            shake_trauma: total.shake_trauma + feel.shake_trauma,
+            flash_intensity: total.flash_intensity.max(feel.flash_intensity),
+            rumble_strength: total.rumble_strength.max(feel.rumble_strength),
+        });
+
+    if feel.hitstop_frames > 0 {
+        time_scale.freeze(feel.hitstop_frames as f32 / HITSTOP_FRAMES_PER_SEC);
+    }
+    if settings.screen_shake && feel.shake_trauma > 0.0 {
+        shake.add_trauma(feel.shake_trauma);
+    }
+    if settings.flashes && feel.flash_intensity > 0.0 {
+        let alpha = MAX_FLASH_ALPHA * feel.flash_intensity.min(1.0);
+        commands.spawn((
+            Name::new("Screen Flash"),
+            ScreenFlash {
+                timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once),
+                alpha,
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(Color::WHITE.with_alpha(alpha)),
+            Pickable::IGNORE,
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+    if feel.rumble_strength > 0.0 {
+        let strength = feel.rumble_strength.min(1.0);
+        for gamepad in &gamepads {
+            rumble_requests.write(GamepadRumbleRequest::Add {
+                gamepad,
+                duration: Duration::from_secs_f32(RUMBLE_SECS),
+                intensity: GamepadRumbleIntensity {
+                    strong_motor: strength,
+                    weak_motor: strength,
+                },
+            });
+        }
+    }
+}
+
+/// Fade flashes out in real time, so they don't hang around during the hitstop.
+fn fade_screen_flashes(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut flash_query: Query<(Entity, &mut ScreenFlash, &mut BackgroundColor)>,
+) {
+    for (entity, mut flash, mut background) in &mut flash_query {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        background
+            .0
+            .set_alpha(flash.alpha * flash.timer.fraction_remaining());
+    }
+}
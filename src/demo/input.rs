@@ -1,10 +1,16 @@
 use bevy::prelude::*;
 use bevy_enhanced_input::prelude::*;
 
-use crate::demo::balistics::{FrostCooldown, LightningCooldown};
+use crate::demo::balistics::{AbilityKind, CasterCooldowns, UnlockedAbilities};
 
 use super::{
-    balistics::FireballCooldown, movement::MovementController, player::CharacterController,
+    carry::Carrier,
+    charges::DetonateCharges,
+    dialogue::DialogueQueue,
+    loadout::Loadout,
+    movement::{CrouchController, DashController, JumpController, MovementController},
+    player::{CharacterController, Ragdoll},
+    trajectory::Aiming,
 };
 
 pub struct InputPlugin;
@@ -14,128 +20,347 @@ impl Plugin for InputPlugin {
         app.add_plugins(EnhancedInputPlugin);
         app.add_input_context::<PlatformerContext>();
         app.add_observer(binding);
-        app.add_observer(record_player_fire_input);
-        app.add_observer(record_player_ice_input);
-        app.add_observer(record_player_lightning_input);
+        app.add_observer(record_player_slot_input::<AbilitySlot1, 0>);
+        app.add_observer(record_player_slot_input::<AbilitySlot2, 1>);
+        app.add_observer(record_player_slot_input::<AbilitySlot3, 2>);
         app.add_observer(record_player_directional_input);
+        app.add_observer(record_player_jump_input);
+        app.add_observer(record_player_jump_release);
+        app.add_observer(record_player_dash_input);
+        app.add_observer(record_player_crouch_input);
+        app.add_observer(record_player_crouch_release);
+        app.add_observer(record_player_grab_input);
+        app.add_observer(record_player_detonate_input);
+        app.add_observer(record_dialogue_advance_input);
+        app.add_observer(record_aim_direction_release);
+        app.add_observer(record_slot_held::<AbilitySlot1, 0>);
+        app.add_observer(record_slot_held::<AbilitySlot2, 1>);
+        app.add_observer(record_slot_held::<AbilitySlot3, 2>);
+        app.add_observer(record_slot_release::<AbilitySlot1, 0>);
+        app.add_observer(record_slot_release::<AbilitySlot2, 1>);
+        app.add_observer(record_slot_release::<AbilitySlot3, 2>);
+        app.add_observer(record_aim_modifier_held);
+        app.add_observer(record_aim_modifier_release);
     }
 }
 
 #[derive(InputContext, Clone)]
 pub struct PlatformerContext;
 
+/// The device a player is controlled with. Players without one use the keyboard.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad(Entity),
+    /// Controlled by another player over the network.
+    Remote,
+}
+
 #[derive(Debug, InputAction)]
 #[input_action(output = Vec2)]
 pub struct LateralMovement;
 
 #[derive(Debug, InputAction)]
 #[input_action(output = bool)]
-pub struct FireAction;
+pub struct JumpAction;
+
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct DashAction;
+
+/// Held to crouch and crawl.
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct CrouchAction;
+
+/// Picks up whatever's in reach, or throws what's being carried.
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct GrabAction;
+
+/// Sets off every placed sticky charge.
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct DetonateAction;
 
+/// Skips to the end of the dialogue line being typed, or closes it.
 #[derive(Debug, InputAction)]
 #[input_action(output = bool)]
-pub struct IceAction;
+pub struct AdvanceDialogueAction;
 
+/// Casts the ability in the first hotbar slot of the [`Loadout`].
 #[derive(Debug, InputAction)]
 #[input_action(output = bool)]
-pub struct LightningAction;
+pub struct AbilitySlot1;
+
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct AbilitySlot2;
+
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct AbilitySlot3;
+
+/// Held to preview a fireball's trajectory without throwing it.
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+pub struct AimAction;
 
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
 pub enum ActionType {
-    FireballAttack { direction: Vec2 },
-    FrostAttack { direction: Vec2 },
-    LightningAttack { direction: Vec2 },
+    /// Cast whatever ability is in this hotbar slot of the [`Loadout`].
+    UseSlot { slot: usize, direction: Vec2 },
+    /// Cast a specific ability whatever the loadout is, e.g. from the dev console.
+    Cast {
+        ability: AbilityKind,
+        direction: Vec2,
+    },
 }
 
+impl ActionType {
+    /// Whether the action came from a button press, so it's only buffered for a moment.
+    pub fn is_buffered(&self) -> bool {
+        matches!(self, ActionType::UseSlot { .. })
+    }
+
+    /// Whether two actions come from the same slot or cast the same ability.
+    pub fn same_source(&self, other: &ActionType) -> bool {
+        match (self, other) {
+            (ActionType::UseSlot { slot: a, .. }, ActionType::UseSlot { slot: b, .. }) => a == b,
+            (ActionType::Cast { ability: a, .. }, ActionType::Cast { ability: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The keys for each hotbar slot, as shown in the HUD.
+pub const SLOT_KEY_NAMES: [&str; 3] = ["Space", "E", "Q"];
+
 fn binding(
     trigger: Trigger<Binding<PlatformerContext>>,
-    mut actions: Query<&mut Actions<PlatformerContext>>,
+    mut actions: Query<(&mut Actions<PlatformerContext>, Option<&InputDevice>)>,
 ) {
-    let mut action = actions.get_mut(trigger.target()).unwrap();
-    action.bind::<LateralMovement>().to((Cardinal {
-        north: KeyCode::ArrowUp,
-        south: KeyCode::ArrowDown,
-        east: KeyCode::ArrowRight,
-        west: KeyCode::ArrowLeft,
-    },));
-    action.bind::<FireAction>().to(KeyCode::Space);
-    action.bind::<IceAction>().to(KeyCode::KeyE);
-    action.bind::<LightningAction>().to(KeyCode::KeyQ);
+    let (mut action, device) = actions.get_mut(trigger.target()).unwrap();
+    match device.copied().unwrap_or_default() {
+        InputDevice::Keyboard => {
+            action.bind::<LateralMovement>().to((Cardinal {
+                north: KeyCode::ArrowUp,
+                south: KeyCode::ArrowDown,
+                east: KeyCode::ArrowRight,
+                west: KeyCode::ArrowLeft,
+            },));
+            action.bind::<JumpAction>().to(KeyCode::KeyW);
+            action.bind::<DashAction>().to(KeyCode::ShiftLeft);
+            action.bind::<CrouchAction>().to(KeyCode::KeyS);
+            action.bind::<GrabAction>().to(KeyCode::KeyF);
+            action.bind::<DetonateAction>().to(KeyCode::KeyR);
+            action.bind::<AdvanceDialogueAction>().to(KeyCode::Enter);
+            action.bind::<AbilitySlot1>().to(KeyCode::Space);
+            action.bind::<AimAction>().to(KeyCode::ControlLeft);
+            action.bind::<AbilitySlot2>().to(KeyCode::KeyE);
+            action.bind::<AbilitySlot3>().to(KeyCode::KeyQ);
+        }
+        InputDevice::Gamepad(gamepad) => {
+            // Only listen to this player's gamepad so players don't control each other.
+            action.set_gamepad(gamepad);
+            action
+                .bind::<LateralMovement>()
+                .to((Cardinal::dpad_buttons(), Axial::left_stick()))
+                .with_modifiers(DeadZone::default());
+            action.bind::<JumpAction>().to(GamepadButton::South);
+            action.bind::<DashAction>().to(GamepadButton::East);
+            // The triggers zoom the camera, see `zoom_with_input`.
+            action.bind::<CrouchAction>().to(GamepadButton::LeftThumb);
+            action.bind::<GrabAction>().to(GamepadButton::Select);
+            action
+                .bind::<DetonateAction>()
+                .to(GamepadButton::RightThumb);
+            action
+                .bind::<AdvanceDialogueAction>()
+                .to(GamepadButton::Start);
+            action.bind::<AbilitySlot1>().to(GamepadButton::West);
+            action.bind::<AimAction>().to(GamepadButton::LeftTrigger);
+            action.bind::<AbilitySlot2>().to(GamepadButton::North);
+            action
+                .bind::<AbilitySlot3>()
+                .to(GamepadButton::RightTrigger);
+        }
+        InputDevice::Remote => {}
+    }
 }
 
 fn record_player_directional_input(
     trigger: Trigger<Fired<LateralMovement>>,
-    mut controller_query: Query<&mut MovementController>,
+    mut controller_query: Query<(&mut MovementController, Option<&mut Aiming>), Without<Ragdoll>>,
 ) {
     // Collect directional input.
-    let mut move_controller = controller_query.get_mut(trigger.target()).unwrap();
+    let Ok((mut move_controller, aiming)) = controller_query.get_mut(trigger.target()) else {
+        return;
+    };
     let intent = trigger.value;
     move_controller.direction = intent.normalize_or_zero();
+    if let Some(mut aiming) = aiming {
+        aiming.direction = move_controller.direction;
+    }
 }
 
-fn record_player_fire_input(
-    trigger: Trigger<Started<FireAction>>,
-    cooldown: Res<FireballCooldown>,
-    mut controller_query: Query<(&mut CharacterController, &MovementController)>,
+fn record_aim_direction_release(
+    trigger: Trigger<Completed<LateralMovement>>,
+    mut aiming_query: Query<&mut Aiming>,
 ) {
-    if !cooldown.timer.finished() {
-        // If the timer is not finished, the ability is on cooldown
-        return;
+    if let Ok(mut aiming) = aiming_query.get_mut(trigger.target()) {
+        aiming.direction = Vec2::ZERO;
     }
-    let (mut character_controller, movement_controller) =
-        controller_query.get_mut(trigger.target()).unwrap();
+}
 
-    // Determine direction based on movement controller
-    let direction = if movement_controller.direction.length_squared() > 0.0 {
-        // Use the current movement direction if moving
-        movement_controller.direction.normalize_or_zero()
-    } else {
-        // Default to last non-zero x direction or right if none
-        Vec2::new(1.0, 0.0)
+fn record_slot_held<A: InputAction, const SLOT: usize>(
+    trigger: Trigger<Started<A>>,
+    mut aiming_query: Query<&mut Aiming>,
+) {
+    if let Ok(mut aiming) = aiming_query.get_mut(trigger.target()) {
+        aiming.slots_held[SLOT] = true;
+    }
+}
+
+fn record_slot_release<A: InputAction, const SLOT: usize>(
+    trigger: Trigger<Completed<A>>,
+    mut aiming_query: Query<&mut Aiming>,
+) {
+    if let Ok(mut aiming) = aiming_query.get_mut(trigger.target()) {
+        aiming.slots_held[SLOT] = false;
+    }
+}
+
+fn record_aim_modifier_held(
+    trigger: Trigger<Started<AimAction>>,
+    mut aiming_query: Query<&mut Aiming>,
+) {
+    if let Ok(mut aiming) = aiming_query.get_mut(trigger.target()) {
+        aiming.modifier_held = true;
+    }
+}
+
+fn record_aim_modifier_release(
+    trigger: Trigger<Completed<AimAction>>,
+    mut aiming_query: Query<&mut Aiming>,
+) {
+    if let Ok(mut aiming) = aiming_query.get_mut(trigger.target()) {
+        aiming.modifier_held = false;
+    }
+}
+
+fn record_player_jump_input(
+    trigger: Trigger<Started<JumpAction>>,
+    mut controller_query: Query<&mut JumpController, Without<Ragdoll>>,
+) {
+    let Ok(mut jump_controller) = controller_query.get_mut(trigger.target()) else {
+        return;
     };
+    jump_controller.request_jump();
+}
 
-    // Queue the action with directional information
-    character_controller.queue_action(ActionType::FireballAttack { direction });
+fn record_player_jump_release(
+    trigger: Trigger<Completed<JumpAction>>,
+    mut controller_query: Query<&mut JumpController>,
+) {
+    let Ok(mut jump_controller) = controller_query.get_mut(trigger.target()) else {
+        return;
+    };
+    jump_controller.release_jump();
 }
 
-fn record_player_ice_input(
-    trigger: Trigger<Started<IceAction>>,
-    cooldown: Res<FrostCooldown>,
-    mut controller_query: Query<(&mut CharacterController, &MovementController)>,
+fn record_player_dash_input(
+    trigger: Trigger<Started<DashAction>>,
+    mut controller_query: Query<&mut DashController, Without<Ragdoll>>,
 ) {
-    info!("Processing ice attack");
+    let Ok(mut dash_controller) = controller_query.get_mut(trigger.target()) else {
+        return;
+    };
+    dash_controller.request_dash();
+}
 
-    if !cooldown.timer.finished() {
-        // If the timer is not finished, the ability is on cooldown
-        info!("Timer not finished!");
+fn record_player_crouch_input(
+    trigger: Trigger<Started<CrouchAction>>,
+    mut controller_query: Query<&mut CrouchController>,
+) {
+    let Ok(mut crouch_controller) = controller_query.get_mut(trigger.target()) else {
         return;
-    }
-    let (mut character_controller, movement_controller) =
-        controller_query.get_mut(trigger.target()).unwrap();
+    };
+    crouch_controller.set_held(true);
+}
 
-    // Determine direction based on movement controller
-    let direction = if movement_controller.direction.length_squared() > 0.0 {
-        // Use the current movement direction if moving
-        movement_controller.direction.normalize_or_zero()
-    } else {
-        // Default to last non-zero x direction or right if none
-        Vec2::new(1.0, 0.0)
+fn record_player_crouch_release(
+    trigger: Trigger<Completed<CrouchAction>>,
+    mut controller_query: Query<&mut CrouchController>,
+) {
+    let Ok(mut crouch_controller) = controller_query.get_mut(trigger.target()) else {
+        return;
     };
-    // Queue the action with directional information
-    character_controller.queue_action(ActionType::FrostAttack { direction });
+    crouch_controller.set_held(false);
+}
+
+fn record_player_grab_input(
+    trigger: Trigger<Started<GrabAction>>,
+    mut controller_query: Query<&mut Carrier, Without<Ragdoll>>,
+) {
+    let Ok(mut carrier) = controller_query.get_mut(trigger.target()) else {
+        return;
+    };
+    carrier.request_grab_or_throw();
+}
+
+fn record_player_detonate_input(
+    trigger: Trigger<Started<DetonateAction>>,
+    player_query: Query<(), Without<Ragdoll>>,
+    mut detonate_events: EventWriter<DetonateCharges>,
+) {
+    if player_query.contains(trigger.target()) {
+        detonate_events.write(DetonateCharges);
+    }
+}
+
+fn record_dialogue_advance_input(
+    _: Trigger<Started<AdvanceDialogueAction>>,
+    mut dialogue_queue: ResMut<DialogueQueue>,
+) {
+    if dialogue_queue.is_active() {
+        dialogue_queue.request_advance();
+    }
 }
 
-fn record_player_lightning_input(
-    trigger: Trigger<Started<LightningAction>>,
-    cooldown: Res<LightningCooldown>,
-    mut controller_query: Query<(&mut CharacterController, &MovementController)>,
+fn record_player_slot_input<A: InputAction, const SLOT: usize>(
+    trigger: Trigger<Started<A>>,
+    time: Res<Time<Real>>,
+    loadout: Res<Loadout>,
+    unlocked: Res<UnlockedAbilities>,
+    mut controller_query: Query<
+        (
+            &mut CharacterController,
+            &MovementController,
+            &CasterCooldowns,
+        ),
+        Without<Ragdoll>,
+    >,
 ) {
-    if !cooldown.timer.finished() {
-        info!("Timer not finished!");
+    let Some(ability) = loadout.slots[SLOT] else {
+        return;
+    };
+    if !unlocked.is_unlocked(ability) {
+        info!("{ability:?} hasn't been unlocked yet!");
         return;
     }
-    let (mut character_controller, movement_controller) =
-        controller_query.get_mut(trigger.target()).unwrap();
+    let Ok((mut character_controller, movement_controller, cooldowns)) =
+        controller_query.get_mut(trigger.target())
+    else {
+        return;
+    };
+    if !cooldowns.is_ready(ability) {
+        // This player's still waiting on the ability's cooldown
+        return;
+    }
+
+    // Determine direction based on movement controller
     let direction = if movement_controller.direction.length_squared() > 0.0 {
         // Use the current movement direction if moving
         movement_controller.direction.normalize_or_zero()
@@ -143,5 +368,13 @@ fn record_player_lightning_input(
         // Default to last non-zero x direction or right if none
         Vec2::new(1.0, 0.0)
     };
-    character_controller.queue_action(ActionType::LightningAttack { direction });
+
+    // Queue the action with directional information
+    character_controller.queue_action(
+        ActionType::UseSlot {
+            slot: SLOT,
+            direction,
+        },
+        time.elapsed(),
+    );
 }
@@ -0,0 +1,224 @@
+//! A kinematic character controller, for levels that need precise platforming.
+//!
+//! Normally the player is a dynamic body pushed around by its velocity, which
+//! lets it knock over castles but also jitter on stacked blocks and get
+//! bounced by the solver. Levels with `KinematicPlayer` set in LDtk (see
+//! [`LevelPhysics`]) turn players into kinematic bodies instead. Each physics
+//! step their velocity is swept through the world with a shape cast and slid
+//! along whatever it hits, so they move exactly as far as there's room for.
+//! A grounded character also steps up small ledges, like a fallen block, and
+//! is snapped back down onto the ground when walking off a slope or a step,
+//! rather than launching off it.
+//!
+//! The movement systems don't need to know which mode they're in, they keep
+//! steering the player through `LinearVelocity`. Gravity and knockback, which
+//! the physics engine only applies to dynamic bodies, are applied here instead.
+
+use avian2d::{
+    prelude::*,
+    schedule::{PhysicsSchedule, PhysicsStepSet},
+};
+use bevy::prelude::*;
+
+use crate::demo::{level_physics::LevelPhysics, player::Player};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<KinematicCharacter>();
+    app.add_systems(Update, switch_player_bodies);
+    app.add_systems(
+        PhysicsSchedule,
+        (apply_kinematic_impulses, move_and_slide)
+            .chain()
+            .in_set(PhysicsStepSet::First),
+    );
+}
+
+/// Gap kept between a kinematic character and what it's touching, so the next
+/// shape cast doesn't start out already overlapping it.
+const SKIN_WIDTH: f32 = 0.5;
+/// How many surfaces a character can slide off in a single step, e.g. a wall
+/// and then the floor in a corner.
+const MAX_SLIDES: usize = 4;
+/// The tallest ledge a grounded character walks up without jumping.
+const STEP_HEIGHT: f32 = 6.0;
+/// How far below its feet a grounded character looks for ground to stick to.
+const SNAP_DISTANCE: f32 = 4.0;
+/// Surfaces whose normal points at least this far up can be stood on.
+const MIN_GROUND_NORMAL_Y: f32 = 0.7;
+
+/// A character moved by [`move_and_slide`] rather than by the physics solver.
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component)]
+pub struct KinematicCharacter {
+    /// Whether the character ended the last step standing on something.
+    pub grounded: bool,
+}
+
+/// Turn players kinematic or back again when the level asks for it.
+fn switch_player_bodies(
+    mut commands: Commands,
+    level_physics: Res<LevelPhysics>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut RigidBody,
+            &mut Rotation,
+            &mut AngularVelocity,
+            Has<KinematicCharacter>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (entity, mut rigid_body, mut rotation, mut angular_velocity, kinematic) in &mut player_query
+    {
+        if kinematic == level_physics.kinematic_player {
+            continue;
+        }
+        if level_physics.kinematic_player {
+            *rigid_body = RigidBody::Kinematic;
+            // The upright spring can't turn a kinematic body, so right it for good.
+            *rotation = Rotation::IDENTITY;
+            angular_velocity.0 = 0.0;
+            commands
+                .entity(entity)
+                .insert(KinematicCharacter::default());
+        } else {
+            *rigid_body = RigidBody::Dynamic;
+            commands.entity(entity).remove::<KinematicCharacter>();
+        }
+    }
+}
+
+/// Kinematic bodies ignore impulses, so turn knockback and shockwaves into a
+/// change of velocity by hand.
+fn apply_kinematic_impulses(
+    mut query: Query<(&mut ExternalImpulse, &Mass, &mut LinearVelocity), With<KinematicCharacter>>,
+) {
+    for (mut impulse, mass, mut velocity) in &mut query {
+        if mass.0 > 0.0 {
+            velocity.0 += impulse.impulse() / mass.0;
+        }
+        impulse.clear();
+    }
+}
+
+/// Sweep each kinematic character along its velocity for this step, sliding
+/// along anything in the way, and leave it with the velocity that gets it there.
+fn move_and_slide(
+    time: Res<Time>,
+    gravity: Res<Gravity>,
+    spatial_query: SpatialQuery,
+    sensor_query: Query<(), With<Sensor>>,
+    mut query: Query<(
+        Entity,
+        &mut KinematicCharacter,
+        &Collider,
+        &Position,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+        Option<&GravityScale>,
+    )>,
+) {
+    let delta_time = time.delta_secs();
+    if delta_time <= 0.0 {
+        return;
+    }
+    for (
+        entity,
+        mut character,
+        collider,
+        position,
+        mut velocity,
+        mut angular_velocity,
+        gravity_scale,
+    ) in &mut query
+    {
+        // A kinematic character never tips over, even when knocked senseless.
+        angular_velocity.0 = 0.0;
+        // Standing on the ground soaks up any fall, including last step's snap.
+        if character.grounded {
+            velocity.y = velocity.y.max(0.0);
+        }
+        velocity.0 += gravity.0 * gravity_scale.map_or(1.0, |scale| scale.0) * delta_time;
+
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+        // Triggers, pickups and water don't get in the way.
+        let sweep = |origin: Vec2, direction: Dir2, distance: f32| {
+            spatial_query.cast_shape_predicate(
+                collider,
+                origin,
+                0.0,
+                direction,
+                &ShapeCastConfig::from_max_distance(distance + SKIN_WIDTH),
+                &filter,
+                &|hit| !sensor_query.contains(hit),
+            )
+        };
+        // How far down the ground is, if there's ground within `distance` to stand on.
+        let ground_below = |origin: Vec2, distance: f32| {
+            sweep(origin, Dir2::NEG_Y, distance)
+                .filter(|hit| hit.normal1.y >= MIN_GROUND_NORMAL_Y)
+                .map(|hit| (hit.distance - SKIN_WIDTH).max(0.0))
+        };
+
+        let mut origin = position.0;
+        let mut remaining = velocity.0 * delta_time;
+        for _ in 0..MAX_SLIDES {
+            let Ok((direction, distance)) = Dir2::new_and_length(remaining) else {
+                break;
+            };
+            let Some(hit) = sweep(origin, direction, distance) else {
+                origin += remaining;
+                break;
+            };
+            let travelled = (hit.distance - SKIN_WIDTH).clamp(0.0, distance);
+            origin += direction * travelled;
+            remaining -= direction * travelled;
+
+            // Walk up a ledge low enough to step onto, rather than stopping at it.
+            let is_wall = hit.normal1.y.abs() < 1.0 - MIN_GROUND_NORMAL_Y;
+            let stepped = (character.grounded && is_wall)
+                .then(|| step_up(origin, Vec2::new(remaining.x, 0.0), &sweep, &ground_below))
+                .flatten();
+            if let Some(stepped) = stepped {
+                origin = stepped;
+                remaining.x = 0.0;
+                continue;
+            }
+            // Whatever's left of the move carries on along the surface.
+            remaining = remaining.reject_from_normalized(hit.normal1);
+        }
+
+        // Stick to the ground when walking off a slope or a step, unless jumping.
+        let falling = origin.y <= position.0.y;
+        if character.grounded && falling {
+            origin.y -= ground_below(origin, SNAP_DISTANCE).unwrap_or(0.0);
+        }
+        character.grounded = ground_below(origin, SKIN_WIDTH).is_some();
+        velocity.0 = (origin - position.0) / delta_time;
+    }
+}
+
+/// Where a character at `origin` ends up if it climbs [`STEP_HEIGHT`], moves
+/// along `step` and settles back onto the ledge, or `None` if there's no ledge
+/// low enough or no room to stand on it.
+fn step_up(
+    origin: Vec2,
+    step: Vec2,
+    sweep: &impl Fn(Vec2, Dir2, f32) -> Option<ShapeHitData>,
+    ground_below: &impl Fn(Vec2, f32) -> Option<f32>,
+) -> Option<Vec2> {
+    let (direction, distance) = Dir2::new_and_length(step).ok()?;
+    let rise = sweep(origin, Dir2::Y, STEP_HEIGHT)
+        .map_or(STEP_HEIGHT, |hit| (hit.distance - SKIN_WIDTH).max(0.0));
+    let raised = origin + Vec2::Y * rise;
+    let travelled = match sweep(raised, direction, distance) {
+        // Still blocked at the top of the step, it's a wall rather than a ledge.
+        Some(hit) if hit.distance <= SKIN_WIDTH => return None,
+        Some(hit) => (hit.distance - SKIN_WIDTH).clamp(0.0, distance),
+        None => distance,
+    };
+    let forward = raised + direction * travelled;
+    let drop = ground_below(forward, rise)?;
+    Some(forward - Vec2::Y * drop)
+}
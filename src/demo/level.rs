@@ -1,9 +1,27 @@
 //! Spawn the main level.
+//!
+//! Levels are laid out at their LDtk world positions with their neighbours
+//! loaded alongside them, so walking off the edge of one level carries the
+//! player into the next. Only the starting level should contain a player.
+//!
+//! In native dev builds the LDtk project is hot reloaded: saving it in the
+//! editor respawns the level in place, and the players are put back where they
+//! were standing.
+//!
+//! [`LevelState`] follows the levels through LDtk's spawn events, so systems
+//! that need the level in place can wait for [`LevelState::Ready`].
 
-use bevy::prelude::*;
-use bevy_ecs_ldtk::prelude::*;
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*};
 
-use crate::{asset_tracking::LoadResource, audio::music, screens::Screen};
+use crate::{
+    asset_tracking::LoadResource,
+    demo::player::{Player, PlayerIndex},
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(LdtkPlugin);
@@ -11,23 +29,49 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<LevelAssets>();
     app.load_resource::<LevelAssets>();
     app.insert_resource(LevelSelection::index(0));
-    app.insert_resource(LdtkReady::default());
+    app.insert_resource(LdtkSettings {
+        level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
+            load_level_neighbors: true,
+        },
+        ..default()
+    });
+    app.add_event::<EnteredNeighborLevel>();
+    app.init_state::<LevelState>();
+    app.init_resource::<SpawningLevels>();
+    app.add_event::<LevelReloaded>();
+    app.init_resource::<ReloadedPlayerPositions>();
+    app.add_systems(OnExit(Screen::Gameplay), unload_levels);
+    app.add_systems(
+        Update,
+        (
+            track_level_events.after(detect_level_reload),
+            finish_unloading.run_if(in_state(LevelState::Unloading)),
+        )
+            .chain(),
+    );
+    app.add_systems(OnEnter(LevelState::Ready), log_level_ready);
+    app.add_systems(
+        Update,
+        (
+            detect_level_reload,
+            restore_player_positions.run_if(in_state(LevelState::Ready)),
+            enter_neighbor_level,
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
 }
 
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct LevelAssets {
-    #[dependency]
-    music: Handle<AudioSource>,
-    ldtk_level: LdtkProjectHandle,
+    pub ldtk_level: LdtkProjectHandle,
 }
 
 impl FromWorld for LevelAssets {
     fn from_world(world: &mut World) -> Self {
         let assets = world.resource::<AssetServer>();
         Self {
-            music: assets.load("audio/music/FireDuck.ogg"),
-
             ldtk_level: LdtkProjectHandle {
                 handle: assets.load("levels/level.ldtk"),
             },
@@ -44,12 +88,230 @@ pub fn spawn_level(mut commands: Commands, level_assets: Res<LevelAssets>) {
             ..default()
         },
         StateScoped(Screen::Gameplay),
-        children![(
-            Name::new("Gameplay Music 1"),
-            music(level_assets.music.clone())
-        )],
     ));
 }
 
-#[derive(Resource, Default)]
-pub struct LdtkReady;
+/// The LDtk identifier of the level that `selection` points at, e.g. `Level_0`.
+pub fn selected_level_identifier(
+    selection: &LevelSelection,
+    project: &LdtkProject,
+) -> Option<String> {
+    match selection {
+        LevelSelection::Identifier(identifier) => Some(identifier.clone()),
+        LevelSelection::Iid(iid) => project
+            .get_raw_level_by_iid(iid.get())
+            .map(|level| level.identifier.clone()),
+        LevelSelection::Indices(indices) => project
+            .json_data()
+            .levels
+            .get(indices.level)
+            .map(|level| level.identifier.clone()),
+        _ => None,
+    }
+}
+
+/// The LDtk level that `selection` points at, if it's in the root of the project.
+pub fn selected_level<'a>(
+    selection: &LevelSelection,
+    project: &'a LdtkProject,
+) -> Option<&'a Level> {
+    project
+        .json_data()
+        .levels
+        .iter()
+        .enumerate()
+        .find(|(index, level)| selection.is_match(&LevelIndices::in_root(*index), level))
+        .map(|(_, level)| level)
+}
+
+/// Whether the LDtk project has finished loading, so levels can be spawned from it.
+pub fn ldtk_project_loaded(
+    level_assets: Option<Res<LevelAssets>>,
+    assets: Res<AssetServer>,
+) -> bool {
+    level_assets.is_some_and(|level_assets| {
+        assets.is_loaded_with_dependencies(&level_assets.ldtk_level.handle)
+    })
+}
+
+/// Where the levels are in their lifecycle.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LevelState {
+    /// No levels are spawned, e.g. while the LDtk project loads or outside gameplay.
+    #[default]
+    Loading,
+    /// LDtk has started spawning levels that haven't been placed in the world yet.
+    Spawning,
+    /// Every spawned level, and everything in it, is in place.
+    Ready,
+    /// The levels are being torn down, either to leave gameplay or to reload them.
+    Unloading,
+}
+
+/// Levels LDtk has started spawning but hasn't placed in the world yet.
+#[derive(Resource, Default, Debug)]
+struct SpawningLevels(HashSet<LevelIid>);
+
+/// Sent when the LDtk project changes on disk and the level is about to be respawned.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelReloaded;
+
+/// Sent when the first player walks into a neighbouring level and it becomes the selected level.
+#[derive(Event, Debug, Clone)]
+pub struct EnteredNeighborLevel {
+    pub level_iid: LevelIid,
+}
+
+/// Where each player stood, relative to the world, before it was reloaded.
+#[derive(Resource, Default, Debug)]
+struct ReloadedPlayerPositions(HashMap<usize, Vec2>);
+
+fn track_level_events(
+    mut level_events: EventReader<LevelEvent>,
+    mut reloaded_events: EventReader<LevelReloaded>,
+    mut spawning: ResMut<SpawningLevels>,
+    level_state: Res<State<LevelState>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    let mut state = *level_state.get();
+    if reloaded_events.read().count() > 0 {
+        spawning.0.clear();
+        state = LevelState::Unloading;
+    }
+    for event in level_events.read() {
+        match event {
+            LevelEvent::SpawnTriggered(level_iid) => {
+                spawning.0.insert(level_iid.clone());
+                state = LevelState::Spawning;
+            }
+            // Levels are only in place once they've been transformed to their world position.
+            LevelEvent::Transformed(level_iid) => {
+                spawning.0.remove(level_iid);
+                if spawning.0.is_empty() && state == LevelState::Spawning {
+                    state = LevelState::Ready;
+                }
+            }
+            LevelEvent::Despawned(level_iid) => {
+                spawning.0.remove(level_iid);
+            }
+            LevelEvent::Spawned(_) => {}
+        }
+    }
+    if state != *level_state.get() {
+        next_level_state.set(state);
+    }
+}
+
+/// Leaving gameplay despawns the whole LDtk world, without any level events.
+fn unload_levels(
+    mut spawning: ResMut<SpawningLevels>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    spawning.0.clear();
+    next_level_state.set(LevelState::Unloading);
+}
+
+fn finish_unloading(
+    level_query: Query<(), With<LevelIid>>,
+    mut next_level_state: ResMut<NextState<LevelState>>,
+) {
+    if level_query.is_empty() {
+        next_level_state.set(LevelState::Loading);
+    }
+}
+
+fn log_level_ready(level_query: Query<(), With<LevelIid>>) {
+    debug!("{} levels spawned and ready", level_query.iter().count());
+}
+
+fn detect_level_reload(
+    mut asset_events: EventReader<AssetEvent<LdtkProject>>,
+    level_assets: Res<LevelAssets>,
+    player_query: Query<(&PlayerIndex, &Transform), With<Player>>,
+    mut positions: ResMut<ReloadedPlayerPositions>,
+    mut reloaded: EventWriter<LevelReloaded>,
+) {
+    let project_id = level_assets.ldtk_level.handle.id();
+    let modified = asset_events
+        .read()
+        .any(|event| event.is_modified(project_id));
+    if !modified {
+        return;
+    }
+    // `LdtkPlugin` respawns every world using the project, which tears down the
+    // castles, their joints and the players with it.
+    info!("LDtk project changed, reloading the level");
+    // Players are worldly, so their translation is relative to the world rather than any one level.
+    positions.0 = player_query
+        .iter()
+        .map(|(index, transform)| (index.0, transform.translation.truncate()))
+        .collect();
+    reloaded.write(LevelReloaded);
+}
+
+fn restore_player_positions(
+    mut positions: ResMut<ReloadedPlayerPositions>,
+    mut player_query: Query<(&PlayerIndex, &mut Transform), Added<Player>>,
+) {
+    if positions.0.is_empty() {
+        return;
+    }
+    for (index, mut transform) in &mut player_query {
+        if let Some(position) = positions.0.remove(&index.0) {
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Select the neighbouring level the first player has walked into.
+fn enter_neighbor_level(
+    level_query: Query<(&LevelIid, &Transform)>,
+    player_query: Query<(&PlayerIndex, &Transform), With<Player>>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut entered_events: EventWriter<EnteredNeighborLevel>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some((_, player_transform)) = player_query.iter().find(|(index, _)| index.0 == 0) else {
+        return;
+    };
+    let Some((_, current_level)) = project
+        .json_data()
+        .levels
+        .iter()
+        .enumerate()
+        .find(|(index, level)| level_selection.is_match(&LevelIndices::in_root(*index), level))
+    else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for (level_iid, level_transform) in &level_query {
+        let is_neighbor = current_level
+            .neighbours
+            .iter()
+            .any(|neighbour| neighbour.level_iid == level_iid.get().as_str());
+        if !is_neighbor {
+            continue;
+        }
+        let Some(level) = project.get_raw_level_by_iid(level_iid.get()) else {
+            continue;
+        };
+        let min = level_transform.translation.truncate();
+        let bounds = Rect::from_corners(
+            min,
+            min + Vec2::new(level.px_wid as f32, level.px_hei as f32),
+        );
+        if bounds.contains(player_position) {
+            info!("Entered neighbouring level {}", level.identifier);
+            *level_selection = LevelSelection::Iid(level_iid.clone());
+            entered_events.write(EnteredNeighborLevel {
+                level_iid: level_iid.clone(),
+            });
+            return;
+        }
+    }
+}
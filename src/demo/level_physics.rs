@@ -0,0 +1,108 @@
+//! Physics tweaks set per level from LDtk level fields.
+//!
+//! Designers can give a level any of these optional float fields in LDtk:
+//!
+//! - `GravityScale`: multiplies gravity, e.g. `0.3` for a moon level.
+//! - `Wind`: sideways acceleration on projectiles and light debris, in world
+//!   units per second squared. Positive blows to the right. See `weather`.
+//! - `MortarStrength`: multiplies how much impulse mortar joints can take,
+//!   e.g. `0.4` for an old crumbling castle.
+//!
+//! Missing fields leave the default physics alone. A level can also tick the
+//! `KinematicPlayer` bool field for precise platforming, see `kinematic`.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*};
+
+use crate::{
+    demo::{
+        level::{EnteredNeighborLevel, LevelAssets, LevelState, selected_level},
+        movement::GRAVITY,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelPhysics>();
+    app.init_resource::<LevelPhysics>();
+    app.add_systems(OnEnter(LevelState::Ready), read_level_physics);
+    app.add_systems(OnExit(Screen::Gameplay), reset_level_physics);
+    app.add_systems(
+        Update,
+        (
+            read_level_physics.run_if(on_event::<EnteredNeighborLevel>),
+            apply_level_gravity.run_if(resource_changed::<LevelPhysics>),
+        )
+            .chain(),
+    );
+}
+
+/// The physics of the level currently being played.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct LevelPhysics {
+    pub gravity_scale: f32,
+    /// Sideways acceleration on projectiles and light debris, everywhere in the level.
+    pub wind: f32,
+    /// Multiplier on the impulse mortar joints can take.
+    pub mortar_strength: f32,
+    /// Whether players are moved by the kinematic controller instead of the solver.
+    pub kinematic_player: bool,
+}
+
+impl Default for LevelPhysics {
+    fn default() -> Self {
+        Self {
+            gravity_scale: 1.0,
+            wind: 0.0,
+            mortar_strength: 1.0,
+            kinematic_player: false,
+        }
+    }
+}
+
+impl LevelPhysics {
+    fn from_level(level: &Level) -> Self {
+        let default = Self::default();
+        let field = |identifier: &str, default: f32| match level.get_maybe_float_field(identifier) {
+            Ok(&Some(value)) => value,
+            _ => default,
+        };
+        Self {
+            gravity_scale: field("GravityScale", default.gravity_scale),
+            wind: field("Wind", default.wind),
+            mortar_strength: field("MortarStrength", default.mortar_strength),
+            kinematic_player: level
+                .get_bool_field("KinematicPlayer")
+                .map_or(default.kinematic_player, |&kinematic| kinematic),
+        }
+    }
+}
+
+fn read_level_physics(
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    level_selection: Res<LevelSelection>,
+    mut level_physics: ResMut<LevelPhysics>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some(level) = selected_level(&level_selection, project) else {
+        return;
+    };
+    let physics = LevelPhysics::from_level(level);
+    if physics != *level_physics {
+        info!("Level {} physics: {physics:?}", level.identifier);
+        *level_physics = physics;
+    }
+}
+
+fn reset_level_physics(mut level_physics: ResMut<LevelPhysics>) {
+    *level_physics = LevelPhysics::default();
+}
+
+fn apply_level_gravity(level_physics: Res<LevelPhysics>, mut gravity: ResMut<Gravity>) {
+    gravity.0 = Vec2::NEG_Y * GRAVITY * level_physics.gravity_scale;
+}
@@ -0,0 +1,334 @@
+//! Ambient light, a day/night cycle and glowing abilities.
+//!
+//! The ambient light darkens and cools the world cameras through their color
+//! grading as night falls. Fireballs, lightning and explosions carry soft
+//! light sprites, bright enough to cut through the dark, so at night the
+//! player's own abilities light up the castle.
+//!
+//! Levels set the time with an optional `TimeOfDay` float field, from `0.0`
+//! at midnight through `0.5` at noon, and make it pass with an optional
+//! `DayLengthSecs` float field. Without them it's always noon.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::ColorGrading,
+    },
+};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        balistics::{Fireball, LightningBolt},
+        camera::UiOnlyCamera,
+        collision::Detonation,
+        level::{EnteredNeighborLevel, LevelAssets, LevelState, selected_level},
+        player::LightningState,
+    },
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DayNight>();
+    app.init_resource::<DayNight>();
+    app.init_resource::<LightAssets>();
+    app.add_systems(OnEnter(LevelState::Ready), read_level_day_night);
+    app.add_systems(OnExit(Screen::Gameplay), reset_day_night);
+    app.add_systems(
+        Update,
+        read_level_day_night.run_if(on_event::<EnteredNeighborLevel>),
+    );
+    app.add_systems(
+        Update,
+        (
+            advance_day_night,
+            attach_ability_lights,
+            flash_explosion_lights,
+            update_lights,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(Update, apply_ambient_light.in_set(AppSystems::Update));
+}
+
+/// Exposure of the world cameras at midnight, in stops.
+const NIGHT_EXPOSURE: f32 = -2.5;
+/// Color temperature of the world cameras at midnight. Negative is bluer.
+const NIGHT_TEMPERATURE: f32 = -0.4;
+/// Size of the generated light texture, in pixels.
+const LIGHT_TEXTURE_SIZE: u32 = 64;
+/// How opaque a light is at midnight. Lights fade out as the day brightens.
+const MAX_LIGHT_ALPHA: f32 = 0.6;
+/// Lights are drawn in front of the level, behind precipitation.
+const LIGHT_Z: f32 = 15.0;
+const FIREBALL_LIGHT: LightSource = LightSource {
+    color: Color::srgb(1.0, 0.6, 0.2),
+    radius: 48.0,
+};
+const LIGHTNING_LIGHT: LightSource = LightSource {
+    color: Color::srgb(0.7, 0.8, 1.0),
+    radius: 64.0,
+};
+const EXPLOSION_LIGHT: LightSource = LightSource {
+    color: Color::srgb(1.0, 0.7, 0.3),
+    radius: 128.0,
+};
+const EXPLOSION_LIGHT_SECS: f32 = 0.6;
+
+/// The time of day in the current level.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct DayNight {
+    /// From 0 at midnight through 0.5 at noon and back round to 1.
+    pub time_of_day: f32,
+    /// How long a whole day lasts, or `None` if the time stands still.
+    pub day_length_secs: Option<f32>,
+}
+
+impl Default for DayNight {
+    fn default() -> Self {
+        Self {
+            time_of_day: 0.5,
+            day_length_secs: None,
+        }
+    }
+}
+
+impl DayNight {
+    /// How light it is, from 0 at midnight to 1 at noon.
+    pub fn daylight(&self) -> f32 {
+        (1.0 - (self.time_of_day * std::f32::consts::TAU).cos()) / 2.0
+    }
+
+    fn exposure(&self) -> f32 {
+        NIGHT_EXPOSURE * (1.0 - self.daylight())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LightSource {
+    color: Color,
+    radius: f32,
+}
+
+/// A soft light sprite following `target`, despawned once the target is gone.
+#[derive(Component, Debug)]
+struct Light {
+    target: Option<Entity>,
+    source: LightSource,
+    /// Lights that fade out on their own, like explosion flashes.
+    fade: Option<Timer>,
+}
+
+/// The texture light sprites are drawn with.
+#[derive(Resource, Debug)]
+struct LightAssets {
+    glow: Handle<Image>,
+}
+
+impl FromWorld for LightAssets {
+    fn from_world(world: &mut World) -> Self {
+        // A white disc fading out towards its edge.
+        let size = LIGHT_TEXTURE_SIZE;
+        let center = Vec2::splat(size as f32 / 2.0);
+        let data = (0..size * size)
+            .flat_map(|index| {
+                let pixel = Vec2::new((index % size) as f32, (index / size) as f32) + 0.5;
+                let falloff = (1.0 - pixel.distance(center) / center.x).clamp(0.0, 1.0);
+                [255, 255, 255, (falloff * falloff * 255.0) as u8]
+            })
+            .collect();
+        let image = Image::new(
+            Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        Self {
+            glow: world.resource_mut::<Assets<Image>>().add(image),
+        }
+    }
+}
+
+fn read_level_day_night(
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    level_selection: Res<LevelSelection>,
+    mut day_night: ResMut<DayNight>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some(level) = selected_level(&level_selection, project) else {
+        return;
+    };
+    let default = DayNight::default();
+    let time_of_day = match level.get_maybe_float_field("TimeOfDay") {
+        Ok(&Some(time_of_day)) => time_of_day.rem_euclid(1.0),
+        _ => default.time_of_day,
+    };
+    let day_length_secs = match level.get_maybe_float_field("DayLengthSecs") {
+        Ok(&Some(secs)) if secs > 0.0 => Some(secs),
+        _ => None,
+    };
+    info!(
+        "Level {} starts at time of day {time_of_day} (day length {day_length_secs:?})",
+        level.identifier
+    );
+    *day_night = DayNight {
+        time_of_day,
+        day_length_secs,
+    };
+}
+
+fn reset_day_night(mut day_night: ResMut<DayNight>) {
+    *day_night = DayNight::default();
+}
+
+fn advance_day_night(time: Res<Time>, mut day_night: ResMut<DayNight>) {
+    let Some(day_length_secs) = day_night.day_length_secs else {
+        return;
+    };
+    day_night.time_of_day = (day_night.time_of_day + time.delta_secs() / day_length_secs) % 1.0;
+}
+
+fn apply_ambient_light(
+    mut commands: Commands,
+    day_night: Res<DayNight>,
+    mut camera_query: Query<
+        (Entity, Option<&mut ColorGrading>),
+        (With<Camera2d>, Without<UiOnlyCamera>),
+    >,
+) {
+    for (entity, color_grading) in &mut camera_query {
+        let Some(mut color_grading) = color_grading else {
+            commands.entity(entity).insert(ColorGrading::default());
+            continue;
+        };
+        color_grading.global.exposure = day_night.exposure();
+        color_grading.global.temperature = NIGHT_TEMPERATURE * (1.0 - day_night.daylight());
+    }
+}
+
+fn spawn_light(
+    commands: &mut Commands,
+    light_assets: &LightAssets,
+    target: Option<Entity>,
+    position: Vec2,
+    source: LightSource,
+    fade: Option<Timer>,
+) {
+    commands.spawn((
+        Name::new("Light"),
+        Light {
+            target,
+            source,
+            fade,
+        },
+        Sprite {
+            image: light_assets.glow.clone(),
+            custom_size: Some(Vec2::splat(source.radius * 2.0)),
+            color: Color::NONE,
+            ..default()
+        },
+        Transform::from_translation(position.extend(LIGHT_Z)),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+fn attach_ability_lights(
+    mut commands: Commands,
+    light_assets: Res<LightAssets>,
+    fireball_query: Query<(Entity, &GlobalTransform), Added<Fireball>>,
+    lightning_query: Query<
+        (Entity, &GlobalTransform),
+        Or<(Added<LightningBolt>, Added<LightningState>)>,
+    >,
+) {
+    let lights = fireball_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform, FIREBALL_LIGHT))
+        .chain(
+            lightning_query
+                .iter()
+                .map(|(entity, transform)| (entity, transform, LIGHTNING_LIGHT)),
+        );
+    for (entity, transform, source) in lights {
+        spawn_light(
+            &mut commands,
+            &light_assets,
+            Some(entity),
+            transform.translation().truncate(),
+            source,
+            None,
+        );
+    }
+}
+
+fn flash_explosion_lights(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    light_assets: Res<LightAssets>,
+    mut detonation_events: EventReader<Detonation>,
+) {
+    if !settings.flashes {
+        detonation_events.clear();
+        return;
+    }
+    for detonation in detonation_events.read() {
+        spawn_light(
+            &mut commands,
+            &light_assets,
+            None,
+            detonation.transform.translation.truncate(),
+            EXPLOSION_LIGHT,
+            Some(Timer::from_seconds(EXPLOSION_LIGHT_SECS, TimerMode::Once)),
+        );
+    }
+}
+
+fn update_lights(
+    mut commands: Commands,
+    time: Res<Time>,
+    day_night: Res<DayNight>,
+    // Pooled projectiles are disabled rather than despawned, which also hides them from this query.
+    target_query: Query<
+        &GlobalTransform,
+        Or<(With<Fireball>, With<LightningBolt>, With<LightningState>)>,
+    >,
+    mut light_query: Query<(Entity, &mut Light, &mut Transform, &mut Sprite)>,
+) {
+    let darkness = 1.0 - day_night.daylight();
+    // Lights are drawn brighter to make up for the darkened exposure, so they glow through the night.
+    let brightness = (-day_night.exposure()).exp2();
+    for (entity, mut light, mut transform, mut sprite) in &mut light_query {
+        let mut alpha = MAX_LIGHT_ALPHA * darkness;
+        if let Some(target) = light.target {
+            let Ok(target_transform) = target_query.get(target) else {
+                commands.entity(entity).despawn();
+                continue;
+            };
+            transform.translation = target_transform.translation().truncate().extend(LIGHT_Z);
+        }
+        if let Some(fade) = &mut light.fade {
+            if fade.tick(time.delta()).finished() {
+                commands.entity(entity).despawn();
+                continue;
+            }
+            alpha *= 1.0 - fade.fraction();
+        }
+        let color = LinearRgba::from(light.source.color) * brightness;
+        sprite.color = Color::LinearRgba(color.with_alpha(alpha));
+    }
+}
@@ -0,0 +1,174 @@
+//! Player lives, respawning and the heart display.
+//!
+//! When the player's [`Health`] runs out they lose a life and respawn where
+//! they entered the level. Losing the last life ends the run, and in endless
+//! mode the player only gets one.
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        attract::AttractMode,
+        game_mode::GameMode,
+        health::Health,
+        hud::{HudAnchor, HudRegion},
+        player::{Player, PlayerIndex},
+        progress::Progress,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Lives>();
+    app.init_resource::<Lives>();
+    app.add_systems(OnEnter(Screen::Gameplay), (reset_lives, spawn_health_ui));
+    app.add_systems(
+        Update,
+        (record_spawn_point, handle_player_death)
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(Update, update_health_ui.run_if(in_state(Screen::Gameplay)));
+}
+
+const STARTING_LIVES: u32 = 3;
+const HEART_COUNT: usize = 5;
+const HEART_FULL: Color = Color::srgb(0.85, 0.1, 0.15);
+const HEART_EMPTY: Color = Color::srgb(0.25, 0.1, 0.1);
+
+/// Lives left in the current run.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct Lives(pub u32);
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self(STARTING_LIVES)
+    }
+}
+
+/// Where the player is put back after losing a life.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnPoint(pub Vec3);
+
+fn reset_lives(mut lives: ResMut<Lives>, game_mode: Res<GameMode>) {
+    *lives = match *game_mode {
+        GameMode::Endless => Lives(1),
+        _ => Lives::default(),
+    };
+}
+
+fn record_spawn_point(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Transform), Added<Player>>,
+) {
+    for (entity, transform) in &player_query {
+        commands
+            .entity(entity)
+            .insert(SpawnPoint(transform.translation));
+    }
+}
+
+fn handle_player_death(
+    mut lives: ResMut<Lives>,
+    mut progress: ResMut<Progress>,
+    attract_mode: Res<AttractMode>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut player_query: Query<
+        (
+            &mut Health,
+            &SpawnPoint,
+            &mut Transform,
+            &mut LinearVelocity,
+        ),
+        With<Player>,
+    >,
+) {
+    for (mut health, spawn_point, mut transform, mut velocity) in &mut player_query {
+        if !health.is_dead() {
+            continue;
+        }
+
+        lives.0 = lives.0.saturating_sub(1);
+        if !attract_mode.active {
+            progress.stats.deaths += 1;
+        }
+        info!("Player died, {} lives left", lives.0);
+        if lives.0 == 0 {
+            next_screen.set(Screen::GameOver);
+            return;
+        }
+
+        *health = Health::new(health.max);
+        transform.translation = spawn_point.0;
+        transform.rotation = Quat::IDENTITY;
+        velocity.0 = Vec2::ZERO;
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct HeartIcon(usize);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LivesLabel;
+
+fn spawn_health_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Health Display"),
+            Node {
+                column_gap: Px(4.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            HudAnchor::new(HudRegion::TopLeft, 1),
+        ))
+        .with_children(|parent| {
+            for index in 0..HEART_COUNT {
+                parent.spawn((
+                    Name::new("Heart"),
+                    Node {
+                        width: Px(16.0),
+                        height: Px(16.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Px(4.0)),
+                    BackgroundColor(HEART_FULL),
+                    HeartIcon(index),
+                ));
+            }
+            parent.spawn((
+                Name::new("Lives"),
+                Text::new(format!("x{STARTING_LIVES}")),
+                TextFont::from_font_size(20.0),
+                LivesLabel,
+            ));
+        });
+}
+
+fn update_health_ui(
+    lives: Res<Lives>,
+    player_query: Query<(&Health, &PlayerIndex), With<Player>>,
+    mut heart_query: Query<(&HeartIcon, &mut BackgroundColor)>,
+    mut lives_label: Single<&mut Text, With<LivesLabel>>,
+) {
+    lives_label.0 = format!("x{}", lives.0);
+
+    // The hearts show the first player's health, co-op partners share the lives.
+    let Some((health, _)) = player_query.iter().find(|(_, index)| index.0 == 0) else {
+        return;
+    };
+    // Each heart stands for an equal share of the player's health.
+    let filled = (health.fraction() * HEART_COUNT as f32).ceil() as usize;
+    for (heart, mut background) in &mut heart_query {
+        background.0 = if heart.0 < filled {
+            HEART_FULL
+        } else {
+            HEART_EMPTY
+        };
+    }
+}
@@ -0,0 +1,176 @@
+//! The abilities a player takes into a level, one per hotbar slot.
+//!
+//! The loadout is picked on the loadout screen before each level, from the
+//! abilities unlocked in [`Progress`]. During gameplay each slot's key casts
+//! whatever ability is in it, and the hotbar at the bottom of the screen shows
+//! the slots along with their cooldowns.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    demo::{
+        balistics::{AbilityKind, CasterCooldowns, UnlockedAbilities},
+        hud::{HudAnchor, HudRegion},
+        input::SLOT_KEY_NAMES,
+        player::{Player, PlayerIndex},
+        progress::Progress,
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Loadout>();
+    app.add_systems(OnEnter(Screen::Gameplay), spawn_hotbar);
+    app.add_systems(
+        Update,
+        (fill_empty_slots, update_hotbar)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+pub const HOTBAR_SLOTS: usize = 3;
+const LOCKED_SLOT_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Loadout {
+    pub slots: [Option<AbilityKind>; HOTBAR_SLOTS],
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            slots: [Some(AbilityKind::Fireball), None, None],
+        }
+    }
+}
+
+impl Loadout {
+    /// Every ability that can go in a loadout, in the order they're offered.
+    pub const ABILITIES: [AbilityKind; 4] = [
+        AbilityKind::Fireball,
+        AbilityKind::Frost,
+        AbilityKind::Lightning,
+        AbilityKind::Charge,
+    ];
+
+    pub fn contains(&self, ability: AbilityKind) -> bool {
+        self.slots.contains(&Some(ability))
+    }
+
+    /// Drop abilities that aren't unlocked, then fill any empty slots.
+    pub fn fit_to(&mut self, is_unlocked: impl Fn(AbilityKind) -> bool) {
+        for slot in &mut self.slots {
+            if slot.is_some_and(|ability| !is_unlocked(ability)) {
+                *slot = None;
+            }
+        }
+        self.fill_empty_slots(is_unlocked);
+    }
+
+    /// Put unlocked abilities that aren't in the loadout yet into any empty slots.
+    pub fn fill_empty_slots(&mut self, is_unlocked: impl Fn(AbilityKind) -> bool) {
+        for ability in Self::ABILITIES {
+            if !is_unlocked(ability) || self.contains(ability) {
+                continue;
+            }
+            let Some(empty) = self.slots.iter_mut().find(|slot| slot.is_none()) else {
+                break;
+            };
+            *empty = Some(ability);
+        }
+    }
+
+    /// Switch `slot` to the next unlocked ability that isn't in another slot,
+    /// going through an empty slot after the last one.
+    pub fn cycle_slot(&mut self, slot: usize, progress: &Progress) {
+        let options: Vec<Option<AbilityKind>> = Self::ABILITIES
+            .into_iter()
+            .filter(|ability| is_collected(progress, *ability))
+            .filter(|ability| self.slots[slot] == Some(*ability) || !self.contains(*ability))
+            .map(Some)
+            .chain([None])
+            .collect();
+        let current = options
+            .iter()
+            .position(|option| *option == self.slots[slot])
+            .unwrap_or(options.len() - 1);
+        self.slots[slot] = options[(current + 1) % options.len()];
+    }
+}
+
+/// Whether `ability` is unlocked for good, outside of any one level.
+pub fn is_collected(progress: &Progress, ability: AbilityKind) -> bool {
+    ability == AbilityKind::Fireball || progress.collected_abilities.contains(&ability)
+}
+
+/// Abilities unlocked mid-level by a rune go straight into an empty slot.
+///
+/// Abilities locked behind a rune in this level stay in their slot, ready for when it's picked up.
+fn fill_empty_slots(unlocked: Res<UnlockedAbilities>, mut loadout: ResMut<Loadout>) {
+    if !unlocked.is_changed() {
+        return;
+    }
+    let mut filled = *loadout;
+    filled.fill_empty_slots(|ability| unlocked.is_unlocked(ability));
+    // Only touch the loadout when it changes, so its change detection stays meaningful.
+    if filled != *loadout {
+        *loadout = filled;
+    }
+}
+
+/// One hotbar slot in the HUD.
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+fn spawn_hotbar(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Hotbar"),
+            Node {
+                column_gap: Px(30.0),
+                ..default()
+            },
+            HudAnchor::new(HudRegion::Bottom, 0),
+        ))
+        .with_children(|parent| {
+            for slot in 0..HOTBAR_SLOTS {
+                parent.spawn((widget::label(""), HotbarSlot(slot)));
+            }
+        });
+}
+
+fn update_hotbar(
+    loadout: Res<Loadout>,
+    unlocked: Res<UnlockedAbilities>,
+    player_query: Query<(&PlayerIndex, &CasterCooldowns), With<Player>>,
+    mut slot_query: Query<(&HotbarSlot, &mut Text, &mut TextColor)>,
+) {
+    // Like the hearts, the hotbar shows the first player's cooldowns.
+    let cooldowns = player_query
+        .iter()
+        .find(|(index, _)| index.0 == 0)
+        .map(|(_, cooldowns)| cooldowns);
+    for (slot, mut text, mut color) in &mut slot_query {
+        let key = SLOT_KEY_NAMES[slot.0];
+        let Some(ability) = loadout.slots[slot.0] else {
+            text.0 = format!("[{key}] --");
+            color.0 = LOCKED_SLOT_COLOR;
+            continue;
+        };
+        if !unlocked.is_unlocked(ability) {
+            text.0 = format!("[{key}] {ability:?} (locked)");
+            color.0 = LOCKED_SLOT_COLOR;
+            continue;
+        }
+        text.0 = match cooldowns.filter(|cooldowns| !cooldowns.is_ready(ability)) {
+            Some(cooldowns) => format!(
+                "[{key}] {ability:?} {:.1}s",
+                cooldowns.remaining_secs(ability)
+            ),
+            None => format!("[{key}] {ability:?}"),
+        };
+        color.0 = ability.color();
+    }
+}
@@ -0,0 +1,196 @@
+//! A little castle being knocked down behind the main menu.
+//!
+//! While the title screen is up, the camera looks at a miniature scene built
+//! well away from where levels are spawned: a patch of ground, a small stone
+//! castle and an invisible caster that lobs fireballs at it every so often.
+//! Fireballs are cast through the same [`CharacterController`] queue players
+//! use, so they fly, explode and push blocks around with the real projectile
+//! and shockwave code. Nobody controls anything. Once the castle is mostly
+//! rubble it's cleared away and built again.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, render::camera::ScalingMode};
+use rand::Rng;
+
+use crate::{
+    demo::{
+        balistics::AbilityKind,
+        camera::{PlayerCamera, UiOnlyCamera},
+        collision::CollisionBundle,
+        input::ActionType,
+        player::CharacterController,
+        rng::GameRng,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Title), (spawn_menu_demo, frame_menu_demo));
+    app.add_systems(
+        Update,
+        (cast_demo_fireballs, rebuild_demo_castle).run_if(in_state(Screen::Title)),
+    );
+}
+
+/// Where the demo scene is built, far from any level.
+const DEMO_ORIGIN: Vec2 = Vec2::new(0.0, -20_000.0);
+/// How much of the scene fits in the height of the window.
+const DEMO_VIEW_HEIGHT: f32 = 360.0;
+const DEMO_GROUND_SIZE: Vec2 = Vec2::new(1200.0, 32.0);
+const DEMO_GROUND_COLOR: Color = Color::srgb(0.25, 0.22, 0.2);
+const DEMO_BLOCK_SIZE: f32 = 16.0;
+const DEMO_CASTLE_WIDTH: i32 = 6;
+const DEMO_CASTLE_HEIGHT: i32 = 5;
+/// The towers on either end of the castle rise this many blocks above the wall.
+const DEMO_TOWER_HEIGHT: i32 = 3;
+/// Where the castle's bottom left block sits, relative to the origin.
+const DEMO_CASTLE_OFFSET: Vec2 = Vec2::new(80.0, 0.0);
+const DEMO_CASTER_OFFSET: Vec2 = Vec2::new(-240.0, 60.0);
+const DEMO_CAST_SECS: f32 = 2.5;
+/// How far off the middle of the castle the caster's aim can wander.
+const DEMO_AIM_JITTER: f32 = 40.0;
+/// Once fewer blocks than this are still near the castle, it's built again.
+const DEMO_REBUILD_BLOCKS: usize = 8;
+/// Blocks further than this from where the castle stands count as rubble.
+const DEMO_RUBBLE_DISTANCE: f32 = 96.0;
+
+/// A block of the castle knocked down behind the main menu.
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct MenuDemoBlock;
+
+/// Throws fireballs at the menu castle every so often.
+#[derive(Component, Debug)]
+struct MenuDemoCaster {
+    timer: Timer,
+}
+
+fn spawn_menu_demo(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Name::new("Menu Demo Ground"),
+        Sprite::from_color(DEMO_GROUND_COLOR, DEMO_GROUND_SIZE),
+        Transform::from_translation((DEMO_ORIGIN - Vec2::Y * DEMO_GROUND_SIZE.y / 2.0).extend(0.0)),
+        RigidBody::Static,
+        Collider::rectangle(DEMO_GROUND_SIZE.x, DEMO_GROUND_SIZE.y),
+        StateScoped(Screen::Title),
+    ));
+    commands.spawn((
+        Name::new("Menu Demo Caster"),
+        MenuDemoCaster {
+            timer: Timer::from_seconds(DEMO_CAST_SECS, TimerMode::Repeating),
+        },
+        CharacterController::default(),
+        Transform::from_translation((DEMO_ORIGIN + DEMO_CASTER_OFFSET).extend(0.0)),
+        StateScoped(Screen::Title),
+    ));
+    spawn_demo_castle(&mut commands, &asset_server);
+}
+
+fn spawn_demo_castle(commands: &mut Commands, asset_server: &AssetServer) {
+    let image = asset_server.load("images/stone.png");
+    for x in 0..DEMO_CASTLE_WIDTH {
+        let tower = x == 0 || x == DEMO_CASTLE_WIDTH - 1;
+        let height = DEMO_CASTLE_HEIGHT + if tower { DEMO_TOWER_HEIGHT } else { 0 };
+        for y in 0..height {
+            let position = DEMO_ORIGIN
+                + DEMO_CASTLE_OFFSET
+                + (Vec2::new(x as f32, y as f32) + 0.5) * DEMO_BLOCK_SIZE;
+            commands.spawn((
+                Name::new("Menu Demo Block"),
+                MenuDemoBlock,
+                Sprite {
+                    image: image.clone(),
+                    custom_size: Some(Vec2::splat(DEMO_BLOCK_SIZE)),
+                    ..default()
+                },
+                CollisionBundle {
+                    collider: Collider::rectangle(DEMO_BLOCK_SIZE, DEMO_BLOCK_SIZE),
+                    ..default()
+                },
+                Transform::from_translation(position.extend(0.0)),
+                StateScoped(Screen::Title),
+            ));
+        }
+    }
+}
+
+/// The middle of the castle when it's standing.
+fn demo_castle_center() -> Vec2 {
+    DEMO_ORIGIN
+        + DEMO_CASTLE_OFFSET
+        + Vec2::new(DEMO_CASTLE_WIDTH as f32, DEMO_CASTLE_HEIGHT as f32) * DEMO_BLOCK_SIZE / 2.0
+}
+
+/// Point the main camera at the demo scene. Gameplay takes it back over once a level is ready.
+fn frame_menu_demo(
+    mut camera_query: Query<
+        (&mut Transform, &mut Projection),
+        (With<Camera2d>, Without<PlayerCamera>, Without<UiOnlyCamera>),
+    >,
+) {
+    let center = (DEMO_ORIGIN + DEMO_CASTER_OFFSET).midpoint(demo_castle_center());
+    for (mut transform, mut projection) in &mut camera_query {
+        transform.translation = center.extend(transform.translation.z);
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.viewport_origin = Vec2::splat(0.5);
+            orthographic.scaling_mode = ScalingMode::FixedVertical {
+                viewport_height: DEMO_VIEW_HEIGHT,
+            };
+        }
+    }
+}
+
+fn cast_demo_fireballs(
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+    mut rng: ResMut<GameRng>,
+    mut caster_query: Query<(&mut MenuDemoCaster, &mut CharacterController, &Transform)>,
+) {
+    for (mut caster, mut controller, transform) in &mut caster_query {
+        caster.timer.tick(time.delta());
+        if !caster.timer.just_finished() {
+            continue;
+        }
+        let target = demo_castle_center()
+            + Vec2::new(
+                rng.gen_range(-DEMO_AIM_JITTER..DEMO_AIM_JITTER),
+                rng.gen_range(-DEMO_AIM_JITTER..DEMO_AIM_JITTER),
+            );
+        let direction = (target - transform.translation.truncate()).normalize_or_zero();
+        controller.queue_action(
+            ActionType::Cast {
+                ability: AbilityKind::Fireball,
+                direction,
+            },
+            real_time.elapsed(),
+        );
+    }
+}
+
+/// Clear the rubble away and build the castle again once it's been knocked down.
+fn rebuild_demo_castle(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    block_query: Query<(Entity, &Transform), With<MenuDemoBlock>>,
+    mut caster_query: Query<&mut MenuDemoCaster>,
+) {
+    let center = demo_castle_center();
+    let standing = block_query
+        .iter()
+        .filter(|(_, transform)| {
+            transform.translation.truncate().distance(center) < DEMO_RUBBLE_DISTANCE
+        })
+        .count();
+    if standing >= DEMO_REBUILD_BLOCKS {
+        return;
+    }
+    for (block, _) in &block_query {
+        commands.entity(block).despawn();
+    }
+    spawn_demo_castle(&mut commands, &asset_server);
+    // Give the new castle a moment before the first fireball.
+    for mut caster in &mut caster_query {
+        caster.timer.set_elapsed(Duration::ZERO);
+    }
+}
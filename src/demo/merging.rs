@@ -0,0 +1,253 @@
+//! Merge untouched parts of the castle into single static colliders.
+//!
+//! A castle is hundreds of jointed dynamic blocks, which the physics has to
+//! solve every frame even while nothing is happening to them. Once the castle
+//! is mortared together, each connected group of undamaged blocks is stood in
+//! for by one static compound collider, with the blocks and their joints
+//! disabled. An explosion nearby wakes the region back into its blocks.
+
+use avian2d::prelude::*;
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::{
+            BlockCleared, BlockDestroyed, BlockSize, CastleBlock, CastleGrid, CastleReady,
+            MortarHealth,
+        },
+        collision::{FrostEffect, Shockwave},
+        elements::Burning,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            merge_castle_regions.run_if(on_event::<CastleReady>),
+            wake_regions_near_shockwaves,
+            wake_disturbed_regions,
+            despawn_cleared_regions,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Groups with fewer blocks than this aren't worth merging.
+const MIN_REGION_BLOCKS: usize = 4;
+/// How far outside a shockwave's radius regions are still woken up.
+const WAKE_MARGIN: f32 = 32.0;
+
+/// A group of castle blocks standing in as a single static collider.
+#[derive(Component, Debug)]
+pub struct MergedRegion {
+    blocks: Vec<Entity>,
+    /// Mortar joints between blocks in the region.
+    joints: Vec<Entity>,
+    bounds: Rect,
+}
+
+impl MergedRegion {
+    pub fn blocks(&self) -> &[Entity] {
+        &self.blocks
+    }
+}
+
+/// A castle block whose physics is disabled while it's part of a [`MergedRegion`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Merged {
+    pub region: Entity,
+}
+
+/// Turn a region back into individual jointed blocks.
+pub fn wake_region(commands: &mut Commands, region_entity: Entity, region: &MergedRegion) {
+    for &block in &region.blocks {
+        commands
+            .entity(block)
+            .try_remove::<(Merged, RigidBodyDisabled, ColliderDisabled)>();
+    }
+    for &joint in &region.joints {
+        commands.entity(joint).try_remove::<JointDisabled>();
+    }
+    commands.entity(region_entity).try_despawn();
+}
+
+fn merge_castle_regions(
+    mut commands: Commands,
+    castle_grid: Res<CastleGrid>,
+    block_query: Query<
+        (&GlobalTransform, &BlockSize),
+        (
+            With<CastleBlock>,
+            Without<Merged>,
+            Without<FrostEffect>,
+            Without<Burning>,
+        ),
+    >,
+    joint_query: Query<(Entity, &FixedJoint, &MortarHealth)>,
+) {
+    // Blocks with worn mortar have already been hit, so they stay as they are.
+    let mut damaged = HashSet::new();
+    for (_, joint, mortar) in &joint_query {
+        if mortar.current < mortar.max {
+            damaged.insert(joint.entity1);
+            damaged.insert(joint.entity2);
+        }
+    }
+    let can_merge = |block: Entity| block_query.contains(block) && !damaged.contains(&block);
+
+    let mut visited = HashSet::new();
+    let mut merged_blocks = 0;
+    let mut region_count = 0;
+    for start in castle_grid.blocks() {
+        if !can_merge(start) || !visited.insert(start) {
+            continue;
+        }
+        let mut region = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(block) = stack.pop() {
+            for neighbor in castle_grid.neighbors(block) {
+                if can_merge(neighbor) && visited.insert(neighbor) {
+                    region.insert(neighbor);
+                    stack.push(neighbor);
+                }
+            }
+        }
+        if region.len() < MIN_REGION_BLOCKS {
+            continue;
+        }
+        merged_blocks += region.len();
+        region_count += 1;
+        spawn_region(&mut commands, region, &block_query, &joint_query);
+    }
+    info!("Merged {merged_blocks} castle blocks into {region_count} static regions");
+}
+
+fn spawn_region(
+    commands: &mut Commands,
+    region: HashSet<Entity>,
+    block_query: &Query<
+        (&GlobalTransform, &BlockSize),
+        (
+            With<CastleBlock>,
+            Without<Merged>,
+            Without<FrostEffect>,
+            Without<Burning>,
+        ),
+    >,
+    joint_query: &Query<(Entity, &FixedJoint, &MortarHealth)>,
+) {
+    let shapes: Vec<Rect> = region
+        .iter()
+        .filter_map(|&block| block_query.get(block).ok())
+        .map(|(transform, size)| Rect::from_center_size(transform.translation().truncate(), size.0))
+        .collect();
+    let Some(bounds) = shapes.iter().copied().reduce(|a, b| a.union(b)) else {
+        return;
+    };
+    let origin = bounds.center();
+    let collider = Collider::compound(
+        shapes
+            .iter()
+            .map(|shape| {
+                (
+                    Position::new(shape.center() - origin),
+                    Rotation::default(),
+                    Collider::rectangle(shape.width(), shape.height()),
+                )
+            })
+            .collect(),
+    );
+    let joints: Vec<Entity> = joint_query
+        .iter()
+        .filter(|(_, joint, _)| region.contains(&joint.entity1) && region.contains(&joint.entity2))
+        .map(|(joint, ..)| joint)
+        .collect();
+
+    let blocks: Vec<Entity> = region.into_iter().collect();
+    let region_entity = commands
+        .spawn((
+            Name::new("Merged Castle Region"),
+            RigidBody::Static,
+            collider,
+            Transform::from_translation(origin.extend(0.0)),
+            StateScoped(Screen::Gameplay),
+        ))
+        .id();
+    for &block in &blocks {
+        commands.entity(block).insert((
+            Merged {
+                region: region_entity,
+            },
+            RigidBodyDisabled,
+            ColliderDisabled,
+        ));
+    }
+    for &joint in &joints {
+        commands.entity(joint).insert(JointDisabled);
+    }
+    commands.entity(region_entity).insert(MergedRegion {
+        blocks,
+        joints,
+        bounds,
+    });
+}
+
+fn wake_regions_near_shockwaves(
+    mut commands: Commands,
+    mut shockwave_events: EventReader<Shockwave>,
+    region_query: Query<(Entity, &MergedRegion)>,
+) {
+    for shockwave in shockwave_events.read() {
+        for (region_entity, region) in &region_query {
+            let closest = shockwave.origin.clamp(region.bounds.min, region.bounds.max);
+            if closest.distance(shockwave.origin) <= shockwave.radius + WAKE_MARGIN {
+                wake_region(&mut commands, region_entity, region);
+            }
+        }
+    }
+}
+
+/// Wake regions whose blocks were frozen, set alight or destroyed some other way.
+fn wake_disturbed_regions(
+    mut commands: Commands,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    disturbed_query: Query<&Merged, Or<(Added<FrostEffect>, Added<Burning>)>>,
+    merged_query: Query<&Merged>,
+    region_query: Query<&MergedRegion>,
+) {
+    let destroyed = destroyed_events
+        .read()
+        .filter_map(|event| merged_query.get(event.block).ok());
+    let mut woken = HashSet::new();
+    for merged in disturbed_query.iter().chain(destroyed) {
+        if !woken.insert(merged.region) {
+            continue;
+        }
+        if let Ok(region) = region_query.get(merged.region) {
+            wake_region(&mut commands, merged.region, region);
+        }
+    }
+}
+
+/// Take away the colliders of regions whose blocks were cleared, which would
+/// otherwise be left standing with nothing to show for them.
+fn despawn_cleared_regions(
+    mut commands: Commands,
+    mut cleared_events: EventReader<BlockCleared>,
+    merged_query: Query<&Merged>,
+) {
+    let mut despawned = HashSet::new();
+    for event in cleared_events.read() {
+        let Ok(merged) = merged_query.get(event.block) else {
+            continue;
+        };
+        if despawned.insert(merged.region) {
+            commands.entity(merged.region).try_despawn();
+        }
+    }
+}
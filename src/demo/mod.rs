@@ -5,17 +5,79 @@
 
 use bevy::prelude::*;
 
+pub mod ability_config;
 mod animation;
-mod balistics;
-mod camera;
-mod castle;
-mod collision;
-mod input;
+pub mod attract;
+pub mod balistics;
+#[cfg(feature = "gameplay_spans")]
+pub mod bench;
+pub mod blueprint;
+pub mod camera;
+pub mod carry;
+pub mod castle;
+#[cfg(test)]
+mod castle_stability;
+pub mod charges;
+pub mod collision;
+mod coop;
+mod damage_numbers;
+mod despawn_queue;
+pub mod determinism;
+pub mod dialogue;
+pub mod difficulty;
+mod doors;
+mod dust;
+mod elements;
+pub mod endless;
+mod fluids;
+pub mod game_mode;
+mod ghost;
+mod goal;
+mod hazards;
+#[cfg(any(test, feature = "gameplay_spans"))]
+mod headless;
+mod health;
+mod health_bars;
+mod hud;
+mod ice;
+mod impact_feel;
+pub mod input;
+mod kinematic;
 pub mod level;
+pub mod level_physics;
+pub mod lighting;
+mod lives;
+pub mod loadout;
+mod menu_demo;
+mod merging;
 mod movement;
+mod music;
+mod netplay;
+pub mod notifications;
+mod pickups;
+mod platform;
 pub mod player;
-mod timer;
+pub mod pool;
+mod post_processing;
+pub mod progress;
+pub mod rng;
+pub mod run_stats;
+pub mod score;
+pub mod sections;
+pub mod skills;
+mod sleeping;
+pub mod smoke;
+mod spawn_conditions;
+mod spectator;
+pub mod time_attack;
+pub mod time_scale;
+pub mod timer;
+mod tint;
+mod trajectory;
+mod triggers;
+mod tutorial;
 mod walls;
+pub mod weather;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
@@ -31,4 +93,67 @@ pub(super) fn plugin(app: &mut App) {
         camera::plugin,
         timer::plugin,
     ));
+    app.add_plugins((
+        platform::plugin,
+        fluids::plugin,
+        health::plugin,
+        hazards::plugin,
+        score::plugin,
+        pickups::plugin,
+        doors::plugin,
+        triggers::plugin,
+        lives::plugin,
+        goal::plugin,
+        progress::plugin,
+        coop::plugin,
+        ghost::plugin,
+        rng::plugin,
+        dust::plugin,
+    ));
+    app.add_plugins((
+        music::plugin,
+        elements::plugin,
+        time_scale::plugin,
+        difficulty::plugin,
+        spawn_conditions::plugin,
+        sections::plugin,
+        blueprint::plugin,
+        game_mode::plugin,
+        endless::plugin,
+        time_attack::plugin,
+        damage_numbers::plugin,
+        trajectory::plugin,
+        skills::plugin,
+        loadout::plugin,
+        netplay::plugin,
+    ));
+    app.add_plugins((
+        spectator::plugin,
+        post_processing::plugin,
+        tint::plugin,
+        ability_config::plugin,
+        merging::plugin,
+        sleeping::plugin,
+        despawn_queue::plugin,
+        level_physics::plugin,
+        weather::plugin,
+        lighting::plugin,
+        dialogue::plugin,
+        tutorial::plugin,
+        run_stats::plugin,
+        determinism::plugin,
+        kinematic::plugin,
+    ));
+    app.add_plugins((
+        attract::plugin,
+        carry::plugin,
+        charges::plugin,
+        health_bars::plugin,
+        hud::plugin,
+        ice::plugin,
+        impact_feel::plugin,
+        menu_demo::plugin,
+        notifications::plugin,
+        smoke::plugin,
+    ));
 }
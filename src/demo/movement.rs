@@ -7,13 +7,20 @@
 //!   This is done in the `player` module, as it is specific to the player
 //!   character.
 //! - Apply movement based on [`MovementController`] intent and maximum speed.
+//! - Jump when a [`JumpController`] has a buffered jump and the character is
+//!   on the ground (or only just left it), or has air jumps left.
+//! - Dash sideways when a [`DashController`] has a dash queued up.
+//! - Crouch while a [`CrouchController`] is held, standing up once there's room.
+//! - Pull the character back upright with its [`UprightSpring`].
 //! - Wrap the character within the window.
 //!
 //! Note that the implementation used here is limited for demonstration
 //! purposes. If you want to move the player in a smoother way,
 //! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
 
-use avian2d::{math::AdjustPrecision, prelude::*};
+use std::time::Duration;
+
+use avian2d::prelude::*;
 use bevy::prelude::*;
 
 use crate::{
@@ -21,20 +28,36 @@ use crate::{
     demo::{
         animation::PlayerAnimation,
         balistics::Ability,
-        player::{LightningState, Player, PlayerAssets},
+        collision::{FrostEffect, GroundDetection},
+        fluids::{SUBMERGED_SPEED_FACTOR, Submerged},
+        platform::MovingPlatform,
+        player::{Invulnerable, LightningState, Player, PlayerAssets, Ragdoll},
+        trajectory::Aiming,
     },
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<MovementController>();
+    app.register_type::<InheritedVelocity>();
+    app.register_type::<JumpController>();
+    app.register_type::<DashController>();
+    app.register_type::<UprightSpring>();
+    app.register_type::<CrouchController>();
     app.add_plugins(PhysicsPlugins::default());
+    // Per-entity tweaks go through `GravityScale` rather than a separate gravity system,
+    // and per-level ones through `LevelPhysics`.
+    app.insert_resource(Gravity(Vec2::NEG_Y * GRAVITY));
     app.add_systems(
         Update,
         (
+            apply_crouch,
             movement_to_physics,
-            apply_gravity,
+            inherit_platform_velocity,
+            apply_jump,
             apply_movement_damping,
-            revert_to_upright,
+            stick_to_slopes,
+            apply_dash,
+            apply_upright_spring,
             movement_to_physics_lightning_mode,
             apply_lightning_mode,
             revert_lightning_mode,
@@ -44,8 +67,12 @@ pub(super) fn plugin(app: &mut App) {
             .in_set(PausableSystems)
             .run_if(resource_exists::<PlayerAssets>),
     );
+    app.add_systems(Update, draw_lightning_indicator.in_set(AppSystems::Update));
 }
 
+/// Downwards acceleration in world units per second squared.
+pub const GRAVITY: f32 = 98.0;
+
 // Add this new component for movement-only entities
 #[derive(Component, Reflect, Clone)]
 #[reflect(Component)]
@@ -68,20 +95,356 @@ impl Default for MovementController {
 
 fn movement_to_physics(
     mut query: Query<
-        (&mut MovementController, Option<&mut LinearVelocity>),
+        (
+            &mut MovementController,
+            Option<&mut LinearVelocity>,
+            Has<Submerged>,
+            Has<JumpController>,
+            Option<&FrostEffect>,
+            Has<Crouching>,
+        ),
         Without<LightningState>,
     >,
 ) {
-    for (mut movement_controller, maybe_velocity) in &mut query {
+    for (mut movement_controller, maybe_velocity, submerged, can_jump, frost, crouching) in
+        &mut query
+    {
         // If the entity has a LinearVelocity component, use it
         if let Some(mut velocity) = maybe_velocity {
+            // Swimming is slower than walking
+            let speed = if submerged {
+                movement_controller.speed * SUBMERGED_SPEED_FACTOR
+            } else {
+                movement_controller.speed
+            };
+            // Frozen characters drag their feet until the frost wears off
+            let speed = speed * frost.map_or(1.0, FrostEffect::speed_factor);
+            // Crawling is slower still
+            let speed = if crouching {
+                speed * CRAWL_SPEED_FACTOR
+            } else {
+                speed
+            };
+            // Characters that can jump only move sideways, vertical input is just for aiming.
+            let direction = if can_jump && !submerged {
+                Vec2::new(movement_controller.direction.x, 0.0)
+            } else {
+                movement_controller.direction
+            };
             // Convert movement intent to velocity
-            velocity.0 += movement_controller.direction * movement_controller.speed;
+            velocity.0 += direction * speed;
             movement_controller.direction = Vec2::ZERO;
         }
     }
 }
 
+/// Horizontal speed while crawling, as a fraction of walking speed.
+const CRAWL_SPEED_FACTOR: f32 = 0.5;
+/// Room left between a crouched character's head and the ceiling it checks
+/// for, so standing up under a ceiling it's only touching isn't blocked.
+const HEAD_CLEARANCE_MARGIN: f32 = 0.5;
+
+/// Lets a character crouch down to crawl through low passages.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct CrouchController {
+    /// Height of the crouched collider, as a fraction of the standing one.
+    pub height_factor: f32,
+    held: bool,
+}
+
+impl Default for CrouchController {
+    fn default() -> Self {
+        Self {
+            height_factor: 0.5,
+            held: false,
+        }
+    }
+}
+
+impl CrouchController {
+    /// Crouch while `held`, and try to stand back up once it's let go.
+    pub fn set_held(&mut self, held: bool) {
+        self.held = held;
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+}
+
+/// A character that's crouched down, with the collider to restore when it stands.
+#[derive(Component, Clone, Debug)]
+pub struct Crouching {
+    standing_collider: Collider,
+}
+
+/// Shrink the colliders of characters that crouch, keeping their feet where
+/// they are, and grow them back once there's room overhead to stand.
+fn apply_crouch(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    sensor_query: Query<(), With<Sensor>>,
+    query: Query<
+        (
+            Entity,
+            &CrouchController,
+            &Collider,
+            &Position,
+            Option<&Crouching>,
+        ),
+        (Without<LightningState>, Without<Ragdoll>),
+    >,
+) {
+    for (entity, crouch, collider, position, crouching) in &query {
+        match (crouch.held, crouching) {
+            (true, None) => {
+                let standing = collider.aabb(Vec2::ZERO, 0.0);
+                let size = standing.size();
+                let height = size.y * crouch.height_factor;
+                let crouched = Collider::compound(vec![(
+                    Vec2::new(0.0, standing.min.y + height / 2.0),
+                    0.0,
+                    Collider::rectangle(size.x, height),
+                )]);
+                commands.entity(entity).insert((
+                    crouched,
+                    Crouching {
+                        standing_collider: collider.clone(),
+                    },
+                ));
+            }
+            (false, Some(crouching)) => {
+                // Check the space the head would stand up into, narrowed a little
+                // so walls the character is leaning on don't count.
+                let crouched = collider.aabb(position.0, 0.0);
+                let standing = crouching.standing_collider.aabb(position.0, 0.0);
+                let head_bottom = crouched.max.y + HEAD_CLEARANCE_MARGIN;
+                let head_height = standing.max.y - HEAD_CLEARANCE_MARGIN - head_bottom;
+                if head_height > 0.0 {
+                    let head_width = standing.size().x - 2.0 * HEAD_CLEARANCE_MARGIN;
+                    let head_center =
+                        Vec2::new(standing.center().x, head_bottom + head_height / 2.0);
+                    let blocked = spatial_query
+                        .shape_intersections(
+                            &Collider::rectangle(head_width, head_height),
+                            head_center,
+                            0.0,
+                            &SpatialQueryFilter::from_excluded_entities([entity]),
+                        )
+                        .into_iter()
+                        .any(|hit| !sensor_query.contains(hit));
+                    if blocked {
+                        continue;
+                    }
+                }
+                commands
+                    .entity(entity)
+                    .insert(crouching.standing_collider.clone())
+                    .remove::<Crouching>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Velocity a character has picked up from the moving platform it's standing on.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct InheritedVelocity(pub Vec2);
+
+fn inherit_platform_velocity(
+    mut riders: Query<
+        (&ShapeHits, &mut LinearVelocity, &mut InheritedVelocity),
+        Without<MovingPlatform>,
+    >,
+    platforms: Query<&LinearVelocity, With<MovingPlatform>>,
+) {
+    for (hits, mut velocity, mut inherited) in &mut riders {
+        let platform_velocity = hits
+            .iter()
+            .find_map(|hit| platforms.get(hit.entity).ok())
+            .map_or(Vec2::ZERO, |platform_velocity| platform_velocity.0);
+
+        // Only apply the change since last frame so the carry doesn't accumulate.
+        velocity.0 += platform_velocity - inherited.0;
+        inherited.0 = platform_velocity;
+    }
+}
+
+/// Lets a character jump, with coyote time and input buffering to make it feel forgiving.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct JumpController {
+    /// Upwards velocity applied when jumping.
+    pub jump_speed: f32,
+    /// How much upwards velocity is kept when the jump key is released early.
+    pub jump_cut_factor: f32,
+    /// How long after walking off a ledge a jump is still allowed, in seconds.
+    pub coyote_time: f32,
+    /// How long a jump pressed before landing is remembered, in seconds.
+    pub buffer_time: f32,
+    /// How many extra jumps can be made in mid-air before landing again.
+    pub extra_air_jumps: u32,
+    air_jumps_left: u32,
+    coyote_time_left: f32,
+    buffer_time_left: f32,
+    /// Whether we're in the rising part of a jump that can still be cut short.
+    rising: bool,
+    cut_requested: bool,
+}
+
+impl Default for JumpController {
+    fn default() -> Self {
+        Self {
+            jump_speed: 120.0,
+            jump_cut_factor: 0.4,
+            coyote_time: 0.1,
+            buffer_time: 0.15,
+            extra_air_jumps: 1,
+            air_jumps_left: 0,
+            coyote_time_left: 0.0,
+            buffer_time_left: 0.0,
+            rising: false,
+            cut_requested: false,
+        }
+    }
+}
+
+impl JumpController {
+    /// Buffer a jump to be performed as soon as it's allowed.
+    pub fn request_jump(&mut self) {
+        self.buffer_time_left = self.buffer_time;
+    }
+
+    /// The jump key was let go, cut the jump short if we're still rising.
+    pub fn release_jump(&mut self) {
+        self.buffer_time_left = 0.0;
+        if self.rising {
+            self.rising = false;
+            self.cut_requested = true;
+        }
+    }
+}
+
+fn apply_jump(
+    time: Res<Time>,
+    mut query: Query<(&mut JumpController, &GroundDetection, &mut LinearVelocity)>,
+) {
+    let delta_time = time.delta_secs();
+    for (mut jump, ground_detection, mut velocity) in &mut query {
+        if ground_detection.on_ground {
+            jump.coyote_time_left = jump.coyote_time;
+            jump.air_jumps_left = jump.extra_air_jumps;
+        } else {
+            jump.coyote_time_left = (jump.coyote_time_left - delta_time).max(0.0);
+        }
+
+        let grounded_jump = jump.coyote_time_left > 0.0;
+        if jump.buffer_time_left > 0.0 && (grounded_jump || jump.air_jumps_left > 0) {
+            if !grounded_jump {
+                jump.air_jumps_left -= 1;
+            }
+            velocity.y = jump.jump_speed;
+            jump.buffer_time_left = 0.0;
+            jump.coyote_time_left = 0.0;
+            jump.rising = true;
+        } else {
+            jump.buffer_time_left = (jump.buffer_time_left - delta_time).max(0.0);
+        }
+
+        if jump.cut_requested {
+            jump.cut_requested = false;
+            if velocity.y > 0.0 {
+                velocity.y *= jump.jump_cut_factor;
+            }
+        }
+        if velocity.y <= 0.0 {
+            jump.rising = false;
+        }
+    }
+}
+
+/// Lets a character do a short horizontal dash, once per landing.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct DashController {
+    /// Horizontal speed during the dash.
+    pub dash_speed: f32,
+    /// How long the dash lasts, the character is invulnerable for this long.
+    pub duration: Duration,
+    requested: bool,
+    /// Whether the dash has been used since the character last touched the ground.
+    used: bool,
+    timer: Timer,
+    direction: f32,
+}
+
+impl Default for DashController {
+    fn default() -> Self {
+        let duration = Duration::from_millis(150);
+        let mut timer = Timer::new(duration, TimerMode::Once);
+        // Start finished so we aren't dashing on spawn.
+        timer.tick(duration);
+        Self {
+            dash_speed: 400.0,
+            duration,
+            requested: false,
+            used: false,
+            timer,
+            direction: 1.0,
+        }
+    }
+}
+
+impl DashController {
+    /// Queue a dash for the next movement update.
+    pub fn request_dash(&mut self) {
+        self.requested = true;
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        !self.timer.finished()
+    }
+}
+
+fn apply_dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<
+        (
+            Entity,
+            &mut DashController,
+            &GroundDetection,
+            &Sprite,
+            &mut LinearVelocity,
+        ),
+        Without<LightningState>,
+    >,
+) {
+    for (entity, mut dash, ground_detection, sprite, mut velocity) in &mut query {
+        if ground_detection.on_ground && !dash.is_dashing() {
+            dash.used = false;
+        }
+
+        if std::mem::take(&mut dash.requested) && !dash.used && !dash.is_dashing() {
+            dash.used = true;
+            // Dash the way the character is facing.
+            dash.direction = if sprite.flip_x { -1.0 } else { 1.0 };
+            dash.timer = Timer::new(dash.duration, TimerMode::Once);
+            commands.entity(entity).insert(Invulnerable {
+                timer: Timer::new(dash.duration, TimerMode::Once),
+            });
+        }
+
+        if dash.is_dashing() {
+            dash.timer.tick(time.delta());
+            // Hold a flat, fast trajectory for the whole dash.
+            velocity.0 = Vec2::new(dash.direction * dash.dash_speed, 0.0);
+        }
+    }
+}
+
 fn movement_to_physics_lightning_mode(
     mut query: Query<(&mut MovementController, Option<&mut LinearVelocity>), With<LightningState>>,
 ) {
@@ -96,52 +459,101 @@ fn movement_to_physics_lightning_mode(
     }
 }
 
-fn revert_to_upright(
-    mut query: Query<(&mut AngularVelocity, &GlobalTransform, &RigidBody), With<Player>>,
-) {
-    const CORRECTION_SPEED: f32 = 0.1; // Adjust the speed of correction
-    const ANGULAR_DAMPING_FACTOR: f32 = 0.95; // Optional damping factor
-
-    for (mut angular_velocity, transform, rigid_body) in &mut query {
-        if matches!(rigid_body, RigidBody::Dynamic) {
-            // Get the current rotation angle (assuming 2D rotation around Z-axis)
-            let current_rotation = transform.rotation().to_euler(EulerRot::XYZ).2;
-
-            // Calculate the corrective angular velocity to move toward upright (0 radians)
-            let corrective_angular_velocity = -current_rotation * CORRECTION_SPEED;
-
-            // Apply the corrective angular velocity
-            angular_velocity.0 = corrective_angular_velocity;
+/// Pulls a character back upright like a torsional spring, so it can be
+/// knocked over by the physics but doesn't stay down.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+#[require(ExternalTorque)]
+pub struct UprightSpring {
+    /// Angular acceleration back towards upright per radian of tilt.
+    pub stiffness: f32,
+    /// Angular acceleration against the spin per radian per second.
+    pub damping: f32,
+}
 
-            // Optionally apply damping to smooth out the motion
-            angular_velocity.0 *= ANGULAR_DAMPING_FACTOR;
+impl Default for UprightSpring {
+    fn default() -> Self {
+        // Just short of critically damped, so it settles with a slight wobble.
+        Self {
+            stiffness: 40.0,
+            damping: 11.0,
         }
     }
 }
 
-fn apply_gravity(
-    time: Res<Time>,
-    mut controllers: Query<(&mut LinearVelocity,), Without<LightningState>>,
+/// Apply each character's upright spring as a torque, scaled by its inertia so
+/// the tuning doesn't depend on its size. Ragdolls and lightning spin freely.
+fn apply_upright_spring(
+    mut query: Query<(
+        &UprightSpring,
+        &Rotation,
+        &AngularVelocity,
+        &ComputedAngularInertia,
+        &mut ExternalTorque,
+        Has<Ragdoll>,
+        Has<LightningState>,
+    )>,
 ) {
-    // Precision is adjusted so that the example works with
-    // both the `f32` and `f64` features. Otherwise you don't need this.
-    let delta_time = time.delta_secs_f64().adjust_precision();
-
-    for mut linear_velocity in &mut controllers {
-        linear_velocity.0.y += -9.8 * delta_time * 10.;
+    for (spring, rotation, angular_velocity, inertia, mut torque, ragdoll, lightning) in &mut query
+    {
+        if ragdoll || lightning {
+            torque.clear();
+            continue;
+        }
+        let acceleration =
+            -spring.stiffness * rotation.as_radians() - spring.damping * angular_velocity.0;
+        torque.set_torque(acceleration * inertia.value());
     }
 }
 
 /// Slows down movement in the X direction.
 fn apply_movement_damping(
     mut query: Query<
-        (&MovementController, &mut LinearVelocity),
-        (Without<Ability>, Without<LightningState>),
+        (
+            &MovementController,
+            &mut LinearVelocity,
+            Option<&InheritedVelocity>,
+        ),
+        (Without<Ability>, Without<LightningState>, Without<Ragdoll>),
     >,
 ) {
-    for (_damping_factor, mut linear_velocity) in &mut query {
+    for (_damping_factor, mut linear_velocity, inherited) in &mut query {
+        // Dampen relative to the platform we're riding, if any, so we keep moving with it.
+        let carried = inherited.map_or(0.0, |inherited| inherited.0.x);
         // We could use `LinearDamping`, but we don't want to dampen movement along the Y axis
-        linear_velocity.x *= 0.9;
+        linear_velocity.x = carried + (linear_velocity.x - carried) * 0.9;
+    }
+}
+
+/// Ground flatter than this, by the up component of its normal, isn't treated as a slope.
+const FLAT_GROUND_NORMAL_Y: f32 = 0.99;
+/// Below this horizontal speed a character on a slope is standing still.
+const SLOPE_IDLE_SPEED: f32 = 1.0;
+
+/// Keep characters on slopes: walking follows the slope rather than launching
+/// off it on the way down, and standing still doesn't slide down it.
+fn stick_to_slopes(
+    mut query: Query<
+        (&GroundDetection, &JumpController, &mut LinearVelocity),
+        (Without<LightningState>, Without<Ragdoll>),
+    >,
+) {
+    for (ground_detection, jump, mut velocity) in &mut query {
+        let normal = ground_detection.ground_normal;
+        if !ground_detection.on_ground
+            || jump.rising
+            || normal.y >= FLAT_GROUND_NORMAL_Y
+            || normal.y <= 0.0
+        {
+            continue;
+        }
+        if velocity.x.abs() < SLOPE_IDLE_SPEED {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+        // Along the slope, scaled to keep the horizontal speed the player chose.
+        let tangent = Vec2::new(normal.y, -normal.x);
+        velocity.0 = tangent * (velocity.x / tangent.x);
     }
 }
 
@@ -173,6 +585,9 @@ fn apply_lightning_mode(
             commands.entity(entity).remove::<PlayerAnimation>();
         }
         commands.entity(entity).insert(Mass(1.0)); // Lightning shouldn't be able to knock down buildings.
+        commands.entity(entity).insert(GravityScale(0.0)); // Lightning travels in straight lines.
+        // At a hundred times the speed the player would otherwise tunnel through thin walls.
+        commands.entity(entity).insert(SweptCcd::default());
     }
 }
 
@@ -216,7 +631,39 @@ fn revert_lightning_mode(
             commands.entity(entity).insert(player_animation);
         };
         commands.entity(entity).insert(Mass(30.)); // Remove the mass component
+        commands.entity(entity).insert(GravityScale(1.0));
+        commands.entity(entity).remove::<SweptCcd>();
         // Remove the LightningState component
         commands.entity(entity).remove::<LightningState>();
     }
 }
+
+/// Length of the lightning direction arrow at the start of the dash, shrinking as it runs out.
+const LIGHTNING_INDICATOR_LENGTH: f32 = 48.0;
+const LIGHTNING_INDICATOR_COLOR: Color = Color::srgb(0.6, 0.85, 1.0);
+
+/// Point an arrow where the lightning dash is headed so it can be steered.
+fn draw_lightning_indicator(
+    mut gizmos: Gizmos,
+    query: Query<(
+        &GlobalTransform,
+        &LightningState,
+        &LinearVelocity,
+        Option<&Aiming>,
+    )>,
+) {
+    for (transform, lightning_state, velocity, aiming) in &query {
+        // Show the held direction, or where the bolt is already going if nothing is held.
+        let direction = aiming
+            .map(|aiming| aiming.direction)
+            .filter(|direction| *direction != Vec2::ZERO)
+            .unwrap_or(velocity.0)
+            .normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+        let start = transform.translation().truncate();
+        let length = LIGHTNING_INDICATOR_LENGTH * (1.0 - lightning_state.timer.fraction());
+        gizmos.arrow_2d(start, start + direction * length, LIGHTNING_INDICATOR_COLOR);
+    }
+}
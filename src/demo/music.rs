@@ -0,0 +1,150 @@
+//! Gameplay music that gets more intense as the castle comes down.
+//!
+//! The level music is split into a calm and an intense stem that play in sync.
+//! Breaking joints and setting off explosions raise a rolling destruction
+//! intensity that slowly dies down again, and the stems crossfade to follow it.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    audio::Music,
+    demo::{animation::ExplosionAnimation, castle::JointBroken},
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<MusicStems>();
+    app.load_resource::<MusicStems>();
+    app.register_type::<DestructionIntensity>();
+    app.init_resource::<DestructionIntensity>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (reset_destruction_intensity, spawn_music_stems),
+    );
+    app.add_systems(
+        Update,
+        (raise_destruction_intensity, decay_destruction_intensity)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        crossfade_music_stems
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Volume of the music before the player's settings are applied.
+const MUSIC_VOLUME: f32 = 0.5;
+const JOINT_BREAK_INTENSITY: f32 = 0.02;
+const EXPLOSION_INTENSITY: f32 = 0.25;
+/// Seconds for the intensity to drop by half once the destruction stops.
+const INTENSITY_HALF_LIFE_SECS: f32 = 3.0;
+/// How far the crossfade can move per second, so the music doesn't lurch with every hit.
+const CROSSFADE_SPEED: f32 = 0.5;
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct MusicStems {
+    #[dependency]
+    calm: Handle<AudioSource>,
+    #[dependency]
+    intense: Handle<AudioSource>,
+}
+
+impl FromWorld for MusicStems {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            calm: assets.load("audio/music/DuckNBass_Main.ogg"),
+            intense: assets.load("audio/music/DuckNBass_Main_Quacks.ogg"),
+        }
+    }
+}
+
+/// How much destruction has happened recently, from 0 (calm) to 1 (chaos).
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct DestructionIntensity {
+    pub level: f32,
+    /// How far the music has crossfaded towards the intense stem.
+    mix: f32,
+}
+
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+enum MusicStem {
+    Calm,
+    Intense,
+}
+
+impl MusicStem {
+    /// Equal-power gain for this stem at the given crossfade position.
+    fn gain(self, mix: f32) -> f32 {
+        match self {
+            Self::Calm => (mix * FRAC_PI_2).cos(),
+            Self::Intense => (mix * FRAC_PI_2).sin(),
+        }
+    }
+}
+
+fn reset_destruction_intensity(mut intensity: ResMut<DestructionIntensity>) {
+    *intensity = DestructionIntensity::default();
+}
+
+fn spawn_music_stems(mut commands: Commands, stems: Res<MusicStems>) {
+    for (stem, handle) in [
+        (MusicStem::Calm, stems.calm.clone()),
+        (MusicStem::Intense, stems.intense.clone()),
+    ] {
+        commands.spawn((
+            Name::new(format!("Gameplay Music ({stem:?})")),
+            AudioPlayer(handle),
+            // Start silent, `crossfade_music_stems` brings the right stem in.
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(0.0)),
+            Music,
+            stem,
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+}
+
+fn raise_destruction_intensity(
+    mut intensity: ResMut<DestructionIntensity>,
+    mut broken_events: EventReader<JointBroken>,
+    explosions: Query<(), Added<ExplosionAnimation>>,
+) {
+    let joints = broken_events.read().count() as f32;
+    let explosions = explosions.iter().count() as f32;
+    let raised =
+        intensity.level + joints * JOINT_BREAK_INTENSITY + explosions * EXPLOSION_INTENSITY;
+    intensity.level = raised.min(1.0);
+}
+
+fn decay_destruction_intensity(time: Res<Time>, mut intensity: ResMut<DestructionIntensity>) {
+    intensity.level *= 0.5_f32.powf(time.delta_secs() / INTENSITY_HALF_LIFE_SECS);
+}
+
+fn crossfade_music_stems(
+    time: Res<Time>,
+    global_volume: Res<GlobalVolume>,
+    settings: Res<Settings>,
+    mut intensity: ResMut<DestructionIntensity>,
+    mut stem_query: Query<(&MusicStem, &mut AudioSink)>,
+) {
+    let max_step = CROSSFADE_SPEED * time.delta_secs();
+    intensity.mix += (intensity.level - intensity.mix).clamp(-max_step, max_step);
+
+    let music_volume =
+        global_volume.volume * Volume::Linear(MUSIC_VOLUME) * Volume::Linear(settings.music_volume);
+    for (stem, mut sink) in &mut stem_query {
+        sink.set_volume(music_volume * Volume::Linear(stem.gain(intensity.mix)));
+    }
+}
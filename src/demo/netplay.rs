@@ -0,0 +1,988 @@
+//! Online co-op for two players.
+//!
+//! Start the game with `--host <port>` on one machine and `--join <address>`
+//! on the other, then both pick the same level. The host is authoritative: it
+//! simulates both players from the client's inputs, decides which mortar
+//! joints break and runs the castle's physics. The client moves its own player
+//! straight away and is pulled back toward the host's position when the two
+//! disagree. Its castle blocks are kinematic and go wherever the host says.
+//! The host sends a block again once it has strayed from where the client will
+//! have carried it on at its last known velocity.
+//!
+//! Messages go through a [`Transport`], so other transports can be added
+//! alongside the built-in [`UdpTransport`].
+
+use std::{
+    collections::VecDeque,
+    f32::consts::{PI, TAU},
+};
+
+use avian2d::prelude::*;
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems,
+    demo::{
+        balistics::AbilityKind,
+        castle::{BlockDestroyed, CastleBlock, JointBroken, MortarHealth},
+        input::{ActionType, DashAction, InputDevice, JumpAction},
+        loadout::Loadout,
+        merging::{Merged, MergedRegion, wake_region},
+        movement::{CrouchController, DashController, JumpController, MovementController},
+        player::{CharacterController, Player, PlayerBundle, PlayerIndex},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(session) = NetSession::from_args(std::env::args().skip(1)) {
+        app.insert_resource(session);
+    }
+    app.add_observer(record_local_jump);
+    app.add_observer(record_local_jump_release);
+    app.add_observer(record_local_dash);
+    app.add_systems(
+        Update,
+        (
+            spawn_remote_player,
+            make_client_mortar_unbreakable,
+            make_client_blocks_kinematic,
+            receive_messages,
+            record_local_casts,
+        )
+            .chain()
+            .in_set(AppSystems::RecordInput)
+            .run_if(resource_exists::<NetSession>.and(in_state(Screen::Gameplay))),
+    );
+    app.add_systems(
+        PostUpdate,
+        (record_castle_damage, send_messages)
+            .chain()
+            .run_if(resource_exists::<NetSession>.and(in_state(Screen::Gameplay))),
+    );
+}
+
+/// Largest message that can be received in one datagram.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+/// How many snapshots each castle change is repeated in, in case some are dropped.
+const CASTLE_CHANGE_REDUNDANCY: u32 = 5;
+/// Most castle changes sent in one snapshot. A big collapse is spread over
+/// several snapshots, so none of them outgrows [`MAX_MESSAGE_BYTES`].
+const MAX_CASTLE_CHANGES_PER_SNAPSHOT: usize = 128;
+/// Most castle block states sent in one snapshot, the blocks that have strayed
+/// furthest go first.
+const MAX_BLOCK_STATES_PER_SNAPSHOT: usize = 32;
+/// How far a block can stray from where the client expects it before it's sent again.
+const BLOCK_SYNC_DISTANCE: f32 = 0.5;
+/// How far a block can turn from where the client expects it before it's sent again, in radians.
+const BLOCK_SYNC_ANGLE: f32 = 0.01;
+/// Seconds before a block is sent again even if it hasn't strayed, in case
+/// its last state was dropped.
+const BLOCK_REFRESH_SECS: f32 = 1.0;
+/// How many messages each jump, dash and cast is repeated in, in case some are dropped.
+const EVENT_REDUNDANCY: u32 = 5;
+/// Most one-shot events sent in one message, the rest wait for the next.
+const MAX_EVENTS_PER_MESSAGE: usize = 32;
+/// How far the client's own player can drift from the host's before it's snapped back.
+const RECONCILE_SNAP_DISTANCE: f32 = 48.0;
+/// Fraction of a small disagreement corrected each snapshot, so it's smoothed out.
+const RECONCILE_RATE: f32 = 0.2;
+/// The remote player appears this far from the local one until the first snapshot arrives.
+const JOIN_OFFSET: Vec3 = Vec3::new(16.0, 0.0, 0.0);
+
+/// Sends and receives messages to and from the other player.
+///
+/// Delivery doesn't have to be reliable or ordered, anything that matters is repeated.
+pub trait Transport: Send + Sync + 'static {
+    fn send(&mut self, message: &[u8]) -> Result<(), String>;
+    /// Every message that has arrived since the last call, without blocking.
+    fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+/// Messages over a UDP socket.
+#[cfg(not(target_family = "wasm"))]
+pub struct UdpTransport {
+    socket: std::net::UdpSocket,
+    /// The host learns this from the first message the client sends.
+    peer: Option<std::net::SocketAddr>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl UdpTransport {
+    pub fn host(port: u16) -> std::io::Result<Self> {
+        let socket = std::net::UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer: None })
+    }
+
+    pub fn join(address: &str) -> std::io::Result<Self> {
+        use std::net::ToSocketAddrs;
+
+        let peer = address.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Host address not found")
+        })?;
+        let socket = std::net::UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer: Some(peer),
+        })
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Transport for UdpTransport {
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        let Some(peer) = self.peer else {
+            // Nobody has joined yet.
+            return Ok(());
+        };
+        self.socket
+            .send_to(message, peer)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        let mut buffer = [0; MAX_MESSAGE_BYTES];
+        while let Ok((length, from)) = self.socket.recv_from(&mut buffer) {
+            self.peer.get_or_insert(from);
+            messages.push(buffer[..length].to_vec());
+        }
+        messages
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRole {
+    /// Simulates the game for both players.
+    Host,
+    Client,
+}
+
+/// A castle block, identified by its grid cell since entities differ between machines.
+type Cell = [i32; 2];
+
+/// An ability cast, with the slot already looked up in the caster's loadout.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct NetCast {
+    ability: AbilityKind,
+    direction: [f32; 2],
+}
+
+/// Something the client's player did once, rather than held.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+enum NetEvent {
+    JumpPressed,
+    JumpReleased,
+    DashPressed,
+    Cast(NetCast),
+}
+
+/// What the client's player is doing.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct NetInput {
+    direction: [f32; 2],
+    crouch_held: bool,
+    /// Recent presses and casts, numbered and oldest first.
+    events: Vec<(u32, NetEvent)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PlayerState {
+    /// The player's index on the host, where the host is player 0.
+    index: usize,
+    position: [f32; 2],
+    velocity: [f32; 2],
+}
+
+/// Where a castle block is on the host and how it's moving.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct BlockState {
+    cell: Cell,
+    position: [f32; 2],
+    rotation: f32,
+    velocity: [f32; 2],
+    angular_velocity: f32,
+}
+
+impl BlockState {
+    /// How far the block has strayed from `sent`, carried on at its velocity
+    /// for `secs`, as a multiple of how far it's allowed to.
+    fn drift_from(&self, sent: &BlockState, secs: f32) -> f32 {
+        let expected_position = Vec2::from(sent.position) + Vec2::from(sent.velocity) * secs;
+        let expected_rotation = sent.rotation + sent.angular_velocity * secs;
+        let distance = Vec2::from(self.position).distance(expected_position);
+        let angle = (self.rotation - expected_rotation + PI).rem_euclid(TAU) - PI;
+        (distance / BLOCK_SYNC_DISTANCE).max(angle.abs() / BLOCK_SYNC_ANGLE)
+    }
+}
+
+/// A castle block's state as last sent to the client.
+#[derive(Debug, Clone, Copy)]
+struct SentBlock {
+    state: BlockState,
+    /// Seconds since startup when it was sent.
+    sent_at: f32,
+}
+
+/// The host's view of the game this frame.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct NetSnapshot {
+    players: Vec<PlayerState>,
+    /// Abilities the host's player cast recently, numbered and oldest first.
+    casts: Vec<(u32, NetCast)>,
+    /// Recently broken mortar joints, as the cells of the two blocks they held together.
+    broken_joints: Vec<[Cell; 2]>,
+    /// Recently loosened blocks.
+    loosened_blocks: Vec<Cell>,
+    /// Castle blocks that have moved since the client was last told.
+    blocks: Vec<BlockState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum NetMessage {
+    Input(NetInput),
+    Snapshot(NetSnapshot),
+}
+
+/// A castle change being repeated in the next few snapshots.
+#[derive(Debug, Clone, Copy)]
+enum CastleChange {
+    JointBroken([Cell; 2]),
+    BlockLoosened(Cell),
+}
+
+/// One-shot events, like a jump press, repeated in the next few messages
+/// since any single message can be dropped.
+///
+/// Events are numbered in the order they happened, so the other side can
+/// apply each one once with [`is_new_event`].
+#[derive(Debug)]
+struct RepeatedEvents<T> {
+    next_id: u32,
+    /// Events, their numbers and how many more messages to send them in.
+    pending: VecDeque<(u32, T, u32)>,
+}
+
+impl<T> Default for RepeatedEvents<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Clone> RepeatedEvents<T> {
+    fn push(&mut self, event: T) {
+        self.pending
+            .push_back((self.next_id, event, EVENT_REDUNDANCY));
+        self.next_id += 1;
+    }
+
+    /// The oldest events still being repeated, up to [`MAX_EVENTS_PER_MESSAGE`].
+    fn take_for_message(&mut self) -> Vec<(u32, T)> {
+        let mut events = Vec::new();
+        for (id, event, repeats_left) in self.pending.iter_mut().take(MAX_EVENTS_PER_MESSAGE) {
+            events.push((*id, event.clone()));
+            *repeats_left -= 1;
+        }
+        self.pending.retain(|(.., repeats_left)| *repeats_left > 0);
+        events
+    }
+}
+
+/// Whether the event numbered `id` hasn't been applied yet, noting it as applied if so.
+///
+/// Events are always sent oldest first, so anything at or below the last one
+/// applied is a repeat.
+fn is_new_event(last_applied: &mut Option<u32>, id: u32) -> bool {
+    if last_applied.is_some_and(|last| id <= last) {
+        return false;
+    }
+    *last_applied = Some(id);
+    true
+}
+
+/// The connection to the other player, present while playing online.
+#[derive(Resource)]
+pub struct NetSession {
+    pub role: NetRole,
+    transport: Box<dyn Transport>,
+    /// The client's presses and casts still being repeated to the host.
+    input_events: RepeatedEvents<NetEvent>,
+    /// Abilities cast by the host's player still being repeated to the client.
+    casts: RepeatedEvents<NetCast>,
+    /// The last event from the other player that's been applied.
+    last_applied_event: Option<u32>,
+    /// Castle changes on the host and how many more snapshots to send them in.
+    castle_changes: VecDeque<(CastleChange, u32)>,
+    /// Blocks the client has already loosened, so repeats aren't counted twice.
+    loosened: HashSet<Cell>,
+    /// What the host last told the client about each castle block.
+    sent_blocks: HashMap<Cell, SentBlock>,
+}
+
+impl NetSession {
+    pub fn new(role: NetRole, transport: impl Transport) -> Self {
+        Self {
+            role,
+            transport: Box::new(transport),
+            input_events: RepeatedEvents::default(),
+            casts: RepeatedEvents::default(),
+            last_applied_event: None,
+            castle_changes: VecDeque::new(),
+            loosened: HashSet::default(),
+            sent_blocks: HashMap::default(),
+        }
+    }
+
+    /// Start a session from `--host <port>` or `--join <address>`.
+    #[cfg(not(target_family = "wasm"))]
+    fn from_args(mut args: impl Iterator<Item = String>) -> Option<Self> {
+        while let Some(arg) = args.next() {
+            let result = match arg.as_str() {
+                "--host" => {
+                    let port = args.next()?.parse().ok()?;
+                    info!("Hosting online co-op on port {port}");
+                    UdpTransport::host(port).map(|transport| Self::new(NetRole::Host, transport))
+                }
+                "--join" => {
+                    let address = args.next()?;
+                    info!("Joining online co-op at {address}");
+                    UdpTransport::join(&address)
+                        .map(|transport| Self::new(NetRole::Client, transport))
+                }
+                _ => continue,
+            };
+            return result
+                .inspect_err(|error| error!("Couldn't start online co-op: {error}"))
+                .ok();
+        }
+        None
+    }
+
+    fn send(&mut self, message: &NetMessage) {
+        let result = serde_json::to_vec(message)
+            .map_err(|error| error.to_string())
+            .and_then(|bytes| self.transport.send(&bytes));
+        if let Err(error) = result {
+            warn!("Couldn't send to the other player: {error}");
+        }
+    }
+
+    fn is_client(&self) -> bool {
+        self.role == NetRole::Client
+    }
+}
+
+/// Player indices are swapped on the client, where its own player is player 0.
+fn client_index(host_index: usize) -> usize {
+    1 - host_index
+}
+
+fn spawn_remote_player(
+    mut commands: Commands,
+    player_query: Query<(&Transform, &PlayerIndex, Option<&ChildOf>), With<Player>>,
+) {
+    if player_query.iter().any(|(_, index, _)| index.0 == 1) {
+        return;
+    }
+    let Some((local_transform, _, parent)) = player_query.iter().find(|(_, index, _)| index.0 == 0)
+    else {
+        return;
+    };
+    let mut remote_player = commands.spawn((
+        Name::new("Remote Player"),
+        PlayerBundle::joining(1),
+        InputDevice::Remote,
+        Transform::from_translation(local_transform.translation + JOIN_OFFSET),
+    ));
+    if let Some(parent) = parent {
+        remote_player.insert(ChildOf(parent.parent()));
+    }
+}
+
+/// Only the host breaks mortar, the client waits to be told.
+fn make_client_mortar_unbreakable(
+    session: Res<NetSession>,
+    mut joint_query: Query<&mut MortarHealth, Added<MortarHealth>>,
+) {
+    if !session.is_client() {
+        return;
+    }
+    for mut mortar in &mut joint_query {
+        mortar.current = f32::INFINITY;
+    }
+}
+
+/// The host runs the castle's physics, the client's blocks just follow along.
+fn make_client_blocks_kinematic(
+    session: Res<NetSession>,
+    mut block_query: Query<&mut RigidBody, (With<CastleBlock>, Added<RigidBody>)>,
+) {
+    if !session.is_client() {
+        return;
+    }
+    for mut rigid_body in &mut block_query {
+        *rigid_body = RigidBody::Kinematic;
+    }
+}
+
+fn is_local_player(player_query: &Query<&PlayerIndex>, entity: Entity) -> bool {
+    player_query.get(entity).is_ok_and(|index| index.0 == 0)
+}
+
+fn record_local_jump(
+    trigger: Trigger<Started<JumpAction>>,
+    session: Option<ResMut<NetSession>>,
+    player_query: Query<&PlayerIndex>,
+) {
+    let Some(mut session) = session.filter(|session| session.is_client()) else {
+        return;
+    };
+    if is_local_player(&player_query, trigger.target()) {
+        session.input_events.push(NetEvent::JumpPressed);
+    }
+}
+
+fn record_local_jump_release(
+    trigger: Trigger<Completed<JumpAction>>,
+    session: Option<ResMut<NetSession>>,
+    player_query: Query<&PlayerIndex>,
+) {
+    let Some(mut session) = session.filter(|session| session.is_client()) else {
+        return;
+    };
+    if is_local_player(&player_query, trigger.target()) {
+        session.input_events.push(NetEvent::JumpReleased);
+    }
+}
+
+fn record_local_dash(
+    trigger: Trigger<Started<DashAction>>,
+    session: Option<ResMut<NetSession>>,
+    player_query: Query<&PlayerIndex>,
+) {
+    let Some(mut session) = session.filter(|session| session.is_client()) else {
+        return;
+    };
+    if is_local_player(&player_query, trigger.target()) {
+        session.input_events.push(NetEvent::DashPressed);
+    }
+}
+
+/// Pick up the abilities the local player cast this frame so the other player sees them too.
+fn record_local_casts(
+    time: Res<Time<Real>>,
+    mut session: ResMut<NetSession>,
+    loadout: Res<Loadout>,
+    player_query: Query<(&PlayerIndex, &CharacterController)>,
+) {
+    let Some((_, controller)) = player_query.iter().find(|(index, _)| index.0 == 0) else {
+        return;
+    };
+    // Anything queued on an earlier frame has already been sent.
+    let casts = controller
+        .action_queue
+        .iter()
+        .filter(|queued| queued.queued_at == time.elapsed())
+        .filter_map(|queued| match queued.action {
+            ActionType::UseSlot { slot, direction } => loadout
+                .slots
+                .get(slot)
+                .copied()
+                .flatten()
+                .map(|ability| (ability, direction)),
+            ActionType::Cast { ability, direction } => Some((ability, direction)),
+        })
+        .map(|(ability, direction)| NetCast {
+            ability,
+            direction: direction.into(),
+        });
+    for cast in casts {
+        match session.role {
+            NetRole::Host => session.casts.push(cast),
+            NetRole::Client => session.input_events.push(NetEvent::Cast(cast)),
+        }
+    }
+}
+
+fn receive_messages(
+    time: Res<Time<Real>>,
+    mut session: ResMut<NetSession>,
+    mut player_query: Query<(
+        &PlayerIndex,
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut MovementController,
+        &mut JumpController,
+        &mut DashController,
+        &mut CrouchController,
+        &mut CharacterController,
+    )>,
+    mut castle: CastleReplication,
+) {
+    for bytes in session.transport.receive() {
+        let message = match serde_json::from_slice::<NetMessage>(&bytes) {
+            Ok(message) => message,
+            Err(error) => {
+                warn!("Ignoring a malformed message: {error}");
+                continue;
+            }
+        };
+        match (session.role, message) {
+            (NetRole::Host, NetMessage::Input(input)) => {
+                let Some((.., mut movement, mut jump, mut dash, mut crouch, mut controller)) =
+                    player_query.iter_mut().find(|(index, ..)| index.0 == 1)
+                else {
+                    continue;
+                };
+                movement.direction = Vec2::from(input.direction);
+                crouch.set_held(input.crouch_held);
+                for (id, event) in input.events {
+                    if !is_new_event(&mut session.last_applied_event, id) {
+                        continue;
+                    }
+                    match event {
+                        NetEvent::JumpPressed => jump.request_jump(),
+                        NetEvent::JumpReleased => jump.release_jump(),
+                        NetEvent::DashPressed => dash.request_dash(),
+                        NetEvent::Cast(cast) => {
+                            controller.queue_action(cast.into(), time.elapsed());
+                        }
+                    }
+                }
+            }
+            (NetRole::Client, NetMessage::Snapshot(snapshot)) => {
+                for state in &snapshot.players {
+                    let index = client_index(state.index);
+                    let Some((_, mut transform, mut velocity, ..)) =
+                        player_query.iter_mut().find(|(i, ..)| i.0 == index)
+                    else {
+                        continue;
+                    };
+                    let position = Vec2::from(state.position);
+                    if index == 0 {
+                        reconcile(&mut transform, &mut velocity, position, state.velocity);
+                    } else {
+                        transform.translation.x = position.x;
+                        transform.translation.y = position.y;
+                        velocity.0 = Vec2::from(state.velocity);
+                    }
+                }
+                if let Some((.., mut controller)) =
+                    player_query.iter_mut().find(|(index, ..)| index.0 == 1)
+                {
+                    for (id, cast) in &snapshot.casts {
+                        if is_new_event(&mut session.last_applied_event, *id) {
+                            controller.queue_action((*cast).into(), time.elapsed());
+                        }
+                    }
+                }
+                castle.apply(&mut session, &snapshot);
+            }
+            // Both sides think they're the host, or both the client.
+            _ => warn!("Ignoring a message meant for the other role"),
+        }
+    }
+}
+
+/// Pull the client's own player toward where the host has it.
+fn reconcile(
+    transform: &mut Transform,
+    velocity: &mut LinearVelocity,
+    host_position: Vec2,
+    host_velocity: [f32; 2],
+) {
+    let predicted = transform.translation.truncate();
+    let corrected = if predicted.distance(host_position) > RECONCILE_SNAP_DISTANCE {
+        velocity.0 = Vec2::from(host_velocity);
+        host_position
+    } else {
+        predicted.lerp(host_position, RECONCILE_RATE)
+    };
+    transform.translation.x = corrected.x;
+    transform.translation.y = corrected.y;
+}
+
+impl From<NetCast> for ActionType {
+    fn from(cast: NetCast) -> Self {
+        ActionType::Cast {
+            ability: cast.ability,
+            direction: Vec2::from(cast.direction),
+        }
+    }
+}
+
+/// Applies the host's castle changes on the client.
+#[derive(bevy::ecs::system::SystemParam)]
+struct CastleReplication<'w, 's> {
+    commands: Commands<'w, 's>,
+    joint_query: Query<'w, 's, (Entity, &'static FixedJoint), With<MortarHealth>>,
+    block_query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static GridCoords,
+            &'static Mass,
+            &'static GlobalTransform,
+        ),
+        With<CastleBlock>,
+    >,
+    body_query: Query<
+        'w,
+        's,
+        (
+            &'static mut Position,
+            &'static mut Rotation,
+            &'static mut LinearVelocity,
+            &'static mut AngularVelocity,
+            Option<&'static Merged>,
+        ),
+        (With<CastleBlock>, Without<PlayerIndex>),
+    >,
+    region_query: Query<'w, 's, (Entity, &'static MergedRegion)>,
+    broken_events: EventWriter<'w, JointBroken>,
+    destroyed_events: EventWriter<'w, BlockDestroyed>,
+}
+
+impl CastleReplication<'_, '_> {
+    fn apply(&mut self, session: &mut NetSession, snapshot: &NetSnapshot) {
+        // Every block by its cell, with its mass and position.
+        let blocks: HashMap<Cell, (Entity, f32, Vec2)> = self
+            .block_query
+            .iter()
+            .map(|(block, coords, mass, transform)| {
+                (
+                    [coords.x, coords.y],
+                    (block, mass.0, transform.translation().truncate()),
+                )
+            })
+            .collect();
+        let block_at = |cell: &Cell| blocks.get(cell).copied();
+
+        for [cell1, cell2] in &snapshot.broken_joints {
+            let (Some((block1, _, position1)), Some((block2, _, position2))) =
+                (block_at(cell1), block_at(cell2))
+            else {
+                continue;
+            };
+            // A repeat of a break that's already been applied won't find the joint.
+            let Some((joint, _)) = self.joint_query.iter().find(|(_, joint)| {
+                (joint.entity1, joint.entity2) == (block1, block2)
+                    || (joint.entity1, joint.entity2) == (block2, block1)
+            }) else {
+                continue;
+            };
+            self.commands.entity(joint).despawn();
+            self.broken_events.write(JointBroken {
+                joint,
+                block1,
+                block2,
+                // The mortar sits between the two blocks.
+                position: position1.midpoint(position2),
+            });
+        }
+        for cell in &snapshot.loosened_blocks {
+            if !session.loosened.insert(*cell) {
+                continue;
+            }
+            if let Some((block, mass, _)) = block_at(cell) {
+                self.destroyed_events.write(BlockDestroyed { block, mass });
+            }
+        }
+        for state in &snapshot.blocks {
+            let Some((block, ..)) = block_at(&state.cell) else {
+                continue;
+            };
+            let Ok((mut position, mut rotation, mut velocity, mut angular_velocity, merged)) =
+                self.body_query.get_mut(block)
+            else {
+                continue;
+            };
+            // The block has moved on the host, so it's not part of a still region anymore.
+            if let Some((region_entity, region)) =
+                merged.and_then(|merged| self.region_query.get(merged.region).ok())
+            {
+                wake_region(&mut self.commands, region_entity, region);
+            }
+            position.0 = Vec2::from(state.position);
+            *rotation = Rotation::radians(state.rotation);
+            velocity.0 = Vec2::from(state.velocity);
+            angular_velocity.0 = state.angular_velocity;
+        }
+    }
+}
+
+/// The blocks the client most needs to hear about, noting them as sent.
+///
+/// A block is due once it has strayed from its last sent state, or that
+/// state is older than [`BLOCK_REFRESH_SECS`]. Blocks that have never been
+/// sent are due too, since the client's copy hasn't settled like the host's.
+fn take_block_states(
+    sent_blocks: &mut HashMap<Cell, SentBlock>,
+    now: f32,
+    blocks: impl Iterator<Item = BlockState>,
+) -> Vec<BlockState> {
+    let mut due: Vec<(f32, BlockState)> = blocks
+        .map(|state| {
+            let urgency = sent_blocks.get(&state.cell).map_or(1.0, |sent| {
+                let secs = now - sent.sent_at;
+                state
+                    .drift_from(&sent.state, secs)
+                    .max(secs / BLOCK_REFRESH_SECS)
+            });
+            (urgency, state)
+        })
+        .filter(|(urgency, _)| *urgency >= 1.0)
+        .collect();
+    due.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    due.truncate(MAX_BLOCK_STATES_PER_SNAPSHOT);
+    due.into_iter()
+        .map(|(_, state)| {
+            sent_blocks.insert(
+                state.cell,
+                SentBlock {
+                    state,
+                    sent_at: now,
+                },
+            );
+            state
+        })
+        .collect()
+}
+
+/// Note the host's castle changes so they can be sent to the client.
+fn record_castle_damage(
+    mut session: ResMut<NetSession>,
+    mut broken_events: EventReader<JointBroken>,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    block_query: Query<&GridCoords, With<CastleBlock>>,
+) {
+    if session.is_client() {
+        broken_events.clear();
+        destroyed_events.clear();
+        return;
+    }
+    let cell = |block| {
+        block_query
+            .get(block)
+            .ok()
+            .map(|coords| [coords.x, coords.y])
+    };
+    for event in broken_events.read() {
+        if let (Some(cell1), Some(cell2)) = (cell(event.block1), cell(event.block2)) {
+            session.castle_changes.push_back((
+                CastleChange::JointBroken([cell1, cell2]),
+                CASTLE_CHANGE_REDUNDANCY,
+            ));
+        }
+    }
+    for event in destroyed_events.read() {
+        if let Some(cell) = cell(event.block) {
+            session
+                .castle_changes
+                .push_back((CastleChange::BlockLoosened(cell), CASTLE_CHANGE_REDUNDANCY));
+        }
+    }
+}
+
+fn send_messages(
+    time: Res<Time>,
+    mut session: ResMut<NetSession>,
+    player_query: Query<(
+        &PlayerIndex,
+        &Transform,
+        &LinearVelocity,
+        &MovementController,
+        &CrouchController,
+    )>,
+    // Merged blocks are disabled and haven't moved since the castle was built.
+    block_query: Query<
+        (
+            &GridCoords,
+            &Position,
+            &Rotation,
+            &LinearVelocity,
+            &AngularVelocity,
+        ),
+        (With<CastleBlock>, Without<Merged>),
+    >,
+) {
+    match session.role {
+        NetRole::Client => {
+            let mut input = NetInput {
+                events: session.input_events.take_for_message(),
+                ..default()
+            };
+            if let Some((.., movement, crouch)) =
+                player_query.iter().find(|(index, ..)| index.0 == 0)
+            {
+                input.direction = movement.direction.into();
+                input.crouch_held = crouch.is_held();
+            }
+            session.send(&NetMessage::Input(input));
+        }
+        NetRole::Host => {
+            let mut snapshot = NetSnapshot {
+                players: player_query
+                    .iter()
+                    // Only the two online players, not anyone who joined locally.
+                    .filter(|(index, ..)| index.0 <= 1)
+                    .map(|(index, transform, velocity, ..)| PlayerState {
+                        index: index.0,
+                        position: transform.translation.truncate().into(),
+                        velocity: velocity.0.into(),
+                    })
+                    .collect(),
+                casts: session.casts.take_for_message(),
+                ..default()
+            };
+            // The oldest changes go first, the rest wait for later snapshots.
+            for (change, repeats_left) in session
+                .castle_changes
+                .iter_mut()
+                .take(MAX_CASTLE_CHANGES_PER_SNAPSHOT)
+            {
+                match change {
+                    CastleChange::JointBroken(cells) => snapshot.broken_joints.push(*cells),
+                    CastleChange::BlockLoosened(cell) => snapshot.loosened_blocks.push(*cell),
+                }
+                *repeats_left -= 1;
+            }
+            session
+                .castle_changes
+                .retain(|(_, repeats_left)| *repeats_left > 0);
+            let blocks = block_query.iter().map(
+                |(coords, position, rotation, velocity, angular_velocity)| BlockState {
+                    cell: [coords.x, coords.y],
+                    position: position.0.into(),
+                    rotation: rotation.as_radians(),
+                    velocity: velocity.0.into(),
+                    angular_velocity: angular_velocity.0,
+                },
+            );
+            snapshot.blocks =
+                take_block_states(&mut session.sent_blocks, time.elapsed_secs(), blocks);
+            session.send(&NetMessage::Snapshot(snapshot));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worst_case_cast() -> NetCast {
+        NetCast {
+            ability: AbilityKind::Lightning,
+            direction: [f32::MIN; 2],
+        }
+    }
+
+    fn worst_case_block() -> BlockState {
+        BlockState {
+            cell: [i32::MIN; 2],
+            position: [f32::MIN; 2],
+            rotation: f32::MIN,
+            velocity: [f32::MIN; 2],
+            angular_velocity: f32::MIN,
+        }
+    }
+
+    fn message_bytes(message: &NetMessage) -> usize {
+        serde_json::to_vec(message).unwrap().len()
+    }
+
+    #[test]
+    fn worst_case_snapshot_fits_in_a_message() {
+        let snapshot = NetSnapshot {
+            players: (0..2)
+                .map(|index| PlayerState {
+                    index,
+                    position: [f32::MIN; 2],
+                    velocity: [f32::MIN; 2],
+                })
+                .collect(),
+            casts: vec![(u32::MAX, worst_case_cast()); MAX_EVENTS_PER_MESSAGE],
+            broken_joints: vec![[[i32::MIN; 2]; 2]; MAX_CASTLE_CHANGES_PER_SNAPSHOT],
+            loosened_blocks: Vec::new(),
+            blocks: vec![worst_case_block(); MAX_BLOCK_STATES_PER_SNAPSHOT],
+        };
+        assert!(message_bytes(&NetMessage::Snapshot(snapshot)) <= MAX_MESSAGE_BYTES);
+    }
+
+    #[test]
+    fn worst_case_input_fits_in_a_message() {
+        let input = NetInput {
+            direction: [f32::MIN; 2],
+            crouch_held: true,
+            events: vec![(u32::MAX, NetEvent::Cast(worst_case_cast())); MAX_EVENTS_PER_MESSAGE],
+        };
+        assert!(message_bytes(&NetMessage::Input(input)) <= MAX_MESSAGE_BYTES);
+    }
+
+    #[test]
+    fn blocks_are_sent_again_once_they_stray() {
+        let mut sent_blocks = HashMap::default();
+        let moving = BlockState {
+            cell: [0, 0],
+            position: [0.0, 0.0],
+            rotation: 0.0,
+            velocity: [10.0, 0.0],
+            angular_velocity: 0.0,
+        };
+        assert_eq!(
+            take_block_states(&mut sent_blocks, 0.0, [moving].into_iter()).len(),
+            1
+        );
+
+        // Right where the client will have carried it on to.
+        let on_course = BlockState {
+            position: [1.0, 0.0],
+            ..moving
+        };
+        assert!(take_block_states(&mut sent_blocks, 0.1, [on_course].into_iter()).is_empty());
+
+        // Stopped short, while the client still has it moving.
+        let stopped = BlockState {
+            velocity: [0.0, 0.0],
+            ..moving
+        };
+        assert_eq!(
+            take_block_states(&mut sent_blocks, 0.1, [stopped].into_iter()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn repeated_events_are_applied_once() {
+        let mut events = RepeatedEvents::default();
+        events.push(NetEvent::JumpPressed);
+        events.push(NetEvent::DashPressed);
+
+        let mut last_applied = None;
+        let mut applied = 0;
+        for _ in 0..EVENT_REDUNDANCY {
+            for (id, _) in events.take_for_message() {
+                if is_new_event(&mut last_applied, id) {
+                    applied += 1;
+                }
+            }
+        }
+
+        assert_eq!(applied, 2);
+        assert!(events.take_for_message().is_empty());
+    }
+}
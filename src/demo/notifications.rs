@@ -0,0 +1,134 @@
+//! Short notifications that pop up in the corner of the HUD.
+//!
+//! Any system can announce something with [`Notifications::push`]. Messages
+//! slide in under the score, stay up for a few seconds and fade out. At most
+//! [`MAX_TOASTS`] are on screen at once, the rest wait their turn.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    demo::hud::{HudAnchor, HudRegion},
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Notifications>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (clear_notifications, spawn_notification_area),
+    );
+    app.add_systems(
+        Update,
+        (show_notifications, animate_toasts)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Most notifications shown at once.
+const MAX_TOASTS: usize = 3;
+const TOAST_SLIDE_SECS: f32 = 0.25;
+const TOAST_SECS: f32 = 3.0;
+const TOAST_FADE_SECS: f32 = 0.5;
+/// How far toasts slide in from, in pixels.
+const TOAST_SLIDE_DISTANCE: f32 = 200.0;
+const TOAST_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+/// Messages waiting to be shown in the HUD.
+#[derive(Resource, Debug, Default)]
+pub struct Notifications {
+    queue: VecDeque<String>,
+}
+
+impl Notifications {
+    /// Show `message` once there's room for it.
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.queue.push_back(message.into());
+    }
+}
+
+/// Where toasts stack up.
+#[derive(Component)]
+struct NotificationArea;
+
+/// A notification on screen.
+#[derive(Component, Debug)]
+struct Toast {
+    /// Seconds since the toast appeared.
+    age: f32,
+}
+
+fn clear_notifications(mut notifications: ResMut<Notifications>) {
+    notifications.queue.clear();
+}
+
+fn spawn_notification_area(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Notifications"),
+        NotificationArea,
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            row_gap: Px(4.0),
+            ..default()
+        },
+        HudAnchor::new(HudRegion::TopRight, 2),
+    ));
+}
+
+fn show_notifications(
+    mut commands: Commands,
+    mut notifications: ResMut<Notifications>,
+    area_query: Query<Entity, With<NotificationArea>>,
+    toast_query: Query<(), With<Toast>>,
+) {
+    let Ok(area) = area_query.single() else {
+        return;
+    };
+    for _ in toast_query.iter().count()..MAX_TOASTS {
+        let Some(message) = notifications.queue.pop_front() else {
+            return;
+        };
+        commands.spawn((
+            widget::label(message),
+            Toast { age: 0.0 },
+            Node {
+                padding: UiRect::axes(Px(10.0), Px(4.0)),
+                left: Px(TOAST_SLIDE_DISTANCE),
+                ..default()
+            },
+            BackgroundColor(TOAST_BACKGROUND),
+            BorderRadius::all(Px(4.0)),
+            ChildOf(area),
+        ));
+    }
+}
+
+/// Slide toasts in, then fade them out, in real time so they keep going while the game's paused.
+fn animate_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut toast_query: Query<(
+        Entity,
+        &mut Toast,
+        &mut Node,
+        &mut TextColor,
+        &mut BackgroundColor,
+    )>,
+) {
+    for (entity, mut toast, mut node, mut text_color, mut background) in &mut toast_query {
+        toast.age += time.delta_secs();
+        if toast.age >= TOAST_SECS {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let slide = (toast.age / TOAST_SLIDE_SECS).min(1.0);
+        node.left = Px(TOAST_SLIDE_DISTANCE * (1.0 - slide).powi(2));
+        let alpha = ((TOAST_SECS - toast.age) / TOAST_FADE_SECS).min(1.0);
+        text_color.0.set_alpha(alpha);
+        background.0 = TOAST_BACKGROUND.with_alpha(TOAST_BACKGROUND.alpha() * alpha);
+    }
+}
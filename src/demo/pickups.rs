@@ -0,0 +1,275 @@
+//! Collectibles placed in LDtk: coins that add to the score, and ability
+//! runes that unlock an ability when the player touches them.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    audio::sound_effect,
+    demo::{
+        attract::AttractMode,
+        balistics::{AbilityKind, UnlockedAbilities},
+        hud::{HudAnchor, HudRegion},
+        notifications::Notifications,
+        player::Player,
+        progress::Progress,
+        score::Score,
+    },
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PickupAssets>();
+    app.load_resource::<PickupAssets>();
+    app.register_type::<CoinCounter>();
+    app.init_resource::<CoinCounter>();
+    app.register_ldtk_entity::<CoinBundle>("Coin");
+    app.register_ldtk_entity::<AbilityRuneBundle>("AbilityRune");
+
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (reset_coin_counter, spawn_coin_counter_ui),
+    );
+    app.add_systems(
+        Update,
+        (
+            lock_abilities_with_runes,
+            (collect_coins, collect_ability_runes).run_if(resource_exists::<PickupAssets>),
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        update_coin_counter_ui
+            .run_if(in_state(Screen::Gameplay).and(resource_changed::<CoinCounter>)),
+    );
+}
+
+const COIN_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+const DEFAULT_COIN_VALUE: u32 = 10;
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct PickupAssets {
+    #[dependency]
+    pub pickup: Handle<AudioSource>,
+}
+
+impl FromWorld for PickupAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            // Placeholder until we have a dedicated pickup sound.
+            pickup: assets.load("audio/sound_effects/button_hover.ogg"),
+        }
+    }
+}
+
+/// How many coins the player has collected this run.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct CoinCounter(pub u32);
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Coin {
+    /// Points awarded when collected.
+    pub value: u32,
+}
+
+impl Default for Coin {
+    fn default() -> Self {
+        Self {
+            value: DEFAULT_COIN_VALUE,
+        }
+    }
+}
+
+impl From<&EntityInstance> for Coin {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let value = entity_instance
+            .get_int_field("Value")
+            .map(|value| (*value).max(0) as u32)
+            .unwrap_or(DEFAULT_COIN_VALUE);
+        Self { value }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct CoinBundle {
+    #[from_entity_instance]
+    pub coin: Coin,
+    pub sprite: Sprite,
+    pub pickup_sensor: PickupSensor,
+}
+
+impl Default for CoinBundle {
+    fn default() -> Self {
+        Self {
+            coin: Coin::default(),
+            sprite: Sprite::from_color(COIN_COLOR, Vec2::splat(8.0)),
+            pickup_sensor: PickupSensor::default(),
+        }
+    }
+}
+
+/// Unlocks an ability when picked up.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct AbilityRune {
+    pub ability: AbilityKind,
+}
+
+impl From<&EntityInstance> for AbilityRune {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let ability = match entity_instance
+            .get_string_field("Ability")
+            .map(String::as_str)
+        {
+            Ok("Lightning") => AbilityKind::Lightning,
+            Ok("Fireball") => AbilityKind::Fireball,
+            Ok("Charge") => AbilityKind::Charge,
+            _ => AbilityKind::Frost,
+        };
+        Self { ability }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct AbilityRuneBundle {
+    #[from_entity_instance]
+    pub rune: AbilityRune,
+    #[with(rune_sprite)]
+    pub sprite: Sprite,
+    pub pickup_sensor: PickupSensor,
+}
+
+impl Default for AbilityRuneBundle {
+    fn default() -> Self {
+        Self {
+            rune: AbilityRune::default(),
+            sprite: Sprite::default(),
+            pickup_sensor: PickupSensor::default(),
+        }
+    }
+}
+
+fn rune_sprite(entity_instance: &EntityInstance) -> Sprite {
+    let rune = AbilityRune::from(entity_instance);
+    Sprite::from_color(rune.ability.color(), Vec2::splat(12.0))
+}
+
+/// A static sensor so pickups don't fall or block movement.
+#[derive(Clone, Bundle)]
+pub struct PickupSensor {
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+    pub sensor: Sensor,
+    pub colliding_entities: CollidingEntities,
+}
+
+impl Default for PickupSensor {
+    fn default() -> Self {
+        Self {
+            collider: Collider::circle(6.0),
+            rigid_body: RigidBody::Static,
+            sensor: Sensor,
+            colliding_entities: CollidingEntities::default(),
+        }
+    }
+}
+
+fn collect_coins(
+    mut commands: Commands,
+    pickup_assets: Res<PickupAssets>,
+    mut score: ResMut<Score>,
+    mut coin_counter: ResMut<CoinCounter>,
+    coin_query: Query<(Entity, &Coin, &CollidingEntities)>,
+    player_query: Query<(), With<Player>>,
+) {
+    for (coin_entity, coin, colliding_entities) in &coin_query {
+        if !colliding_entities
+            .iter()
+            .any(|entity| player_query.contains(*entity))
+        {
+            continue;
+        }
+        score.add(coin.value);
+        coin_counter.0 += 1;
+        commands.spawn(sound_effect(pickup_assets.pickup.clone()));
+        commands.entity(coin_entity).despawn();
+    }
+}
+
+/// A rune in the level means its ability has to be earned there first,
+/// unless it was already collected in an earlier run.
+fn lock_abilities_with_runes(
+    mut commands: Commands,
+    mut unlocked: ResMut<UnlockedAbilities>,
+    progress: Res<Progress>,
+    rune_query: Query<(Entity, &AbilityRune), Added<AbilityRune>>,
+) {
+    for (rune_entity, rune) in &rune_query {
+        if progress.collected_abilities.contains(&rune.ability) {
+            commands.entity(rune_entity).despawn();
+        } else {
+            unlocked.set(rune.ability, false);
+        }
+    }
+}
+
+fn collect_ability_runes(
+    mut commands: Commands,
+    pickup_assets: Res<PickupAssets>,
+    mut unlocked: ResMut<UnlockedAbilities>,
+    mut progress: ResMut<Progress>,
+    attract_mode: Res<AttractMode>,
+    mut notifications: ResMut<Notifications>,
+    rune_query: Query<(Entity, &AbilityRune, &CollidingEntities)>,
+    player_query: Query<(), With<Player>>,
+) {
+    for (rune_entity, rune, colliding_entities) in &rune_query {
+        if !colliding_entities
+            .iter()
+            .any(|entity| player_query.contains(*entity))
+        {
+            continue;
+        }
+        info!("Unlocked ability {:?}", rune.ability);
+        unlocked.set(rune.ability, true);
+        // Runes picked up by the demo stay for the player to find.
+        if !attract_mode.active {
+            progress.collected_abilities.insert(rune.ability);
+        }
+        notifications.push(format!("Ability unlocked: {:?}", rune.ability));
+        commands.spawn(sound_effect(pickup_assets.pickup.clone()));
+        commands.entity(rune_entity).despawn();
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct CoinCounterLabel;
+
+fn reset_coin_counter(mut coin_counter: ResMut<CoinCounter>) {
+    coin_counter.0 = 0;
+}
+
+fn spawn_coin_counter_ui(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Coin Counter"),
+        Node::default(),
+        HudAnchor::new(HudRegion::TopRight, 1),
+        children![(widget::label("Coins: 0"), CoinCounterLabel)],
+    ));
+}
+
+fn update_coin_counter_ui(
+    coin_counter: Res<CoinCounter>,
+    mut label: Single<&mut Text, With<CoinCounterLabel>>,
+) {
+    label.0 = format!("Coins: {}", coin_counter.0);
+}
@@ -0,0 +1,141 @@
+//! Moving platforms that travel along a path of waypoints authored in LDtk.
+//!
+//! A "MovingPlatform" entity reads a `Path` points field (and an optional
+//! `Speed` float field) and loops through the waypoints forever. Characters
+//! standing on a platform are carried along by `movement.rs`.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{AppSystems, PausableSystems};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<MovingPlatform>();
+    app.register_ldtk_entity::<MovingPlatformBundle>("MovingPlatform");
+    app.add_systems(
+        Update,
+        (resolve_platform_waypoints, move_platforms)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const GRID_SIZE: f32 = 16.0;
+const DEFAULT_PLATFORM_SPEED: f32 = 60.0;
+
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct MovingPlatform {
+    /// Waypoints relative to where the platform was spawned, in pixels.
+    offsets: Vec<Vec2>,
+    /// Waypoints in the platform's parent space, resolved once it has spawned.
+    waypoints: Vec<Vec2>,
+    /// Index of the waypoint the platform is currently heading towards.
+    current: usize,
+    size: Vec2,
+    /// Travel speed in world units per second.
+    pub speed: f32,
+}
+
+impl From<&EntityInstance> for MovingPlatform {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        // The spawn position is always the first waypoint so the path loops back to it.
+        let mut offsets = vec![Vec2::ZERO];
+        let path = entity_instance
+            .field_instances
+            .iter()
+            .find(|f| f.identifier == "Path")
+            .map(|f| &f.value);
+        if let Some(FieldValue::Points(points)) = path {
+            for point in points.iter().flatten() {
+                let cells = *point - entity_instance.grid;
+                // LDtk grid coordinates grow downwards, ours grow upwards.
+                offsets.push(Vec2::new(cells.x as f32, -cells.y as f32) * GRID_SIZE);
+            }
+        }
+        let speed = entity_instance
+            .get_float_field("Speed")
+            .copied()
+            .unwrap_or(DEFAULT_PLATFORM_SPEED);
+
+        Self {
+            offsets,
+            waypoints: Vec::new(),
+            current: 0,
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+            speed,
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct MovingPlatformBundle {
+    #[from_entity_instance]
+    pub platform: MovingPlatform,
+    #[sprite("images/stone2.png")]
+    pub sprite: Sprite,
+    #[with(platform_collider)]
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+}
+
+impl Default for MovingPlatformBundle {
+    fn default() -> Self {
+        Self {
+            platform: MovingPlatform::default(),
+            sprite: Sprite::default(),
+            collider: Collider::rectangle(GRID_SIZE, GRID_SIZE),
+            // Kinematic bodies are driven purely by their velocity and push dynamic bodies aside.
+            rigid_body: RigidBody::Kinematic,
+        }
+    }
+}
+
+fn platform_collider(entity_instance: &EntityInstance) -> Collider {
+    Collider::rectangle(entity_instance.width as f32, entity_instance.height as f32)
+}
+
+fn resolve_platform_waypoints(
+    mut platforms: Query<(&mut MovingPlatform, &Transform, &mut Sprite), Added<MovingPlatform>>,
+) {
+    for (mut platform, transform, mut sprite) in &mut platforms {
+        let origin = transform.translation.truncate();
+        platform.waypoints = platform
+            .offsets
+            .iter()
+            .map(|offset| origin + *offset)
+            .collect();
+        platform.current = 0;
+
+        sprite.custom_size = Some(platform.size);
+        sprite.image_mode = SpriteImageMode::Tiled {
+            tile_x: true,
+            tile_y: true,
+            stretch_value: 1.0,
+        };
+    }
+}
+
+fn move_platforms(
+    time: Res<Time>,
+    mut platforms: Query<(&mut MovingPlatform, &Transform, &mut LinearVelocity)>,
+) {
+    for (mut platform, transform, mut velocity) in &mut platforms {
+        if platform.waypoints.len() < 2 {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+
+        let target = platform.waypoints[platform.current];
+        let to_target = target - transform.translation.truncate();
+        // Close enough that we'd overshoot this frame, so head for the next waypoint.
+        if to_target.length() <= platform.speed * time.delta_secs() {
+            platform.current = (platform.current + 1) % platform.waypoints.len();
+            continue;
+        }
+
+        velocity.0 = to_target.normalize_or_zero() * platform.speed;
+    }
+}
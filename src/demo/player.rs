@@ -1,23 +1,38 @@
 //! Player-specific behavior.
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, time::Duration};
 
-use avian2d::prelude::Mass;
+use avian2d::prelude::{AngularVelocity, CollisionEventsEnabled, ExternalImpulse, Mass};
 use bevy::{
     image::{ImageLoaderSettings, ImageSampler},
+    platform::collections::HashSet,
     prelude::*,
 };
 use bevy_ecs_ldtk::prelude::*;
 
 use crate::{
+    AppSystems, PausableSystems,
     asset_tracking::LoadResource,
-    demo::{animation::PlayerAnimation, movement::MovementController},
+    demo::{
+        animation::PlayerAnimation,
+        balistics::CasterCooldowns,
+        carry::Carrier,
+        health::{DamageEvent, Health},
+        level::LevelState,
+        movement::{
+            CrouchController, DashController, InheritedVelocity, JumpController,
+            MovementController, UprightSpring,
+        },
+        tint::Tints,
+        trajectory::Aiming,
+    },
+    settings::Settings,
 };
 
 use bevy_enhanced_input::prelude::*;
 
 use super::{
-    collision::HeroCollisionBundle,
+    collision::{CombatHit, HeroCollisionBundle},
     input::{ActionType, PlatformerContext},
 };
 
@@ -28,21 +43,82 @@ pub(super) fn plugin(app: &mut App) {
     app.register_ldtk_entity::<PlayerBundle>("Player");
     app.add_systems(
         Update,
-        post_process_player_bundle.run_if(resource_exists::<PlayerAssets>),
+        post_process_player_bundle
+            .run_if(resource_exists::<PlayerAssets>.and(in_state(LevelState::Ready))),
+    );
+    app.add_systems(
+        Update,
+        (tick_invulnerability, recover_from_ragdoll)
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        (
+            react_to_hits,
+            ragdoll_on_heavy_hits,
+            flash_invulnerable_sprites,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
     );
 }
 
+const PLAYER_MAX_HEALTH: f32 = 100.0;
+/// How long the player is invulnerable after taking a hit.
+const HIT_INVULNERABILITY_SECS: f32 = 1.0;
+/// Knockback impulse for a hit, before scaling by the damage dealt.
+const KNOCKBACK_BASE_IMPULSE: f32 = 3000.0;
+/// Extra knockback impulse per point of damage.
+const KNOCKBACK_IMPULSE_PER_DAMAGE: f32 = 100.0;
+/// Sprite blinks per second while invulnerable.
+const INVULNERABLE_FLASH_RATE: f32 = 12.0;
+const INVULNERABLE_FLASH_ALPHA: f32 = 0.3;
+/// Steady alpha while invulnerable, when flashes are turned off.
+const INVULNERABLE_STEADY_ALPHA: f32 = 0.6;
+/// A shockwave impulse at least this big sends the player tumbling.
+const RAGDOLL_MIN_IMPULSE: f32 = 20000.0;
+const RAGDOLL_SECS: f32 = 1.0;
+/// How fast a ragdolled player spins, in radians per second.
+const RAGDOLL_SPIN_SPEED: f32 = 12.0;
+
 #[derive(Bundle, Default, LdtkEntity)]
 pub struct PlayerBundle {
     a: Player,
+    pub index: PlayerIndex,
     pub actions: Actions<PlatformerContext>,
     pub sprite: Sprite,
     pub player_animation: PlayerAnimation,
     pub movement_controller: MovementController,
+    pub jump_controller: JumpController,
+    pub dash_controller: DashController,
+    pub crouch_controller: CrouchController,
+    pub upright_spring: UprightSpring,
+    pub inherited_velocity: InheritedVelocity,
     pub character_controller: CharacterController,
+    pub aiming: Aiming,
+    pub carrier: Carrier,
+    pub inventory: Inventory,
     pub collision_bundle: HeroCollisionBundle,
+    /// Avian only reports collisions for bodies that ask for them, and
+    /// `damage_player_on_impact` needs to hear about everything that hits the player.
+    pub collision_events: CollisionEventsEnabled,
     #[grid_coords]
     pub grid_coords: GridCoords,
+    /// Players belong to the world rather than a level so they survive walking into the next one.
+    #[worldly]
+    pub worldly: Worldly,
+}
+
+impl PlayerBundle {
+    /// A player that joins mid-game rather than being placed in LDtk.
+    pub fn joining(index: usize) -> Self {
+        Self {
+            index: PlayerIndex(index),
+            ..default()
+        }
+    }
 }
 
 fn post_process_player_bundle(
@@ -70,6 +146,9 @@ fn post_process_player_bundle(
             ..default()
         });
         commands.entity(entity).insert(Mass(30.));
+        commands
+            .entity(entity)
+            .insert(Health::new(PLAYER_MAX_HEALTH));
         commands.entity(entity).insert(player_animation.clone());
     }
 }
@@ -79,10 +158,128 @@ pub struct LightningState {
     pub timer: Timer,
 }
 
+/// Damage is ignored while this timer is running.
+#[derive(Component, Debug, Reflect)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// A player knocked senseless by a huge blast. They tumble freely and ignore
+/// input until the timer runs out, then their [`UprightSpring`] rights them.
+#[derive(Component, Debug, Reflect)]
+pub struct Ragdoll {
+    pub timer: Timer,
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, Option<&mut Sprite>)>,
+) {
+    for (entity, mut invulnerable, sprite) in &mut query {
+        invulnerable.timer.tick(time.delta());
+        if invulnerable.timer.finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            // Don't leave the sprite stuck half-transparent from the hit flash.
+            if let Some(mut sprite) = sprite {
+                sprite.color.set_alpha(1.0);
+            }
+        }
+    }
+}
+
+/// Knock the player back and grant i-frames when they take a hit.
+fn react_to_hits(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    player_query: Query<(), (With<Player>, Without<Invulnerable>)>,
+) {
+    let mut already_hit = HashSet::new();
+    for event in damage_events.read() {
+        let Some(direction) = event.knockback else {
+            continue;
+        };
+        if !player_query.contains(event.target) || !already_hit.insert(event.target) {
+            continue;
+        }
+
+        let mut entity = commands.entity(event.target);
+        entity.try_insert(Invulnerable::from_seconds(HIT_INVULNERABILITY_SECS));
+        if direction != Vec2::ZERO {
+            let strength = KNOCKBACK_BASE_IMPULSE + event.amount * KNOCKBACK_IMPULSE_PER_DAMAGE;
+            entity.try_insert(ExternalImpulse::new(direction * strength));
+        }
+    }
+}
+
+/// Send players tumbling when a shockwave hits them hard enough.
+fn ragdoll_on_heavy_hits(
+    mut commands: Commands,
+    mut hit_events: EventReader<CombatHit>,
+    mut player_query: Query<&mut AngularVelocity, (With<Player>, Without<Ragdoll>)>,
+) {
+    for hit in hit_events.read() {
+        if hit.impulse.length() < RAGDOLL_MIN_IMPULSE {
+            continue;
+        }
+        let Ok(mut angular_velocity) = player_query.get_mut(hit.target) else {
+            continue;
+        };
+        // Spin away from the blast.
+        angular_velocity.0 = -hit.impulse.x.signum() * RAGDOLL_SPIN_SPEED;
+        commands.entity(hit.target).try_insert(Ragdoll {
+            timer: Timer::from_seconds(RAGDOLL_SECS, TimerMode::Once),
+        });
+    }
+}
+
+fn recover_from_ragdoll(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Ragdoll)>,
+) {
+    for (entity, mut ragdoll) in &mut query {
+        ragdoll.timer.tick(time.delta());
+        if ragdoll.timer.finished() {
+            commands.entity(entity).remove::<Ragdoll>();
+        }
+    }
+}
+
+fn flash_invulnerable_sprites(
+    settings: Res<Settings>,
+    mut query: Query<(&Invulnerable, &mut Sprite), With<Player>>,
+) {
+    for (invulnerable, mut sprite) in &mut query {
+        let alpha = if !settings.flashes {
+            INVULNERABLE_STEADY_ALPHA
+        } else if (invulnerable.timer.elapsed_secs() * INVULNERABLE_FLASH_RATE) as u32 % 2 == 0 {
+            INVULNERABLE_FLASH_ALPHA
+        } else {
+            1.0
+        };
+        sprite.color.set_alpha(alpha);
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
+#[require(Tints)]
 pub struct Player;
 
+/// Which player this is in local co-op. The player placed in LDtk is player 0.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct PlayerIndex(pub usize);
+
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct PlayerAssets {
@@ -116,19 +313,58 @@ impl FromWorld for PlayerAssets {
     }
 }
 
+/// How long a pressed ability waits in the queue before it's dropped.
+const ACTION_BUFFER_WINDOW: Duration = Duration::from_millis(150);
+
+/// An action and when it was queued, in real time.
+#[derive(Debug, Clone, Copy)]
+pub struct QueuedAction {
+    pub action: ActionType,
+    pub queued_at: Duration,
+}
+
 #[derive(Component, Default, Debug, Clone)]
+#[require(CasterCooldowns)]
 pub struct CharacterController {
-    pub action_queue: VecDeque<ActionType>,
+    pub action_queue: VecDeque<QueuedAction>,
 }
 
 impl CharacterController {
-    /// Queue an action to be processed
-    pub fn queue_action(&mut self, action: ActionType) {
-        self.action_queue.push_back(action);
+    /// Queue an action to be processed, `now` being the real time it was pressed.
+    pub fn queue_action(&mut self, action: ActionType, now: Duration) {
+        self.action_queue.push_back(QueuedAction {
+            action,
+            queued_at: now,
+        });
     }
 
-    /// Pop the next action from the queue
-    pub fn pop_action(&mut self) -> Option<ActionType> {
-        self.action_queue.pop_front()
+    /// Take every action that's ready this frame.
+    ///
+    /// Player presses older than the buffering window are dropped. Distinct
+    /// actions pressed together are all taken, while repeats of the same one
+    /// wait for a later frame.
+    pub fn take_actions(&mut self, now: Duration) -> Vec<ActionType> {
+        self.action_queue.retain(|queued| {
+            !queued.action.is_buffered()
+                || now.saturating_sub(queued.queued_at) <= ACTION_BUFFER_WINDOW
+        });
+        let mut taken: Vec<ActionType> = Vec::new();
+        self.action_queue.retain(|queued| {
+            if taken
+                .iter()
+                .any(|action| action.same_source(&queued.action))
+            {
+                return true;
+            }
+            taken.push(queued.action);
+            false
+        });
+        taken
     }
 }
+
+/// Items the player is carrying, such as door keys.
+#[derive(Component, Default, Debug, Clone)]
+pub struct Inventory {
+    pub keys: HashSet<String>,
+}
@@ -0,0 +1,79 @@
+//! Entity pools for things that are spawned and despawned all the time.
+//!
+//! Fireballs, explosions, dust, debris, smoke and rain come and go many times
+//! a second. Instead of despawning them they're released into a [`Pool`],
+//! which disables them, and the next one is made by re-enabling a released
+//! entity with a fresh bundle.
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::entity_disabling::Disabled, prelude::*};
+
+/// Released entities kept around per pool. Any more are despawned as usual.
+const MAX_FREE_ENTITIES: usize = 64;
+
+/// A bundle whose entities can be recycled through a [`Pool`].
+pub trait Poolable: Bundle {
+    /// Undo anything added to the entity while it was in use, so it doesn't
+    /// carry over to the next time the entity is used.
+    fn reset(entity: &mut EntityCommands);
+}
+
+/// Disabled entities that were spawned with `B`, ready to be used again.
+#[derive(Resource)]
+pub struct Pool<B: Poolable> {
+    free: Vec<Entity>,
+    /// Entities spawned because there were none free.
+    pub spawned: u32,
+    /// Times a released entity was used again instead of spawning one.
+    pub reused: u32,
+    marker: PhantomData<fn() -> B>,
+}
+
+impl<B: Poolable> Default for Pool<B> {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            spawned: 0,
+            reused: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<B: Poolable> Pool<B> {
+    /// Re-enable a released entity with `bundle`, or spawn one if there are none.
+    pub fn acquire(&mut self, commands: &mut Commands, bundle: B) -> Entity {
+        let Some(entity) = self.free.pop() else {
+            self.spawned += 1;
+            return commands.spawn(bundle).id();
+        };
+        self.reused += 1;
+        commands
+            .entity(entity)
+            .try_remove::<Disabled>()
+            .try_insert(bundle);
+        entity
+    }
+
+    /// Disable `entity` so it can be used again, instead of despawning it.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        // Several systems can let go of the same entity in one frame.
+        if self.free.contains(&entity) {
+            return;
+        }
+        if self.free.len() >= MAX_FREE_ENTITIES {
+            commands.entity(entity).try_despawn();
+            return;
+        }
+        let mut entity_commands = commands.entity(entity);
+        B::reset(&mut entity_commands);
+        entity_commands.try_insert(Disabled);
+        self.free.push(entity);
+    }
+
+    /// How many released entities are waiting to be used again.
+    pub fn free(&self) -> usize {
+        self.free.len()
+    }
+}
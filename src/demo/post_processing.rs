@@ -0,0 +1,160 @@
+//! Optional post-processing, each effect toggled in the settings menu.
+//!
+//! Explosions and lightning are drawn brighter than white so they bloom,
+//! shockwaves send a chromatic aberration pulse across the screen, and the
+//! colors drain out while the game is held in hitstop.
+
+use bevy::{
+    core_pipeline::{bloom::Bloom, post_process::ChromaticAberration},
+    prelude::*,
+    render::view::ColorGrading,
+};
+
+use crate::{
+    AppSystems,
+    demo::{
+        animation::ExplosionAnimation, balistics::LightningBolt, camera::UiOnlyCamera,
+        collision::Shockwave, player::LightningState, time_scale::TimeScale,
+    },
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AberrationPulse>();
+    app.add_systems(
+        Update,
+        (
+            apply_post_processing_settings,
+            make_effects_glow,
+            pulse_chromatic_aberration,
+            desaturate_during_hitstop,
+        )
+            .chain()
+            .in_set(AppSystems::Update),
+    );
+}
+
+/// How much brighter than white glowing sprites are drawn.
+const GLOW_BRIGHTNESS: f32 = 4.0;
+/// Aberration intensity for a shockwave of [`PULSE_REFERENCE_RADIUS`].
+const PULSE_INTENSITY: f32 = 0.04;
+const PULSE_REFERENCE_RADIUS: f32 = 100.0;
+const MAX_PULSE_INTENSITY: f32 = 0.08;
+/// How long it takes a pulse to fade to half its intensity.
+const PULSE_HALF_LIFE_SECS: f32 = 0.08;
+/// Saturation during hitstop, 1 being normal.
+const HITSTOP_SATURATION: f32 = 0.15;
+/// How quickly the saturation changes, per second.
+const SATURATION_SPEED: f32 = 8.0;
+
+/// The current strength of the shockwave aberration pulse.
+#[derive(Resource, Debug, Default)]
+struct AberrationPulse {
+    intensity: f32,
+}
+
+/// Add or remove each effect from the world cameras to match the settings.
+fn apply_post_processing_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut camera_query: Query<
+        (Entity, &mut Camera, Has<Bloom>, Has<ChromaticAberration>),
+        (With<Camera2d>, Without<UiOnlyCamera>),
+    >,
+) {
+    for (entity, mut camera, has_bloom, has_aberration) in &mut camera_query {
+        // Colors brighter than white only survive in HDR, which bloom needs.
+        if camera.hdr != settings.bloom {
+            camera.hdr = settings.bloom;
+        }
+        if settings.bloom && !has_bloom {
+            commands.entity(entity).insert(Bloom::NATURAL);
+        } else if !settings.bloom && has_bloom {
+            commands.entity(entity).remove::<Bloom>();
+        }
+        if settings.chromatic_aberration && !has_aberration {
+            commands.entity(entity).insert(ChromaticAberration {
+                intensity: 0.0,
+                ..default()
+            });
+        } else if !settings.chromatic_aberration && has_aberration {
+            commands.entity(entity).remove::<ChromaticAberration>();
+        }
+    }
+}
+
+fn make_effects_glow(
+    settings: Res<Settings>,
+    mut explosion_query: Query<&mut Sprite, Added<ExplosionAnimation>>,
+    mut lightning_query: Query<
+        &mut Sprite,
+        (
+            Or<(With<LightningState>, With<LightningBolt>)>,
+            Without<ExplosionAnimation>,
+        ),
+    >,
+) {
+    if !settings.bloom {
+        return;
+    }
+    let glow = Color::LinearRgba(LinearRgba::rgb(
+        GLOW_BRIGHTNESS,
+        GLOW_BRIGHTNESS,
+        GLOW_BRIGHTNESS,
+    ));
+    for mut sprite in &mut explosion_query {
+        sprite.color = glow;
+    }
+    // Lightning mode swaps the player's sprite, so keep reapplying the glow.
+    for mut sprite in &mut lightning_query {
+        if sprite.color != glow {
+            sprite.color = glow;
+        }
+    }
+}
+
+fn pulse_chromatic_aberration(
+    // Real time so the pulse doesn't linger through slow motion.
+    time: Res<Time<Real>>,
+    mut pulse: ResMut<AberrationPulse>,
+    mut shockwave_events: EventReader<Shockwave>,
+    mut camera_query: Query<&mut ChromaticAberration>,
+) {
+    pulse.intensity *= 0.5_f32.powf(time.delta_secs() / PULSE_HALF_LIFE_SECS);
+    for shockwave in shockwave_events.read() {
+        let intensity = PULSE_INTENSITY * shockwave.radius / PULSE_REFERENCE_RADIUS;
+        pulse.intensity = pulse.intensity.max(intensity.min(MAX_PULSE_INTENSITY));
+    }
+    for mut aberration in &mut camera_query {
+        aberration.intensity = pulse.intensity;
+    }
+}
+
+fn desaturate_during_hitstop(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    time_scale: Res<TimeScale>,
+    mut camera_query: Query<
+        (Entity, Option<&mut ColorGrading>),
+        (With<Camera2d>, Without<UiOnlyCamera>),
+    >,
+) {
+    let target = if settings.hitstop_desaturation && time_scale.is_holding() {
+        HITSTOP_SATURATION
+    } else {
+        1.0
+    };
+    let max_step = SATURATION_SPEED * time.delta_secs();
+    for (entity, color_grading) in &mut camera_query {
+        let Some(mut color_grading) = color_grading else {
+            commands.entity(entity).insert(ColorGrading::default());
+            continue;
+        };
+        let saturation = color_grading.global.post_saturation;
+        if saturation != target {
+            color_grading.global.post_saturation =
+                saturation + (target - saturation).clamp(-max_step, max_step);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Progress that carries over between runs: which levels are unlocked, which
+//! abilities have been collected, skill upgrades and lifetime stats. It's
+//! saved automatically between levels, and save slots keep copies of it, see
+//! `persistence.rs`.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        attract::attract_mode_active,
+        balistics::AbilityKind,
+        castle::BlockDestroyed,
+        level::{LevelAssets, selected_level_identifier},
+        skills::SkillUpgrades,
+    },
+    persistence,
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(persistence::load::<Progress>(PROGRESS_KEY).unwrap_or_default());
+    // Play time ticks up every frame, so wait until the level is over to save.
+    app.add_systems(
+        Update,
+        save_progress.run_if(resource_changed::<Progress>.and(not(in_state(Screen::Gameplay)))),
+    );
+    // The attract mode demo doesn't count towards the player's stats.
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        count_run_started.run_if(not(attract_mode_active)),
+    );
+    app.add_systems(
+        OnEnter(Screen::LevelComplete),
+        unlock_next_level.run_if(not(attract_mode_active)),
+    );
+    app.add_systems(
+        Update,
+        (track_play_time, count_destroyed_blocks)
+            .run_if(in_state(Screen::Gameplay).and(not(attract_mode_active)))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+const PROGRESS_KEY: &str = "progress";
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Progress {
+    /// LDtk identifiers of levels that have been unlocked. The first level is always unlocked.
+    pub unlocked_levels: HashSet<String>,
+    /// Abilities whose runes have been picked up, so they stay unlocked in later runs.
+    pub collected_abilities: HashSet<AbilityKind>,
+    /// LDtk identifiers of levels that have been completed at least once.
+    pub completed_levels: HashSet<String>,
+    /// Skill points that haven't been spent on the skill tree yet.
+    pub skill_points: u32,
+    pub upgrades: SkillUpgrades,
+    pub stats: Stats,
+}
+
+impl Progress {
+    pub fn is_level_unlocked(&self, index: usize, identifier: &str) -> bool {
+        index == 0 || self.unlocked_levels.contains(identifier)
+    }
+}
+
+/// Lifetime totals across every run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Stats {
+    pub runs_started: u32,
+    pub levels_completed: u32,
+    pub blocks_destroyed: u32,
+    pub deaths: u32,
+    pub play_time_secs: f32,
+}
+
+fn save_progress(progress: Res<Progress>) {
+    // Skip the write triggered by inserting the resource on startup.
+    if progress.is_added() {
+        return;
+    }
+    persistence::save(PROGRESS_KEY, &*progress);
+}
+
+fn count_run_started(mut progress: ResMut<Progress>) {
+    progress.stats.runs_started += 1;
+}
+
+fn track_play_time(time: Res<Time>, mut progress: ResMut<Progress>) {
+    progress.stats.play_time_secs += time.delta_secs();
+}
+
+fn count_destroyed_blocks(
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    mut progress: ResMut<Progress>,
+) {
+    let destroyed = destroyed_events.read().count() as u32;
+    if destroyed > 0 {
+        progress.stats.blocks_destroyed += destroyed;
+    }
+}
+
+fn unlock_next_level(
+    mut progress: ResMut<Progress>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+) {
+    progress.stats.levels_completed += 1;
+
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some(current) = selected_level_identifier(&level_selection, project) else {
+        return;
+    };
+    let levels = &project.json_data().levels;
+    let next = levels
+        .iter()
+        .position(|level| level.identifier == current)
+        .and_then(|index| levels.get(index + 1));
+    if let Some(next) = next {
+        info!("Unlocked level {}", next.identifier);
+        progress.unlocked_levels.insert(next.identifier.clone());
+    }
+}
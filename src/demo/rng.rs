@@ -0,0 +1,119 @@
+//! The game's single source of randomness.
+//!
+//! Gameplay code draws from [`GameRng`] instead of `rand::thread_rng()`, so a
+//! run can be reproduced from its seed. A fresh seed is picked at the start of
+//! every run unless [`RngSeed::fixed`] is set, either from the `GAME_SEED`
+//! environment variable or through the inspector.
+//!
+//! Effects that don't change how a run plays out, like rain or smoke puffs,
+//! draw from [`CosmeticRng`] instead, so how many of them there are doesn't
+//! shift the gameplay rolls.
+
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::screens::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<RngSeed>();
+    app.insert_resource(RngSeed::from_env());
+    app.insert_resource(GameRng::from_seed(0));
+    app.insert_resource(CosmeticRng::from_seed(rand::random()));
+    app.add_systems(OnEnter(Screen::Gameplay), reseed_game_rng);
+}
+
+/// Which seed the next run should use.
+#[derive(Resource, Reflect, Debug, Clone, Default)]
+#[reflect(Resource)]
+pub struct RngSeed {
+    /// Use this seed for every run instead of a random one.
+    pub fixed: Option<u64>,
+}
+
+impl RngSeed {
+    #[cfg(not(target_family = "wasm"))]
+    fn from_env() -> Self {
+        let fixed = std::env::var("GAME_SEED")
+            .ok()
+            .and_then(|seed| seed.trim().parse().ok());
+        Self { fixed }
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn from_env() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct GameRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed this run started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Randomness for purely visual effects, never reseeded.
+#[derive(Resource, Debug, Clone)]
+pub struct CosmeticRng(ChaCha8Rng);
+
+impl CosmeticRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl RngCore for CosmeticRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+fn reseed_game_rng(seed: Res<RngSeed>, mut game_rng: ResMut<GameRng>) {
+    let seed = seed.fixed.unwrap_or_else(rand::random);
+    info!("Starting run with RNG seed {seed}");
+    *game_rng = GameRng::from_seed(seed);
+}
@@ -0,0 +1,128 @@
+//! Statistics for the current run, shown on the breakdown screen after a level.
+//!
+//! Everything is tallied from events the rest of the game already sends, so
+//! nothing needs to know it's being counted.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        balistics::{AbilityCast, AbilityKind},
+        castle::BlockDestroyed,
+        collision::CombatHit,
+        player::Player,
+        score::Score,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RunStats>();
+    app.add_systems(OnEnter(Screen::Gameplay), reset_run_stats);
+    app.add_systems(
+        Update,
+        (
+            count_destroyed_blocks,
+            track_largest_chain,
+            count_ability_casts,
+            tally_ability_hits,
+            track_distance_traveled,
+        )
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Length of each bar in the destruction timeline, in seconds.
+pub const TIMELINE_BUCKET_SECS: f32 = 5.0;
+/// Moving further than this in one frame is a respawn or teleport, not travel.
+const MAX_TRAVEL_PER_FRAME: f32 = 64.0;
+
+/// How much one ability was used and what it did.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AbilityStats {
+    pub casts: u32,
+    pub hits: u32,
+    pub damage: f32,
+}
+
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct RunStats {
+    pub blocks_destroyed: u32,
+    /// Most blocks destroyed in one chain of destruction.
+    pub largest_chain: u32,
+    pub abilities: HashMap<AbilityKind, AbilityStats>,
+    /// Distance the player covered, in world units.
+    pub distance_traveled: f32,
+    /// Blocks destroyed in each [`TIMELINE_BUCKET_SECS`] of the run.
+    pub destruction_timeline: Vec<u32>,
+    elapsed_secs: f32,
+}
+
+impl RunStats {
+    pub fn ability(&self, ability: AbilityKind) -> AbilityStats {
+        self.abilities.get(&ability).copied().unwrap_or_default()
+    }
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn count_destroyed_blocks(
+    time: Res<Time>,
+    mut stats: ResMut<RunStats>,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+) {
+    stats.elapsed_secs += time.delta_secs();
+    let bucket = (stats.elapsed_secs / TIMELINE_BUCKET_SECS) as usize;
+    if stats.destruction_timeline.len() <= bucket {
+        stats.destruction_timeline.resize(bucket + 1, 0);
+    }
+    let destroyed = destroyed_events.read().count() as u32;
+    stats.blocks_destroyed += destroyed;
+    stats.destruction_timeline[bucket] += destroyed;
+}
+
+fn track_largest_chain(score: Res<Score>, mut stats: ResMut<RunStats>) {
+    if score.chain > stats.largest_chain {
+        stats.largest_chain = score.chain;
+    }
+}
+
+fn count_ability_casts(mut stats: ResMut<RunStats>, mut cast_events: EventReader<AbilityCast>) {
+    for event in cast_events.read() {
+        stats.abilities.entry(event.ability).or_default().casts += 1;
+    }
+}
+
+fn tally_ability_hits(mut stats: ResMut<RunStats>, mut hit_events: EventReader<CombatHit>) {
+    for hit in hit_events.read() {
+        let Some(ability) = hit.ability else {
+            continue;
+        };
+        let ability_stats = stats.abilities.entry(ability).or_default();
+        ability_stats.hits += 1;
+        ability_stats.damage += hit.damage;
+    }
+}
+
+fn track_distance_traveled(
+    mut stats: ResMut<RunStats>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    mut last_positions: Local<HashMap<Entity, Vec2>>,
+) {
+    for (entity, transform) in &player_query {
+        let position = transform.translation().truncate();
+        if let Some(last) = last_positions.insert(entity, position) {
+            let traveled = last.distance(position);
+            if traveled <= MAX_TRAVEL_PER_FRAME {
+                stats.distance_traveled += traveled;
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! The player's score for the current run.
+//!
+//! Most points come from wrecking the castle: every [`BlockDestroyed`] is worth
+//! points based on the block's mass, multiplied by how long the current chain
+//! of destruction is, plus style bonuses for showing off. Cracking the mortar
+//! between blocks is worth a little on its own, and bringing down a whole
+//! castle section earns a bonus.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        castle::{BlockDestroyed, JointBroken},
+        collision::GroundDetection,
+        hud::{HudAnchor, HudRegion},
+        notifications::Notifications,
+        player::{LightningState, Player},
+        sections::SectionDestroyed,
+    },
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Score>();
+    app.init_resource::<Score>();
+    app.add_systems(OnEnter(Screen::Gameplay), (reset_score, spawn_score_ui));
+    app.add_systems(
+        Update,
+        (
+            tick_destruction_chain,
+            score_destroyed_blocks,
+            score_broken_joints,
+            score_destroyed_sections,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        update_score_ui.run_if(in_state(Screen::Gameplay).and(resource_changed::<Score>)),
+    );
+}
+
+/// Mass of a block worth one point.
+const MASS_PER_POINT: f32 = 10.0;
+/// Blocks destroyed within this many seconds of each other extend the chain.
+const CHAIN_WINDOW_SECS: f32 = 1.5;
+/// Extra multiplier for each block in the chain after the first.
+const CHAIN_MULTIPLIER_STEP: f32 = 0.5;
+const MAX_CHAIN_MULTIPLIER: f32 = 5.0;
+/// Points for each mortar joint broken, whether or not the block comes loose.
+const POINTS_PER_JOINT: u32 = 2;
+/// Bonus for destroying a whole castle section.
+const POINTS_PER_SECTION: u32 = 250;
+/// Bonus for a block destroyed while the player is a lightning bolt.
+const LIGHTNING_STYLE_BONUS: u32 = 50;
+/// Bonus for a block destroyed while the player is in the air.
+const AIRBORNE_STYLE_BONUS: u32 = 20;
+
+#[derive(Resource, Reflect, Debug, Default, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct Score {
+    pub points: u32,
+    /// Blocks destroyed in the current chain.
+    pub chain: u32,
+    /// Longest chain this run.
+    pub best_chain: u32,
+    /// Seconds left before the current chain ends.
+    chain_time_left: f32,
+}
+
+impl Score {
+    pub fn add(&mut self, points: u32) {
+        self.points += points;
+    }
+
+    /// Multiplier applied to block points for the current chain.
+    pub fn chain_multiplier(&self) -> f32 {
+        let extra_blocks = self.chain.saturating_sub(1) as f32;
+        (1.0 + extra_blocks * CHAIN_MULTIPLIER_STEP).min(MAX_CHAIN_MULTIPLIER)
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn tick_destruction_chain(time: Res<Time>, mut score: ResMut<Score>) {
+    if score.chain == 0 {
+        return;
+    }
+    score.chain_time_left -= time.delta_secs();
+    if score.chain_time_left <= 0.0 {
+        score.chain = 0;
+    }
+}
+
+fn score_destroyed_blocks(
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    mut score: ResMut<Score>,
+    player_query: Query<(Has<LightningState>, &GroundDetection), With<Player>>,
+) {
+    let (in_lightning, airborne) = player_query
+        .iter()
+        .next()
+        .map(|(in_lightning, ground)| (in_lightning, !ground.on_ground))
+        .unwrap_or_default();
+
+    for event in destroyed_events.read() {
+        score.chain += 1;
+        score.best_chain = score.best_chain.max(score.chain);
+        score.chain_time_left = CHAIN_WINDOW_SECS;
+
+        let base_points = (event.mass / MASS_PER_POINT).round().max(1.0);
+        let mut points = (base_points * score.chain_multiplier()) as u32;
+        if in_lightning {
+            points += LIGHTNING_STYLE_BONUS;
+        } else if airborne {
+            points += AIRBORNE_STYLE_BONUS;
+        }
+        score.add(points);
+    }
+}
+
+fn score_broken_joints(mut broken_events: EventReader<JointBroken>, mut score: ResMut<Score>) {
+    let broken_joints = broken_events.read().count() as u32;
+    if broken_joints > 0 {
+        score.add(broken_joints * POINTS_PER_JOINT);
+    }
+}
+
+fn score_destroyed_sections(
+    mut section_destroyed_events: EventReader<SectionDestroyed>,
+    mut score: ResMut<Score>,
+    mut notifications: ResMut<Notifications>,
+) {
+    for _ in section_destroyed_events.read() {
+        score.add(POINTS_PER_SECTION);
+        notifications.push(format!("Section destroyed +{POINTS_PER_SECTION}"));
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ScoreLabel;
+
+fn spawn_score_ui(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Score"),
+        Node::default(),
+        HudAnchor::new(HudRegion::TopRight, 0),
+        children![(widget::label("Score: 0"), ScoreLabel)],
+    ));
+}
+
+fn update_score_ui(score: Res<Score>, mut label: Single<&mut Text, With<ScoreLabel>>) {
+    label.0 = if score.chain > 1 {
+        format!(
+            "Score: {}  (x{:.1} chain)",
+            score.points,
+            score.chain_multiplier()
+        )
+    } else {
+        format!("Score: {}", score.points)
+    };
+}
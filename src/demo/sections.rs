@@ -0,0 +1,140 @@
+//! Track how much of each named castle section has been destroyed.
+//!
+//! Blocks are grouped by their [`CastleSection`] as they spawn. Once enough of
+//! a section's blocks are destroyed the whole section counts as destroyed,
+//! which is announced with a [`SectionDestroyed`] event for scoring, level
+//! objectives and an audio stinger.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    audio::sound_effect,
+    demo::{
+        balistics::SfxAssets,
+        castle::{BlockCleared, BlockDestroyed, CastleBlock, CastleSection},
+        level::LevelReloaded,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<SectionDestroyed>();
+    app.init_resource::<CastleSections>();
+    app.add_systems(OnEnter(Screen::Gameplay), reset_castle_sections);
+    app.add_systems(
+        Update,
+        (
+            reset_castle_sections.run_if(on_event::<LevelReloaded>),
+            register_section_blocks,
+            track_section_destruction,
+            forget_cleared_blocks,
+            play_section_stinger.run_if(resource_exists::<SfxAssets>),
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Fraction of a section's blocks that must be destroyed for the section to count as destroyed.
+const SECTION_DESTROYED_FRACTION: f32 = 0.8;
+
+/// Sent once when a castle section has been mostly destroyed.
+#[derive(Event, Debug, Clone)]
+pub struct SectionDestroyed {
+    pub section: CastleSection,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SectionStats {
+    pub total_blocks: u32,
+    pub destroyed_blocks: u32,
+    pub destroyed: bool,
+}
+
+impl SectionStats {
+    /// How much of the section has been destroyed, from 0 to 1.
+    pub fn destruction(&self) -> f32 {
+        if self.total_blocks == 0 {
+            return 0.0;
+        }
+        self.destroyed_blocks as f32 / self.total_blocks as f32
+    }
+}
+
+/// Every castle section in the level and how far along its destruction is.
+#[derive(Resource, Debug, Default)]
+pub struct CastleSections {
+    pub sections: HashMap<CastleSection, SectionStats>,
+    /// The section of each block that hasn't been destroyed yet.
+    blocks: HashMap<Entity, CastleSection>,
+}
+
+impl CastleSections {
+    /// Whether the level has sections and all of them have been destroyed.
+    pub fn all_destroyed(&self) -> bool {
+        !self.sections.is_empty() && self.sections.values().all(|stats| stats.destroyed)
+    }
+}
+
+fn reset_castle_sections(mut sections: ResMut<CastleSections>) {
+    *sections = CastleSections::default();
+}
+
+fn register_section_blocks(
+    mut sections: ResMut<CastleSections>,
+    block_query: Query<(Entity, &CastleSection), Added<CastleBlock>>,
+) {
+    for (block, section) in &block_query {
+        sections.blocks.insert(block, section.clone());
+        sections
+            .sections
+            .entry(section.clone())
+            .or_default()
+            .total_blocks += 1;
+    }
+}
+
+fn track_section_destruction(
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    mut sections: ResMut<CastleSections>,
+    mut section_destroyed_events: EventWriter<SectionDestroyed>,
+) {
+    for event in destroyed_events.read() {
+        // A block can be reported more than once, e.g. shattered after it was knocked loose.
+        let Some(section) = sections.blocks.remove(&event.block) else {
+            continue;
+        };
+        let Some(stats) = sections.sections.get_mut(&section) else {
+            continue;
+        };
+        stats.destroyed_blocks += 1;
+        if !stats.destroyed && stats.destruction() >= SECTION_DESTROYED_FRACTION {
+            stats.destroyed = true;
+            info!("Castle section {} destroyed", section.0);
+            section_destroyed_events.write(SectionDestroyed { section });
+        }
+    }
+}
+
+/// Stop tracking blocks that were cleared away rather than destroyed.
+fn forget_cleared_blocks(
+    mut cleared_events: EventReader<BlockCleared>,
+    mut sections: ResMut<CastleSections>,
+) {
+    for event in cleared_events.read() {
+        sections.blocks.remove(&event.block);
+    }
+}
+
+fn play_section_stinger(
+    mut commands: Commands,
+    sfx_assets: Res<SfxAssets>,
+    mut section_destroyed_events: EventReader<SectionDestroyed>,
+) {
+    // Several sections going at once still only get one stinger.
+    if section_destroyed_events.read().count() > 0 {
+        commands.spawn(sound_effect(sfx_assets.section_destroyed.clone()));
+    }
+}
@@ -0,0 +1,224 @@
+//! Skill points and the upgrades they buy.
+//!
+//! Completing a level for the first time earns a skill point, which can be
+//! spent on the skill tree screen to rank up an upgrade. Ranks are part of
+//! [`Progress`], so they carry over between runs and into save slots.
+//!
+//! Levels can also hand out an ability through their `UnlocksAbility` field,
+//! the same way picking up its rune would.
+
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    demo::{
+        attract::attract_mode_active,
+        balistics::{
+            AbilityKind, CHARGE_COOLDOWN_SECS, ChargeCooldown, FIREBALL_COOLDOWN_SECS,
+            FROST_COOLDOWN_SECS, Fireball, FireballCooldown, FrostCooldown,
+            LIGHTNING_COOLDOWN_SECS, LightningCooldown,
+        },
+        difficulty::DifficultyTuning,
+        level::LevelAssets,
+        progress::Progress,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Gameplay), apply_cooldown_multipliers);
+    app.add_systems(
+        OnEnter(Screen::LevelComplete),
+        reward_level_completion.run_if(not(attract_mode_active)),
+    );
+    app.add_systems(
+        Update,
+        give_fireballs_ricochets.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Skill points earned the first time a level is completed.
+const SKILL_POINTS_PER_LEVEL: u32 = 1;
+/// Highest rank any upgrade can reach.
+pub const MAX_RANK: u32 = 3;
+/// How much bigger explosions get per rank of [`SkillUpgrade::BlastRadius`].
+const BLAST_RADIUS_PER_RANK: f32 = 0.15;
+/// How much shorter cooldowns get per rank of [`SkillUpgrade::QuickCasting`].
+const COOLDOWN_REDUCTION_PER_RANK: f32 = 0.15;
+/// How bouncy fireballs are once they can ricochet.
+const RICOCHET_RESTITUTION: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkillUpgrade {
+    BlastRadius,
+    QuickCasting,
+    Ricochet,
+}
+
+impl SkillUpgrade {
+    pub const ALL: [SkillUpgrade; 3] = [
+        SkillUpgrade::BlastRadius,
+        SkillUpgrade::QuickCasting,
+        SkillUpgrade::Ricochet,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkillUpgrade::BlastRadius => "Blast radius",
+            SkillUpgrade::QuickCasting => "Quick casting",
+            SkillUpgrade::Ricochet => "Ricochet",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SkillUpgrade::BlastRadius => "Fireball explosions reach further",
+            SkillUpgrade::QuickCasting => "Every ability recharges faster",
+            SkillUpgrade::Ricochet => "Fireballs bounce off walls before exploding",
+        }
+    }
+}
+
+/// The rank bought in each upgrade.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct SkillUpgrades {
+    pub blast_radius: u32,
+    pub quick_casting: u32,
+    pub ricochet: u32,
+}
+
+impl SkillUpgrades {
+    pub fn rank(&self, upgrade: SkillUpgrade) -> u32 {
+        match upgrade {
+            SkillUpgrade::BlastRadius => self.blast_radius,
+            SkillUpgrade::QuickCasting => self.quick_casting,
+            SkillUpgrade::Ricochet => self.ricochet,
+        }
+    }
+
+    fn rank_mut(&mut self, upgrade: SkillUpgrade) -> &mut u32 {
+        match upgrade {
+            SkillUpgrade::BlastRadius => &mut self.blast_radius,
+            SkillUpgrade::QuickCasting => &mut self.quick_casting,
+            SkillUpgrade::Ricochet => &mut self.ricochet,
+        }
+    }
+
+    /// How much bigger explosions are than normal.
+    pub fn blast_radius_multiplier(&self) -> f32 {
+        1.0 + self.blast_radius as f32 * BLAST_RADIUS_PER_RANK
+    }
+
+    /// How long cooldowns are compared to normal.
+    pub fn cooldown_multiplier(&self) -> f32 {
+        1.0 - self.quick_casting as f32 * COOLDOWN_REDUCTION_PER_RANK
+    }
+}
+
+impl Progress {
+    /// Spend a skill point on `upgrade`. Returns false if there are no points or it's maxed out.
+    pub fn buy_upgrade(&mut self, upgrade: SkillUpgrade) -> bool {
+        if self.skill_points == 0 || self.upgrades.rank(upgrade) >= MAX_RANK {
+            return false;
+        }
+        self.skill_points -= 1;
+        *self.upgrades.rank_mut(upgrade) += 1;
+        info!(
+            "Upgraded {} to rank {}",
+            upgrade.name(),
+            self.upgrades.rank(upgrade)
+        );
+        true
+    }
+}
+
+/// How many more times a fireball bounces before it explodes on contact.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Ricochets {
+    pub remaining: u32,
+    /// Whether the fireball was touching something last frame, so one bounce isn't counted twice.
+    pub touching: bool,
+}
+
+/// Set cooldowns from the base lengths, upgrades and difficulty.
+fn apply_cooldown_multipliers(
+    progress: Res<Progress>,
+    difficulty: DifficultyTuning,
+    mut fireball_cooldown: ResMut<FireballCooldown>,
+    mut frost_cooldown: ResMut<FrostCooldown>,
+    mut lightning_cooldown: ResMut<LightningCooldown>,
+    mut charge_cooldown: ResMut<ChargeCooldown>,
+) {
+    let multiplier = progress.upgrades.cooldown_multiplier() * difficulty.multipliers().cooldown;
+    for (timer, base_secs) in [
+        (&mut fireball_cooldown.timer, FIREBALL_COOLDOWN_SECS),
+        (&mut frost_cooldown.timer, FROST_COOLDOWN_SECS),
+        (&mut lightning_cooldown.timer, LIGHTNING_COOLDOWN_SECS),
+        (&mut charge_cooldown.timer, CHARGE_COOLDOWN_SECS),
+    ] {
+        timer.set_duration(Duration::from_secs_f32(base_secs * multiplier));
+    }
+}
+
+fn give_fireballs_ricochets(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    fireball_query: Query<Entity, Added<Fireball>>,
+) {
+    let remaining = progress.upgrades.ricochet;
+    if remaining == 0 {
+        return;
+    }
+    for fireball in &fireball_query {
+        commands.entity(fireball).insert((
+            Ricochets {
+                remaining,
+                touching: false,
+            },
+            Restitution::new(RICOCHET_RESTITUTION),
+        ));
+    }
+}
+
+/// Hand out a skill point for a first completion, and the level's ability if it has one.
+fn reward_level_completion(
+    mut progress: ResMut<Progress>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some((_, level)) = project
+        .json_data()
+        .levels
+        .iter()
+        .enumerate()
+        .find(|(index, level)| level_selection.is_match(&LevelIndices::in_root(*index), level))
+    else {
+        return;
+    };
+    if progress.completed_levels.insert(level.identifier.clone()) {
+        progress.skill_points += SKILL_POINTS_PER_LEVEL;
+        info!(
+            "First completion of {}, earned a skill point",
+            level.identifier
+        );
+    }
+    if let Ok(Some(name)) = level.get_maybe_string_field("UnlocksAbility") {
+        match AbilityKind::from_name(name) {
+            Some(ability) => {
+                if progress.collected_abilities.insert(ability) {
+                    info!("Unlocked ability {ability:?} by completing the level");
+                }
+            }
+            None => warn!("Level unlocks unknown ability {name}"),
+        }
+    }
+}
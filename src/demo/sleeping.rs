@@ -0,0 +1,115 @@
+//! Let settled bodies sleep, tuned from `physics.ron`.
+//!
+//! Avian stops simulating bodies that have been at rest for a while. Castle
+//! blocks that have settled would otherwise keep being solved every frame, so
+//! the thresholds are tuned for them, and a shockwave wakes just the sleeping
+//! blocks within its reach.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    asset_tracking::{LoadResource, LoadRonAsset},
+    demo::{castle::CastleBlock, collision::Shockwave},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<SleepConfig>(&["physics.ron"]);
+    app.load_resource::<SleepConfigAssets>();
+    app.add_systems(
+        Update,
+        (
+            apply_sleep_config.run_if(on_event::<AssetEvent<SleepConfig>>),
+            wake_blocks_in_shockwaves,
+        ),
+    );
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SleepConfig {
+    /// Speed below which a body counts as at rest, in pixels per second.
+    pub linear_threshold: f32,
+    /// Angular speed below which a body counts as at rest, in radians per second.
+    pub angular_threshold: f32,
+    /// Seconds a body has to stay at rest before it falls asleep.
+    pub time_to_sleep: f32,
+    /// How far past a shockwave's radius sleeping castle blocks are woken.
+    pub wake_margin: f32,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            linear_threshold: 4.0,
+            angular_threshold: 0.5,
+            time_to_sleep: 0.5,
+            wake_margin: 16.0,
+        }
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct SleepConfigAssets {
+    #[dependency]
+    pub config: Handle<SleepConfig>,
+}
+
+impl FromWorld for SleepConfigAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            config: assets.load("physics.ron"),
+        }
+    }
+}
+
+impl SleepConfigAssets {
+    /// The loaded config, or the defaults until it has loaded.
+    fn get(handles: Option<&Self>, configs: &Assets<SleepConfig>) -> SleepConfig {
+        handles
+            .and_then(|handles| configs.get(&handles.config))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Hand the thresholds to avian whenever the config is loaded or edited.
+fn apply_sleep_config(
+    handles: Option<Res<SleepConfigAssets>>,
+    configs: Res<Assets<SleepConfig>>,
+    mut threshold: ResMut<SleepingThreshold>,
+    mut deactivation_time: ResMut<DeactivationTime>,
+) {
+    let config = SleepConfigAssets::get(handles.as_deref(), &configs);
+    threshold.linear = config.linear_threshold;
+    threshold.angular = config.angular_threshold;
+    deactivation_time.0 = config.time_to_sleep;
+}
+
+fn wake_blocks_in_shockwaves(
+    mut commands: Commands,
+    handles: Option<Res<SleepConfigAssets>>,
+    configs: Res<Assets<SleepConfig>>,
+    mut shockwave_events: EventReader<Shockwave>,
+    sleeping_query: Query<(Entity, &GlobalTransform), (With<CastleBlock>, With<Sleeping>)>,
+) {
+    let wake_margin = SleepConfigAssets::get(handles.as_deref(), &configs).wake_margin;
+    for shockwave in shockwave_events.read() {
+        let reach = shockwave.radius + wake_margin;
+        for (block, transform) in &sleeping_query {
+            if transform
+                .translation()
+                .truncate()
+                .distance(shockwave.origin)
+                <= reach
+            {
+                commands
+                    .entity(block)
+                    .try_remove::<Sleeping>()
+                    .try_insert(TimeSleeping(0.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Smoke from explosions that go off near a fire.
+//!
+//! An explosion close to a [`Burning`] block throws up a cloud of smoke that
+//! slowly drifts upwards and thins out. Each cloud is a stack of overlapping
+//! translucent puffs drawn in front of the level, so the thicker the middle,
+//! the less shows through it. Puffs are recycled through a [`Pool`]. Clouds also block sight lines: anything that
+//! needs to know whether one point can see another should ask [`Smoke`], which
+//! makes hiding in the smoke a way past watchful eyes, like the boulder
+//! throwers' in endless mode.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        collision::Detonation,
+        elements::Burning,
+        pool::{Pool, Poolable},
+        rng::CosmeticRng,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SmokeCloud>();
+    app.init_resource::<Pool<SmokePuffBundle>>();
+    app.add_systems(
+        Update,
+        (spawn_smoke_clouds, drift_smoke_clouds)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// An explosion this close to a burning block makes smoke.
+const SMOKE_TRIGGER_RADIUS: f32 = 64.0;
+const SMOKE_SECS: f32 = 6.0;
+/// Radius of the part of a cloud that blocks sight lines.
+const SMOKE_RADIUS: f32 = 40.0;
+const SMOKE_PUFFS: usize = 6;
+const SMOKE_PUFF_SIZE: f32 = 48.0;
+const SMOKE_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.45);
+/// How fast clouds rise, in world units per second.
+const SMOKE_DRIFT_SPEED: f32 = 12.0;
+/// Drawn in front of everything else in the level, like steam.
+const SMOKE_Z: f32 = 50.0;
+
+/// A cloud of smoke that hides what's behind it until it thins out.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct SmokeCloud {
+    timer: Timer,
+}
+
+/// One of the translucent sprites a smoke cloud is built from.
+#[derive(Component, Debug)]
+struct SmokePuff;
+
+#[derive(Bundle)]
+struct SmokePuffBundle {
+    puff: SmokePuff,
+    sprite: Sprite,
+    transform: Transform,
+    child_of: ChildOf,
+}
+
+impl Poolable for SmokePuffBundle {
+    fn reset(entity: &mut EntityCommands) {
+        // Let go of the cloud so despawning it doesn't take the puff with it.
+        entity.try_remove::<ChildOf>();
+    }
+}
+
+/// The smoke clouds in the level, for checking sight lines through them.
+#[derive(SystemParam)]
+pub struct Smoke<'w, 's> {
+    clouds: Query<'w, 's, &'static GlobalTransform, With<SmokeCloud>>,
+}
+
+impl Smoke<'_, '_> {
+    /// Whether smoke hides `to` from someone looking from `from`.
+    pub fn blocks_line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        self.clouds.iter().any(|transform| {
+            let center = transform.translation().truncate();
+            distance_to_segment(center, from, to) < SMOKE_RADIUS
+        })
+    }
+}
+
+/// How far `point` is from the closest point on the segment between `start` and `end`.
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+fn spawn_smoke_clouds(
+    mut commands: Commands,
+    mut detonation_events: EventReader<Detonation>,
+    burning_query: Query<&GlobalTransform, With<Burning>>,
+    mut rng: ResMut<CosmeticRng>,
+    mut pool: ResMut<Pool<SmokePuffBundle>>,
+) {
+    for detonation in detonation_events.read() {
+        let position = detonation.transform.translation.truncate();
+        let near_fire = burning_query.iter().any(|transform| {
+            transform.translation().truncate().distance(position) < SMOKE_TRIGGER_RADIUS
+        });
+        if !near_fire {
+            continue;
+        }
+        let cloud = commands
+            .spawn((
+                Name::new("Smoke Cloud"),
+                SmokeCloud {
+                    timer: Timer::from_seconds(SMOKE_SECS, TimerMode::Once),
+                },
+                Transform::from_translation(position.extend(SMOKE_Z)),
+                Visibility::default(),
+                StateScoped(Screen::Gameplay),
+            ))
+            .id();
+        for _ in 0..SMOKE_PUFFS {
+            let offset = Vec2::new(
+                rng.gen_range(-SMOKE_RADIUS..SMOKE_RADIUS),
+                rng.gen_range(-SMOKE_RADIUS..SMOKE_RADIUS),
+            ) / 2.0;
+            pool.acquire(
+                &mut commands,
+                SmokePuffBundle {
+                    puff: SmokePuff,
+                    sprite: Sprite::from_color(SMOKE_COLOR, Vec2::splat(SMOKE_PUFF_SIZE)),
+                    transform: Transform::from_translation(offset.extend(0.0)),
+                    child_of: ChildOf(cloud),
+                },
+            );
+        }
+    }
+}
+
+/// Float clouds upwards and thin them out, removing them once they're gone.
+fn drift_smoke_clouds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<Pool<SmokePuffBundle>>,
+    mut cloud_query: Query<(Entity, &mut SmokeCloud, &mut Transform, &Children)>,
+    mut puff_query: Query<&mut Sprite, With<SmokePuff>>,
+) {
+    for (entity, mut cloud, mut transform, children) in &mut cloud_query {
+        cloud.timer.tick(time.delta());
+        if cloud.timer.finished() {
+            for &puff in children {
+                pool.release(&mut commands, puff);
+            }
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += SMOKE_DRIFT_SPEED * time.delta_secs();
+        let alpha = SMOKE_COLOR.alpha() * cloud.timer.fraction_remaining();
+        let mut puffs = puff_query.iter_many_mut(children);
+        while let Some(mut sprite) = puffs.fetch_next() {
+            sprite.color.set_alpha(alpha);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Conditions on LDtk entities that decide whether they spawn at all.
+//!
+//! Any LDtk entity can have these optional fields, so one map can serve
+//! several difficulties and randomized castle layouts:
+//! - `SpawnOnDifficulty`: comma separated difficulties it appears on, e.g. `Normal, Hard`.
+//! - `RequiresUnlock`: an ability (`Fireball`, `Frost` or `Lightning`) that must be unlocked.
+//! - `Probability`: the chance from 0 to 1 that it appears, rolled with [`GameRng`].
+//!
+//! Entities that fail a condition are despawned before any gameplay system sees them.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use rand::Rng;
+
+use crate::demo::{
+    balistics::{AbilityKind, UnlockedAbilities},
+    difficulty::Difficulty,
+    rng::GameRng,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        despawn_unmet_spawn_conditions.after(LdtkSystemSet::ProcessApi),
+    );
+}
+
+fn despawn_unmet_spawn_conditions(
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    unlocked: Res<UnlockedAbilities>,
+    mut rng: ResMut<GameRng>,
+    query: Query<(Entity, &EntityInstance), Added<EntityInstance>>,
+) {
+    for (entity, entity_instance) in &query {
+        if !meets_spawn_conditions(entity_instance, *difficulty, &unlocked, &mut rng) {
+            debug!(
+                "Skipping LDtk entity {} whose spawn conditions aren't met",
+                entity_instance.identifier
+            );
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn meets_spawn_conditions(
+    entity_instance: &EntityInstance,
+    difficulty: Difficulty,
+    unlocked: &UnlockedAbilities,
+    rng: &mut GameRng,
+) -> bool {
+    if let Ok(difficulties) = entity_instance.get_string_field("SpawnOnDifficulty") {
+        let allowed = difficulties
+            .split(',')
+            .filter_map(Difficulty::from_name)
+            .any(|allowed| allowed == difficulty);
+        if !allowed {
+            return false;
+        }
+    }
+
+    if let Ok(ability) = entity_instance.get_string_field("RequiresUnlock") {
+        let parsed = AbilityKind::from_name(ability);
+        if parsed.is_none() {
+            warn!("Unknown ability `{ability}` in RequiresUnlock");
+        }
+        if parsed.is_some_and(|ability| !unlocked.is_unlocked(ability)) {
+            return false;
+        }
+    }
+
+    // Roll last, and only when needed, so the rolls don't depend on entities that were already skipped.
+    let probability = entity_instance
+        .get_float_field("Probability")
+        .map_or(1.0, |probability| probability.clamp(0.0, 1.0));
+    probability >= 1.0 || rng.gen_bool(probability as f64)
+}
@@ -0,0 +1,218 @@
+//! A spectator camera for recording runs, toggled with F9.
+//!
+//! Instead of following the player, the camera frames wherever the castle is
+//! coming down: recent explosions, broken mortar and falling blocks. The
+//! more intense the destruction, the tighter it closes in, and once things
+//! calm down it pulls back out to the whole castle.
+
+use avian2d::prelude::*;
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    demo::{
+        animation::ExplosionAnimation,
+        camera::{PlayerCamera, SpectatorCamera, UiOnlyCamera},
+        castle::{CastleBlock, JointBroken},
+        music::DestructionIntensity,
+        player::{Player, PlayerIndex},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Hotspots>();
+    app.add_systems(OnEnter(Screen::Gameplay), reset_hotspots);
+    app.add_systems(
+        Update,
+        toggle_spectator.run_if(in_state(Screen::Gameplay).and(input_just_pressed(SPECTATOR_KEY))),
+    );
+    app.add_systems(
+        Update,
+        (record_hotspots, direct_spectator_camera)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), stop_spectating);
+}
+
+const SPECTATOR_KEY: KeyCode = KeyCode::F9;
+const EXPLOSION_INTEREST: f32 = 1.0;
+const JOINT_BREAK_INTEREST: f32 = 0.3;
+/// Interest of each falling block, for as long as it's falling.
+const FALLING_BLOCK_INTEREST: f32 = 0.1;
+/// Castle blocks moving faster than this count as falling.
+const FALLING_SPEED: f32 = 80.0;
+/// How long it takes a hotspot to become half as interesting.
+const HOTSPOT_HALF_LIFE_SECS: f32 = 0.75;
+/// Hotspots less interesting than this are forgotten.
+const MIN_HOTSPOT_INTEREST: f32 = 0.02;
+/// Below this much total interest the camera shows the whole castle instead.
+const MIN_TOTAL_INTEREST: f32 = 0.5;
+/// Space left around the framed action, shrinking as the destruction gets more intense.
+const CALM_MARGIN: f32 = 160.0;
+const INTENSE_MARGIN: f32 = 48.0;
+/// The smallest area the camera closes in on.
+const MIN_FRAME_SIZE: Vec2 = Vec2::new(240.0, 135.0);
+const MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 2.0;
+/// How quickly the camera catches up with what it's framing, per second.
+const DIRECTOR_SMOOTHING: f32 = 2.5;
+
+#[derive(Debug, Clone, Copy)]
+struct Hotspot {
+    position: Vec2,
+    interest: f32,
+}
+
+/// Where destruction has happened recently, fading away over time.
+#[derive(Resource, Debug, Default)]
+struct Hotspots(Vec<Hotspot>);
+
+fn reset_hotspots(mut hotspots: ResMut<Hotspots>) {
+    hotspots.0.clear();
+}
+
+fn toggle_spectator(
+    mut commands: Commands,
+    mut camera_query: Query<
+        (Entity, &mut Projection, Has<SpectatorCamera>),
+        (With<Camera2d>, Without<PlayerCamera>, Without<UiOnlyCamera>),
+    >,
+) {
+    let Ok((camera, mut projection, spectating)) = camera_query.single_mut() else {
+        return;
+    };
+    if spectating {
+        // Snapping takes back over and restores the level's framing.
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.scale = 1.0;
+        }
+        commands.entity(camera).remove::<SpectatorCamera>();
+        info!("Spectator camera off");
+    } else {
+        commands.entity(camera).insert(SpectatorCamera);
+        info!("Spectator camera on");
+    }
+}
+
+fn stop_spectating(
+    mut commands: Commands,
+    mut camera_query: Query<(Entity, &mut Projection), With<SpectatorCamera>>,
+) {
+    for (camera, mut projection) in &mut camera_query {
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.scale = 1.0;
+        }
+        commands.entity(camera).remove::<SpectatorCamera>();
+    }
+}
+
+fn record_hotspots(
+    time: Res<Time>,
+    mut hotspots: ResMut<Hotspots>,
+    mut broken_events: EventReader<JointBroken>,
+    explosions: Query<&GlobalTransform, Added<ExplosionAnimation>>,
+) {
+    let decay = 0.5_f32.powf(time.delta_secs() / HOTSPOT_HALF_LIFE_SECS);
+    for hotspot in &mut hotspots.0 {
+        hotspot.interest *= decay;
+    }
+    hotspots
+        .0
+        .retain(|hotspot| hotspot.interest >= MIN_HOTSPOT_INTEREST);
+
+    hotspots.0.extend(broken_events.read().map(|event| Hotspot {
+        position: event.position,
+        interest: JOINT_BREAK_INTEREST,
+    }));
+    hotspots
+        .0
+        .extend(explosions.iter().map(|transform| Hotspot {
+            position: transform.translation().truncate(),
+            interest: EXPLOSION_INTEREST,
+        }));
+}
+
+fn direct_spectator_camera(
+    time: Res<Time>,
+    hotspots: Res<Hotspots>,
+    intensity: Res<DestructionIntensity>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<SpectatorCamera>>,
+    block_query: Query<(&GlobalTransform, &LinearVelocity), With<CastleBlock>>,
+    player_query: Query<(&GlobalTransform, &PlayerIndex), With<Player>>,
+) {
+    let Ok((mut transform, mut projection)) = camera_query.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(orthographic) = &mut *projection else {
+        return;
+    };
+
+    let falling = block_query
+        .iter()
+        .filter(|(_, velocity)| velocity.length() > FALLING_SPEED)
+        .map(|(transform, _)| Hotspot {
+            position: transform.translation().truncate(),
+            interest: FALLING_BLOCK_INTEREST,
+        });
+    let interesting: Vec<Hotspot> = hotspots.0.iter().copied().chain(falling).collect();
+    let total_interest: f32 = interesting.iter().map(|hotspot| hotspot.interest).sum();
+
+    let frame = if total_interest >= MIN_TOTAL_INTEREST {
+        // Center on the action, weighted by how interesting each part of it is.
+        let center = interesting
+            .iter()
+            .map(|hotspot| hotspot.position * hotspot.interest)
+            .sum::<Vec2>()
+            / total_interest;
+        let bounds = interesting.iter().fold(
+            Rect::from_center_size(center, Vec2::ZERO),
+            |bounds, hotspot| bounds.union_point(hotspot.position),
+        );
+        let margin = CALM_MARGIN.lerp(INTENSE_MARGIN, intensity.level.clamp(0.0, 1.0));
+        Rect::from_center_size(center, bounds.size() + 2.0 * margin)
+    } else {
+        // Nothing much is happening, show the whole castle, or the player if it's gone.
+        let castle = block_query
+            .iter()
+            .map(|(transform, _)| transform.translation().truncate())
+            .fold(None, |bounds: Option<Rect>, position| {
+                Some(
+                    bounds.map_or(Rect::from_center_size(position, Vec2::ZERO), |bounds| {
+                        bounds.union_point(position)
+                    }),
+                )
+            });
+        let Some(bounds) = castle.or_else(|| {
+            player_query
+                .iter()
+                .find(|(_, index)| index.0 == 0)
+                .map(|(transform, _)| {
+                    Rect::from_center_size(transform.translation().truncate(), Vec2::ZERO)
+                })
+        }) else {
+            return;
+        };
+        Rect::from_center_size(bounds.center(), bounds.size() + 2.0 * CALM_MARGIN)
+    };
+
+    // The view at a scale of 1, as set up by the level's framing.
+    let base_size = orthographic.area.size() / orthographic.scale;
+    if base_size.min_element() <= 0.0 {
+        return;
+    }
+    let frame_size = frame.size().max(MIN_FRAME_SIZE);
+    let target_scale = (frame_size / base_size)
+        .max_element()
+        .clamp(MIN_SCALE, MAX_SCALE);
+
+    let t = (DIRECTOR_SMOOTHING * time.delta_secs()).min(1.0);
+    orthographic.scale = orthographic.scale.lerp(target_scale, t);
+    // The camera's origin is the bottom left corner of the view.
+    let view_size = base_size * orthographic.scale;
+    let corner = transform
+        .translation
+        .truncate()
+        .lerp(frame.center() - view_size / 2.0, t);
+    transform.translation = corner.extend(transform.translation.z);
+}
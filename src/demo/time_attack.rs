@@ -0,0 +1,152 @@
+//! Time attack mode: race the clock for a medal on each level.
+//!
+//! Par times for every level live in `levels/level.par.ron`. Finishing at or
+//! under a par time earns its medal, and the level complete screen compares
+//! the run against the best time saved for the level.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    asset_tracking::{LoadResource, LoadRonAsset},
+    demo::{
+        difficulty::DifficultyTuning,
+        game_mode::{GameMode, game_mode_is},
+        hud::{HudAnchor, HudRegion},
+        level::{LevelAssets, selected_level_identifier},
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_ron_asset::<ParTimes>(&["par.ron"]);
+    app.load_resource::<TimeAttackAssets>();
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        spawn_par_time_ui.run_if(game_mode_is(GameMode::TimeAttack)),
+    );
+}
+
+/// The times needed for each medal on one level, in seconds.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelParTimes {
+    pub gold: f32,
+    pub silver: f32,
+    pub bronze: f32,
+}
+
+impl LevelParTimes {
+    /// Every par time multiplied by `multiplier`, e.g. for the difficulty.
+    pub fn scaled(self, multiplier: f32) -> Self {
+        Self {
+            gold: self.gold * multiplier,
+            silver: self.silver * multiplier,
+            bronze: self.bronze * multiplier,
+        }
+    }
+
+    /// The best medal earned by finishing in `time` seconds, if any.
+    pub fn medal(&self, time: f32) -> Option<Medal> {
+        if time <= self.gold {
+            Some(Medal::Gold)
+        } else if time <= self.silver {
+            Some(Medal::Silver)
+        } else if time <= self.bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Medal {
+    Gold,
+    Silver,
+    Bronze,
+}
+
+impl Medal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Medal::Gold => "Gold",
+            Medal::Silver => "Silver",
+            Medal::Bronze => "Bronze",
+        }
+    }
+}
+
+/// Par times for every level, keyed by LDtk level identifier.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct ParTimes {
+    pub levels: HashMap<String, LevelParTimes>,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct TimeAttackAssets {
+    #[dependency]
+    pub par_times: Handle<ParTimes>,
+}
+
+impl FromWorld for TimeAttackAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            par_times: assets.load("levels/level.par.ron"),
+        }
+    }
+}
+
+/// The par times for the selected level at the current difficulty, if it has any.
+pub fn selected_level_par_times(
+    level_selection: &LevelSelection,
+    level_assets: &LevelAssets,
+    projects: &Assets<LdtkProject>,
+    time_attack_assets: &TimeAttackAssets,
+    par_times: &Assets<ParTimes>,
+    difficulty: &DifficultyTuning,
+) -> Option<LevelParTimes> {
+    let project = projects.get(&level_assets.ldtk_level.handle)?;
+    let level = selected_level_identifier(level_selection, project)?;
+    let par = par_times
+        .get(&time_attack_assets.par_times)?
+        .levels
+        .get(&level)
+        .copied()?;
+    Some(par.scaled(difficulty.multipliers().par_time))
+}
+
+fn spawn_par_time_ui(
+    mut commands: Commands,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    time_attack_assets: Res<TimeAttackAssets>,
+    par_times: Res<Assets<ParTimes>>,
+    difficulty: DifficultyTuning,
+) {
+    let Some(par) = selected_level_par_times(
+        &level_selection,
+        &level_assets,
+        &projects,
+        &time_attack_assets,
+        &par_times,
+        &difficulty,
+    ) else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Par Time Display"),
+        Node::default(),
+        HudAnchor::new(HudRegion::TopCenter, 1),
+        children![widget::label(format!(
+            "Gold {:.0}s  Silver {:.0}s  Bronze {:.0}s",
+            par.gold, par.silver, par.bronze
+        ))],
+    ));
+}
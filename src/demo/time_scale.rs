@@ -0,0 +1,163 @@
+//! Slow motion for big moments.
+//!
+//! When a large chunk of the castle collapses at once, or the player turns into
+//! lightning, the game's virtual clock briefly slows down and then ramps back
+//! to normal speed. Audio and anything timed with [`Time<Real>`] carry on at
+//! normal speed.
+//!
+//! The game speed picked in the settings scales the whole thing, slow motion
+//! included, but only during gameplay.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems,
+    demo::{castle::BlockDestroyed, player::LightningState},
+    screens::Screen,
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<TimeScale>();
+    app.init_resource::<TimeScale>();
+    app.add_systems(
+        Update,
+        (
+            trigger_collapse_slowdown,
+            trigger_lightning_slowdown,
+            apply_time_scale,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), reset_time_scale);
+}
+
+/// Blocks that must be destroyed within [`COLLAPSE_WINDOW_SECS`] to count as a big collapse.
+const COLLAPSE_BLOCKS: u32 = 12;
+const COLLAPSE_WINDOW_SECS: f32 = 0.5;
+const COLLAPSE_SPEED: f32 = 0.25;
+const COLLAPSE_HOLD_SECS: f32 = 0.4;
+const LIGHTNING_SPEED: f32 = 0.4;
+const LIGHTNING_HOLD_SECS: f32 = 0.15;
+/// How much game speed is regained per real second once the hold is over.
+const RAMP_PER_SEC: f32 = 2.0;
+
+/// Controls the speed of [`Time<Virtual>`] for slow motion effects.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct TimeScale {
+    /// The game speed outside of slow motion, 1 is normal.
+    pub base_speed: f32,
+    /// The current game speed.
+    pub speed: f32,
+    /// Real seconds left before the speed starts ramping back up.
+    hold_secs_left: f32,
+    /// Real seconds left of a hitstop freeze, which stops the game outright.
+    freeze_secs_left: f32,
+    /// Blocks destroyed in the current collapse window.
+    collapse_blocks: u32,
+    collapse_window_left: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            base_speed: 1.0,
+            speed: 1.0,
+            hold_secs_left: 0.0,
+            freeze_secs_left: 0.0,
+            collapse_blocks: 0,
+            collapse_window_left: 0.0,
+        }
+    }
+}
+
+impl TimeScale {
+    /// Slow the game to `speed` times its base speed for `hold_secs` real
+    /// seconds, unless it's already slower.
+    pub fn slow_down(&mut self, speed: f32, hold_secs: f32) {
+        self.speed = self.speed.min(speed * self.base_speed);
+        self.hold_secs_left = self.hold_secs_left.max(hold_secs);
+    }
+
+    /// Stop the game entirely for `secs` real seconds, then carry on at
+    /// whatever speed it was going before, without ramping.
+    pub fn freeze(&mut self, secs: f32) {
+        self.freeze_secs_left = self.freeze_secs_left.max(secs);
+    }
+
+    /// Whether the game is being held in slow motion, the hitstop before it ramps back up.
+    pub fn is_holding(&self) -> bool {
+        self.hold_secs_left > 0.0 || self.freeze_secs_left > 0.0
+    }
+}
+
+fn trigger_collapse_slowdown(
+    time: Res<Time<Real>>,
+    mut destroyed_events: EventReader<BlockDestroyed>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    time_scale.collapse_window_left -= time.delta_secs();
+    if time_scale.collapse_window_left <= 0.0 {
+        time_scale.collapse_blocks = 0;
+    }
+
+    let destroyed = destroyed_events.read().count() as u32;
+    if destroyed == 0 {
+        return;
+    }
+    if time_scale.collapse_blocks == 0 {
+        time_scale.collapse_window_left = COLLAPSE_WINDOW_SECS;
+    }
+    time_scale.collapse_blocks += destroyed;
+    if time_scale.collapse_blocks >= COLLAPSE_BLOCKS {
+        time_scale.slow_down(COLLAPSE_SPEED, COLLAPSE_HOLD_SECS);
+        time_scale.collapse_blocks = 0;
+    }
+}
+
+fn trigger_lightning_slowdown(
+    mut time_scale: ResMut<TimeScale>,
+    lightning_query: Query<(), Added<LightningState>>,
+) {
+    if !lightning_query.is_empty() {
+        time_scale.slow_down(LIGHTNING_SPEED, LIGHTNING_HOLD_SECS);
+    }
+}
+
+fn apply_time_scale(
+    real_time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    // Ramp with real time, otherwise the slowdown would slow its own recovery.
+    let delta_secs = real_time.delta_secs();
+    if time_scale.freeze_secs_left > 0.0 {
+        time_scale.freeze_secs_left -= delta_secs;
+        if virtual_time.relative_speed() != 0.0 {
+            virtual_time.set_relative_speed(0.0);
+        }
+        return;
+    }
+    if time_scale.hold_secs_left > 0.0 {
+        time_scale.hold_secs_left -= delta_secs;
+    } else {
+        let ramped = time_scale.speed + RAMP_PER_SEC * delta_secs;
+        time_scale.speed = ramped.min(time_scale.base_speed);
+    }
+    let speed = time_scale.speed * settings.game_speed;
+    if virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+}
+
+fn reset_time_scale(mut virtual_time: ResMut<Time<Virtual>>, mut time_scale: ResMut<TimeScale>) {
+    time_scale.speed = time_scale.base_speed;
+    time_scale.hold_secs_left = 0.0;
+    time_scale.freeze_secs_left = 0.0;
+    time_scale.collapse_blocks = 0;
+    virtual_time.set_relative_speed(1.0);
+}
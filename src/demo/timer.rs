@@ -1,52 +1,350 @@
-use crate::{screens::Screen, theme::widget};
-use bevy::prelude::*;
+//! The level timer.
+//!
+//! The timer counts up by default. Levels with a `TimeLimit` field count down
+//! from it instead, and when the time runs out the level's `TimeUpConsequence`
+//! kicks in: `Fail` ends the run, `SuddenDeath` reinforces whatever is left of
+//! the castle so the player has to finish it off the hard way.
+//!
+//! The timer runs on virtual time and stops while the game is paused. It
+//! doesn't start until the intro pan hands the players control. Time
+//! penalties, like the one for getting hit, are added on top of it with a
+//! [`TimePenalty`] event and flash up next to the timer.
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        camera::intro_finished,
+        castle::{CastleBlock, MortarHealth},
+        health::DamageEvent,
+        hud::{HudAnchor, HudRegion},
+        level::LevelAssets,
+        player::{Invulnerable, Player},
+        tint::{TintLayer, Tints},
+    },
+    screens::Screen,
+    settings::Settings,
+    theme::widget,
+};
 use bevy::time::Stopwatch;
+use bevy::{prelude::*, ui::Val::*};
+use bevy_ecs_ldtk::prelude::*;
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<GameTimer>() // Initialize the timer resource
-        .add_systems(OnEnter(Screen::Gameplay), spawn_game_timer_ui) // Setup the timer UI
+        .add_event::<TimeUp>()
+        .add_event::<TimePenalty>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            (
+                (reset_game_timer, apply_level_time_limit).chain(),
+                spawn_game_timer_ui,
+            ),
+        ) // Setup the timer UI
+        .add_systems(
+            Update,
+            tick_game_timer
+                .run_if(in_state(Screen::Gameplay).and(intro_finished))
+                .in_set(AppSystems::TickTimers)
+                .in_set(PausableSystems),
+        )
         .add_systems(
             Update,
-            update_game_timer_ui.run_if(in_state(Screen::Gameplay)),
-        ); // Update the timer
+            (
+                penalize_damage,
+                apply_time_penalties,
+                update_game_timer_ui,
+                check_time_limit.run_if(intro_finished),
+                handle_time_up,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(AppSystems::Update)
+                .in_set(PausableSystems),
+        ) // Update the timer
+        .add_systems(Update, fade_time_penalty_popups);
 }
 
+/// How many times stronger the castle's mortar gets in sudden death.
+const SUDDEN_DEATH_REINFORCEMENT: f32 = 3.0;
+/// Remaining seconds at which the countdown turns red.
+const LOW_TIME_SECS: f32 = 10.0;
+const LOW_TIME_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+/// Seconds added to the clock each time the player takes a hit.
+const DAMAGE_PENALTY_SECS: f32 = 5.0;
+const PENALTY_POPUP_SECS: f32 = 1.2;
+/// How far a penalty popup drifts away from the timer while it fades, in pixels.
+const PENALTY_POPUP_DRIFT: f32 = 24.0;
+/// Space between the timer and its penalty popups.
+const PENALTY_POPUP_GAP: f32 = 8.0;
+
 #[derive(Resource)]
 pub struct GameTimer {
+    /// Time spent in the level, counted up even when the level has a time limit.
     pub timer: Stopwatch,
+    pub limit: Option<TimeLimit>,
+    /// Seconds of penalties added to the clock.
+    penalty_secs: f32,
 }
 
 impl Default for GameTimer {
     fn default() -> Self {
         Self {
             timer: Stopwatch::new(),
+            limit: None,
+            penalty_secs: 0.0,
+        }
+    }
+}
+
+impl GameTimer {
+    /// The level's time so far, penalties included. This is the time that
+    /// counts for records; [`GameTimer::timer`] alone is the time actually played.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.timer.elapsed_secs() + self.penalty_secs
+    }
+
+    /// Seconds left before the time limit runs out, if the level has one.
+    pub fn remaining_secs(&self) -> Option<f32> {
+        self.limit
+            .map(|limit| (limit.secs - self.elapsed_secs()).max(0.0))
+    }
+
+    /// Whether the level's time limit has run out.
+    pub fn expired(&self) -> bool {
+        self.limit.is_some_and(|limit| limit.expired)
+    }
+}
+
+/// A level's time limit, from its `TimeLimit` and `TimeUpConsequence` LDtk fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeLimit {
+    pub secs: f32,
+    pub consequence: TimeUpConsequence,
+    pub expired: bool,
+}
+
+/// What happens when a level's time limit runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeUpConsequence {
+    /// The run is over.
+    #[default]
+    Fail,
+    /// The remaining castle blocks are reinforced and the clock keeps counting up.
+    SuddenDeath,
+}
+
+impl TimeUpConsequence {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Fail" => Some(Self::Fail),
+            "SuddenDeath" => Some(Self::SuddenDeath),
+            _ => None,
         }
     }
 }
 
+/// Adds `secs` to the level's time.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TimePenalty {
+    pub secs: f32,
+}
+
+/// Sent once when the level's time limit runs out.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TimeUp {
+    pub consequence: TimeUpConsequence,
+}
+
+fn reset_game_timer(mut timer: ResMut<GameTimer>) {
+    timer.timer.reset();
+    timer.penalty_secs = 0.0;
+}
+
+fn apply_level_time_limit(
+    mut timer: ResMut<GameTimer>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+) {
+    timer.limit = None;
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some((_, level)) = project
+        .json_data()
+        .levels
+        .iter()
+        .enumerate()
+        .find(|(index, level)| level_selection.is_match(&LevelIndices::in_root(*index), level))
+    else {
+        return;
+    };
+    let Ok(&Some(secs)) = level.get_maybe_float_field("TimeLimit") else {
+        return;
+    };
+    let consequence = match level.get_maybe_string_field("TimeUpConsequence") {
+        Ok(Some(name)) => TimeUpConsequence::from_name(name).unwrap_or_else(|| {
+            warn!("Unknown time up consequence {name}, failing the level instead");
+            TimeUpConsequence::default()
+        }),
+        _ => TimeUpConsequence::default(),
+    };
+    info!("Level has a {secs} second time limit ({consequence:?})");
+    timer.limit = Some(TimeLimit {
+        secs,
+        consequence,
+        expired: false,
+    });
+}
+
 fn spawn_game_timer_ui(mut commands: Commands) {
     commands.spawn((
-        widget::ui_root("Game Timer"),
-        GlobalZIndex(2),
-        StateScoped(Screen::Gameplay),
-        Text2d::new("Time: 0.00 seconds"),
-        TextLayout {
-            justify: JustifyText::Left, // Align text to the left
+        Name::new("Game Timer"),
+        Node {
+            column_gap: Px(PENALTY_POPUP_GAP),
+            align_items: AlignItems::Center,
             ..default()
         },
+        HudAnchor::new(HudRegion::TopLeft, 0),
+        GameTimerDisplay,
+        children![(widget::label("Time: 0.00 seconds"), GameTimerLabel)],
     ));
 }
 
-fn update_game_timer_ui(
-    time: Res<Time>,
+/// Marks the HUD widget holding the timer, which penalty popups hang off.
+#[derive(Component)]
+struct GameTimerDisplay;
+
+/// Marks the text that displays the [`GameTimer`].
+#[derive(Component)]
+struct GameTimerLabel;
+
+/// Marks a penalty popping up next to the timer.
+#[derive(Component)]
+struct TimePenaltyPopup {
+    timer: Timer,
+}
+
+/// Tick on virtual time with the game speed setting taken back out, so
+/// slowing the game down doesn't slow the clock and make for easy records.
+/// Hitstop and slow motion still stop and slow the clock.
+fn tick_game_timer(time: Res<Time>, settings: Res<Settings>, mut timer: ResMut<GameTimer>) {
+    timer.timer.tick(time.delta().div_f32(settings.game_speed));
+}
+
+/// Hits cost time. Damage over time doesn't, or standing in lava would add up fast.
+fn penalize_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    player_query: Query<(), (With<Player>, Without<Invulnerable>)>,
+    mut penalty_events: EventWriter<TimePenalty>,
+) {
+    for event in damage_events.read() {
+        if event.knockback.is_some() && event.amount > 0.0 && player_query.contains(event.target) {
+            penalty_events.write(TimePenalty {
+                secs: DAMAGE_PENALTY_SECS,
+            });
+        }
+    }
+}
+
+fn apply_time_penalties(
+    mut commands: Commands,
+    mut penalty_events: EventReader<TimePenalty>,
     mut timer: ResMut<GameTimer>,
-    mut query: Query<&mut Text>,
+    display_query: Query<Entity, With<GameTimerDisplay>>,
+) {
+    for event in penalty_events.read() {
+        info!("{} second time penalty", event.secs);
+        timer.penalty_secs += event.secs;
+        let Ok(display) = display_query.single() else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("Time Penalty Popup"),
+            TimePenaltyPopup {
+                timer: Timer::from_seconds(PENALTY_POPUP_SECS, TimerMode::Once),
+            },
+            Text::new(format!("+{:.0}s", event.secs)),
+            TextColor(LOW_TIME_COLOR),
+            // Laid out in the timer's row rather than over it, so it can't
+            // cover the health display underneath.
+            Node::default(),
+            ChildOf(display),
+        ));
+    }
+}
+
+/// Drift penalty popups away from the timer while they fade, in real time so
+/// they still clear away while the game's paused.
+fn fade_time_penalty_popups(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut popup_query: Query<(Entity, &mut TimePenaltyPopup, &mut Node, &mut TextColor)>,
 ) {
-    // Tick the timer
-    timer.timer.tick(time.delta());
+    for (entity, mut popup, mut node, mut color) in &mut popup_query {
+        popup.timer.tick(time.delta());
+        if popup.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        node.left = Px(PENALTY_POPUP_DRIFT * popup.timer.fraction());
+        color.0.set_alpha(popup.timer.fraction_remaining());
+    }
+}
 
-    // Update the text with the remaining time
-    for mut text in &mut query {
-        let remaining_time = timer.timer.elapsed_secs();
-        text.0 = format!("Time: {:.2} seconds", remaining_time);
+fn update_game_timer_ui(
+    mut commands: Commands,
+    timer: Res<GameTimer>,
+    mut query: Query<(Entity, &mut Text), With<GameTimerLabel>>,
+) {
+    // Count down while there's a time limit left, otherwise show the time spent
+    let remaining = timer.remaining_secs().filter(|_| !timer.expired());
+    for (entity, mut text) in &mut query {
+        match remaining {
+            Some(remaining) => {
+                text.0 = format!("Time left: {:.2} seconds", remaining);
+                if remaining <= LOW_TIME_SECS {
+                    commands.entity(entity).insert(TextColor(LOW_TIME_COLOR));
+                }
+            }
+            None => {
+                text.0 = format!("Time: {:.2} seconds", timer.elapsed_secs());
+            }
+        }
+    }
+}
+
+fn check_time_limit(mut timer: ResMut<GameTimer>, mut time_up_events: EventWriter<TimeUp>) {
+    if timer.remaining_secs() != Some(0.0) {
+        return;
+    }
+    let Some(limit) = timer.limit.as_mut().filter(|limit| !limit.expired) else {
+        return;
+    };
+    limit.expired = true;
+    info!("Time's up ({:?})", limit.consequence);
+    time_up_events.write(TimeUp {
+        consequence: limit.consequence,
+    });
+}
+
+fn handle_time_up(
+    mut time_up_events: EventReader<TimeUp>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut joint_query: Query<&mut MortarHealth>,
+    mut block_query: Query<&mut Tints, With<CastleBlock>>,
+) {
+    for event in time_up_events.read() {
+        match event.consequence {
+            TimeUpConsequence::Fail => next_screen.set(Screen::GameOver),
+            TimeUpConsequence::SuddenDeath => {
+                for mut mortar in &mut joint_query {
+                    mortar.current *= SUDDEN_DEATH_REINFORCEMENT;
+                    mortar.max *= SUDDEN_DEATH_REINFORCEMENT;
+                }
+                for mut tints in &mut block_query {
+                    tints.set(TintLayer::Reinforced, 1.0);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,335 @@
+//! Layered sprite tints.
+//!
+//! Frost, fire, water and hit flashes all color sprites. Rather than each one
+//! overwriting the sprite's color and remembering what to put back, they set
+//! their own layer on [`Tints`], and the layers are blended together in a
+//! fixed order so they compose however they overlap.
+//!
+//! Layers only say how strongly they show; their colors come from the
+//! [`ColorPalette`] picked in the settings, so a colorblind player can swap
+//! the whole set at once. Status effects can also be marked with small icons,
+//! so they don't rely on color alone.
+
+use std::f32::consts::FRAC_PI_3;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{castle::JointBroken, health::DamageEvent},
+    settings::{ColorPalette, Settings},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (flash_on_hit, fade_hit_flashes)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.init_resource::<StatusIconAssets>();
+    app.add_systems(PostUpdate, (apply_tints, update_status_icons));
+}
+
+const HIT_FLASH_SECS: f32 = 0.12;
+/// Brighter than white, so the flash lightens the texture rather than just leaving it as is.
+const HIT_FLASH_COLOR: Color = Color::srgb(3.0, 3.0, 3.0);
+/// Size of the generated status icon textures, in pixels.
+const STATUS_ICON_TEXTURE_SIZE: u32 = 16;
+/// Size status icons are drawn at, in world units.
+const STATUS_ICON_SIZE: f32 = 8.0;
+/// Distance between the centers of icons shown side by side.
+const STATUS_ICON_SPACING: f32 = 9.0;
+/// Icons are drawn just in front of the sprite they mark.
+const STATUS_ICON_Z: f32 = 0.5;
+
+/// A tint layer. Later layers are drawn over earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TintLayer {
+    Reinforced,
+    Water,
+    Burning,
+    Frost,
+    HitFlash,
+}
+
+impl TintLayer {
+    /// The layer's color in `palette`.
+    pub fn color(self, palette: ColorPalette) -> Color {
+        match (self, palette) {
+            (Self::HitFlash, _) => HIT_FLASH_COLOR,
+            (Self::Reinforced, ColorPalette::Standard) => Color::srgb(0.7, 0.75, 1.0),
+            (Self::Water, ColorPalette::Standard) => Color::srgb(0.6, 0.75, 1.0),
+            (Self::Burning, ColorPalette::Standard) => Color::srgb(1.0, 0.6, 0.4),
+            (Self::Frost, ColorPalette::Standard) => Color::srgb(0.0, 0.0, 1.0),
+            // Blue and orange stay apart for red-green colorblindness.
+            (Self::Reinforced, ColorPalette::RedGreen) => Color::srgb(0.75, 0.75, 0.75),
+            (Self::Water, ColorPalette::RedGreen) => Color::srgb(0.7, 0.85, 1.0),
+            (Self::Burning, ColorPalette::RedGreen) => Color::srgb(1.0, 0.7, 0.1),
+            (Self::Frost, ColorPalette::RedGreen) => Color::srgb(0.1, 0.3, 1.0),
+            // Red and cyan stay apart for blue-yellow colorblindness.
+            (Self::Reinforced, ColorPalette::BlueYellow) => Color::srgb(0.75, 0.75, 0.75),
+            (Self::Water, ColorPalette::BlueYellow) => Color::srgb(0.7, 1.0, 1.0),
+            (Self::Burning, ColorPalette::BlueYellow) => Color::srgb(1.0, 0.3, 0.3),
+            (Self::Frost, ColorPalette::BlueYellow) => Color::srgb(0.0, 0.8, 0.8),
+        }
+    }
+}
+
+/// Every tint on a sprite, with how strongly each one shows from 0 to 1.
+#[derive(Component, Debug, Clone, Default)]
+#[require(StatusIcons)]
+pub struct Tints {
+    /// Kept sorted by layer.
+    layers: Vec<(TintLayer, f32)>,
+}
+
+impl Tints {
+    pub fn set(&mut self, layer: TintLayer, strength: f32) {
+        let tint = (layer, strength.clamp(0.0, 1.0));
+        match self
+            .layers
+            .binary_search_by_key(&layer, |(layer, ..)| *layer)
+        {
+            Ok(index) => self.layers[index] = tint,
+            Err(index) => self.layers.insert(index, tint),
+        }
+    }
+
+    pub fn clear(&mut self, layer: TintLayer) {
+        self.layers.retain(|(existing, ..)| *existing != layer);
+    }
+
+    /// The sprite color with every layer blended in.
+    fn color(&self, palette: ColorPalette) -> Color {
+        self.layers
+            .iter()
+            .fold(Srgba::WHITE, |color, (layer, strength)| {
+                color.mix(&layer.color(palette).into(), *strength)
+            })
+            .into()
+    }
+
+    /// The layers showing on the sprite, in order.
+    fn showing(&self) -> impl Iterator<Item = TintLayer> {
+        self.layers
+            .iter()
+            .filter(|(_, strength)| *strength > 0.0)
+            .map(|(layer, _)| *layer)
+    }
+}
+
+/// The icon sprites marking status effects on a sprite, by layer.
+#[derive(Component, Debug, Default)]
+struct StatusIcons {
+    icons: Vec<(TintLayer, Entity)>,
+}
+
+/// Generated icon textures for each status effect.
+#[derive(Resource, Debug)]
+struct StatusIconAssets {
+    water: Handle<Image>,
+    burning: Handle<Image>,
+    frost: Handle<Image>,
+}
+
+impl StatusIconAssets {
+    /// The icon for a status effect layer, or `None` for layers that aren't one.
+    fn icon(&self, layer: TintLayer) -> Option<Handle<Image>> {
+        match layer {
+            TintLayer::Water => Some(self.water.clone()),
+            TintLayer::Burning => Some(self.burning.clone()),
+            TintLayer::Frost => Some(self.frost.clone()),
+            TintLayer::Reinforced | TintLayer::HitFlash => None,
+        }
+    }
+}
+
+impl FromWorld for StatusIconAssets {
+    fn from_world(world: &mut World) -> Self {
+        // Shapes in a -1..1 square, so each one reads without its color.
+        let droplet = |point: Vec2| {
+            point.distance(Vec2::new(0.0, -0.3)) < 0.6
+                || (point.y > 0.0 && point.x.abs() < 0.9 - point.y)
+        };
+        let flame = |point: Vec2| {
+            let width = 0.8 * (1.0 - point.y) / 2.0;
+            point.x.abs() < width && (point.y < 0.3 || point.x.abs() > 0.15 * (1.0 - point.y))
+        };
+        let snowflake = |point: Vec2| {
+            let arm = |direction: Vec2| point.perp_dot(direction).abs() < 0.15;
+            point.length() < 0.95
+                && (arm(Vec2::X)
+                    || arm(Vec2::from_angle(FRAC_PI_3))
+                    || arm(Vec2::from_angle(2.0 * FRAC_PI_3)))
+        };
+        let mut images = world.resource_mut::<Assets<Image>>();
+        Self {
+            water: images.add(status_icon_image(droplet)),
+            burning: images.add(status_icon_image(flame)),
+            frost: images.add(status_icon_image(snowflake)),
+        }
+    }
+}
+
+/// A white icon with a dark outline, filled wherever `shape` is true.
+fn status_icon_image(shape: impl Fn(Vec2) -> bool) -> Image {
+    let size = STATUS_ICON_TEXTURE_SIZE;
+    let pixel_size = 2.0 / size as f32;
+    // Pixel centers, flipped so +y is up like the world.
+    let point = |x: u32, y: u32| {
+        Vec2::new(x as f32 + 0.5, (size - 1 - y) as f32 + 0.5) * pixel_size - Vec2::ONE
+    };
+    let data = (0..size * size)
+        .flat_map(|index| {
+            let (x, y) = (index % size, index / size);
+            let center = point(x, y);
+            let outline = [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y]
+                .into_iter()
+                .any(|offset| shape(center + offset * pixel_size));
+            if shape(center) {
+                [255, 255, 255, 255]
+            } else if outline {
+                [20, 20, 30, 255]
+            } else {
+                [0, 0, 0, 0]
+            }
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Briefly flashes a sprite white after it's hit.
+#[derive(Component, Debug)]
+pub struct HitFlash {
+    timer: Timer,
+}
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(HIT_FLASH_SECS, TimerMode::Once),
+        }
+    }
+}
+
+fn flash_on_hit(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut broken_events: EventReader<JointBroken>,
+    tinted_query: Query<(), With<Tints>>,
+) {
+    if !settings.flashes {
+        damage_events.clear();
+        broken_events.clear();
+        return;
+    }
+    let damaged = damage_events
+        .read()
+        // Damage over time would keep the sprite permanently white.
+        .filter(|event| event.knockback.is_some())
+        .map(|event| event.target);
+    let loosened = broken_events
+        .read()
+        .flat_map(|event| [event.block1, event.block2]);
+    for entity in damaged.chain(loosened) {
+        if tinted_query.contains(entity) {
+            // Restarts the flash if it's already flashing.
+            commands.entity(entity).try_insert(HitFlash::default());
+        }
+    }
+}
+
+fn fade_hit_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HitFlash, &mut Tints)>,
+) {
+    for (entity, mut flash, mut tints) in &mut query {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            tints.clear(TintLayer::HitFlash);
+            commands.entity(entity).try_remove::<HitFlash>();
+        } else {
+            tints.set(TintLayer::HitFlash, 1.0 - flash.timer.fraction());
+        }
+    }
+}
+
+fn apply_tints(settings: Res<Settings>, mut query: Query<(Ref<Tints>, &mut Sprite)>) {
+    for (tints, mut sprite) in &mut query {
+        // Every sprite changes color when the palette does.
+        if !tints.is_changed() && !settings.is_changed() {
+            continue;
+        }
+        // Leave the alpha alone, fading and blinking are handled separately.
+        let alpha = sprite.color.alpha();
+        sprite.color = tints.color(settings.color_palette).with_alpha(alpha);
+    }
+}
+
+/// Keep an icon over the sprite for each status effect on it, colored to match.
+fn update_status_icons(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    icon_assets: Res<StatusIconAssets>,
+    mut query: Query<(Entity, Ref<Tints>, &mut StatusIcons)>,
+) {
+    for (entity, tints, mut status_icons) in &mut query {
+        if !tints.is_changed() && !settings.is_changed() {
+            continue;
+        }
+        let statuses: Vec<(TintLayer, Handle<Image>)> = if settings.status_icons {
+            tints
+                .showing()
+                .filter_map(|layer| Some((layer, icon_assets.icon(layer)?)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let shown: Vec<TintLayer> = status_icons.icons.iter().map(|(layer, _)| *layer).collect();
+        let wanted: Vec<TintLayer> = statuses.iter().map(|(layer, _)| *layer).collect();
+        // Palette changes recolor the icons, so they're respawned too.
+        if shown == wanted && !settings.is_changed() {
+            continue;
+        }
+
+        for (_, icon) in status_icons.icons.drain(..) {
+            commands.entity(icon).try_despawn();
+        }
+        let first_x = -(statuses.len().saturating_sub(1) as f32) * STATUS_ICON_SPACING / 2.0;
+        for (index, (layer, image)) in statuses.into_iter().enumerate() {
+            let x = first_x + index as f32 * STATUS_ICON_SPACING;
+            let icon = commands
+                .spawn((
+                    Name::new("Status Icon"),
+                    Sprite {
+                        image,
+                        color: layer.color(settings.color_palette),
+                        custom_size: Some(Vec2::splat(STATUS_ICON_SIZE)),
+                        ..default()
+                    },
+                    Transform::from_xyz(x, 0.0, STATUS_ICON_Z),
+                    ChildOf(entity),
+                ))
+                .id();
+            status_icons.icons.push((layer, icon));
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! A dotted preview of where a fireball will fly.
+//!
+//! While a player holds a fireball's hotbar key or the aim modifier, its path
+//! is predicted by stepping the ballistic equation from its launch speed and
+//! gravity, and drawn as dots up to the first thing it would hit. This makes it
+//! possible to plan arcing shots over walls.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems,
+    demo::{
+        balistics::{
+            Ability, AbilityKind, FIREBALL_GRAVITY_SCALE, FIREBALL_LIFETIME_SECS, FIREBALL_SPEED,
+            UnlockedAbilities,
+        },
+        loadout::{HOTBAR_SLOTS, Loadout},
+        player::Player,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Aiming>();
+    app.add_systems(
+        Update,
+        draw_fireball_trajectory
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Seconds of flight between two dots of the preview.
+const SAMPLE_STEP_SECS: f32 = 0.04;
+const DOT_RADIUS: f32 = 1.5;
+const DOT_COLOR: Color = Color::srgba(1.0, 0.6, 0.2, 0.8);
+/// How far in front of the player fireballs are launched from, matching `spawn_ability`.
+const LAUNCH_OFFSET: f32 = 24.0;
+
+/// What a player is doing with their aim, kept up to date by the input observers.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct Aiming {
+    /// Which hotbar slot keys are held down.
+    pub slots_held: [bool; HOTBAR_SLOTS],
+    pub modifier_held: bool,
+    /// The direction being held, or zero if none is.
+    pub direction: Vec2,
+}
+
+impl Aiming {
+    /// Whether the player is holding the aim modifier or the key for a fireball in their loadout.
+    pub fn is_aiming(&self, loadout: &Loadout) -> bool {
+        self.modifier_held
+            || self
+                .slots_held
+                .iter()
+                .zip(loadout.slots)
+                .any(|(held, ability)| *held && ability == Some(AbilityKind::Fireball))
+    }
+
+    /// The direction a fireball would be thrown in right now.
+    pub fn fire_direction(&self) -> Vec2 {
+        // Same fallback as firing: straight ahead to the right when no direction is held.
+        if self.direction.length_squared() > 0.0 {
+            self.direction.normalize_or_zero()
+        } else {
+            Vec2::X
+        }
+    }
+}
+
+/// Position of a fireball `t` seconds after launch, ignoring collisions.
+///
+/// Nothing slows fireballs down in the air, so their mass doesn't change the arc.
+pub fn ballistic_position(origin: Vec2, velocity: Vec2, gravity: Vec2, t: f32) -> Vec2 {
+    origin + velocity * t + 0.5 * gravity * t * t
+}
+
+fn draw_fireball_trajectory(
+    mut gizmos: Gizmos,
+    gravity: Res<Gravity>,
+    unlocked: Res<UnlockedAbilities>,
+    loadout: Res<Loadout>,
+    spatial_query: SpatialQuery,
+    player_query: Query<(Entity, &GlobalTransform, &Aiming), With<Player>>,
+    ability_query: Query<Entity, With<Ability>>,
+) {
+    if !unlocked.is_unlocked(AbilityKind::Fireball) {
+        return;
+    }
+    let gravity = gravity.0 * FIREBALL_GRAVITY_SCALE;
+    for (player, transform, aiming) in &player_query {
+        if !aiming.is_aiming(&loadout) {
+            continue;
+        }
+        let direction = aiming.fire_direction();
+        let origin = transform.translation().truncate() + direction * LAUNCH_OFFSET;
+        let velocity = direction * FIREBALL_SPEED;
+        // Fireballs already in the air shouldn't cut the preview short.
+        let filter = SpatialQueryFilter::from_excluded_entities(
+            std::iter::once(player).chain(ability_query.iter()),
+        );
+
+        let steps = (FIREBALL_LIFETIME_SECS / SAMPLE_STEP_SECS) as usize;
+        let mut previous = origin;
+        for step in 1..=steps {
+            let point =
+                ballistic_position(origin, velocity, gravity, step as f32 * SAMPLE_STEP_SECS);
+            let Ok(segment) = Dir2::new(point - previous) else {
+                continue;
+            };
+            let length = previous.distance(point);
+            if let Some(hit) = spatial_query.cast_ray(previous, segment, length, true, &filter) {
+                gizmos.circle_2d(
+                    Isometry2d::from_translation(previous + *segment * hit.distance),
+                    DOT_RADIUS * 2.0,
+                    DOT_COLOR,
+                );
+                break;
+            }
+            gizmos.circle_2d(Isometry2d::from_translation(point), DOT_RADIUS, DOT_COLOR);
+            previous = point;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Trigger volumes placed in LDtk.
+//!
+//! A "Trigger" entity has an `Id` string field and an optional `Once` bool
+//! field. When the player walks into it a [`TriggerActivated`] event is sent
+//! with that id, so other systems can react without knowing about the zone.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{AppSystems, PausableSystems, demo::player::Player};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<TriggerActivated>();
+    app.register_type::<TriggerZone>();
+    app.register_ldtk_entity::<TriggerZoneBundle>("Trigger");
+    app.add_systems(
+        Update,
+        detect_trigger_entry
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Sent when the player enters a [`TriggerZone`].
+#[derive(Event, Debug, Clone)]
+pub struct TriggerActivated {
+    pub id: String,
+}
+
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct TriggerZone {
+    pub id: String,
+    /// Whether the zone should only ever fire once.
+    pub once: bool,
+    /// Whether the player was inside the zone last frame.
+    occupied: bool,
+    fired: bool,
+}
+
+impl From<&EntityInstance> for TriggerZone {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            id: entity_instance
+                .get_string_field("Id")
+                .cloned()
+                .unwrap_or_default(),
+            once: entity_instance
+                .get_bool_field("Once")
+                .copied()
+                .unwrap_or(true),
+            ..default()
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct TriggerZoneBundle {
+    #[from_entity_instance]
+    pub zone: TriggerZone,
+    #[with(trigger_collider)]
+    pub collider: Collider,
+    pub rigid_body: RigidBody,
+    pub sensor: Sensor,
+    pub colliding_entities: CollidingEntities,
+}
+
+impl Default for TriggerZoneBundle {
+    fn default() -> Self {
+        Self {
+            zone: TriggerZone::default(),
+            collider: Collider::rectangle(16.0, 16.0),
+            rigid_body: RigidBody::Static,
+            sensor: Sensor,
+            colliding_entities: CollidingEntities::default(),
+        }
+    }
+}
+
+fn trigger_collider(entity_instance: &EntityInstance) -> Collider {
+    Collider::rectangle(entity_instance.width as f32, entity_instance.height as f32)
+}
+
+fn detect_trigger_entry(
+    mut zone_query: Query<(&mut TriggerZone, &CollidingEntities)>,
+    player_query: Query<(), With<Player>>,
+    mut trigger_events: EventWriter<TriggerActivated>,
+) {
+    for (mut zone, colliding_entities) in &mut zone_query {
+        let occupied = colliding_entities
+            .iter()
+            .any(|entity| player_query.contains(*entity));
+        let entered = occupied && !zone.occupied;
+        zone.occupied = occupied;
+
+        if !entered || (zone.once && zone.fired) {
+            continue;
+        }
+        info!("Trigger {:?} activated", zone.id);
+        zone.fired = true;
+        trigger_events.write(TriggerActivated {
+            id: zone.id.clone(),
+        });
+    }
+}
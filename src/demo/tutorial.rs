@@ -0,0 +1,196 @@
+//! The scripted tutorial, taught one ability at a time.
+//!
+//! A level with its `Tutorial` bool field set plays through the steps in
+//! [`TutorialStep`]. Each step after the first starts when the player walks
+//! into the trigger zone with the step's id, e.g. `tutorial_frost`, which
+//! also plays the step's dialogue. Only the abilities taught so far can be
+//! cast, and the hotbar slot of the one being taught is highlighted.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    AppSystems,
+    demo::{
+        balistics::{AbilityKind, UnlockedAbilities},
+        dialogue::ShowDialogue,
+        level::{LevelAssets, LevelState, selected_level},
+        loadout::{HotbarSlot, Loadout},
+        triggers::TriggerActivated,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Tutorial>();
+    app.add_systems(OnEnter(LevelState::Ready), start_tutorial);
+    app.add_systems(OnExit(Screen::Gameplay), end_tutorial);
+    app.add_systems(
+        Update,
+        (advance_tutorial, highlight_hotbar_slot)
+            .chain()
+            .run_if(in_tutorial)
+            .in_set(AppSystems::Update),
+    );
+}
+
+const HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+const HIGHLIGHT_WIDTH: f32 = 3.0;
+/// Highlight pulses per second.
+const HIGHLIGHT_PULSE_RATE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TutorialStep {
+    Movement,
+    Fireball,
+    FrostSpread,
+    LightningDash,
+    Done,
+}
+
+impl TutorialStep {
+    const ALL: [Self; 5] = [
+        Self::Movement,
+        Self::Fireball,
+        Self::FrostSpread,
+        Self::LightningDash,
+        Self::Done,
+    ];
+
+    /// The id of the trigger zone and conversation for the step.
+    fn id(self) -> &'static str {
+        match self {
+            Self::Movement => "tutorial_movement",
+            Self::Fireball => "tutorial_fireball",
+            Self::FrostSpread => "tutorial_frost",
+            Self::LightningDash => "tutorial_lightning",
+            Self::Done => "tutorial_done",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.id() == id)
+    }
+
+    /// The ability taught in this step.
+    fn ability(self) -> Option<AbilityKind> {
+        match self {
+            Self::Fireball => Some(AbilityKind::Fireball),
+            Self::FrostSpread => Some(AbilityKind::Frost),
+            Self::LightningDash => Some(AbilityKind::Lightning),
+            Self::Movement | Self::Done => None,
+        }
+    }
+
+    /// Abilities are unlocked once their step is reached, and stay unlocked after.
+    fn unlocks(self, ability: AbilityKind) -> bool {
+        Self::ALL
+            .into_iter()
+            .filter(|step| *step <= self)
+            .any(|step| step.ability() == Some(ability))
+    }
+}
+
+/// Where the player is in the tutorial, if they're playing it.
+#[derive(Resource, Debug, Default)]
+pub struct Tutorial {
+    pub step: Option<TutorialStep>,
+}
+
+fn in_tutorial(tutorial: Res<Tutorial>) -> bool {
+    tutorial.step.is_some()
+}
+
+fn set_step(
+    step: TutorialStep,
+    tutorial: &mut Tutorial,
+    unlocked: &mut UnlockedAbilities,
+    show_events: Option<&mut EventWriter<ShowDialogue>>,
+) {
+    info!("Tutorial step {step:?}");
+    tutorial.step = Some(step);
+    for ability in Loadout::ABILITIES {
+        unlocked.set(ability, step.unlocks(ability));
+    }
+    if let Some(show_events) = show_events {
+        show_events.write(ShowDialogue {
+            id: step.id().to_string(),
+        });
+    }
+}
+
+fn start_tutorial(
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    level_selection: Res<LevelSelection>,
+    mut tutorial: ResMut<Tutorial>,
+    mut unlocked: ResMut<UnlockedAbilities>,
+    mut show_events: EventWriter<ShowDialogue>,
+) {
+    // Reloading the level shouldn't send the player back to the start.
+    if tutorial.step.is_some() {
+        return;
+    }
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some(level) = selected_level(&level_selection, project) else {
+        return;
+    };
+    if let Ok(&Some(true)) = level.get_maybe_bool_field("Tutorial") {
+        set_step(
+            TutorialStep::Movement,
+            &mut tutorial,
+            &mut unlocked,
+            Some(&mut show_events),
+        );
+    }
+}
+
+fn end_tutorial(mut tutorial: ResMut<Tutorial>) {
+    tutorial.step = None;
+}
+
+/// Move on to the step whose trigger zone the player walked into, if it's the next one.
+fn advance_tutorial(
+    mut trigger_events: EventReader<TriggerActivated>,
+    mut tutorial: ResMut<Tutorial>,
+    mut unlocked: ResMut<UnlockedAbilities>,
+) {
+    for event in trigger_events.read() {
+        let Some(step) = TutorialStep::from_id(&event.id) else {
+            continue;
+        };
+        if tutorial.step.is_some_and(|current| step > current) {
+            // The zone plays the step's dialogue itself.
+            set_step(step, &mut tutorial, &mut unlocked, None);
+        }
+    }
+}
+
+/// Pulse an outline around the hotbar slot holding the ability being taught.
+fn highlight_hotbar_slot(
+    mut commands: Commands,
+    time: Res<Time>,
+    tutorial: Res<Tutorial>,
+    loadout: Res<Loadout>,
+    slot_query: Query<(Entity, &HotbarSlot)>,
+) {
+    let highlighted_slot = tutorial
+        .step
+        .and_then(TutorialStep::ability)
+        .and_then(|ability| loadout.slots.iter().position(|slot| *slot == Some(ability)));
+    let pulse = (time.elapsed_secs() * HIGHLIGHT_PULSE_RATE * std::f32::consts::TAU).sin();
+    let color = HIGHLIGHT_COLOR.with_alpha(0.6 + 0.4 * pulse);
+    for (entity, slot) in &slot_query {
+        if Some(slot.0) == highlighted_slot {
+            commands.entity(entity).insert(Outline::new(
+                Val::Px(HIGHLIGHT_WIDTH),
+                Val::Px(4.0),
+                color,
+            ));
+        } else {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+}
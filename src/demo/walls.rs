@@ -1,27 +1,220 @@
+//! Walls, slopes and stairs painted into the LDtk int-grid.
+//!
+//! Giving every wall tile its own collider makes thousands of colliders, and
+//! bodies sliding along a wall catch on the seams between them. Instead, once
+//! a level's walls spawn, contiguous tiles are merged into as few rectangle
+//! colliders as possible, spawned as children of the level.
+//!
+//! Slope and stair tiles each get a 45° triangle collider. Stairs only look
+//! different, so characters walk up them as smoothly as up a slope.
+
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
 use bevy_ecs_ldtk::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_ldtk_int_cell::<WallBundle>(1);
+    app.register_type::<Slope>();
+    app.register_ldtk_int_cell::<WallBundle>(WALL_INT_CELL);
+    for int_cell in [
+        SLOPE_UP_RIGHT_INT_CELL,
+        SLOPE_UP_LEFT_INT_CELL,
+        STAIRS_UP_RIGHT_INT_CELL,
+        STAIRS_UP_LEFT_INT_CELL,
+    ] {
+        app.register_ldtk_int_cell::<SlopeBundle>(int_cell);
+    }
+    app.add_systems(Update, spawn_wall_colliders);
 }
 
+/// The int-grid value used for wall cells in LDtk.
+const WALL_INT_CELL: i32 = 1;
+/// Int-grid values for slopes and stairs, by which way they go up.
+const SLOPE_UP_RIGHT_INT_CELL: i32 = 4;
+const SLOPE_UP_LEFT_INT_CELL: i32 = 5;
+const STAIRS_UP_RIGHT_INT_CELL: i32 = 6;
+const STAIRS_UP_LEFT_INT_CELL: i32 = 7;
+/// Size of an int-grid cell in world units.
+const WALL_CELL_SIZE: f32 = 16.0;
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Wall;
 
-#[derive(Clone, Debug, Bundle, LdtkIntCell)]
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct WallBundle {
     pub wall: Wall,
+}
+
+/// A 45° slope or flight of stairs filling one int-grid cell.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct Slope {
+    /// Whether the slope goes up to the right rather than to the left.
+    pub rises_right: bool,
+    pub stairs: bool,
+}
+
+impl From<IntGridCell> for Slope {
+    fn from(cell: IntGridCell) -> Self {
+        Self {
+            rises_right: matches!(
+                cell.value,
+                SLOPE_UP_RIGHT_INT_CELL | STAIRS_UP_RIGHT_INT_CELL
+            ),
+            stairs: matches!(
+                cell.value,
+                STAIRS_UP_RIGHT_INT_CELL | STAIRS_UP_LEFT_INT_CELL
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Bundle, LdtkIntCell)]
+pub struct SlopeBundle {
+    #[from_int_grid_cell]
+    pub slope: Slope,
+    #[with(slope_collider)]
     pub collider: Collider,
     pub rigid_body: RigidBody,
 }
 
-impl Default for WallBundle {
+impl Default for SlopeBundle {
     fn default() -> Self {
         Self {
-            wall: Wall,
-            collider: Collider::rectangle(16., 16.), // Default size for wall collision),
+            slope: Slope::default(),
+            collider: slope_collider(IntGridCell::default()),
             rigid_body: RigidBody::Static,
         }
     }
 }
+
+/// A right-angled triangle with its long side facing up the slope.
+fn slope_collider(cell: IntGridCell) -> Collider {
+    let half = WALL_CELL_SIZE / 2.0;
+    let high_x = if Slope::from(cell).rises_right {
+        half
+    } else {
+        -half
+    };
+    Collider::triangle(
+        Vec2::new(-half, -half),
+        Vec2::new(half, -half),
+        Vec2::new(high_x, half),
+    )
+}
+
+/// A run of wall cells in one row, from `left` to `right` inclusive.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+struct Plate {
+    left: i32,
+    right: i32,
+}
+
+/// A rectangle of wall cells, with inclusive bounds.
+#[derive(Clone, Copy, Debug)]
+struct WallRect {
+    left: i32,
+    right: i32,
+    bottom: i32,
+    top: i32,
+}
+
+/// Cover `cells` with rectangles by joining runs in each row, then stacking
+/// runs with the same span in consecutive rows.
+fn merge_wall_cells(cells: &HashSet<GridCoords>) -> Vec<WallRect> {
+    let Some(min_y) = cells.iter().map(|cell| cell.y).min() else {
+        return Vec::new();
+    };
+    let max_y = cells.iter().map(|cell| cell.y).max().unwrap_or(min_y);
+    let min_x = cells.iter().map(|cell| cell.x).min().unwrap_or_default();
+    let max_x = cells.iter().map(|cell| cell.x).max().unwrap_or_default();
+
+    let mut finished = Vec::new();
+    let mut open: HashMap<Plate, WallRect> = HashMap::new();
+    for y in min_y..=max_y {
+        let mut plates = Vec::new();
+        let mut run_start = None;
+        // One past the end, so a run touching the right edge is closed too.
+        for x in min_x..=max_x + 1 {
+            match (run_start, cells.contains(&GridCoords::new(x, y))) {
+                (None, true) => run_start = Some(x),
+                (Some(left), false) => {
+                    plates.push(Plate { left, right: x - 1 });
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        let mut continued = HashMap::new();
+        for plate in plates {
+            let rect = match open.remove(&plate) {
+                Some(rect) => WallRect { top: y, ..rect },
+                None => WallRect {
+                    left: plate.left,
+                    right: plate.right,
+                    bottom: y,
+                    top: y,
+                },
+            };
+            continued.insert(plate, rect);
+        }
+        // Whatever didn't carry on into this row is as tall as it gets.
+        finished.extend(open.into_values());
+        open = continued;
+    }
+    finished.extend(open.into_values());
+    finished
+}
+
+fn spawn_wall_colliders(
+    mut commands: Commands,
+    wall_query: Query<(&GridCoords, &ChildOf), Added<Wall>>,
+    layer_query: Query<&ChildOf, Without<Wall>>,
+    level_query: Query<(), With<LevelIid>>,
+) {
+    if wall_query.is_empty() {
+        return;
+    }
+    // Int-grid cells are children of their layer, which is a child of the level.
+    let mut level_walls: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    for (coords, layer) in &wall_query {
+        let Ok(level) = layer_query.get(layer.parent()) else {
+            continue;
+        };
+        if level_query.contains(level.parent()) {
+            level_walls
+                .entry(level.parent())
+                .or_default()
+                .insert(*coords);
+        }
+    }
+
+    for (level, cells) in level_walls {
+        let rects = merge_wall_cells(&cells);
+        debug!(
+            "Merged {} wall cells into {} colliders",
+            cells.len(),
+            rects.len()
+        );
+        commands.entity(level).with_children(|level| {
+            for rect in rects {
+                let width = (rect.right - rect.left + 1) as f32 * WALL_CELL_SIZE;
+                let height = (rect.top - rect.bottom + 1) as f32 * WALL_CELL_SIZE;
+                let center = Vec2::new(
+                    rect.left as f32 * WALL_CELL_SIZE + width / 2.0,
+                    rect.bottom as f32 * WALL_CELL_SIZE + height / 2.0,
+                );
+                level.spawn((
+                    Name::new("Wall Collider"),
+                    Wall,
+                    Collider::rectangle(width, height),
+                    RigidBody::Static,
+                    Transform::from_translation(center.extend(0.0)),
+                ));
+            }
+        });
+    }
+}
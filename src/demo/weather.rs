@@ -0,0 +1,347 @@
+//! Wind, rain and snow.
+//!
+//! A level's weather comes from its optional `Weather` string field in LDtk:
+//! `Clear`, `Rain` or `Snow`. Rain damps fire explosions, snow settles on the
+//! castle and marks every block with nothing on top of it as [`Snowed`]. Snow
+//! doesn't freeze blocks by itself, but frost that hits a snowed block starts
+//! a stack higher.
+//!
+//! Wind blows projectiles and light debris sideways. It blows everywhere in
+//! the level from the level's `Wind` field (see `level_physics`), and inside
+//! "WindZone" entities from their `Wind` and `Lift` float fields.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use rand::Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    demo::{
+        balistics::{Fireball, Frostbolt},
+        camera::UiOnlyCamera,
+        castle::{CastleBlock, CastleGrid, CastleReady},
+        despawn_queue::Debris,
+        level::{EnteredNeighborLevel, LevelAssets, LevelState, selected_level},
+        level_physics::LevelPhysics,
+        pool::{Pool, Poolable},
+        rng::CosmeticRng,
+        tint::{TintLayer, Tints},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Weather>();
+    app.register_type::<WindZone>();
+    app.init_resource::<Weather>();
+    app.init_resource::<Pool<PrecipitationBundle>>();
+    app.register_ldtk_entity::<WindZoneBundle>("WindZone");
+    app.add_systems(OnEnter(LevelState::Ready), read_level_weather);
+    app.add_systems(OnExit(Screen::Gameplay), reset_weather);
+    app.add_systems(
+        Update,
+        read_level_weather.run_if(on_event::<EnteredNeighborLevel>),
+    );
+    app.add_systems(
+        Update,
+        (
+            apply_wind,
+            frost_exposed_blocks.run_if(on_event::<CastleReady>),
+            spawn_precipitation,
+            animate_precipitation,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+}
+
+/// Fire explosion radius multiplier while it's raining.
+const RAIN_FIRE_RADIUS: f32 = 0.75;
+/// Extra frost stacks a frost hit leaves on a [`Snowed`] block.
+pub const SNOW_FROST_STACKS: f32 = 1.0;
+/// How strongly snowed blocks are tinted with frost.
+const SNOW_TINT: f32 = 0.25;
+const RAIN_DROPS_PER_SEC: f32 = 240.0;
+const SNOW_FLAKES_PER_SEC: f32 = 60.0;
+const RAIN_FALL_SPEED: f32 = 400.0;
+const SNOW_FALL_SPEED: f32 = 40.0;
+const RAIN_DROP_SIZE: Vec2 = Vec2::new(1.0, 6.0);
+const SNOW_FLAKE_SIZE: Vec2 = Vec2::splat(2.0);
+const RAIN_COLOR: Color = Color::srgba(0.6, 0.7, 0.9, 0.5);
+const SNOW_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.8);
+/// How much of the wind precipitation picks up compared to projectiles.
+const PRECIPITATION_WIND_FACTOR: f32 = 0.5;
+/// Precipitation is drawn in front of everything in the level.
+const PRECIPITATION_Z: f32 = 20.0;
+
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[reflect(Resource)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Snow,
+}
+
+impl Weather {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Clear" => Some(Self::Clear),
+            "Rain" => Some(Self::Rain),
+            "Snow" => Some(Self::Snow),
+            _ => None,
+        }
+    }
+
+    /// Multiplier for the radius of fire explosions.
+    pub fn fire_radius_multiplier(self) -> f32 {
+        match self {
+            Self::Rain => RAIN_FIRE_RADIUS,
+            Self::Clear | Self::Snow => 1.0,
+        }
+    }
+}
+
+/// An area where the wind blows differently, on top of the level's own wind.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct WindZone {
+    /// Acceleration on projectiles and debris inside the zone.
+    pub wind: Vec2,
+    pub size: Vec2,
+}
+
+impl From<&EntityInstance> for WindZone {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let field = |identifier: &str| match entity_instance.get_maybe_float_field(identifier) {
+            Ok(&Some(value)) => value,
+            _ => 0.0,
+        };
+        Self {
+            wind: Vec2::new(field("Wind"), field("Lift")),
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+        }
+    }
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+pub struct WindZoneBundle {
+    #[from_entity_instance]
+    pub zone: WindZone,
+}
+
+/// A castle block snow has settled on.
+#[derive(Component, Debug)]
+pub struct Snowed;
+
+/// A raindrop or snowflake.
+#[derive(Component, Debug)]
+struct Precipitation {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// Raindrops and snowflakes come and go by the hundred, so they're recycled.
+#[derive(Bundle)]
+struct PrecipitationBundle {
+    name: Name,
+    precipitation: Precipitation,
+    sprite: Sprite,
+    transform: Transform,
+    state_scoped: StateScoped<Screen>,
+}
+
+impl Poolable for PrecipitationBundle {
+    fn reset(_entity: &mut EntityCommands) {}
+}
+
+fn read_level_weather(
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    level_selection: Res<LevelSelection>,
+    mut weather: ResMut<Weather>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    let Some(level) = selected_level(&level_selection, project) else {
+        return;
+    };
+    let level_weather = match level.get_maybe_string_field("Weather") {
+        Ok(Some(name)) => Weather::from_name(name).unwrap_or_else(|| {
+            warn!("Unknown weather {name}, keeping the sky clear");
+            Weather::Clear
+        }),
+        _ => Weather::Clear,
+    };
+    if level_weather != *weather {
+        info!("Level {} weather: {level_weather:?}", level.identifier);
+        *weather = level_weather;
+    }
+}
+
+fn reset_weather(mut weather: ResMut<Weather>) {
+    *weather = Weather::Clear;
+}
+
+/// The wind at `position`, from the level and any zones it's in.
+fn wind_at(
+    position: Vec2,
+    level_physics: &LevelPhysics,
+    zone_query: &Query<(&WindZone, &GlobalTransform)>,
+) -> Vec2 {
+    let zone_wind: Vec2 = zone_query
+        .iter()
+        .filter(|(zone, transform)| {
+            Rect::from_center_size(transform.translation().truncate(), zone.size).contains(position)
+        })
+        .map(|(zone, _)| zone.wind)
+        .sum();
+    Vec2::X * level_physics.wind + zone_wind
+}
+
+fn apply_wind(
+    time: Res<Time>,
+    level_physics: Res<LevelPhysics>,
+    zone_query: Query<(&WindZone, &GlobalTransform)>,
+    mut projectile_query: Query<
+        (&mut LinearVelocity, &GlobalTransform),
+        Or<(With<Fireball>, With<Frostbolt>)>,
+    >,
+    mut debris_query: Query<(&mut Debris, &Transform)>,
+) {
+    if level_physics.wind == 0.0 && zone_query.is_empty() {
+        return;
+    }
+    let delta_time = time.delta_secs();
+    for (mut velocity, transform) in &mut projectile_query {
+        velocity.0 += wind_at(
+            transform.translation().truncate(),
+            &level_physics,
+            &zone_query,
+        ) * delta_time;
+    }
+    for (mut debris, transform) in &mut debris_query {
+        debris.velocity += wind_at(
+            transform.translation.truncate(),
+            &level_physics,
+            &zone_query,
+        ) * delta_time;
+    }
+}
+
+/// Snow settles on every castle block with nothing resting on top of it.
+fn frost_exposed_blocks(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    castle_grid: Res<CastleGrid>,
+    mut block_query: Query<&mut Tints, (With<CastleBlock>, Without<Snowed>)>,
+) {
+    if *weather != Weather::Snow {
+        return;
+    }
+    let mut snowed = 0;
+    for block in castle_grid.blocks() {
+        if !castle_grid.is_exposed(block) {
+            continue;
+        }
+        let Ok(mut tints) = block_query.get_mut(block) else {
+            continue;
+        };
+        tints.set(TintLayer::Frost, SNOW_TINT);
+        commands.entity(block).try_insert(Snowed);
+        snowed += 1;
+    }
+    debug!("Snow settled on {snowed} exposed castle blocks");
+}
+
+fn spawn_precipitation(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    mut rng: ResMut<CosmeticRng>,
+    mut pool: ResMut<Pool<PrecipitationBundle>>,
+    camera_query: Query<(&Camera, &GlobalTransform), (With<Camera2d>, Without<UiOnlyCamera>)>,
+    mut pending: Local<f32>,
+) {
+    let (rate, fall_speed, size, color) = match *weather {
+        Weather::Clear => return,
+        Weather::Rain => (
+            RAIN_DROPS_PER_SEC,
+            RAIN_FALL_SPEED,
+            RAIN_DROP_SIZE,
+            RAIN_COLOR,
+        ),
+        Weather::Snow => (
+            SNOW_FLAKES_PER_SEC,
+            SNOW_FALL_SPEED,
+            SNOW_FLAKE_SIZE,
+            SNOW_COLOR,
+        ),
+    };
+    *pending += rate * time.delta_secs();
+    let count = pending.floor();
+    *pending -= count;
+
+    for (camera, camera_transform) in &camera_query {
+        let Some(viewport) = camera.logical_viewport_rect() else {
+            continue;
+        };
+        let (Ok(top_left), Ok(bottom_right)) = (
+            camera.viewport_to_world_2d(camera_transform, viewport.min),
+            camera.viewport_to_world_2d(camera_transform, viewport.max),
+        ) else {
+            continue;
+        };
+        let view = Rect::from_corners(top_left, bottom_right);
+        // Long enough to fall through the whole view.
+        let lifetime = view.height() / fall_speed;
+        for _ in 0..count as u32 {
+            let x = rng.gen_range(view.min.x..=view.max.x);
+            pool.acquire(
+                &mut commands,
+                PrecipitationBundle {
+                    name: Name::new("Precipitation"),
+                    precipitation: Precipitation {
+                        velocity: Vec2::NEG_Y * fall_speed,
+                        timer: Timer::from_seconds(lifetime, TimerMode::Once),
+                    },
+                    sprite: Sprite::from_color(color, size),
+                    transform: Transform::from_translation(Vec3::new(
+                        x,
+                        view.max.y,
+                        PRECIPITATION_Z,
+                    )),
+                    state_scoped: StateScoped(Screen::Gameplay),
+                },
+            );
+        }
+    }
+}
+
+fn animate_precipitation(
+    mut commands: Commands,
+    time: Res<Time>,
+    level_physics: Res<LevelPhysics>,
+    zone_query: Query<(&WindZone, &GlobalTransform)>,
+    mut pool: ResMut<Pool<PrecipitationBundle>>,
+    mut precipitation_query: Query<(Entity, &mut Precipitation, &mut Transform)>,
+) {
+    let delta_time = time.delta_secs();
+    for (entity, mut precipitation, mut transform) in &mut precipitation_query {
+        if precipitation.timer.tick(time.delta()).finished() {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+        let wind = wind_at(
+            transform.translation.truncate(),
+            &level_physics,
+            &zone_query,
+        );
+        let drift = wind.x * PRECIPITATION_WIND_FACTOR * precipitation.timer.elapsed_secs();
+        let velocity = precipitation.velocity + Vec2::X * drift;
+        transform.translation += (velocity * delta_time).extend(0.0);
+    }
+}
@@ -0,0 +1,175 @@
+//! A log of every hit in a run, for balancing ability numbers.
+//!
+//! Each [`CombatHit`] is kept as a structured record, which is dumped to JSON
+//! when leaving gameplay or with the `combatlog dump` console command.
+//! `combatlog` prints totals per ability instead.
+
+use bevy::{ecs::name::NameOrEntity, platform::collections::HashMap, prelude::*};
+use serde::Serialize;
+
+use super::console::{ConsoleCommand, DevConsole};
+use crate::{
+    demo::{balistics::AbilityKind, collision::CombatHit},
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CombatLog>();
+    app.add_systems(OnEnter(Screen::Gameplay), clear_combat_log);
+    app.add_systems(OnExit(Screen::Gameplay), dump_combat_log_on_exit);
+    app.add_systems(Update, (record_combat_hits, run_combat_log_commands));
+}
+
+/// Where combat logs are written to in native builds.
+#[cfg(not(target_family = "wasm"))]
+const COMBAT_LOG_DIR: &str = "combat_logs";
+
+#[derive(Serialize, Debug, Clone)]
+struct CombatRecord {
+    /// Seconds since the app started.
+    time: f32,
+    attacker: Option<String>,
+    target: String,
+    ability: Option<AbilityKind>,
+    damage: f32,
+    impulse: [f32; 2],
+    frost: f32,
+}
+
+#[derive(Resource, Default, Debug)]
+struct CombatLog {
+    records: Vec<CombatRecord>,
+}
+
+impl CombatLog {
+    #[cfg(not(target_family = "wasm"))]
+    fn save(&self) -> Result<std::path::PathBuf, String> {
+        let contents =
+            serde_json::to_string_pretty(&self.records).map_err(|error| error.to_string())?;
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let path = std::path::Path::new(COMBAT_LOG_DIR).join(format!("combat_log_{seconds}.json"));
+        std::fs::create_dir_all(COMBAT_LOG_DIR)
+            .and_then(|()| std::fs::write(&path, contents))
+            .map_err(|error| error.to_string())?;
+        Ok(path)
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn save(&self) -> Result<std::path::PathBuf, String> {
+        Err("Combat logs can't be saved on the web".into())
+    }
+
+    /// One line per ability with its hit count, total damage and impulse.
+    fn summary(&self) -> Vec<String> {
+        #[derive(Default)]
+        struct Totals {
+            hits: u32,
+            damage: f32,
+            impulse: f32,
+            max_impulse: f32,
+            frost: f32,
+        }
+
+        let mut totals: HashMap<String, Totals> = HashMap::default();
+        for record in &self.records {
+            let ability = record
+                .ability
+                .map_or_else(|| "Other".to_string(), |ability| format!("{ability:?}"));
+            let impulse = Vec2::from(record.impulse).length();
+            let totals = totals.entry(ability).or_default();
+            totals.hits += 1;
+            totals.damage += record.damage;
+            totals.impulse += impulse;
+            totals.max_impulse = totals.max_impulse.max(impulse);
+            totals.frost += record.frost;
+        }
+        let mut lines: Vec<String> = totals
+            .into_iter()
+            .map(|(ability, totals)| {
+                format!(
+                    "{ability:<10} {} hits, {:.1} damage, {:.0} mean / {:.0} max impulse, {:.1} frost",
+                    totals.hits,
+                    totals.damage,
+                    totals.impulse / totals.hits as f32,
+                    totals.max_impulse,
+                    totals.frost,
+                )
+            })
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+fn clear_combat_log(mut log: ResMut<CombatLog>) {
+    log.records.clear();
+}
+
+fn record_combat_hits(
+    time: Res<Time<Real>>,
+    mut hit_events: EventReader<CombatHit>,
+    name_query: Query<NameOrEntity>,
+    mut log: ResMut<CombatLog>,
+) {
+    // Projectiles may already be despawned, in which case the bare entity is used.
+    let name = |entity: Entity| {
+        name_query
+            .get(entity)
+            .map_or_else(|_| entity.to_string(), |name| name.to_string())
+    };
+    for hit in hit_events.read() {
+        log.records.push(CombatRecord {
+            time: time.elapsed_secs(),
+            attacker: hit.attacker.map(name),
+            target: name(hit.target),
+            ability: hit.ability,
+            damage: hit.damage,
+            impulse: hit.impulse.into(),
+            frost: hit.frost,
+        });
+    }
+}
+
+fn dump_combat_log_on_exit(log: Res<CombatLog>) {
+    if log.records.is_empty() {
+        return;
+    }
+    match log.save() {
+        Ok(path) => info!(
+            "Saved {} combat records to {}",
+            log.records.len(),
+            path.display()
+        ),
+        Err(error) => warn!("Couldn't save the combat log: {error}"),
+    }
+}
+
+fn run_combat_log_commands(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    log: Res<CombatLog>,
+) {
+    for command in console_commands.read() {
+        match command {
+            ConsoleCommand::CombatLogSummary => {
+                if log.records.is_empty() {
+                    console.print("No hits logged yet");
+                }
+                for line in log.summary() {
+                    console.print(line);
+                }
+            }
+            ConsoleCommand::DumpCombatLog => match log.save() {
+                Ok(path) => console.print(format!(
+                    "Saved {} combat records to {}",
+                    log.records.len(),
+                    path.display()
+                )),
+                Err(error) => console.print(format!("Couldn't save the combat log: {error}")),
+            },
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! An in-game developer console.
+//!
+//! Typed lines are parsed into [`ConsoleCommand`] events, which are handled by
+//! ordinary systems so other dev tools can add their own commands. Press TAB
+//! to complete a command.
+
+use bevy::{
+    input::common_conditions::input_just_pressed, log::tracing_subscriber::filter::LevelFilter,
+    prelude::*,
+};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContextPass, EguiContexts, egui};
+
+use crate::{
+    demo::{
+        balistics::{AbilityKind, UnlockedAbilities},
+        blueprint::{BlockMaterial, CastleBlueprint, SpawnBlueprint},
+        castle::{BlockSize, CastleBlock, CastleSection},
+        input::ActionType,
+        level::LevelAssets,
+        player::{CharacterController, PlayerIndex},
+        time_scale::TimeScale,
+    },
+    logging::{LogLevels, LogSubsystem},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<DevConsole>();
+    app.add_event::<ConsoleCommand>();
+    app.add_systems(
+        Update,
+        toggle_console.run_if(input_just_pressed(CONSOLE_KEY)),
+    );
+    app.add_systems(EguiContextPass, draw_console.run_if(console_open));
+    app.add_systems(
+        Update,
+        (
+            run_builtin_commands,
+            spawn_projectiles,
+            give_abilities,
+            set_time_scale,
+            teleport_player,
+            load_level,
+            save_blueprint,
+            load_blueprint,
+            set_log_level,
+        ),
+    );
+}
+
+const CONSOLE_KEY: KeyCode = KeyCode::F1;
+/// Lines of output kept in the console.
+const MAX_LOG_LINES: usize = 200;
+
+/// Every command with its arguments, used for `help` and TAB completion.
+const COMMANDS: &[(&str, &str)] = &[
+    ("help", ""),
+    ("clear", ""),
+    ("spawn fireball", "<count>"),
+    ("spawn frostbolt", "<count>"),
+    ("give ability fireball", ""),
+    ("give ability frost", ""),
+    ("give ability lightning", ""),
+    ("give ability charge", ""),
+    ("set timescale", "<speed>"),
+    ("teleport", "<x> <y>"),
+    ("load level", "<index>"),
+    ("freecam", ""),
+    ("noclip", ""),
+    ("blueprint save", "<name>"),
+    ("blueprint load", "<name> <x> <y>"),
+    ("combatlog", ""),
+    ("combatlog dump", ""),
+    ("log physics", "<level>"),
+    ("log abilities", "<level>"),
+    ("log castle", "<level>"),
+    ("log other", "<level>"),
+];
+
+#[derive(Resource, Default, Debug)]
+pub struct DevConsole {
+    pub open: bool,
+    input: String,
+    log: Vec<String>,
+}
+
+impl DevConsole {
+    /// Print a line of output to the console.
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.log.push(line.into());
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+}
+
+fn console_open(console: Res<DevConsole>) -> bool {
+    console.open
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Help,
+    Clear,
+    SpawnProjectile {
+        kind: AbilityKind,
+        count: u32,
+    },
+    GiveAbility(AbilityKind),
+    SetTimeScale(f32),
+    Teleport(Vec2),
+    LoadLevel(usize),
+    ToggleFreecam,
+    ToggleNoclip,
+    SaveBlueprint(String),
+    LoadBlueprint {
+        name: String,
+        position: Vec2,
+    },
+    CombatLogSummary,
+    DumpCombatLog,
+    SetLogLevel {
+        subsystem: LogSubsystem,
+        level: LevelFilter,
+    },
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["help"] => Ok(Self::Help),
+            ["clear"] => Ok(Self::Clear),
+            ["spawn", kind, rest @ ..] => {
+                let kind = match *kind {
+                    "fireball" => AbilityKind::Fireball,
+                    "frostbolt" => AbilityKind::Frost,
+                    _ => return Err(format!("Can't spawn `{kind}`")),
+                };
+                let count = match rest {
+                    [] => 1,
+                    [count] => parse_arg(count, "count")?,
+                    _ => return Err("Usage: spawn <fireball|frostbolt> <count>".into()),
+                };
+                Ok(Self::SpawnProjectile { kind, count })
+            }
+            ["give", "ability", ability] => {
+                let ability = match *ability {
+                    "fireball" => AbilityKind::Fireball,
+                    "frost" => AbilityKind::Frost,
+                    "lightning" => AbilityKind::Lightning,
+                    "charge" => AbilityKind::Charge,
+                    _ => return Err(format!("Unknown ability `{ability}`")),
+                };
+                Ok(Self::GiveAbility(ability))
+            }
+            ["set", "timescale", speed] => {
+                let speed: f32 = parse_arg(speed, "speed")?;
+                if speed <= 0.0 {
+                    return Err("Speed must be greater than 0".into());
+                }
+                Ok(Self::SetTimeScale(speed))
+            }
+            ["teleport", x, y] => Ok(Self::Teleport(Vec2::new(
+                parse_arg(x, "x")?,
+                parse_arg(y, "y")?,
+            ))),
+            ["load", "level", index] => Ok(Self::LoadLevel(parse_arg(index, "index")?)),
+            ["freecam"] => Ok(Self::ToggleFreecam),
+            ["noclip"] => Ok(Self::ToggleNoclip),
+            ["blueprint", "save", name] => Ok(Self::SaveBlueprint(name.to_string())),
+            ["blueprint", "load", name, x, y] => Ok(Self::LoadBlueprint {
+                name: name.to_string(),
+                position: Vec2::new(parse_arg(x, "x")?, parse_arg(y, "y")?),
+            }),
+            ["combatlog"] => Ok(Self::CombatLogSummary),
+            ["combatlog", "dump"] => Ok(Self::DumpCombatLog),
+            ["log", subsystem, level] => {
+                let subsystem = LogSubsystem::from_name(subsystem)
+                    .ok_or_else(|| format!("Unknown subsystem `{subsystem}`"))?;
+                let level = parse_arg(level, "level")?;
+                Ok(Self::SetLogLevel { subsystem, level })
+            }
+            [] => Err("Type `help` for a list of commands".into()),
+            _ => Err(format!("Unknown command `{line}`, type `help` for a list")),
+        }
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str, name: &str) -> Result<T, String> {
+    arg.parse()
+        .map_err(|_| format!("`{arg}` isn't a valid {name}"))
+}
+
+/// Complete `input` as far as every matching command agrees.
+fn complete(input: &str) -> (String, Vec<&'static str>) {
+    let matches: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(command, _)| *command)
+        .filter(|command| command.starts_with(input))
+        .collect();
+    let Some(first) = matches.first() else {
+        return (input.to_string(), matches);
+    };
+    let common_len = matches.iter().fold(first.len(), |len, command| {
+        first
+            .bytes()
+            .zip(command.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    let mut completed = first[..common_len].to_string();
+    if matches.len() == 1 {
+        completed.push(' ');
+    }
+    (completed, matches)
+}
+
+fn toggle_console(mut console: ResMut<DevConsole>) {
+    console.open = !console.open;
+}
+
+fn draw_console(
+    mut contexts: EguiContexts,
+    mut console: ResMut<DevConsole>,
+    mut console_commands: EventWriter<ConsoleCommand>,
+) {
+    let console = &mut *console;
+    egui::Window::new("Console")
+        .default_width(480.0)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &console.log {
+                        ui.monospace(line);
+                    }
+                });
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut console.input)
+                    .lock_focus(true)
+                    .desired_width(f32::INFINITY)
+                    .font(egui::TextStyle::Monospace),
+            );
+
+            // TAB is typed into the input because focus is locked, so it's picked up here.
+            if console.input.contains('\t') {
+                let input = console.input.replace('\t', "");
+                let (completed, matches) = complete(&input);
+                if matches.len() > 1 {
+                    console.print(matches.join(", "));
+                }
+                console.input = completed;
+                move_cursor_to_end(ui.ctx(), response.id, &console.input);
+            }
+
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                let line = std::mem::take(&mut console.input);
+                console.print(format!("> {line}"));
+                match ConsoleCommand::parse(&line) {
+                    Ok(command) => {
+                        console_commands.write(command);
+                    }
+                    Err(error) => console.print(error),
+                }
+            }
+            response.request_focus();
+        });
+}
+
+fn move_cursor_to_end(ctx: &egui::Context, id: egui::Id, text: &str) {
+    if let Some(mut state) = egui::TextEdit::load_state(ctx, id) {
+        let end = egui::text::CCursor::new(text.chars().count());
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(end)));
+        state.store(ctx, id);
+    }
+}
+
+fn run_builtin_commands(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+) {
+    for command in console_commands.read() {
+        match command {
+            ConsoleCommand::Help => {
+                for (command, args) in COMMANDS {
+                    console.print(format!("{command} {args}"));
+                }
+            }
+            ConsoleCommand::Clear => console.log.clear(),
+            _ => {}
+        }
+    }
+}
+
+fn spawn_projectiles(
+    time: Res<Time<Real>>,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut player_query: Query<(&PlayerIndex, &Sprite, &mut CharacterController)>,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::SpawnProjectile { kind, count } = command else {
+            continue;
+        };
+        let Some((_, sprite, mut controller)) =
+            player_query.iter_mut().find(|(index, ..)| index.0 == 0)
+        else {
+            console.print("There's no player to spawn from");
+            continue;
+        };
+        let direction = if sprite.flip_x { Vec2::NEG_X } else { Vec2::X };
+        for _ in 0..*count {
+            controller.queue_action(
+                ActionType::Cast {
+                    ability: *kind,
+                    direction,
+                },
+                time.elapsed(),
+            );
+        }
+        console.print(format!("Spawning {count} {kind:?}"));
+    }
+}
+
+fn give_abilities(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut unlocked: ResMut<UnlockedAbilities>,
+) {
+    for command in console_commands.read() {
+        if let ConsoleCommand::GiveAbility(ability) = command {
+            unlocked.set(*ability, true);
+            console.print(format!("Unlocked {ability:?}"));
+        }
+    }
+}
+
+fn set_time_scale(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    for command in console_commands.read() {
+        if let ConsoleCommand::SetTimeScale(speed) = command {
+            time_scale.base_speed = *speed;
+            console.print(format!("Game speed set to {speed}"));
+        }
+    }
+}
+
+fn teleport_player(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut player_query: Query<(&PlayerIndex, &mut Transform)>,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::Teleport(position) = command else {
+            continue;
+        };
+        let Some((_, mut transform)) = player_query.iter_mut().find(|(index, _)| index.0 == 0)
+        else {
+            console.print("There's no player to teleport");
+            continue;
+        };
+        transform.translation = position.extend(transform.translation.z);
+        console.print(format!("Teleported to {position}"));
+    }
+}
+
+fn load_level(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    level_assets: Option<Res<LevelAssets>>,
+    projects: Res<Assets<LdtkProject>>,
+    mut level_selection: ResMut<LevelSelection>,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::LoadLevel(index) = command else {
+            continue;
+        };
+        let level_count = level_assets
+            .as_ref()
+            .and_then(|assets| projects.get(&assets.ldtk_level.handle))
+            .map_or(0, |project| project.json_data().levels.len());
+        if *index >= level_count {
+            console.print(format!("There's no level {index}, there are {level_count}"));
+            continue;
+        }
+        *level_selection = LevelSelection::index(*index);
+        console.print(format!("Loading level {index}"));
+    }
+}
+
+fn save_blueprint(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    block_query: Query<
+        (
+            &GlobalTransform,
+            &BlockSize,
+            &GridCoords,
+            &CastleSection,
+            &BlockMaterial,
+        ),
+        With<CastleBlock>,
+    >,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::SaveBlueprint(name) = command else {
+            continue;
+        };
+        let blueprint = CastleBlueprint::capture(block_query.iter().map(
+            |(transform, size, cell, section, material)| {
+                (
+                    transform.translation().truncate(),
+                    size,
+                    cell,
+                    section,
+                    material,
+                )
+            },
+        ));
+        match blueprint.save(name) {
+            Ok(()) => console.print(format!(
+                "Saved {} blocks to blueprint {name}",
+                blueprint.blocks.len()
+            )),
+            Err(error) => console.print(format!("Couldn't save blueprint {name}: {error}")),
+        }
+    }
+}
+
+fn load_blueprint(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut spawn_events: EventWriter<SpawnBlueprint>,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::LoadBlueprint { name, position } = command else {
+            continue;
+        };
+        match CastleBlueprint::load(name) {
+            Ok(blueprint) => {
+                console.print(format!("Spawning blueprint {name} at {position}"));
+                spawn_events.write(SpawnBlueprint {
+                    blueprint,
+                    position: *position,
+                });
+            }
+            Err(error) => console.print(format!("Couldn't load blueprint {name}: {error}")),
+        }
+    }
+}
+
+fn set_log_level(
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut log_levels: ResMut<LogLevels>,
+) {
+    for command in console_commands.read() {
+        let ConsoleCommand::SetLogLevel { subsystem, level } = command else {
+            continue;
+        };
+        let previous = log_levels.get(*subsystem);
+        log_levels.set(*subsystem, *level);
+        console.print(format!(
+            "{} logging set to {level} (was {previous})",
+            subsystem.name()
+        ));
+    }
+}
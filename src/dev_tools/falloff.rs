@@ -0,0 +1,85 @@
+//! An overlay plotting explosion falloff curves, toggled with F8.
+//!
+//! Draws the impulse each exploding ability's shockwave delivers at every
+//! distance out to its radius, with the other falloff curves faded behind it
+//! for comparison.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_inspector_egui::bevy_egui::{EguiContextPass, EguiContexts, egui};
+
+use crate::demo::{
+    ability_config::{AbilityConfigs, FalloffCurve},
+    balistics::AbilityKind,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<FalloffOverlay>();
+    app.add_systems(
+        Update,
+        toggle_falloff_overlay.run_if(input_just_pressed(FALLOFF_KEY)),
+    );
+    app.add_systems(
+        EguiContextPass,
+        draw_falloff_overlay.run_if(|overlay: Res<FalloffOverlay>| overlay.open),
+    );
+}
+
+const FALLOFF_KEY: KeyCode = KeyCode::F8;
+const GRAPH_SIZE: egui::Vec2 = egui::vec2(240.0, 100.0);
+/// Points sampled along each curve.
+const SAMPLES: usize = 48;
+/// Abilities whose projectiles explode.
+const EXPLODING_ABILITIES: [AbilityKind; 2] = [AbilityKind::Fireball, AbilityKind::Charge];
+
+#[derive(Resource, Default, Debug)]
+struct FalloffOverlay {
+    open: bool,
+}
+
+fn toggle_falloff_overlay(mut overlay: ResMut<FalloffOverlay>) {
+    overlay.open = !overlay.open;
+}
+
+fn draw_falloff_overlay(mut contexts: EguiContexts, ability_configs: AbilityConfigs) {
+    egui::Window::new("Explosion Falloff")
+        .default_pos(egui::pos2(8.0, 260.0))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for ability in EXPLODING_ABILITIES {
+                let explosion = ability_configs.explosion(ability);
+                ui.monospace(format!(
+                    "{ability:?}: {} falloff, radius {:.0}, impulse {:.0}",
+                    explosion.falloff.name(),
+                    explosion.radius,
+                    explosion.base_impulse
+                ));
+                draw_falloff_graph(ui, explosion.falloff);
+            }
+        });
+}
+
+fn draw_falloff_graph(ui: &mut egui::Ui, selected: FalloffCurve) {
+    let (response, painter) = ui.allocate_painter(GRAPH_SIZE, egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+
+    for curve in FalloffCurve::ALL {
+        // Distance runs along the x axis out to the radius, impulse up the y axis.
+        let points = (0..=SAMPLES)
+            .map(|i| {
+                let t = i as f32 / SAMPLES as f32;
+                let factor = curve.factor(t, 1.0);
+                egui::pos2(
+                    rect.left() + t * rect.width(),
+                    rect.bottom() - factor * rect.height(),
+                )
+            })
+            .collect();
+        let color = if curve == selected {
+            egui::Color32::LIGHT_RED
+        } else {
+            egui::Color32::DARK_GRAY
+        };
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+    }
+}
@@ -0,0 +1,131 @@
+//! A free camera and noclip for getting around levels quickly.
+//!
+//! `freecam` in the console detaches the main camera from the player and level
+//! so it can be flown with WASD and zoomed with the mouse wheel. `noclip` lets
+//! player 0 drift through the level without colliding or falling.
+
+use avian2d::prelude::*;
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::demo::{
+    camera::{FreeCamera, PlayerCamera, UiOnlyCamera},
+    player::PlayerIndex,
+};
+
+use super::console::{ConsoleCommand, DevConsole};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, (toggle_freecam, toggle_noclip, fly_freecam));
+}
+
+/// World units per second at normal zoom.
+const FLY_SPEED: f32 = 800.0;
+/// How much one notch of the mouse wheel changes the zoom.
+const WHEEL_ZOOM_FACTOR: f32 = 1.1;
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 10.0;
+
+fn toggle_freecam(
+    mut commands: Commands,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    mut camera_query: Query<
+        (Entity, &mut Projection, Has<FreeCamera>),
+        (With<Camera2d>, Without<PlayerCamera>, Without<UiOnlyCamera>),
+    >,
+) {
+    for command in console_commands.read() {
+        if *command != ConsoleCommand::ToggleFreecam {
+            continue;
+        }
+        let Ok((camera, mut projection, free)) = camera_query.single_mut() else {
+            console.print("There's no main camera to detach");
+            continue;
+        };
+        if free {
+            // Snapping takes back over and restores the level's framing.
+            if let Projection::Orthographic(orthographic) = &mut *projection {
+                orthographic.scale = 1.0;
+            }
+            commands.entity(camera).remove::<FreeCamera>();
+            console.print("Freecam off");
+        } else {
+            commands.entity(camera).insert(FreeCamera);
+            console.print("Freecam on, fly with WASD and zoom with the mouse wheel");
+        }
+    }
+}
+
+fn toggle_noclip(
+    mut commands: Commands,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut console: ResMut<DevConsole>,
+    player_query: Query<(Entity, &PlayerIndex, Has<ColliderDisabled>)>,
+) {
+    for command in console_commands.read() {
+        if *command != ConsoleCommand::ToggleNoclip {
+            continue;
+        }
+        let Some((player, _, noclip)) = player_query.iter().find(|(_, index, _)| index.0 == 0)
+        else {
+            console.print("There's no player to noclip");
+            continue;
+        };
+        if noclip {
+            commands
+                .entity(player)
+                .remove::<(ColliderDisabled, GravityScale)>();
+            console.print("Noclip off");
+        } else {
+            commands
+                .entity(player)
+                .insert((ColliderDisabled, GravityScale(0.0)));
+            console.print("Noclip on");
+        }
+    }
+}
+
+fn fly_freecam(
+    // Real time so the freecam keeps working while the game is slowed down.
+    time: Res<Time<Real>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<FreeCamera>>,
+) {
+    let mut direction = Vec2::ZERO;
+    if keyboard.pressed(KeyCode::KeyW) {
+        direction.y += 1.0;
+    }
+    if keyboard.pressed(KeyCode::KeyS) {
+        direction.y -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::KeyA) {
+        direction.x -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::KeyD) {
+        direction.x += 1.0;
+    }
+    let notches: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+
+    for (mut transform, mut projection) in &mut camera_query {
+        let Projection::Orthographic(orthographic) = &mut *projection else {
+            continue;
+        };
+        // Scrolling up zooms in.
+        orthographic.scale =
+            (orthographic.scale * WHEEL_ZOOM_FACTOR.powf(-notches)).clamp(MIN_SCALE, MAX_SCALE);
+        // Fly faster when zoomed out so crossing the level takes the same time on screen.
+        let step =
+            direction.normalize_or_zero() * FLY_SPEED * orthographic.scale * time.delta_secs();
+        transform.translation += step.extend(0.0);
+    }
+}
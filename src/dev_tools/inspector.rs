@@ -0,0 +1,121 @@
+//! The world inspector, with extra help for debugging the mortar graph.
+//!
+//! Selecting a castle block in the hierarchy draws a line from it to every
+//! block its mortar joints hold it to, colored by how much mortar is left, and
+//! lists each joint's compliance, torque and health above the block's components.
+
+use avian2d::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::{
+    bevy_egui::{EguiContext, EguiContextPass, egui},
+    bevy_inspector::{
+        self,
+        hierarchy::{SelectedEntities, hierarchy_ui},
+    },
+};
+
+use crate::demo::castle::{CastleBlock, MortarHealth};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InspectorSelection>();
+    app.add_systems(EguiContextPass, draw_world_inspector);
+    app.add_systems(Update, draw_selected_block_joints);
+}
+
+const INSPECTOR_SIZE: egui::Vec2 = egui::vec2(320.0, 480.0);
+
+/// The entities picked in the inspector's hierarchy.
+#[derive(Resource, Default)]
+struct InspectorSelection(SelectedEntities);
+
+fn draw_world_inspector(world: &mut World) {
+    let Ok(egui_context) = world
+        .query_filtered::<&EguiContext, With<PrimaryWindow>>()
+        .single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    world.resource_scope(|world, mut selection: Mut<InspectorSelection>| {
+        egui::Window::new("World Inspector")
+            .default_size(INSPECTOR_SIZE)
+            .show(egui_context.get_mut(), |ui| {
+                egui::ScrollArea::both().show(ui, |ui| {
+                    hierarchy_ui(world, ui, &mut selection.0);
+                    ui.separator();
+                    match selection.0.as_slice() {
+                        &[entity] => {
+                            draw_mortar_joints(world, entity, ui);
+                            bevy_inspector::ui_for_entity(world, entity, ui);
+                        }
+                        entities => {
+                            bevy_inspector::ui_for_entities_shared_components(world, entities, ui);
+                        }
+                    }
+                });
+            });
+    });
+}
+
+/// List the joints holding a castle block to its neighbors.
+fn draw_mortar_joints(world: &mut World, entity: Entity, ui: &mut egui::Ui) {
+    let Ok(children) = world
+        .query_filtered::<&Children, With<CastleBlock>>()
+        .get(world, entity)
+    else {
+        return;
+    };
+    let children: Vec<Entity> = children.iter().collect();
+    let mut joint_query = world.query::<(Entity, &FixedJoint, &MortarHealth)>();
+
+    ui.collapsing("Mortar Joints", |ui| {
+        for (joint_entity, joint, mortar) in joint_query.iter_many(world, &children) {
+            let neighbor = other_body(joint, entity);
+            ui.monospace(format!(
+                "{joint_entity} -> {neighbor}\n  compliance {:.1e}, align torque {:.0}\n  force {:.0}, mortar {:.0}/{:.0}",
+                joint.compliance,
+                joint.align_torque,
+                joint.force.length(),
+                mortar.current,
+                mortar.max,
+            ));
+        }
+    });
+}
+
+/// Draw a line from each selected castle block to the blocks it's mortared to.
+fn draw_selected_block_joints(
+    selection: Res<InspectorSelection>,
+    block_query: Query<(&GlobalTransform, &Children), With<CastleBlock>>,
+    joint_query: Query<(&FixedJoint, &MortarHealth)>,
+    transform_query: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    for block in selection.0.iter() {
+        let Ok((transform, children)) = block_query.get(block) else {
+            continue;
+        };
+        let start = transform.translation().truncate();
+        for (joint, mortar) in joint_query.iter_many(children.iter()) {
+            let Ok(neighbor) = transform_query.get(other_body(joint, block)) else {
+                continue;
+            };
+            let health = (mortar.current / mortar.max).clamp(0.0, 1.0);
+            gizmos.line_2d(
+                start,
+                neighbor.translation().truncate(),
+                Color::srgb(1.0 - health, health, 0.0),
+            );
+        }
+    }
+}
+
+/// The body on the other end of a joint from `block`.
+fn other_body(joint: &FixedJoint, block: Entity) -> Entity {
+    if joint.entity1 == block {
+        joint.entity2
+    } else {
+        joint.entity1
+    }
+}
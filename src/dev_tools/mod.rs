@@ -7,9 +7,16 @@ use bevy::{
 };
 use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::prelude::*;
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
 // `InspectorOptions` are completely optional
 
+mod combat_log;
+mod console;
+mod falloff;
+mod freecam;
+mod inspector;
+mod physics_debug;
+mod profiler;
+
 #[derive(Reflect, Resource, Default, InspectorOptions)]
 #[reflect(Resource, InspectorOptions)]
 struct Configuration {
@@ -26,7 +33,15 @@ pub(super) fn plugin(app: &mut App) {
     app.add_plugins(EguiPlugin {
         enable_multipass_for_primary_context: true,
     });
-    app.add_plugins(WorldInspectorPlugin::new());
+    app.add_plugins((
+        combat_log::plugin,
+        console::plugin,
+        falloff::plugin,
+        freecam::plugin,
+        inspector::plugin,
+        physics_debug::plugin,
+        profiler::plugin,
+    ));
     // Toggle the debug overlay for UI.
     app.add_systems(
         Update,
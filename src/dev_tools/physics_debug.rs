@@ -0,0 +1,176 @@
+//! Physics debug overlays, each toggled with its own key:
+//!
+//! - F2: collider outlines
+//! - F3: joint anchors
+//! - F4: shape casts, e.g. the characters' ground sensors
+//! - F5: shockwave radii, shown briefly after each explosion
+//! - F6: mortar stress, outlining castle blocks from green to red as their joints weaken
+//! - F10: sleeping bodies
+
+use avian2d::prelude::*;
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::demo::{
+    castle::{BlockSize, CastleBlock, MortarHealth},
+    collision::Shockwave,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(PhysicsDebugPlugin::default());
+    app.insert_gizmo_config(PhysicsGizmos::none(), GizmoConfig::default());
+    app.init_resource::<PhysicsDebugOverlays>();
+    app.add_systems(
+        Update,
+        (
+            toggle_colliders.run_if(input_just_pressed(COLLIDERS_KEY)),
+            toggle_joints.run_if(input_just_pressed(JOINTS_KEY)),
+            toggle_shape_casts.run_if(input_just_pressed(SHAPE_CASTS_KEY)),
+            toggle_shockwaves.run_if(input_just_pressed(SHOCKWAVES_KEY)),
+            toggle_joint_stress.run_if(input_just_pressed(JOINT_STRESS_KEY)),
+            toggle_sleeping.run_if(input_just_pressed(SLEEPING_KEY)),
+        ),
+    );
+    app.add_systems(
+        Update,
+        (
+            record_shockwaves,
+            draw_shockwaves.run_if(|overlays: Res<PhysicsDebugOverlays>| overlays.shockwaves),
+            draw_joint_stress.run_if(|overlays: Res<PhysicsDebugOverlays>| overlays.joint_stress),
+            draw_sleeping_bodies.run_if(|overlays: Res<PhysicsDebugOverlays>| overlays.sleeping),
+        )
+            .chain(),
+    );
+}
+
+const COLLIDERS_KEY: KeyCode = KeyCode::F2;
+const JOINTS_KEY: KeyCode = KeyCode::F3;
+const SHAPE_CASTS_KEY: KeyCode = KeyCode::F4;
+const SHOCKWAVES_KEY: KeyCode = KeyCode::F5;
+const JOINT_STRESS_KEY: KeyCode = KeyCode::F6;
+const SLEEPING_KEY: KeyCode = KeyCode::F10;
+
+const COLLIDER_COLOR: Color = Color::srgb(0.0, 1.0, 1.0);
+const JOINT_ANCHOR_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+const JOINT_SEPARATION_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
+const SHAPE_CAST_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+const SHAPE_CAST_HIT_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+const SHOCKWAVE_COLOR: Color = Color::srgb(1.0, 0.4, 0.1);
+const SLEEPING_COLOR: Color = Color::srgb(0.4, 0.4, 1.0);
+/// How long a shockwave's radius stays on screen.
+const SHOCKWAVE_DISPLAY_SECS: f32 = 0.5;
+
+/// The overlays that are drawn by us rather than avian's debug renderer.
+#[derive(Resource, Default, Debug)]
+struct PhysicsDebugOverlays {
+    shockwaves: bool,
+    joint_stress: bool,
+    sleeping: bool,
+    /// Recent shockwaves and how many seconds they have left on screen.
+    recent_shockwaves: Vec<(Shockwave, f32)>,
+}
+
+/// Turn a gizmo color on or off.
+fn toggle_color(color: &mut Option<Color>, on: Color) {
+    *color = match color {
+        Some(_) => None,
+        None => Some(on),
+    };
+}
+
+fn toggle_colliders(mut store: ResMut<GizmoConfigStore>) {
+    let (_, gizmos) = store.config_mut::<PhysicsGizmos>();
+    toggle_color(&mut gizmos.collider_color, COLLIDER_COLOR);
+}
+
+fn toggle_joints(mut store: ResMut<GizmoConfigStore>) {
+    let (_, gizmos) = store.config_mut::<PhysicsGizmos>();
+    toggle_color(&mut gizmos.joint_anchor_color, JOINT_ANCHOR_COLOR);
+    toggle_color(&mut gizmos.joint_separation_color, JOINT_SEPARATION_COLOR);
+}
+
+fn toggle_shape_casts(mut store: ResMut<GizmoConfigStore>) {
+    let (_, gizmos) = store.config_mut::<PhysicsGizmos>();
+    toggle_color(&mut gizmos.shapecast_color, SHAPE_CAST_COLOR);
+    toggle_color(&mut gizmos.shapecast_shape_color, SHAPE_CAST_COLOR);
+    toggle_color(&mut gizmos.shapecast_point_color, SHAPE_CAST_HIT_COLOR);
+}
+
+fn toggle_shockwaves(mut overlays: ResMut<PhysicsDebugOverlays>) {
+    overlays.shockwaves = !overlays.shockwaves;
+}
+
+fn toggle_joint_stress(mut overlays: ResMut<PhysicsDebugOverlays>) {
+    overlays.joint_stress = !overlays.joint_stress;
+}
+
+fn toggle_sleeping(mut overlays: ResMut<PhysicsDebugOverlays>) {
+    overlays.sleeping = !overlays.sleeping;
+}
+
+fn record_shockwaves(
+    time: Res<Time>,
+    mut shockwave_events: EventReader<Shockwave>,
+    mut overlays: ResMut<PhysicsDebugOverlays>,
+) {
+    for (_, secs_left) in &mut overlays.recent_shockwaves {
+        *secs_left -= time.delta_secs();
+    }
+    overlays
+        .recent_shockwaves
+        .retain(|(_, secs_left)| *secs_left > 0.0);
+    if overlays.shockwaves {
+        for shockwave in shockwave_events.read() {
+            overlays
+                .recent_shockwaves
+                .push((*shockwave, SHOCKWAVE_DISPLAY_SECS));
+        }
+    } else {
+        shockwave_events.clear();
+    }
+}
+
+fn draw_shockwaves(overlays: Res<PhysicsDebugOverlays>, mut gizmos: Gizmos) {
+    for (shockwave, secs_left) in &overlays.recent_shockwaves {
+        let alpha = secs_left / SHOCKWAVE_DISPLAY_SECS;
+        gizmos.circle_2d(
+            shockwave.origin,
+            shockwave.radius,
+            SHOCKWAVE_COLOR.with_alpha(alpha),
+        );
+    }
+}
+
+/// Outline each castle block by how close its weakest joint is to breaking.
+fn draw_joint_stress(
+    block_query: Query<(&GlobalTransform, &BlockSize, &Children), With<CastleBlock>>,
+    joint_query: Query<&MortarHealth>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, block_size, children) in &block_query {
+        let Some(weakest) = joint_query
+            .iter_many(children.iter())
+            .map(|mortar| mortar.current / mortar.max)
+            .reduce(f32::min)
+        else {
+            continue;
+        };
+        let stress = 1.0 - weakest.clamp(0.0, 1.0);
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        let isometry = Isometry2d::new(
+            translation.truncate(),
+            Rot2::radians(rotation.to_euler(EulerRot::ZYX).0),
+        );
+        gizmos.rect_2d(
+            isometry,
+            block_size.0,
+            Color::srgb(stress, 1.0 - stress, 0.0),
+        );
+    }
+}
+
+/// Outline every body that avian has put to sleep.
+fn draw_sleeping_bodies(sleeping_query: Query<&ColliderAabb, With<Sleeping>>, mut gizmos: Gizmos) {
+    for aabb in &sleeping_query {
+        gizmos.rect_2d(aabb.center(), aabb.size(), SLEEPING_COLOR);
+    }
+}
@@ -0,0 +1,150 @@
+//! A profiling overlay for physics-heavy levels, toggled with F7.
+//!
+//! Shows the frame rate with a graph of recent frame times, along with how many
+//! entities, joints and colliders are alive, how much entity pools are reused
+//! and where each ability's cooldown is.
+
+use avian2d::prelude::*;
+use bevy::{
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+};
+use bevy_inspector_egui::bevy_egui::{EguiContextPass, EguiContexts, egui};
+
+use crate::demo::{
+    balistics::{AbilityKind, CasterCooldowns, ExplosionBundle, FireballBundle},
+    player::{Player, PlayerIndex},
+    pool::{Pool, Poolable},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins((
+        FrameTimeDiagnosticsPlugin::default(),
+        EntityCountDiagnosticsPlugin,
+    ));
+    app.init_resource::<ProfilerOverlay>();
+    app.add_systems(
+        Update,
+        toggle_profiler.run_if(input_just_pressed(PROFILER_KEY)),
+    );
+    app.add_systems(
+        EguiContextPass,
+        draw_profiler.run_if(|overlay: Res<ProfilerOverlay>| overlay.open),
+    );
+}
+
+const PROFILER_KEY: KeyCode = KeyCode::F7;
+const GRAPH_SIZE: egui::Vec2 = egui::vec2(240.0, 60.0);
+/// The frame time at the top of the graph.
+const GRAPH_MAX_MS: f32 = 50.0;
+/// The frame time budget at 60 FPS, drawn as a line across the graph.
+const TARGET_FRAME_MS: f32 = 1000.0 / 60.0;
+
+#[derive(Resource, Default, Debug)]
+struct ProfilerOverlay {
+    open: bool,
+}
+
+fn toggle_profiler(mut overlay: ResMut<ProfilerOverlay>) {
+    overlay.open = !overlay.open;
+}
+
+fn draw_profiler(
+    mut contexts: EguiContexts,
+    diagnostics: Res<DiagnosticsStore>,
+    joint_query: Query<(), With<FixedJoint>>,
+    collider_query: Query<(), (With<Collider>, Without<ColliderDisabled>)>,
+    sleeping_query: Query<(), With<Sleeping>>,
+    player_query: Query<(&PlayerIndex, &CasterCooldowns), With<Player>>,
+    fireball_pool: Res<Pool<FireballBundle>>,
+    explosion_pool: Res<Pool<ExplosionBundle>>,
+) {
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let frame_times: Vec<f32> = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .map(|frame_time| frame_time.values().map(|&ms| ms as f32).collect())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|count| count.value())
+        .unwrap_or_default();
+
+    egui::Window::new("Profiler")
+        .default_pos(egui::pos2(8.0, 8.0))
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let last_frame_ms = frame_times.last().copied().unwrap_or_default();
+            ui.monospace(format!("{fps:.0} FPS ({last_frame_ms:.1} ms)"));
+            draw_frame_time_graph(ui, &frame_times);
+
+            ui.separator();
+            ui.monospace(format!("Entities:  {entities:.0}"));
+            ui.monospace(format!("Joints:    {}", joint_query.iter().count()));
+            ui.monospace(format!("Colliders: {}", collider_query.iter().count()));
+            ui.monospace(format!("Sleeping:  {}", sleeping_query.iter().count()));
+
+            ui.separator();
+            draw_pool_stats(ui, "Fireballs", &fireball_pool);
+            draw_pool_stats(ui, "Explosions", &explosion_pool);
+
+            for (index, cooldowns) in &player_query {
+                ui.separator();
+                ui.monospace(format!("Player {}", index.0 + 1));
+                for ability in [
+                    AbilityKind::Fireball,
+                    AbilityKind::Frost,
+                    AbilityKind::Lightning,
+                    AbilityKind::Charge,
+                ] {
+                    let name = format!("{ability:?}");
+                    if cooldowns.is_ready(ability) {
+                        ui.monospace(format!("{name:<10} ready"));
+                    } else {
+                        ui.monospace(format!(
+                            "{name:<10} {:.2}s",
+                            cooldowns.remaining_secs(ability)
+                        ));
+                    }
+                }
+            }
+        });
+}
+
+fn draw_pool_stats<B: Poolable>(ui: &mut egui::Ui, name: &str, pool: &Pool<B>) {
+    ui.monospace(format!(
+        "{name:<10} {} spawned, {} reused, {} free",
+        pool.spawned,
+        pool.reused,
+        pool.free()
+    ));
+}
+
+fn draw_frame_time_graph(ui: &mut egui::Ui, frame_times: &[f32]) {
+    let (response, painter) = ui.allocate_painter(GRAPH_SIZE, egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+
+    let y_for = |ms: f32| rect.bottom() - (ms / GRAPH_MAX_MS).min(1.0) * rect.height();
+    painter.hline(
+        rect.x_range(),
+        y_for(TARGET_FRAME_MS),
+        egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+    );
+    if frame_times.len() < 2 {
+        return;
+    }
+    let step = rect.width() / (frame_times.len() - 1) as f32;
+    let points = frame_times
+        .iter()
+        .enumerate()
+        .map(|(i, &ms)| egui::pos2(rect.left() + i as f32 * step, y_for(ms)))
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN),
+    ));
+}
@@ -0,0 +1,306 @@
+//! An online leaderboard, enabled with the `leaderboard` feature.
+//!
+//! Every [`PostLeaderboardScore`] the platform layer sends is also posted to
+//! the game's own leaderboard server, so a completed level posts its time and
+//! score, and an endless run its wave and score. The level's top 10 times on
+//! the same difficulty are fetched and shown on the level complete screen.
+//! Requests run on the IO task pool so the game never waits on the network.
+//!
+//! The server is set with the `LEADERBOARD_URL` environment variable at build
+//! time, or at run time on native. If it isn't set or can't be reached, the
+//! screen shows the local best instead, and scores that failed to post are
+//! kept and sent again with the next ones.
+
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures_lite::future},
+    ui::Val::*,
+};
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    demo::{
+        difficulty::Difficulty,
+        level::{LevelAssets, selected_level_identifier},
+    },
+    persistence::{self, BestRecords},
+    platform::{PostLeaderboardScore, time_leaderboard},
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LeaderboardConfig>();
+    app.add_systems(OnEnter(Screen::LevelComplete), spawn_leaderboard_panel);
+    app.add_systems(
+        Update,
+        (
+            post_leaderboard_scores,
+            poll_leaderboard_requests.run_if(in_state(Screen::LevelComplete)),
+        )
+            .chain(),
+    );
+}
+
+const TOP_ENTRIES: usize = 10;
+/// Where scores that couldn't be posted are kept until they can be.
+const PENDING_KEY: &str = "leaderboard_pending_scores";
+
+#[derive(Resource, Debug, Clone)]
+pub struct LeaderboardConfig {
+    /// Base URL of the leaderboard server, or `None` to stay offline.
+    pub endpoint: Option<String>,
+    /// The name runs are posted under.
+    pub player_name: String,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        let runtime_endpoint = std::env::var("LEADERBOARD_URL").ok();
+        #[cfg(target_family = "wasm")]
+        let runtime_endpoint = None;
+        Self {
+            endpoint: runtime_endpoint
+                .or_else(|| option_env!("LEADERBOARD_URL").map(str::to_string))
+                .filter(|endpoint| !endpoint.is_empty()),
+            player_name: "Anonymous".to_string(),
+        }
+    }
+}
+
+/// One score on a leaderboard.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub leaderboard: String,
+    pub name: String,
+    /// Milliseconds on time leaderboards, points on score ones.
+    pub score: i32,
+    /// Whether lower scores are better, like times.
+    #[serde(default)]
+    pub lower_is_better: bool,
+}
+
+/// The level's top times being fetched in the background.
+#[derive(Component)]
+struct LeaderboardRequest(Task<Result<Vec<LeaderboardEntry>, String>>);
+
+/// Filled with the leaderboard once it arrives.
+#[derive(Component)]
+struct LeaderboardList {
+    level: String,
+    difficulty: Difficulty,
+}
+
+/// Post the scores sent this frame, along with any that failed to go out
+/// before, and fetch the top times for a leaderboard panel that just opened.
+fn post_leaderboard_scores(
+    mut commands: Commands,
+    config: Res<LeaderboardConfig>,
+    mut score_events: EventReader<PostLeaderboardScore>,
+    list_query: Query<&LeaderboardList, Added<LeaderboardList>>,
+) {
+    let new_entries: Vec<_> = score_events
+        .read()
+        .map(|event| LeaderboardEntry {
+            leaderboard: event.leaderboard.clone(),
+            name: config.player_name.clone(),
+            score: event.score,
+            lower_is_better: event.lower_is_better,
+        })
+        .collect();
+    let leaderboard = list_query
+        .iter()
+        .next()
+        .map(|list| time_leaderboard(&list.level, list.difficulty));
+    if new_entries.is_empty() && leaderboard.is_none() {
+        return;
+    }
+
+    let mut pending: Vec<LeaderboardEntry> = persistence::load(PENDING_KEY).unwrap_or_default();
+    if !new_entries.is_empty() {
+        pending.extend(new_entries);
+        // Keep the scores before trying to send them. The request is dropped if
+        // the player leaves the screen before it finishes, and they go out next time.
+        persistence::save(PENDING_KEY, &pending);
+    }
+
+    let Some(endpoint) = config.endpoint.clone() else {
+        // Nowhere to send them, but they're kept in case a server is set up later.
+        return;
+    };
+    let task_pool = IoTaskPool::get();
+    let Some(leaderboard) = leaderboard else {
+        task_pool
+            .spawn(async move { post_pending(&endpoint, pending).await })
+            .detach();
+        return;
+    };
+    let task = task_pool.spawn(async move {
+        post_pending(&endpoint, pending).await;
+        fetch_top_entries(&endpoint, &leaderboard).await
+    });
+    commands.spawn((
+        Name::new("Leaderboard Request"),
+        LeaderboardRequest(task),
+        StateScoped(Screen::LevelComplete),
+    ));
+}
+
+/// Post everything that's waiting, keeping whatever fails for next time.
+async fn post_pending(endpoint: &str, pending: Vec<LeaderboardEntry>) {
+    let mut unsent = Vec::new();
+    for entry in pending {
+        if let Err(error) = post_entry(endpoint, &entry).await {
+            warn!("Couldn't post to the leaderboard: {error}");
+            unsent.push(entry);
+        }
+    }
+    persistence::save(PENDING_KEY, &unsent);
+}
+
+async fn post_entry(endpoint: &str, entry: &LeaderboardEntry) -> Result<(), String> {
+    let body = serde_json::to_vec(entry).map_err(|error| error.to_string())?;
+    let mut request = ehttp::Request::post(format!("{endpoint}/scores"), body);
+    request.headers.insert("Content-Type", "application/json");
+    let response = ehttp::fetch_async(request).await?;
+    if !response.ok {
+        return Err(format!("{} {}", response.status, response.status_text));
+    }
+    Ok(())
+}
+
+/// The best times on `leaderboard`, fastest first.
+async fn fetch_top_entries(
+    endpoint: &str,
+    leaderboard: &str,
+) -> Result<Vec<LeaderboardEntry>, String> {
+    let request = ehttp::Request::get(format!(
+        "{endpoint}/scores?leaderboard={}&limit={TOP_ENTRIES}",
+        encode_query_value(leaderboard)
+    ));
+    let response = ehttp::fetch_async(request).await?;
+    if !response.ok {
+        return Err(format!("{} {}", response.status, response.status_text));
+    }
+    let mut entries: Vec<LeaderboardEntry> =
+        serde_json::from_slice(&response.bytes).map_err(|error| error.to_string())?;
+    entries.sort_by_key(|entry| entry.score);
+    entries.truncate(TOP_ENTRIES);
+    Ok(entries)
+}
+
+/// Percent-encode `value` for a URL query, as level names can contain anything.
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn spawn_leaderboard_panel(
+    mut commands: Commands,
+    config: Res<LeaderboardConfig>,
+    records: Res<BestRecords>,
+    difficulty: Res<Difficulty>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+) {
+    let Some(level) = projects
+        .get(&level_assets.ldtk_level.handle)
+        .and_then(|project| selected_level_identifier(&level_selection, project))
+    else {
+        return;
+    };
+    let difficulty = *difficulty;
+    let status = if config.endpoint.is_some() {
+        "Loading leaderboard...".to_string()
+    } else {
+        offline_text(&records, &level, difficulty)
+    };
+    commands.spawn((
+        Name::new("Leaderboard Panel"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Px(20.0),
+            right: Px(20.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(6.0),
+            ..default()
+        },
+        GlobalZIndex(3),
+        StateScoped(Screen::LevelComplete),
+        children![
+            widget::label("Leaderboard"),
+            (
+                Name::new("Leaderboard List"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                LeaderboardList { level, difficulty },
+                children![widget::label(status)],
+            ),
+        ],
+    ));
+}
+
+fn offline_text(records: &BestRecords, level: &str, difficulty: Difficulty) -> String {
+    match records
+        .get(level, difficulty)
+        .and_then(|record| record.best_time)
+    {
+        Some(best) => format!("Offline, your best: {best:.2}s"),
+        None => "Offline".to_string(),
+    }
+}
+
+fn poll_leaderboard_requests(
+    mut commands: Commands,
+    mut request_query: Query<(Entity, &mut LeaderboardRequest)>,
+    list_query: Query<(Entity, &LeaderboardList)>,
+    records: Res<BestRecords>,
+) {
+    for (request_entity, mut request) in &mut request_query {
+        let Some(result) = block_on(future::poll_once(&mut request.0)) else {
+            continue;
+        };
+        commands.entity(request_entity).despawn();
+        for (list_entity, list) in &list_query {
+            let mut list_commands = commands.entity(list_entity);
+            list_commands.despawn_related::<Children>();
+            match &result {
+                Ok(entries) if entries.is_empty() => {
+                    list_commands.with_child(widget::label("No runs yet"));
+                }
+                Ok(entries) => {
+                    list_commands.with_children(|parent| {
+                        for (rank, entry) in entries.iter().enumerate() {
+                            parent.spawn(widget::label(format!(
+                                "{}. {} {:.2}s",
+                                rank + 1,
+                                entry.name,
+                                entry.score as f32 / 1000.0
+                            )));
+                        }
+                    });
+                }
+                Err(error) => {
+                    warn!("Couldn't fetch the leaderboard: {error}");
+                    list_commands.with_child(widget::label(offline_text(
+                        &records,
+                        &list.level,
+                        list.difficulty,
+                    )));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Gameplay log levels per subsystem, changeable while the game is running.
+//!
+//! The game's own log lines are routed through a filter layer that looks up
+//! the level of the subsystem their module belongs to. Change the levels with
+//! the [`LogLevels`] resource, e.g. from the dev console with `log castle debug`.
+//!
+//! Hot systems also open tracing spans when the `gameplay_spans` feature is
+//! enabled, so their work shows up grouped in a profiler like Tracy.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use bevy::{
+    log::{
+        BoxedLayer, DEFAULT_FILTER,
+        tracing::{Metadata, subscriber::Interest},
+        tracing_subscriber::{Layer, filter::LevelFilter, layer::Context},
+    },
+    prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LogLevels>();
+    app.add_systems(
+        Update,
+        sync_log_levels.run_if(resource_changed::<LogLevels>),
+    );
+}
+
+/// Filter for [`LogPlugin`], letting the game's own logs through at any
+/// level so the subsystem layer can decide what to show.
+pub fn log_filter() -> String {
+    format!("{DEFAULT_FILTER},{}=trace", env!("CARGO_CRATE_NAME"))
+}
+
+/// The layer for [`LogPlugin`] that filters the game's logs by subsystem.
+pub fn log_layer(_app: &mut App) -> Option<BoxedLayer> {
+    Some(Box::new(SubsystemFilter))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogSubsystem {
+    Physics,
+    Abilities,
+    Castle,
+    /// Everything else in the game.
+    Other,
+}
+
+impl LogSubsystem {
+    pub const ALL: [Self; 4] = [Self::Physics, Self::Abilities, Self::Castle, Self::Other];
+
+    /// The modules each subsystem logs from.
+    fn modules(self) -> &'static [&'static str] {
+        match self {
+            Self::Physics => &["collision", "fluids", "movement", "walls"],
+            Self::Abilities => &["balistics", "elements", "skills", "ability_config"],
+            Self::Castle => &["castle", "sections", "blueprint"],
+            Self::Other => &[],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Physics => "physics",
+            Self::Abilities => "abilities",
+            Self::Castle => "castle",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|subsystem| subsystem.name() == name)
+    }
+
+    /// The subsystem a log target belongs to, or `None` if it isn't the game's.
+    fn for_target(target: &str) -> Option<Self> {
+        let module = target
+            .strip_prefix(env!("CARGO_CRATE_NAME"))?
+            .rsplit("::")
+            .next()
+            .unwrap_or_default();
+        Some(
+            Self::ALL
+                .into_iter()
+                .find(|subsystem| subsystem.modules().contains(&module))
+                .unwrap_or(Self::Other),
+        )
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How much each gameplay subsystem logs.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct LogLevels {
+    levels: [LevelFilter; LogSubsystem::ALL.len()],
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            levels: [LevelFilter::INFO; LogSubsystem::ALL.len()],
+        }
+    }
+}
+
+impl LogLevels {
+    pub fn get(&self, subsystem: LogSubsystem) -> LevelFilter {
+        self.levels[subsystem.index()]
+    }
+
+    pub fn set(&mut self, subsystem: LogSubsystem, level: LevelFilter) {
+        self.levels[subsystem.index()] = level;
+    }
+}
+
+/// The levels from [`LogLevels`], readable from the logging layer on any thread.
+static ACTIVE_LEVELS: [AtomicU8; LogSubsystem::ALL.len()] =
+    [const { AtomicU8::new(LEVEL_INFO) }; LogSubsystem::ALL.len()];
+
+const LEVEL_INFO: u8 = 3;
+/// Levels by how much they let through, matching what's stored in [`ACTIVE_LEVELS`].
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::OFF,
+    LevelFilter::ERROR,
+    LevelFilter::WARN,
+    LevelFilter::INFO,
+    LevelFilter::DEBUG,
+    LevelFilter::TRACE,
+];
+
+fn sync_log_levels(levels: Res<LogLevels>) {
+    for subsystem in LogSubsystem::ALL {
+        let level = levels.get(subsystem);
+        let stored = LEVELS.iter().position(|l| *l == level).unwrap_or_default() as u8;
+        ACTIVE_LEVELS[subsystem.index()].store(stored, Ordering::Relaxed);
+    }
+}
+
+struct SubsystemFilter;
+
+impl<S: bevy::log::tracing::Subscriber> Layer<S> for SubsystemFilter {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // The game's callsites have to be checked every time, as their level can change.
+        if LogSubsystem::for_target(metadata.target()).is_some() {
+            Interest::sometimes()
+        } else {
+            Interest::always()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        let Some(subsystem) = LogSubsystem::for_target(metadata.target()) else {
+            return true;
+        };
+        let stored = ACTIVE_LEVELS[subsystem.index()].load(Ordering::Relaxed);
+        *metadata.level() <= LEVELS[stored as usize]
+    }
+}
@@ -8,13 +8,24 @@ mod audio;
 mod demo;
 #[cfg(feature = "dev")]
 mod dev_tools;
+#[cfg(feature = "leaderboard")]
+mod leaderboard;
+mod logging;
 mod menus;
+mod persistence;
+mod platform;
 mod screens;
+mod settings;
 mod theme;
 
-use bevy::{asset::AssetMetaCheck, prelude::*};
+use bevy::{asset::AssetMetaCheck, log::LogPlugin, prelude::*};
 
 fn main() -> AppExit {
+    // Time the heaviest physics systems instead of playing, see `src/demo/bench.rs`.
+    #[cfg(feature = "gameplay_spans")]
+    if std::env::args().any(|arg| arg == "--bench-mode") {
+        return demo::bench::run();
+    }
     App::new().add_plugins(AppPlugin).run()
 }
 
@@ -32,6 +43,11 @@ impl Plugin for AppPlugin {
                     meta_check: AssetMetaCheck::Never,
                     ..default()
                 })
+                .set(LogPlugin {
+                    filter: logging::log_filter(),
+                    custom_layer: logging::log_layer,
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Window {
                         title: "Gamejam2".to_string(),
@@ -50,8 +66,14 @@ impl Plugin for AppPlugin {
             demo::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
+            #[cfg(feature = "leaderboard")]
+            leaderboard::plugin,
+            logging::plugin,
             menus::plugin,
+            persistence::plugin,
+            platform::plugin,
             screens::plugin,
+            settings::plugin,
             theme::plugin,
         ));
 
@@ -98,5 +120,10 @@ struct Pause(pub bool);
 struct PausableSystems;
 
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn((Name::new("Camera"), Camera2d));
+    commands.spawn((
+        Name::new("Camera"),
+        Camera2d,
+        // Spatial sound effects are heard from the camera's point of view.
+        SpatialListener::new(audio::LISTENER_EAR_GAP),
+    ));
 }
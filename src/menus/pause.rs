@@ -2,7 +2,7 @@
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
-use crate::{menus::Menu, screens::Screen, theme::widget};
+use crate::{demo::rng::GameRng, menus::Menu, screens::Screen, theme::widget};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Pause), spawn_pause_menu);
@@ -12,7 +12,7 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-fn spawn_pause_menu(mut commands: Commands) {
+fn spawn_pause_menu(mut commands: Commands, game_rng: Res<GameRng>) {
     commands.spawn((
         widget::ui_root("Pause Menu"),
         GlobalZIndex(2),
@@ -20,12 +20,19 @@ fn spawn_pause_menu(mut commands: Commands) {
         children![
             widget::header("Game paused"),
             widget::button("Continue", close_menu),
+            widget::button("Save game", open_save_menu),
             widget::button("Settings", open_settings_menu),
             widget::button("Quit to title", quit_to_title),
+            // Handy for reproducing a run with `GAME_SEED`.
+            widget::label(format!("Seed: {}", game_rng.seed())),
         ],
     ));
 }
 
+fn open_save_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::SaveGame);
+}
+
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
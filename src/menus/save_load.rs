@@ -0,0 +1,153 @@
+//! The save and load menus, listing each save slot.
+//!
+//! Saving is done from the pause menu and loading from the main menu.
+
+use bevy::{
+    ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*,
+};
+
+use crate::{
+    asset_tracking::ResourceHandles,
+    demo::progress::Progress,
+    menus::Menu,
+    persistence::{SAVE_SLOTS, SaveData, load_slot, save_slot},
+    screens::Screen,
+    settings::Settings,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::SaveGame), spawn_save_menu);
+    app.add_systems(OnEnter(Menu::LoadGame), spawn_load_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(
+            in_state(Menu::SaveGame)
+                .or(in_state(Menu::LoadGame))
+                .and(input_just_pressed(KeyCode::Escape)),
+        ),
+    );
+}
+
+fn spawn_save_menu(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Save Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::SaveGame),
+        children![
+            widget::header("Save game"),
+            save_slot_list(),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+fn spawn_load_menu(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Load Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::LoadGame),
+        children![
+            widget::header("Load game"),
+            load_slot_list(),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+fn save_slot_list() -> impl Bundle {
+    (
+        slot_list_node(),
+        Children::spawn(SpawnIter(SAVE_SLOTS.into_iter().map(|slot| {
+            widget::button(
+                slot_summary(slot),
+                move |_: Trigger<Pointer<Click>>,
+                      progress: Res<Progress>,
+                      settings: Res<Settings>,
+                      mut next_menu: ResMut<NextState<Menu>>| {
+                    let data = SaveData {
+                        progress: progress.clone(),
+                        settings: settings.clone(),
+                        ..default()
+                    };
+                    save_slot(slot, &data);
+                    next_menu.set(Menu::Pause);
+                },
+            )
+        }))),
+    )
+}
+
+fn load_slot_list() -> impl Bundle {
+    (
+        slot_list_node(),
+        Children::spawn(SpawnIter(SAVE_SLOTS.into_iter().map(|slot| {
+            widget::button(
+                slot_summary(slot),
+                move |_: Trigger<Pointer<Click>>,
+                      mut commands: Commands,
+                      mut settings: ResMut<Settings>,
+                      resource_handles: Res<ResourceHandles>,
+                      mut next_screen: ResMut<NextState<Screen>>| {
+                    let Some(data) = load_slot(slot) else {
+                        return;
+                    };
+                    info!("Loaded game from {slot}");
+                    commands.insert_resource(data.progress);
+                    *settings = data.settings;
+                    next_screen.set(if resource_handles.is_all_done() {
+                        Screen::LevelSelect
+                    } else {
+                        Screen::Loading
+                    });
+                },
+            )
+        }))),
+    )
+}
+
+fn slot_list_node() -> impl Bundle {
+    (
+        Name::new("Save Slots"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(20.0),
+            ..default()
+        },
+    )
+}
+
+/// A button label summarizing what's saved in `slot`.
+fn slot_summary(slot: &str) -> String {
+    match load_slot(slot) {
+        Some(data) => {
+            let stats = &data.progress.stats;
+            format!(
+                "{slot}: {} cleared, {:.0} min",
+                stats.levels_completed,
+                stats.play_time_secs / 60.0
+            )
+        }
+        None => format!("{slot}: empty"),
+    }
+}
+
+fn go_back_on_click(
+    _: Trigger<Pointer<Click>>,
+    screen: Res<State<Screen>>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    next_menu.set(if screen.get() == &Screen::Title {
+        Menu::Main
+    } else {
+        Menu::Pause
+    });
+}
+
+fn go_back(screen: Res<State<Screen>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(if screen.get() == &Screen::Title {
+        Menu::Main
+    } else {
+        Menu::Pause
+    });
+}
@@ -2,9 +2,17 @@
 //!
 //! Additional settings and accessibility options should go here.
 
-use bevy::{audio::Volume, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+use bevy::{
+    ecs::system::IntoObserverSystem, input::common_conditions::input_just_pressed, prelude::*,
+    ui::Val::*,
+};
 
-use crate::{menus::Menu, screens::Screen, theme::prelude::*};
+use crate::{
+    menus::Menu,
+    screens::Screen,
+    settings::{MAX_VOLUME, MIN_VOLUME, Settings},
+    theme::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::Settings), spawn_settings_menu);
@@ -13,10 +21,25 @@ pub(super) fn plugin(app: &mut App) {
         go_back.run_if(in_state(Menu::Settings).and(input_just_pressed(KeyCode::Escape))),
     );
 
-    app.register_type::<GlobalVolumeLabel>();
+    app.register_type::<VolumeLabel>();
+    app.register_type::<VolumeBarFill>();
+    app.register_type::<WindowModeLabel>();
+    app.register_type::<VsyncLabel>();
+    app.register_type::<EffectLabel>();
+    app.register_type::<ColorPaletteLabel>();
+    app.register_type::<GameSpeedLabel>();
     app.add_systems(
         Update,
-        update_global_volume_label.run_if(in_state(Menu::Settings)),
+        (
+            update_volume_labels,
+            update_volume_bars,
+            update_window_mode_label,
+            update_vsync_label,
+            update_effect_labels,
+            update_color_palette_label,
+            update_game_speed_label,
+        )
+            .run_if(in_state(Menu::Settings)),
     );
 }
 
@@ -44,63 +67,326 @@ fn settings_grid() -> impl Bundle {
             ..default()
         },
         children![
+            settings_label("Master Volume"),
+            volume_widget(VolumeChannel::Master),
+            settings_label("Music Volume"),
+            volume_widget(VolumeChannel::Music),
+            settings_label("Sound Effects Volume"),
+            volume_widget(VolumeChannel::SoundEffects),
+            settings_label("Window Mode"),
+            stepper_widget(
+                "Window Mode Widget",
+                WindowModeLabel,
+                previous_window_mode,
+                next_window_mode
+            ),
+            settings_label("VSync"),
+            stepper_widget("VSync Widget", VsyncLabel, disable_vsync, enable_vsync),
+            settings_label("Bloom"),
+            effect_widget(Effect::Bloom),
+            settings_label("Shockwave Aberration"),
+            effect_widget(Effect::ChromaticAberration),
+            settings_label("Hitstop Desaturation"),
+            effect_widget(Effect::HitstopDesaturation),
+            settings_label("Status Colors"),
+            stepper_widget(
+                "Color Palette Widget",
+                ColorPaletteLabel,
+                previous_color_palette,
+                next_color_palette
+            ),
+            settings_label("Status Icons"),
+            effect_widget(Effect::StatusIcons),
+            settings_label("Health Bars"),
+            effect_widget(Effect::HealthBars),
+            settings_label("Screen Shake"),
+            effect_widget(Effect::ScreenShake),
+            settings_label("Flashes"),
+            effect_widget(Effect::Flashes),
+            settings_label("Game Speed"),
+            stepper_widget(
+                "Game Speed Widget",
+                GameSpeedLabel,
+                lower_game_speed,
+                raise_game_speed
+            ),
+        ],
+    )
+}
+
+fn settings_label(text: &'static str) -> impl Bundle {
+    (
+        widget::label(text),
+        Node {
+            justify_self: JustifySelf::End,
+            ..default()
+        },
+    )
+}
+
+/// Which volume a volume widget controls.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+enum VolumeChannel {
+    Master,
+    Music,
+    SoundEffects,
+}
+
+impl VolumeChannel {
+    fn get(self, settings: &Settings) -> f32 {
+        match self {
+            Self::Master => settings.master_volume,
+            Self::Music => settings.music_volume,
+            Self::SoundEffects => settings.sfx_volume,
+        }
+    }
+
+    fn get_mut(self, settings: &mut Settings) -> &mut f32 {
+        match self {
+            Self::Master => &mut settings.master_volume,
+            Self::Music => &mut settings.music_volume,
+            Self::SoundEffects => &mut settings.sfx_volume,
+        }
+    }
+}
+
+const VOLUME_STEP: f32 = 0.1;
+const GAME_SPEED_STEP: f32 = 0.1;
+const VOLUME_BAR_WIDTH: f32 = 120.0;
+
+/// A slider for one volume channel: a bar showing the level between `-` and `+` buttons.
+fn volume_widget(channel: VolumeChannel) -> impl Bundle {
+    (
+        Name::new("Volume Widget"),
+        Node {
+            justify_self: JustifySelf::Start,
+            align_items: AlignItems::Center,
+            column_gap: Px(10.0),
+            ..default()
+        },
+        children![
+            widget::button_small(
+                "-",
+                move |_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>| {
+                    let volume = channel.get_mut(&mut settings);
+                    *volume = (*volume - VOLUME_STEP).max(MIN_VOLUME);
+                }
+            ),
             (
-                widget::label("Master Volume"),
+                Name::new("Volume Bar"),
                 Node {
-                    justify_self: JustifySelf::End,
+                    width: Px(VOLUME_BAR_WIDTH),
+                    height: Px(12.0),
                     ..default()
+                },
+                BackgroundColor(ui_palette::BUTTON_PRESSED_BACKGROUND),
+                children![(
+                    Name::new("Volume Bar Fill"),
+                    Node {
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(ui_palette::LABEL_TEXT),
+                    VolumeBarFill(channel),
+                )],
+            ),
+            widget::button_small(
+                "+",
+                move |_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>| {
+                    let volume = channel.get_mut(&mut settings);
+                    *volume = (*volume + VOLUME_STEP).min(MAX_VOLUME);
                 }
             ),
-            global_volume_widget(),
+            (widget::label(""), VolumeLabel(channel)),
         ],
     )
 }
 
-fn global_volume_widget() -> impl Bundle {
+/// A value label between `-` and `+` buttons.
+fn stepper_widget<M1, M2>(
+    name: &'static str,
+    label_marker: impl Component,
+    lower: impl IntoObserverSystem<Pointer<Click>, (), M1>,
+    raise: impl IntoObserverSystem<Pointer<Click>, (), M2>,
+) -> impl Bundle {
     (
-        Name::new("Global Volume Widget"),
+        Name::new(name),
         Node {
             justify_self: JustifySelf::Start,
+            align_items: AlignItems::Center,
             ..default()
         },
         children![
-            widget::button_small("-", lower_global_volume),
+            widget::button_small("-", lower),
             (
-                Name::new("Current Volume"),
+                Name::new("Current Value"),
                 Node {
                     padding: UiRect::horizontal(Px(10.0)),
                     justify_content: JustifyContent::Center,
                     ..default()
                 },
-                children![(widget::label(""), GlobalVolumeLabel)],
+                children![(widget::label(""), label_marker)],
             ),
-            widget::button_small("+", raise_global_volume),
+            widget::button_small("+", raise),
         ],
     )
 }
 
-const MIN_VOLUME: f32 = 0.0;
-const MAX_VOLUME: f32 = 2.0;
+/// Which visual effect an effect widget toggles.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+enum Effect {
+    Bloom,
+    ChromaticAberration,
+    HitstopDesaturation,
+    StatusIcons,
+    HealthBars,
+    ScreenShake,
+    Flashes,
+}
+
+impl Effect {
+    fn get(self, settings: &Settings) -> bool {
+        match self {
+            Self::Bloom => settings.bloom,
+            Self::ChromaticAberration => settings.chromatic_aberration,
+            Self::HitstopDesaturation => settings.hitstop_desaturation,
+            Self::StatusIcons => settings.status_icons,
+            Self::HealthBars => settings.health_bars,
+            Self::ScreenShake => settings.screen_shake,
+            Self::Flashes => settings.flashes,
+        }
+    }
 
-fn lower_global_volume(_: Trigger<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() - 0.1).max(MIN_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+    fn get_mut(self, settings: &mut Settings) -> &mut bool {
+        match self {
+            Self::Bloom => &mut settings.bloom,
+            Self::ChromaticAberration => &mut settings.chromatic_aberration,
+            Self::HitstopDesaturation => &mut settings.hitstop_desaturation,
+            Self::StatusIcons => &mut settings.status_icons,
+            Self::HealthBars => &mut settings.health_bars,
+            Self::ScreenShake => &mut settings.screen_shake,
+            Self::Flashes => &mut settings.flashes,
+        }
+    }
 }
 
-fn raise_global_volume(_: Trigger<Pointer<Click>>, mut global_volume: ResMut<GlobalVolume>) {
-    let linear = (global_volume.volume.to_linear() + 0.1).min(MAX_VOLUME);
-    global_volume.volume = Volume::Linear(linear);
+fn effect_widget(effect: Effect) -> impl Bundle {
+    stepper_widget(
+        "Effect Widget",
+        EffectLabel(effect),
+        move |_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>| {
+            *effect.get_mut(&mut settings) = false;
+        },
+        move |_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>| {
+            *effect.get_mut(&mut settings) = true;
+        },
+    )
+}
+
+fn previous_window_mode(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.window_mode = settings.window_mode.previous();
 }
 
+fn next_window_mode(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.window_mode = settings.window_mode.next();
+}
+
+fn previous_color_palette(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.color_palette = settings.color_palette.previous();
+}
+
+fn next_color_palette(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.color_palette = settings.color_palette.next();
+}
+
+fn lower_game_speed(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    let speed = settings.game_speed - GAME_SPEED_STEP;
+    settings.set_game_speed(speed);
+}
+
+fn raise_game_speed(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    let speed = settings.game_speed + GAME_SPEED_STEP;
+    settings.set_game_speed(speed);
+}
+
+fn disable_vsync(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.vsync = false;
+}
+
+fn enable_vsync(_: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>) {
+    settings.vsync = true;
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct VolumeLabel(VolumeChannel);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct VolumeBarFill(VolumeChannel);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct WindowModeLabel;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct VsyncLabel;
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-struct GlobalVolumeLabel;
+struct EffectLabel(Effect);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ColorPaletteLabel;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct GameSpeedLabel;
+
+fn update_volume_labels(settings: Res<Settings>, mut labels: Query<(&VolumeLabel, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        let percent = 100.0 * label.0.get(&settings);
+        text.0 = format!("{percent:3.0}%");
+    }
+}
+
+fn update_volume_bars(settings: Res<Settings>, mut bars: Query<(&VolumeBarFill, &mut Node)>) {
+    for (bar, mut node) in &mut bars {
+        node.width = Percent(100.0 * bar.0.get(&settings) / MAX_VOLUME);
+    }
+}
+
+fn update_window_mode_label(
+    settings: Res<Settings>,
+    mut label: Single<&mut Text, With<WindowModeLabel>>,
+) {
+    label.0 = settings.window_mode.label().to_string();
+}
+
+fn update_vsync_label(settings: Res<Settings>, mut label: Single<&mut Text, With<VsyncLabel>>) {
+    label.0 = if settings.vsync { "On" } else { "Off" }.to_string();
+}
+
+fn update_effect_labels(settings: Res<Settings>, mut labels: Query<(&EffectLabel, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        text.0 = if label.0.get(&settings) { "On" } else { "Off" }.to_string();
+    }
+}
+
+fn update_color_palette_label(
+    settings: Res<Settings>,
+    mut label: Single<&mut Text, With<ColorPaletteLabel>>,
+) {
+    label.0 = settings.color_palette.label().to_string();
+}
 
-fn update_global_volume_label(
-    global_volume: Res<GlobalVolume>,
-    mut label: Single<&mut Text, With<GlobalVolumeLabel>>,
+fn update_game_speed_label(
+    settings: Res<Settings>,
+    mut label: Single<&mut Text, With<GameSpeedLabel>>,
 ) {
-    let percent = 100.0 * global_volume.volume.to_linear();
+    let percent = 100.0 * settings.game_speed;
     label.0 = format!("{percent:3.0}%");
 }
 
@@ -0,0 +1,329 @@
+//! Saving data between runs.
+//!
+//! Values are stored as JSON under a key by a [`StorageBackend`]: as files in
+//! the user's data directory on native, and in `localStorage` on the web.
+//!
+//! [`BestRecords`] keeps each level's best time and high score, and the best
+//! endless run, for each difficulty, and is written back whenever it changes. Game progress is
+//! saved to named slots as [`SaveData`] from the save and load menus.
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    demo::{difficulty::Difficulty, progress::Progress},
+    settings::Settings,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(load::<BestRecords>(BEST_RECORDS_KEY).unwrap_or_default());
+    app.add_systems(
+        Update,
+        save_best_records.run_if(resource_changed::<BestRecords>),
+    );
+}
+
+/// Name of the folder (or `localStorage` key prefix) everything is saved under.
+const SAVE_NAMESPACE: &str = "gamejam2";
+const BEST_RECORDS_KEY: &str = "best_records";
+
+/// The best results achieved on a single level.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct LevelRecord {
+    /// Fastest completion time in seconds.
+    pub best_time: Option<f32>,
+    pub high_score: u32,
+}
+
+/// The best endless mode run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct EndlessRecord {
+    pub best_wave: u32,
+    pub high_score: u32,
+    /// Longest survival time in seconds.
+    pub longest_time: f32,
+}
+
+/// Best times and high scores for every level and difficulty.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct BestRecords {
+    /// Keyed by [`Difficulty::record_key`] of the LDtk level identifier.
+    pub levels: HashMap<String, LevelRecord>,
+    /// The best endless run on Normal.
+    pub endless: EndlessRecord,
+    /// The best endless runs on the other difficulties.
+    pub endless_by_difficulty: HashMap<Difficulty, EndlessRecord>,
+}
+
+impl BestRecords {
+    pub fn get(&self, level: &str, difficulty: Difficulty) -> Option<&LevelRecord> {
+        self.levels.get(&difficulty.record_key(level))
+    }
+
+    /// Record a completed run, returning whether it beat the previous time or
+    /// score on the same difficulty.
+    pub fn submit(&mut self, level: &str, difficulty: Difficulty, time: f32, score: u32) -> bool {
+        let record = self.levels.entry(difficulty.record_key(level)).or_default();
+        let mut improved = false;
+        if record.best_time.is_none_or(|best| time < best) {
+            record.best_time = Some(time);
+            improved = true;
+        }
+        if score > record.high_score {
+            record.high_score = score;
+            improved = true;
+        }
+        improved
+    }
+
+    pub fn endless(&self, difficulty: Difficulty) -> EndlessRecord {
+        match difficulty {
+            Difficulty::Normal => self.endless,
+            _ => self
+                .endless_by_difficulty
+                .get(&difficulty)
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Record a finished endless run, returning whether it beat the previous
+    /// wave or score on the same difficulty.
+    pub fn submit_endless(
+        &mut self,
+        difficulty: Difficulty,
+        wave: u32,
+        time: f32,
+        score: u32,
+    ) -> bool {
+        let record = match difficulty {
+            Difficulty::Normal => &mut self.endless,
+            _ => self.endless_by_difficulty.entry(difficulty).or_default(),
+        };
+        record.longest_time = record.longest_time.max(time);
+        let mut improved = false;
+        if wave > record.best_wave {
+            record.best_wave = wave;
+            improved = true;
+        }
+        if score > record.high_score {
+            record.high_score = score;
+            improved = true;
+        }
+        improved
+    }
+}
+
+/// The names of the save slots offered in the save and load menus.
+pub const SAVE_SLOTS: [&str; 3] = ["Slot 1", "Slot 2", "Slot 3"];
+const SAVE_DATA_VERSION: u32 = 1;
+
+/// Everything stored in a save slot.
+///
+/// Every struct in here uses `#[serde(default)]`, so fields added later are
+/// simply filled with their defaults when loading an older save, and fields
+/// that no longer exist are ignored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SaveData {
+    /// Bumped whenever a change needs old saves to be migrated.
+    pub version: u32,
+    pub progress: Progress,
+    pub settings: Settings,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_DATA_VERSION,
+            progress: Progress::default(),
+            settings: Settings::default(),
+        }
+    }
+}
+
+fn slot_key(slot: &str) -> String {
+    format!("save_{}", slot.to_lowercase().replace(' ', "_"))
+}
+
+pub fn save_slot(slot: &str, data: &SaveData) {
+    info!("Saving game to {slot}");
+    save(&slot_key(slot), data);
+}
+
+pub fn load_slot(slot: &str) -> Option<SaveData> {
+    load(&slot_key(slot))
+}
+
+fn save_best_records(records: Res<BestRecords>) {
+    // Skip the write triggered by inserting the resource on startup.
+    if records.is_added() {
+        return;
+    }
+    save(BEST_RECORDS_KEY, &*records);
+}
+
+/// Where saved values are kept. Values are JSON strings stored under a key.
+pub trait StorageBackend: Send + Sync + 'static {
+    fn read(&self, key: &str) -> Option<String>;
+    fn write(&self, key: &str, contents: &str) -> Result<(), String>;
+}
+
+static BACKEND: OnceLock<Box<dyn StorageBackend>> = OnceLock::new();
+
+fn backend() -> &'static dyn StorageBackend {
+    BACKEND.get_or_init(default_backend).as_ref()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn default_backend() -> Box<dyn StorageBackend> {
+    Box::new(FileStorage::new(FileStorage::default_dir()))
+}
+
+#[cfg(target_family = "wasm")]
+fn default_backend() -> Box<dyn StorageBackend> {
+    match LocalStorage::local_storage() {
+        Some(_) => Box::new(LocalStorage),
+        None => {
+            // e.g. when the browser blocks storage, the game still plays but nothing persists.
+            warn!("localStorage is unavailable, nothing will be saved after the page closes");
+            Box::new(MemoryStorage::default())
+        }
+    }
+}
+
+/// Read the value saved under `key`, if there is one and it can be parsed.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let contents = backend().read(key)?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Ignoring unreadable save data for {key}: {error}");
+            None
+        }
+    }
+}
+
+/// Save `value` under `key`, logging rather than failing if it can't be written.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let contents = match serde_json::to_string_pretty(value) {
+        Ok(contents) => contents,
+        Err(error) => {
+            warn!("Failed to serialize save data for {key}: {error}");
+            return;
+        }
+    };
+    if let Err(error) = backend().write(key, &contents) {
+        warn!("Failed to save {key}: {error}");
+    }
+}
+
+/// Keeps values in memory only, so they're lost when the game closes.
+#[cfg(target_family = "wasm")]
+#[derive(Default, Debug)]
+pub struct MemoryStorage {
+    values: std::sync::Mutex<HashMap<String, String>>,
+}
+
+#[cfg(target_family = "wasm")]
+impl StorageBackend for MemoryStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        self.values.lock().ok()?.get(key).cloned()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        self.values
+            .lock()
+            .map_err(|error| error.to_string())?
+            .insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+/// Stores each value as a JSON file in a directory.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl FileStorage {
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The platform's per-user data directory, falling back to the working directory.
+    pub fn default_dir() -> std::path::PathBuf {
+        use std::{env, path::PathBuf};
+
+        let base = if cfg!(target_os = "windows") {
+            env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            env::var_os("XDG_DATA_HOME").map(PathBuf::from).or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+        };
+        base.unwrap_or_default().join(SAVE_NAMESPACE)
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl StorageBackend for FileStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)).ok()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        let path = self.path(key);
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|()| std::fs::write(&path, contents))
+            .map_err(|error| format!("{}: {error}", path.display()))
+    }
+}
+
+/// Stores each value in the browser's `localStorage`, under a namespaced key.
+#[cfg(target_family = "wasm")]
+pub struct LocalStorage;
+
+#[cfg(target_family = "wasm")]
+impl LocalStorage {
+    /// The page's `localStorage`, if the browser allows it.
+    ///
+    /// Looked up on every use since browser handles can't be kept in a `Send` static.
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn storage_key(key: &str) -> String {
+        format!("{SAVE_NAMESPACE}.{key}")
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl StorageBackend for LocalStorage {
+    fn read(&self, key: &str) -> Option<String> {
+        Self::local_storage()?
+            .get_item(&Self::storage_key(key))
+            .ok()
+            .flatten()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        let storage = Self::local_storage().ok_or("localStorage is unavailable")?;
+        storage
+            .set_item(&Self::storage_key(key), contents)
+            .map_err(|_| "localStorage refused the write, it may be full".to_string())
+    }
+}
@@ -0,0 +1,131 @@
+//! Turn what happens in the game into platform events.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    demo::{
+        attract::attract_mode_active,
+        difficulty::Difficulty,
+        endless::EndlessRun,
+        game_mode::{GameMode, game_mode_is},
+        level::{LevelAssets, selected_level_identifier},
+        score::Score,
+        sections::SectionDestroyed,
+        timer::GameTimer,
+    },
+    platform::{
+        Achievement, PostLeaderboardScore, SetRichPresence, UnlockAchievement,
+        endless_score_leaderboard, endless_wave_leaderboard, score_leaderboard, time_leaderboard,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::LevelComplete),
+        report_level_completion.run_if(not(attract_mode_active)),
+    );
+    app.add_systems(
+        OnEnter(Screen::GameOver),
+        report_endless_run.run_if(game_mode_is(GameMode::Endless).and(not(attract_mode_active))),
+    );
+    app.add_systems(
+        Update,
+        (
+            unlock_demolition.run_if(on_event::<SectionDestroyed>),
+            unlock_chain_reaction.run_if(resource_changed::<Score>),
+        )
+            .run_if(in_state(Screen::Gameplay).and(not(attract_mode_active))),
+    );
+    app.add_systems(Update, update_rich_presence.run_if(state_changed::<Screen>));
+}
+
+/// Blocks in one chain that earn [`Achievement::ChainReaction`].
+const CHAIN_REACTION_BLOCKS: u32 = 25;
+
+fn selected_level(
+    level_selection: &LevelSelection,
+    level_assets: &LevelAssets,
+    projects: &Assets<LdtkProject>,
+) -> Option<String> {
+    let project = projects.get(&level_assets.ldtk_level.handle)?;
+    selected_level_identifier(level_selection, project)
+}
+
+fn report_level_completion(
+    timer: Res<GameTimer>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    mut achievement_events: EventWriter<UnlockAchievement>,
+    mut score_events: EventWriter<PostLeaderboardScore>,
+) {
+    achievement_events.write(UnlockAchievement(Achievement::FirstClear));
+    let Some(level) = selected_level(&level_selection, &level_assets, &projects) else {
+        return;
+    };
+    let millis = (timer.elapsed_secs() * 1000.0).round() as i32;
+    score_events.write(PostLeaderboardScore {
+        leaderboard: time_leaderboard(&level, *difficulty),
+        score: millis,
+        lower_is_better: true,
+    });
+    score_events.write(PostLeaderboardScore {
+        leaderboard: score_leaderboard(&level, *difficulty),
+        score: score.points.try_into().unwrap_or(i32::MAX),
+        lower_is_better: false,
+    });
+}
+
+fn report_endless_run(
+    run: Res<EndlessRun>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    mut score_events: EventWriter<PostLeaderboardScore>,
+) {
+    score_events.write(PostLeaderboardScore {
+        leaderboard: endless_wave_leaderboard(*difficulty),
+        score: run.wave.try_into().unwrap_or(i32::MAX),
+        lower_is_better: false,
+    });
+    score_events.write(PostLeaderboardScore {
+        leaderboard: endless_score_leaderboard(*difficulty),
+        score: score.points.try_into().unwrap_or(i32::MAX),
+        lower_is_better: false,
+    });
+}
+
+fn unlock_demolition(mut achievement_events: EventWriter<UnlockAchievement>) {
+    achievement_events.write(UnlockAchievement(Achievement::Demolition));
+}
+
+fn unlock_chain_reaction(
+    score: Res<Score>,
+    mut achievement_events: EventWriter<UnlockAchievement>,
+    mut unlocked: Local<bool>,
+) {
+    if score.chain >= CHAIN_REACTION_BLOCKS && !*unlocked {
+        *unlocked = true;
+        achievement_events.write(UnlockAchievement(Achievement::ChainReaction));
+    }
+}
+
+fn update_rich_presence(
+    screen: Res<State<Screen>>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Option<Res<LevelAssets>>,
+    projects: Res<Assets<LdtkProject>>,
+    mut presence_events: EventWriter<SetRichPresence>,
+) {
+    let level = level_assets
+        .and_then(|level_assets| selected_level(&level_selection, &level_assets, &projects));
+    let status = match (screen.get(), level) {
+        (Screen::Gameplay, Some(level)) => format!("Wrecking {}", level.replace('_', " ")),
+        (Screen::Gameplay, None) => "Wrecking castles".to_string(),
+        _ => "In the menus".to_string(),
+    };
+    presence_events.write(SetRichPresence { status });
+}
@@ -0,0 +1,107 @@
+//! The store the game ships on: achievements, leaderboards and rich presence.
+//!
+//! Gameplay code only sends the events in this module, and whichever backend
+//! is compiled in passes them on to its store. Build with the `steam` feature
+//! for Steam. Otherwise, e.g. for itch.io, which has no such APIs, the no-op
+//! backend just logs them. Leaderboard scores also go to the game's own server
+//! with the `leaderboard` feature, see `leaderboard.rs`.
+
+mod achievements;
+#[cfg(feature = "steam")]
+mod steam;
+
+use bevy::prelude::*;
+
+use crate::demo::difficulty::Difficulty;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<UnlockAchievement>();
+    app.add_event::<PostLeaderboardScore>();
+    app.add_event::<SetRichPresence>();
+    app.add_plugins(achievements::plugin);
+
+    #[cfg(feature = "steam")]
+    app.add_plugins(steam::plugin);
+    #[cfg(not(feature = "steam"))]
+    app.add_systems(Update, log_platform_events);
+}
+
+/// Every achievement in the game. The ids must match the store's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    /// Complete any level.
+    FirstClear,
+    /// Bring down a whole castle section.
+    Demolition,
+    /// Destroy enough blocks in one chain.
+    ChainReaction,
+}
+
+impl Achievement {
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::FirstClear => "FIRST_CLEAR",
+            Self::Demolition => "DEMOLITION",
+            Self::ChainReaction => "CHAIN_REACTION",
+        }
+    }
+}
+
+/// Unlock an achievement. Unlocking one that's already unlocked does nothing.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UnlockAchievement(pub Achievement);
+
+/// Post a score to a leaderboard, keeping the player's best.
+#[derive(Event, Debug, Clone)]
+pub struct PostLeaderboardScore {
+    pub leaderboard: String,
+    pub score: i32,
+    /// Whether lower scores are better, like times.
+    pub lower_is_better: bool,
+}
+
+/// The leaderboard of fastest times on `level`, one per difficulty.
+pub fn time_leaderboard(level: &str, difficulty: Difficulty) -> String {
+    format!("{}_time", difficulty.record_key(level))
+}
+
+/// The leaderboard of high scores on `level`, one per difficulty.
+pub fn score_leaderboard(level: &str, difficulty: Difficulty) -> String {
+    format!("{}_score", difficulty.record_key(level))
+}
+
+/// The leaderboard of furthest waves reached in endless mode, one per difficulty.
+pub fn endless_wave_leaderboard(difficulty: Difficulty) -> String {
+    format!("{}_wave", difficulty.record_key(ENDLESS_LEADERBOARD))
+}
+
+/// The leaderboard of high scores in endless mode, one per difficulty.
+pub fn endless_score_leaderboard(difficulty: Difficulty) -> String {
+    format!("{}_score", difficulty.record_key(ENDLESS_LEADERBOARD))
+}
+
+/// Stands in for the level in endless mode's leaderboard names.
+const ENDLESS_LEADERBOARD: &str = "endless";
+
+/// Tell friends what the player is doing, e.g. which level they're on.
+#[derive(Event, Debug, Clone)]
+pub struct SetRichPresence {
+    pub status: String,
+}
+
+#[cfg(not(feature = "steam"))]
+fn log_platform_events(
+    mut achievement_events: EventReader<UnlockAchievement>,
+    mut score_events: EventReader<PostLeaderboardScore>,
+    mut presence_events: EventReader<SetRichPresence>,
+) {
+    for UnlockAchievement(achievement) in achievement_events.read() {
+        debug!("Achievement unlocked: {}", achievement.id());
+    }
+    for event in score_events.read() {
+        debug!("Leaderboard {}: {}", event.leaderboard, event.score);
+    }
+    for event in presence_events.read() {
+        debug!("Rich presence: {}", event.status);
+    }
+}
@@ -0,0 +1,124 @@
+//! The Steam backend, enabled with the `steam` feature.
+//!
+//! Steam has to be running, and the app id is read from `steam_appid.txt`
+//! next to the executable during development. If Steam can't be reached the
+//! game carries on without it and platform events are dropped.
+
+use bevy::prelude::*;
+use steamworks::{
+    Client, LeaderboardDisplayType, LeaderboardSortMethod, SingleClient, UploadScoreMethod,
+};
+
+use crate::platform::{PostLeaderboardScore, SetRichPresence, UnlockAchievement};
+
+pub(super) fn plugin(app: &mut App) {
+    match Client::init() {
+        Ok((client, single)) => {
+            info!("Connected to Steam as {}", client.friends().name());
+            app.insert_resource(SteamClient(client));
+            // Steam callbacks have to be run from the thread that initialized it.
+            app.insert_non_send_resource(single);
+        }
+        Err(error) => {
+            warn!("Couldn't connect to Steam, achievements are disabled: {error}");
+            return;
+        }
+    }
+    app.add_systems(
+        Update,
+        (
+            run_steam_callbacks,
+            unlock_achievements,
+            post_leaderboard_scores,
+            set_rich_presence,
+        ),
+    );
+}
+
+#[derive(Resource, Clone)]
+struct SteamClient(Client);
+
+fn run_steam_callbacks(single: NonSend<SingleClient>) {
+    single.run_callbacks();
+}
+
+fn unlock_achievements(
+    steam: Res<SteamClient>,
+    mut achievement_events: EventReader<UnlockAchievement>,
+) {
+    let user_stats = steam.0.user_stats();
+    let mut unlocked = false;
+    for UnlockAchievement(achievement) in achievement_events.read() {
+        if user_stats.achievement(achievement.id()).set().is_err() {
+            warn!("Couldn't unlock Steam achievement {}", achievement.id());
+            continue;
+        }
+        unlocked = true;
+    }
+    if unlocked && user_stats.store_stats().is_err() {
+        warn!("Couldn't store Steam stats");
+    }
+}
+
+fn post_leaderboard_scores(
+    steam: Res<SteamClient>,
+    mut score_events: EventReader<PostLeaderboardScore>,
+) {
+    for event in score_events.read() {
+        let (sort, display) = if event.lower_is_better {
+            (
+                LeaderboardSortMethod::Ascending,
+                LeaderboardDisplayType::TimeMilliSeconds,
+            )
+        } else {
+            (
+                LeaderboardSortMethod::Descending,
+                LeaderboardDisplayType::Numeric,
+            )
+        };
+        let client = steam.0.clone();
+        let name = event.leaderboard.clone();
+        let score = event.score;
+        // Steam answers on a later frame, from `run_steam_callbacks`.
+        steam.0.user_stats().find_or_create_leaderboard(
+            &event.leaderboard,
+            sort,
+            display,
+            move |result| {
+                let leaderboard = match result {
+                    Ok(Some(leaderboard)) => leaderboard,
+                    Ok(None) => {
+                        warn!("Steam leaderboard {name} doesn't exist");
+                        return;
+                    }
+                    Err(error) => {
+                        warn!("Couldn't find Steam leaderboard {name}: {error}");
+                        return;
+                    }
+                };
+                client.user_stats().upload_leaderboard_score(
+                    &leaderboard,
+                    UploadScoreMethod::KeepBest,
+                    score,
+                    &[],
+                    move |result| {
+                        if let Err(error) = result {
+                            warn!("Couldn't post to Steam leaderboard {name}: {error}");
+                        }
+                    },
+                );
+            },
+        );
+    }
+}
+
+fn set_rich_presence(steam: Res<SteamClient>, mut presence_events: EventReader<SetRichPresence>) {
+    // Only the latest status matters.
+    let Some(event) = presence_events.read().last() else {
+        return;
+    };
+    steam
+        .0
+        .friends()
+        .set_rich_presence("status", Some(&event.status));
+}
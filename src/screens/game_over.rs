@@ -0,0 +1,86 @@
+//! The screen shown when the player has run out of lives or time.
+//!
+//! In endless mode this is where the run ends, so it shows how far the player
+//! got and records the run.
+
+use bevy::prelude::*;
+
+use crate::{
+    demo::{
+        attract::AttractMode, difficulty::Difficulty, endless::EndlessRun, game_mode::GameMode,
+        score::Score, timer::GameTimer,
+    },
+    persistence::BestRecords,
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::GameOver), spawn_game_over_screen);
+}
+
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    run: Res<EndlessRun>,
+    score: Res<Score>,
+    timer: Res<GameTimer>,
+    attract_mode: Res<AttractMode>,
+    difficulty: Res<Difficulty>,
+    mut records: ResMut<BestRecords>,
+) {
+    if *game_mode != GameMode::Endless {
+        commands.spawn((
+            widget::ui_root("Game Over Screen"),
+            GlobalZIndex(2),
+            StateScoped(Screen::GameOver),
+            children![
+                widget::header(if timer.expired() {
+                    "Time's up"
+                } else {
+                    "Game over"
+                }),
+                widget::button("Retry", retry),
+                widget::button("Breakdown", open_run_stats),
+                widget::button("Quit to title", quit_to_title),
+            ],
+        ));
+        return;
+    }
+
+    let time = timer.elapsed_secs();
+    // The demo run isn't the player's, so it doesn't set records.
+    let improved =
+        !attract_mode.active && records.submit_endless(*difficulty, run.wave, time, score.points);
+    let best = records.endless(*difficulty);
+    commands.spawn((
+        widget::ui_root("Game Over Screen"),
+        GlobalZIndex(2),
+        StateScoped(Screen::GameOver),
+        children![
+            widget::header(if improved { "New record!" } else { "Game over" }),
+            widget::label(format!("Reached wave {}", run.wave)),
+            widget::label(format!("Score: {}", score.points)),
+            widget::label(format!("Survived {time:.2} seconds")),
+            widget::label(format!(
+                "Best: wave {}, {} points",
+                best.best_wave, best.high_score
+            )),
+            widget::button("Retry", retry),
+            widget::button("Breakdown", open_run_stats),
+            widget::button("Quit to title", quit_to_title),
+        ],
+    ));
+}
+
+fn retry(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn open_run_stats(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::RunStats);
+}
+
+fn quit_to_title(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
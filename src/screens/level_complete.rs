@@ -0,0 +1,151 @@
+//! The screen shown when the player has reached every goal in the level.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    demo::{
+        attract::AttractMode,
+        difficulty::{Difficulty, DifficultyTuning},
+        game_mode::GameMode,
+        level::{LevelAssets, selected_level_identifier},
+        score::Score,
+        time_attack::{ParTimes, TimeAttackAssets, selected_level_par_times},
+        timer::GameTimer,
+    },
+    persistence::BestRecords,
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::LevelComplete),
+        (record_level_result, spawn_level_complete_screen).chain(),
+    );
+}
+
+/// Whether the run that just finished set a new best time or high score.
+#[derive(Resource, Debug, Default)]
+struct NewRecord(bool);
+
+/// The level's best time on this difficulty before the run that just finished.
+#[derive(Resource, Debug, Default)]
+struct PreviousBestTime(Option<f32>);
+
+fn record_level_result(
+    mut commands: Commands,
+    mut records: ResMut<BestRecords>,
+    score: Res<Score>,
+    timer: Res<GameTimer>,
+    attract_mode: Res<AttractMode>,
+    difficulty: Res<Difficulty>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+) {
+    // The demo run isn't the player's, so it doesn't set records.
+    let Some(level) = projects
+        .get(&level_assets.ldtk_level.handle)
+        .and_then(|project| selected_level_identifier(&level_selection, project))
+        .filter(|_| !attract_mode.active)
+    else {
+        commands.insert_resource(NewRecord(false));
+        commands.insert_resource(PreviousBestTime(None));
+        return;
+    };
+    let previous_best = records
+        .get(&level, *difficulty)
+        .and_then(|record| record.best_time);
+    commands.insert_resource(PreviousBestTime(previous_best));
+    let improved = records.submit(&level, *difficulty, timer.elapsed_secs(), score.points);
+    commands.insert_resource(NewRecord(improved));
+}
+
+fn spawn_level_complete_screen(
+    mut commands: Commands,
+    score: Res<Score>,
+    timer: Res<GameTimer>,
+    new_record: Res<NewRecord>,
+    previous_best: Res<PreviousBestTime>,
+    game_mode: Res<GameMode>,
+    level_selection: Res<LevelSelection>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    time_attack_assets: Res<TimeAttackAssets>,
+    par_times: Res<Assets<ParTimes>>,
+    difficulty: DifficultyTuning,
+) {
+    let screen = commands
+        .spawn((
+            widget::ui_root("Level Complete Screen"),
+            GlobalZIndex(2),
+            StateScoped(Screen::LevelComplete),
+            children![
+                widget::header(if new_record.0 {
+                    "New record!"
+                } else {
+                    "Level complete!"
+                }),
+                widget::label(format!("Score: {}", score.points)),
+                widget::label(format!("Longest chain: {}", score.best_chain)),
+                widget::label(format!("Time: {:.2} seconds", timer.elapsed_secs())),
+            ],
+        ))
+        .id();
+
+    if *game_mode == GameMode::TimeAttack {
+        let time = timer.elapsed_secs();
+        let par = selected_level_par_times(
+            &level_selection,
+            &level_assets,
+            &projects,
+            &time_attack_assets,
+            &par_times,
+            &difficulty,
+        );
+        commands.entity(screen).with_children(|parent| {
+            if let Some(par) = par {
+                match par.medal(time) {
+                    Some(medal) => {
+                        parent.spawn(widget::header(format!("{} medal", medal.name())));
+                    }
+                    None => {
+                        parent.spawn(widget::label("No medal"));
+                    }
+                }
+                parent.spawn(widget::label(format!(
+                    "Par: gold {:.2}s, silver {:.2}s, bronze {:.2}s",
+                    par.gold, par.silver, par.bronze
+                )));
+            }
+            parent.spawn(widget::label(match previous_best.0 {
+                Some(best) => format!("Best: {best:.2} seconds ({:+.2})", time - best),
+                None => "First finish on this level".to_string(),
+            }));
+        });
+    }
+
+    commands.entity(screen).with_children(|parent| {
+        parent.spawn(widget::button("Play again", play_again));
+        parent.spawn(widget::button("Breakdown", open_run_stats));
+        parent.spawn(widget::button("Level select", open_level_select));
+        parent.spawn(widget::button("Quit to title", quit_to_title));
+    });
+}
+
+fn play_again(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn open_run_stats(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::RunStats);
+}
+
+fn open_level_select(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
+
+fn quit_to_title(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -0,0 +1,198 @@
+//! Pick a level to play, showing the best time and high score saved for each.
+//! The game mode and difficulty to play it in are picked here too, and the
+//! records shown are the ones set on that difficulty.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    demo::{difficulty::Difficulty, game_mode::GameMode, level::LevelAssets, progress::Progress},
+    persistence::{BestRecords, LevelRecord},
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LevelSelect), spawn_level_select_screen);
+    app.add_systems(
+        Update,
+        (
+            (
+                clear_level_list.run_if(resource_changed::<Difficulty>),
+                fill_level_list,
+            )
+                .chain(),
+            update_game_mode_label.run_if(resource_changed::<GameMode>),
+            update_difficulty_label.run_if(resource_changed::<Difficulty>),
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+        )
+            .run_if(in_state(Screen::LevelSelect)),
+    );
+}
+
+/// Holds one entry per level, filled in once the LDtk project has loaded.
+#[derive(Component)]
+struct LevelList;
+
+#[derive(Component)]
+struct GameModeLabel;
+
+#[derive(Component)]
+struct DifficultyLabel;
+
+fn spawn_level_select_screen(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+) {
+    commands.spawn((
+        widget::ui_root("Level Select Screen"),
+        GlobalZIndex(2),
+        StateScoped(Screen::LevelSelect),
+        children![
+            widget::header("Select a level"),
+            (
+                Name::new("Game Mode"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(30.0),
+                    ..default()
+                },
+                children![
+                    (
+                        widget::label(format!("Mode: {}", game_mode.name())),
+                        GameModeLabel
+                    ),
+                    widget::button_small("Change", cycle_game_mode),
+                ],
+            ),
+            (
+                Name::new("Difficulty"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(30.0),
+                    ..default()
+                },
+                children![
+                    (
+                        widget::label(format!("Difficulty: {}", difficulty.name())),
+                        DifficultyLabel
+                    ),
+                    widget::button_small("Change", cycle_difficulty),
+                ],
+            ),
+            (
+                Name::new("Level List"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Px(20.0),
+                    ..default()
+                },
+                LevelList,
+            ),
+            widget::button("Skill tree", open_skill_tree),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+fn fill_level_list(
+    mut commands: Commands,
+    level_list: Single<Entity, (With<LevelList>, Without<Children>)>,
+    level_assets: Res<LevelAssets>,
+    projects: Res<Assets<LdtkProject>>,
+    records: Res<BestRecords>,
+    difficulty: Res<Difficulty>,
+    progress: Res<Progress>,
+) {
+    let Some(project) = projects.get(&level_assets.ldtk_level.handle) else {
+        return;
+    };
+    commands.entity(*level_list).with_children(|parent| {
+        for (index, level) in project.json_data().levels.iter().enumerate() {
+            let record = records
+                .get(&level.identifier, *difficulty)
+                .copied()
+                .unwrap_or_default();
+            if progress.is_level_unlocked(index, &level.identifier) {
+                parent.spawn(level_entry(index, &level.identifier, record));
+            } else {
+                parent.spawn(locked_level_entry(&level.identifier));
+            }
+        }
+    });
+}
+
+fn level_entry(index: usize, identifier: &str, record: LevelRecord) -> impl Bundle {
+    let best_time = record
+        .best_time
+        .map_or_else(|| "--".to_string(), |time| format!("{time:.2}s"));
+    (
+        Name::new("Level Entry"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(30.0),
+            ..default()
+        },
+        children![
+            widget::button(
+                identifier.replace('_', " "),
+                move |_: Trigger<Pointer<Click>>,
+                      mut level_selection: ResMut<LevelSelection>,
+                      mut next_screen: ResMut<NextState<Screen>>| {
+                    *level_selection = LevelSelection::index(index);
+                    next_screen.set(Screen::Loadout);
+                },
+            ),
+            widget::label(format!("Best time: {best_time}")),
+            widget::label(format!("High score: {}", record.high_score)),
+        ],
+    )
+}
+
+fn cycle_game_mode(_: Trigger<Pointer<Click>>, mut game_mode: ResMut<GameMode>) {
+    *game_mode = game_mode.next();
+}
+
+fn update_game_mode_label(
+    game_mode: Res<GameMode>,
+    mut label_query: Query<&mut Text, With<GameModeLabel>>,
+) {
+    for mut text in &mut label_query {
+        text.0 = format!("Mode: {}", game_mode.name());
+    }
+}
+
+fn cycle_difficulty(_: Trigger<Pointer<Click>>, mut difficulty: ResMut<Difficulty>) {
+    *difficulty = difficulty.next();
+}
+
+fn update_difficulty_label(
+    difficulty: Res<Difficulty>,
+    mut label_query: Query<&mut Text, With<DifficultyLabel>>,
+) {
+    for mut text in &mut label_query {
+        text.0 = format!("Difficulty: {}", difficulty.name());
+    }
+}
+
+/// Empty the level list so it's filled again with the records for the new difficulty.
+fn clear_level_list(mut commands: Commands, level_list: Single<Entity, With<LevelList>>) {
+    commands.entity(*level_list).despawn_related::<Children>();
+}
+
+fn locked_level_entry(identifier: &str) -> impl Bundle {
+    widget::label(format!("{} (locked)", identifier.replace('_', " ")))
+}
+
+fn open_skill_tree(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::SkillTree);
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
+
+fn go_back(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -4,7 +4,8 @@
 use bevy::prelude::*;
 
 use crate::{
-    asset_tracking::ResourceHandles, demo::level::LdtkReady, screens::Screen, theme::prelude::*,
+    asset_tracking::ResourceHandles, demo::level::ldtk_project_loaded, screens::Screen,
+    theme::prelude::*,
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -12,10 +13,10 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        enter_gameplay_screen.run_if(
+        enter_level_select_screen.run_if(
             in_state(Screen::Loading)
                 .and(all_assets_loaded)
-                .and(resource_exists::<LdtkReady>),
+                .and(ldtk_project_loaded),
         ),
     );
 }
@@ -28,8 +29,8 @@ fn spawn_loading_screen(mut commands: Commands) {
     ));
 }
 
-fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Gameplay);
+fn enter_level_select_screen(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
 }
 
 fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
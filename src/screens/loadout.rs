@@ -0,0 +1,99 @@
+//! Pick the abilities to take into the level before it starts.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    demo::{
+        input::SLOT_KEY_NAMES,
+        loadout::{HOTBAR_SLOTS, Loadout, is_collected},
+        progress::Progress,
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(Screen::Loadout),
+        (fit_loadout_to_progress, spawn_loadout_screen).chain(),
+    );
+    app.add_systems(
+        Update,
+        (
+            update_slot_labels.run_if(resource_changed::<Loadout>),
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+        )
+            .run_if(in_state(Screen::Loadout)),
+    );
+}
+
+/// Shows the ability in one hotbar slot.
+#[derive(Component)]
+struct SlotLabel(usize);
+
+fn fit_loadout_to_progress(mut loadout: ResMut<Loadout>, progress: Res<Progress>) {
+    loadout.fit_to(|ability| is_collected(&progress, ability));
+}
+
+fn spawn_loadout_screen(mut commands: Commands, loadout: Res<Loadout>) {
+    commands
+        .spawn((
+            widget::ui_root("Loadout Screen"),
+            GlobalZIndex(2),
+            StateScoped(Screen::Loadout),
+            children![widget::header("Choose your loadout")],
+        ))
+        .with_children(|parent| {
+            for slot in 0..HOTBAR_SLOTS {
+                parent.spawn(slot_entry(slot, &loadout));
+            }
+            parent.spawn(widget::button("Start", start_level));
+            parent.spawn(widget::button("Back", go_back_on_click));
+        });
+}
+
+fn slot_entry(slot: usize, loadout: &Loadout) -> impl Bundle {
+    (
+        Name::new("Slot Entry"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(30.0),
+            ..default()
+        },
+        children![
+            (widget::label(slot_text(slot, loadout)), SlotLabel(slot)),
+            widget::button_small(
+                "Change",
+                move |_: Trigger<Pointer<Click>>,
+                      mut loadout: ResMut<Loadout>,
+                      progress: Res<Progress>| {
+                    loadout.cycle_slot(slot, &progress);
+                },
+            ),
+        ],
+    )
+}
+
+fn slot_text(slot: usize, loadout: &Loadout) -> String {
+    let ability =
+        loadout.slots[slot].map_or_else(|| "Empty".to_string(), |ability| format!("{ability:?}"));
+    format!("[{}] {ability}", SLOT_KEY_NAMES[slot])
+}
+
+fn update_slot_labels(loadout: Res<Loadout>, mut label_query: Query<(&mut Text, &SlotLabel)>) {
+    for (mut text, label) in &mut label_query {
+        text.0 = slot_text(label.0, &loadout);
+    }
+}
+
+fn start_level(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
+
+fn go_back(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
@@ -1,7 +1,13 @@
 //! The game's main screen states and transitions between them.
 
+mod game_over;
 mod gameplay;
+mod level_complete;
+mod level_select;
 mod loading;
+mod loadout;
+mod run_stats;
+mod skill_tree;
 mod splash;
 mod title;
 
@@ -11,8 +17,14 @@ pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
 
     app.add_plugins((
+        game_over::plugin,
         gameplay::plugin,
+        level_complete::plugin,
+        level_select::plugin,
         loading::plugin,
+        loadout::plugin,
+        run_stats::plugin,
+        skill_tree::plugin,
         splash::plugin,
         title::plugin,
     ));
@@ -26,5 +38,11 @@ pub enum Screen {
     Splash,
     Title,
     Loading,
+    LevelSelect,
+    Loadout,
+    SkillTree,
     Gameplay,
+    GameOver,
+    LevelComplete,
+    RunStats,
 }
@@ -0,0 +1,173 @@
+//! A breakdown of the run that just finished, with graphs of how each ability
+//! did and how the destruction played out over time.
+
+use bevy::{
+    ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*,
+};
+
+use crate::{
+    demo::{
+        loadout::Loadout,
+        run_stats::{RunStats, TIMELINE_BUCKET_SECS},
+    },
+    screens::Screen,
+    theme::{palette::LABEL_TEXT, widget},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::RunStats), spawn_run_stats_screen);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Screen::RunStats).and(input_just_pressed(KeyCode::Escape))),
+    );
+}
+
+/// Width of the longest bar in the ability graph.
+const ABILITY_BAR_MAX_WIDTH: f32 = 300.0;
+const ABILITY_BAR_HEIGHT: f32 = 20.0;
+/// Height of the tallest bar in the destruction timeline.
+const TIMELINE_MAX_HEIGHT: f32 = 120.0;
+const TIMELINE_BAR_WIDTH: f32 = 16.0;
+/// World units in one level tile, for showing distances in tiles.
+const TILE_SIZE: f32 = 16.0;
+
+fn spawn_run_stats_screen(mut commands: Commands, stats: Res<RunStats>) {
+    commands.spawn((
+        widget::ui_root("Run Stats Screen"),
+        GlobalZIndex(2),
+        StateScoped(Screen::RunStats),
+        children![
+            widget::header("Run breakdown"),
+            widget::label(format!("Blocks destroyed: {}", stats.blocks_destroyed)),
+            widget::label(format!("Largest chain collapse: {}", stats.largest_chain)),
+            widget::label(format!(
+                "Distance traveled: {:.0} tiles",
+                stats.distance_traveled / TILE_SIZE
+            )),
+            widget::label("Damage by ability"),
+            ability_graph(&stats),
+            widget::label(format!(
+                "Blocks destroyed every {TIMELINE_BUCKET_SECS:.0} seconds"
+            )),
+            destruction_timeline(&stats),
+            widget::button("Play again", play_again),
+            widget::button("Level select", open_level_select),
+        ],
+    ));
+}
+
+/// One row per ability: a bar for the damage it dealt, and its numbers.
+fn ability_graph(stats: &RunStats) -> impl Bundle {
+    let max_damage = Loadout::ABILITIES
+        .into_iter()
+        .map(|ability| stats.ability(ability).damage)
+        .fold(0.0, f32::max);
+    let rows: Vec<_> = Loadout::ABILITIES
+        .into_iter()
+        .map(|ability| {
+            let ability_stats = stats.ability(ability);
+            let fraction = if max_damage > 0.0 {
+                ability_stats.damage / max_damage
+            } else {
+                0.0
+            };
+            (
+                Name::new("Ability Row"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(12.0),
+                    ..default()
+                },
+                children![
+                    (
+                        Node {
+                            width: Px(100.0),
+                            ..default()
+                        },
+                        children![small_label(format!("{ability:?}"))],
+                    ),
+                    (
+                        Name::new("Ability Bar"),
+                        Node {
+                            width: Px(ABILITY_BAR_MAX_WIDTH * fraction),
+                            height: Px(ABILITY_BAR_HEIGHT),
+                            ..default()
+                        },
+                        BackgroundColor(ability.color()),
+                    ),
+                    small_label(format!(
+                        "{:.0} damage, {} casts, {} hits",
+                        ability_stats.damage, ability_stats.casts, ability_stats.hits
+                    )),
+                ],
+            )
+        })
+        .collect();
+    (
+        Name::new("Ability Graph"),
+        Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Px(8.0),
+            ..default()
+        },
+        Children::spawn(SpawnIter(rows.into_iter())),
+    )
+}
+
+/// A bar for each stretch of the run, as tall as the number of blocks destroyed in it.
+fn destruction_timeline(stats: &RunStats) -> impl Bundle {
+    let max_destroyed = stats
+        .destruction_timeline
+        .iter()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let bars: Vec<_> = stats
+        .destruction_timeline
+        .iter()
+        .map(|&destroyed| {
+            (
+                Name::new("Timeline Bar"),
+                Node {
+                    width: Px(TIMELINE_BAR_WIDTH),
+                    height: Px(TIMELINE_MAX_HEIGHT * destroyed as f32 / max_destroyed as f32),
+                    ..default()
+                },
+                BackgroundColor(LABEL_TEXT),
+            )
+        })
+        .collect();
+    (
+        Name::new("Destruction Timeline"),
+        Node {
+            height: Px(TIMELINE_MAX_HEIGHT),
+            align_items: AlignItems::End,
+            column_gap: Px(2.0),
+            ..default()
+        },
+        Children::spawn(SpawnIter(bars.into_iter())),
+    )
+}
+
+/// A label small enough to fit alongside a graph.
+fn small_label(text: String) -> impl Bundle {
+    (
+        Name::new("Label"),
+        Text(text),
+        TextFont::from_font_size(18.0),
+        TextColor(LABEL_TEXT),
+    )
+}
+
+fn play_again(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn open_level_select(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
+
+fn go_back(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
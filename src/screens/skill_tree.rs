@@ -0,0 +1,120 @@
+//! Spend skill points on upgrades, and see which abilities have been unlocked.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    demo::{
+        loadout::{Loadout, is_collected},
+        progress::Progress,
+        skills::{MAX_RANK, SkillUpgrade},
+    },
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::SkillTree), spawn_skill_tree_screen);
+    app.add_systems(
+        Update,
+        (
+            update_skill_labels.run_if(resource_changed::<Progress>),
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+        )
+            .run_if(in_state(Screen::SkillTree)),
+    );
+}
+
+#[derive(Component)]
+struct SkillPointsLabel;
+
+/// Shows the current rank of an upgrade.
+#[derive(Component)]
+struct UpgradeRankLabel(SkillUpgrade);
+
+fn spawn_skill_tree_screen(mut commands: Commands, progress: Res<Progress>) {
+    let abilities = Loadout::ABILITIES
+        .into_iter()
+        .map(|ability| {
+            let unlocked = is_collected(&progress, ability);
+            format!(
+                "{ability:?}: {}",
+                if unlocked { "unlocked" } else { "locked" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    commands
+        .spawn((
+            widget::ui_root("Skill Tree Screen"),
+            GlobalZIndex(2),
+            StateScoped(Screen::SkillTree),
+            children![
+                widget::header("Skill tree"),
+                widget::label(abilities),
+                (
+                    widget::label(skill_points_text(&progress)),
+                    SkillPointsLabel
+                ),
+            ],
+        ))
+        .with_children(|parent| {
+            for upgrade in SkillUpgrade::ALL {
+                parent.spawn(upgrade_entry(upgrade, &progress));
+            }
+            parent.spawn(widget::button("Back", go_back_on_click));
+        });
+}
+
+fn upgrade_entry(upgrade: SkillUpgrade, progress: &Progress) -> impl Bundle {
+    (
+        Name::new("Upgrade Entry"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(30.0),
+            ..default()
+        },
+        children![
+            widget::label(format!("{}: {}", upgrade.name(), upgrade.description())),
+            (
+                widget::label(rank_text(upgrade, progress)),
+                UpgradeRankLabel(upgrade)
+            ),
+            widget::button_small(
+                "+",
+                move |_: Trigger<Pointer<Click>>, mut progress: ResMut<Progress>| {
+                    progress.buy_upgrade(upgrade);
+                },
+            ),
+        ],
+    )
+}
+
+fn skill_points_text(progress: &Progress) -> String {
+    format!("Skill points: {}", progress.skill_points)
+}
+
+fn rank_text(upgrade: SkillUpgrade, progress: &Progress) -> String {
+    format!("{}/{MAX_RANK}", progress.upgrades.rank(upgrade))
+}
+
+fn update_skill_labels(
+    progress: Res<Progress>,
+    mut points_query: Query<&mut Text, (With<SkillPointsLabel>, Without<UpgradeRankLabel>)>,
+    mut rank_query: Query<(&mut Text, &UpgradeRankLabel)>,
+) {
+    for mut text in &mut points_query {
+        text.0 = skill_points_text(&progress);
+    }
+    for (mut text, label) in &mut rank_query {
+        text.0 = rank_text(label.0, &progress);
+    }
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
+
+fn go_back(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelSelect);
+}
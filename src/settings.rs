@@ -0,0 +1,217 @@
+//! Player-facing settings that persist between sessions.
+//!
+//! The settings menu only edits the [`Settings`] resource; this module applies
+//! it to the audio and window and saves it whenever it changes.
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{MonitorSelection, PresentMode, PrimaryWindow, VideoModeSelection, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::persistence;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Settings>();
+    let mut settings = persistence::load::<Settings>(SETTINGS_KEY).unwrap_or_default();
+    // The save could've been edited by hand or come from an older version.
+    settings.set_game_speed(settings.game_speed);
+    app.insert_resource(settings);
+    app.add_systems(
+        Update,
+        (apply_audio_settings, apply_window_settings, save_settings)
+            .run_if(resource_changed::<Settings>),
+    );
+}
+
+const SETTINGS_KEY: &str = "settings";
+pub const MIN_VOLUME: f32 = 0.0;
+pub const MAX_VOLUME: f32 = 2.0;
+pub const MIN_GAME_SPEED: f32 = 0.5;
+pub const MAX_GAME_SPEED: f32 = 1.0;
+
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Linear volume applied to every sound.
+    pub master_volume: f32,
+    /// Linear volume applied to [`Music`](crate::audio::Music) on top of the master volume.
+    pub music_volume: f32,
+    /// Linear volume applied to [`SoundEffect`](crate::audio::SoundEffect)s on top of the master volume.
+    pub sfx_volume: f32,
+    pub window_mode: WindowModeSetting,
+    pub vsync: bool,
+    /// Glow around explosions and lightning.
+    pub bloom: bool,
+    /// A chromatic aberration pulse when a shockwave goes off.
+    pub chromatic_aberration: bool,
+    /// Drain the color out of the screen during hitstop.
+    pub hitstop_desaturation: bool,
+    /// Colors for frost, fire and the other status tints.
+    pub color_palette: ColorPalette,
+    /// Mark status effects with icons as well as color.
+    pub status_icons: bool,
+    /// Health bars over anything that's been hurt or frosted.
+    pub health_bars: bool,
+    /// Shake the camera when things explode.
+    pub screen_shake: bool,
+    /// Hit flashes, blinking and explosion flashes.
+    pub flashes: bool,
+    /// Multiplier for the speed of gameplay, from [`MIN_GAME_SPEED`] to
+    /// [`MAX_GAME_SPEED`]. Menus and audio always run at normal speed.
+    pub game_speed: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            window_mode: WindowModeSetting::default(),
+            vsync: true,
+            bloom: true,
+            chromatic_aberration: true,
+            hitstop_desaturation: true,
+            color_palette: ColorPalette::default(),
+            status_icons: false,
+            health_bars: true,
+            screen_shake: true,
+            flashes: true,
+            game_speed: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    /// Set [`Settings::game_speed`], kept within the range the menu offers.
+    /// Gameplay divides by it, so it can never be zero.
+    pub fn set_game_speed(&mut self, speed: f32) {
+        self.game_speed = if speed.is_nan() {
+            1.0
+        } else {
+            speed.clamp(MIN_GAME_SPEED, MAX_GAME_SPEED)
+        };
+    }
+}
+
+/// A serializable stand-in for [`WindowMode`].
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    /// The mode after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Windowed => Self::Borderless,
+            Self::Borderless => Self::Fullscreen,
+            Self::Fullscreen => Self::Windowed,
+        }
+    }
+
+    /// The mode before this one, wrapping around.
+    pub fn previous(self) -> Self {
+        match self {
+            Self::Windowed => Self::Fullscreen,
+            Self::Borderless => Self::Windowed,
+            Self::Fullscreen => Self::Borderless,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Windowed => "Windowed",
+            Self::Borderless => "Borderless",
+            Self::Fullscreen => "Fullscreen",
+        }
+    }
+
+    fn window_mode(self) -> WindowMode {
+        match self {
+            Self::Windowed => WindowMode::Windowed,
+            Self::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            Self::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+/// The set of colors status effects are tinted with, see `TintLayer::color`.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    /// For deuteranopia and protanopia.
+    RedGreen,
+    /// For tritanopia.
+    BlueYellow,
+}
+
+impl ColorPalette {
+    /// The palette after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Standard => Self::RedGreen,
+            Self::RedGreen => Self::BlueYellow,
+            Self::BlueYellow => Self::Standard,
+        }
+    }
+
+    /// The palette before this one, wrapping around.
+    pub fn previous(self) -> Self {
+        match self {
+            Self::Standard => Self::BlueYellow,
+            Self::RedGreen => Self::Standard,
+            Self::BlueYellow => Self::RedGreen,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::RedGreen => "Red-Green Safe",
+            Self::BlueYellow => "Blue-Yellow Safe",
+        }
+    }
+}
+
+fn apply_audio_settings(settings: Res<Settings>, mut global_volume: ResMut<GlobalVolume>) {
+    let master_volume = Volume::Linear(settings.master_volume);
+    if global_volume.volume != master_volume {
+        global_volume.volume = master_volume;
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    let mode = settings.window_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    // Skip the write triggered by inserting the resource on startup.
+    if settings.is_added() {
+        return;
+    }
+    persistence::save(SETTINGS_KEY, &*settings);
+}